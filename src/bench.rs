@@ -0,0 +1,192 @@
+//! The bench module searches a fixed set of positions to a fixed depth, which serves as a fingerprint of the search.
+//!
+//! Every position is searched by a new engine, so that no state is carried over from previous searches, and without
+//! a time limit. This makes the total number of nodes fully deterministic: it only changes if the behavior of the
+//! search or evaluation changes. The number of nodes per second gives a rough measure of the speed.
+
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use crate::board::Board;
+use crate::engine::{CancelHandle, Engine, EngineConfig, SearchLimits};
+
+/// The depth to which the positions are searched by default.
+pub const BENCH_DEPTH: u64 = 4;
+
+/// The positions of the bench, covering openings, middlegames, endgames, promotions and checks.
+pub const BENCH_POSITIONS: [&str; 50] = [
+    // openings
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2",
+    "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4",
+    "rnbqkb1r/pp2pppp/3p1n2/8/3NP3/8/PPP2PPP/RNBQKB1R w KQkq - 1 5",
+    "rnbqk2r/ppp1ppbp/3p1np1/8/2PPP3/2N5/PP3PPP/R1BQKBNR w KQkq - 0 5",
+    "rnbqkb1r/ppp2ppp/4pn2/3p4/2PP4/2N5/PP2PPPP/R1BQKBNR w KQkq - 0 4",
+    // middlegames
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 10",
+    "4rrk1/pp1n3p/3q2pQ/2p1pb2/2PP4/2P3N1/P2B2PP/4RRK1 b - - 7 19",
+    "rq3rk1/ppp2ppp/1bnpb3/3N2B1/3NP3/7P/PPPQ1PP1/2KR3R w - - 7 14",
+    "r1bq1r1k/1pp1n1pp/1p1p4/4p2Q/4Pp2/1BNP4/PPP2PPP/3R1RK1 w - - 2 14",
+    "r3r1k1/2p2ppp/p1p1bn2/8/1q2P3/2NPQN2/PPP3PP/R4RK1 b - - 2 15",
+    "r1bbk1nr/pp3p1p/2n5/1N4p1/2Np1B2/8/PPP2PPP/2KR1B1R w kq - 0 13",
+    "r1bq1rk1/ppp1nppp/4n3/3p3Q/3P4/1BP1B3/PP1N2PP/R4RK1 w - - 1 16",
+    "4r1k1/r1q2ppp/ppp2n2/4P3/5Rb1/1N1BQ3/PPP3PP/R5K1 w - - 1 17",
+    "2rqkb1r/ppp2p2/2npb1p1/1N1Nn2p/2P1PP2/8/PP2B1PP/R1BQK2R b KQ - 0 11",
+    "r1bq1r1k/b1p1npp1/p2p3p/1p6/3PP3/1B2NN2/PP3PPP/R2Q1RK1 w - - 1 16",
+    "3r1rk1/p5pp/bpp1pp2/8/q1PP1P2/b3P3/P2NQRPP/1R2B1K1 b - - 6 22",
+    "r1q2rk1/2p1bppp/2Pp4/p6b/Q1PNp3/4B3/PP1R1PPP/2K4R w - - 2 18",
+    "4k2r/1pb2ppp/1p2p3/1R1p4/3P4/2r1PN2/P4PPP/1R4K1 b - - 3 22",
+    "3q2k1/pb3p1p/4pbp1/2r5/PpN2N2/1P2P2P/5PP1/Q2R2K1 b - - 4 26",
+    "r3k2r/3nnpbp/q2pp1p1/p7/Pp1PPPP1/4BNN1/1P5P/R2Q1RK1 w kq - 0 16",
+    "5rk1/q6p/2p3bR/1pPp1rP1/1P1Pp3/P3B1Q1/1K3P2/R7 w - - 93 90",
+    "4rrk1/1p1nq3/p7/2p1P1pp/3P2bp/3Q1Bn1/PPPB4/1K2R1NR w - - 40 21",
+    "r2r1n2/pp2bk2/2p1p2p/3q4/3PN1QP/2P3R1/P4PP1/5RK1 w - - 0 1",
+    "6k1/3b3r/1p1p4/p1n2p2/1PPNpP1q/P3Q1p1/1R1RB1P1/5K2 b - - 0 1",
+    // endgames
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 11",
+    "6k1/6p1/6Pp/ppp5/3pn2P/1P3K2/1PP2P2/3N4 b - - 0 1",
+    "3b4/5kp1/1p1p1p1p/pP1PpP1P/P1P1P3/3KN3/8/8 w - - 0 1",
+    "8/6pk/1p6/8/PP3p1p/5P2/4KP1q/3Q4 w - - 0 1",
+    "7k/3p2pp/4q3/8/4Q3/5Kp1/P6b/8 w - - 0 1",
+    "8/2p5/8/2kPKp1p/2p4P/2P5/3P4/8 w - - 0 1",
+    "8/1p3pp1/7p/5P1P/2k3P1/8/2K2P2/8 w - - 0 1",
+    "8/pp2r1k1/2p1p3/3pP2p/1P1P1P1P/P5KR/8/8 w - - 0 1",
+    "8/3p4/p1bk3p/Pp6/1Kp1PpPp/2P2P1P/2P5/5B2 b - - 0 1",
+    "5k2/7R/4P2p/5K2/p1r2P1p/8/8/8 b - - 0 1",
+    "6k1/6p1/P6p/r1N5/5p2/7P/1b3PP1/4R1K1 w - - 0 1",
+    "6k1/4pp1p/3p2p1/P1pPb3/R7/1r2P1PP/3B1P2/6K1 w - - 0 1",
+    "8/3p3B/5p2/5P2/p7/PP5b/k7/6K1 w - - 0 1",
+    "8/8/8/8/5kp1/P7/8/1K1N4 w - - 0 1",
+    "8/8/8/5N2/8/p7/8/2NK3k w - - 0 1",
+    "8/3k4/8/8/8/4B3/4KB2/2B5 w - - 0 1",
+    // promotions
+    "8/2p4P/8/kr6/6R1/8/8/1K6 w - - 0 1",
+    "8/8/3P3k/8/1p6/8/1P6/1K3n2 b - - 0 1",
+    "8/R7/2q5/8/6k1/8/1P5p/K6R w - - 0 124",
+    "3k4/1P6/8/8/8/8/6p1/3K4 w - - 0 1",
+    "8/P7/8/8/8/8/k6p/4K3 w - - 0 1",
+    // checks
+    "rnbqk1nr/pppp1ppp/8/4p3/1b1PP3/8/PPP2PPP/RNBQKBNR w KQkq - 1 3",
+    "4k3/3q1r2/1N2r1b1/3ppN2/2nPP3/1B1R2n1/2R1Q3/3K4 w - - 5 1",
+    "1r3k2/4q3/2Pp3b/3Bp3/2Q2p2/1p1P2P1/1P2KP2/3N4 w - - 0 1",
+    "7k/7P/6K1/8/3B4/8/8/8 b - - 0 1",
+];
+
+/// The result of a bench.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct BenchResult {
+    /// The total number of nodes searched.
+    pub nodes: u128,
+    /// The total time spent searching.
+    pub time: Duration,
+}
+
+impl BenchResult {
+    /// Returns the number of nodes searched per second.
+    pub fn nps(&self) -> u128 {
+        self.nodes * 1_000_000 / self.time.as_micros().max(1)
+    }
+}
+
+/// Prints the total time, nodes and nodes per second on separate lines.
+impl Display for BenchResult {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        writeln!(f, "Total time (ms) : {}", self.time.as_millis())?;
+        writeln!(f, "Nodes searched  : {}", self.nodes)?;
+        write!(f, "Nodes/second    : {}", self.nps())
+    }
+}
+
+/// Searches all bench positions to the given depth, and calls the given function with the index of each position and
+/// the number of nodes searched in it.
+pub fn run(depth: u64, on_position: impl FnMut(usize, u128)) -> BenchResult {
+    run_until_stopped(depth, &AtomicBool::new(false), &Mutex::new(None), on_position).unwrap()
+}
+
+/// Searches all bench positions like [`run`], but aborts once the given stop flag is set and returns `None` instead.
+///
+/// The cancel handle of the engine searching the current position is stored in `running_engine`, so that the search
+/// can be cancelled from another thread as well, which has to set the stop flag before cancelling. Positions whose
+/// search was cancelled are not reported.
+pub fn run_until_stopped(depth: u64, stop: &AtomicBool, running_engine: &Mutex<Option<CancelHandle>>, mut on_position: impl FnMut(usize, u128)) -> Option<BenchResult> {
+    let mut nodes = 0;
+    let mut time = Duration::ZERO;
+    for (index, fen) in BENCH_POSITIONS.iter().enumerate() {
+        if stop.load(Ordering::Relaxed) {
+            return None;
+        }
+        let mut engine = Engine::new(EngineConfig::default());
+        engine.set_position(Board::from_fen(fen).unwrap(), Vec::new());
+        *running_engine.lock().unwrap() = Some(engine.cancel_handle());
+
+        // a stop that arrived before the cancel handle was stored did not reach this engine
+        if stop.load(Ordering::Relaxed) {
+            return None;
+        }
+
+        let start_time = Instant::now();
+        let result = engine.search(SearchLimits { depth: Some(depth), ..Default::default() });
+        time += start_time.elapsed();
+
+        // a cancelled search stops early, so its node count must not be reported
+        if stop.load(Ordering::Relaxed) {
+            return None;
+        }
+        nodes += result.nodes;
+        on_position(index, result.nodes);
+    }
+    Some(BenchResult { nodes, time })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Mutex;
+    use std::time::Duration;
+    use crate::bench;
+    use crate::bench::{BENCH_DEPTH, BENCH_POSITIONS, BenchResult};
+    use crate::board::Board;
+
+    #[test]
+    fn bench_positions_are_legal() {
+        for fen in BENCH_POSITIONS {
+            let board = Board::from_fen(fen).unwrap();
+            assert!(!board.position.is_in_check(board.position.color_to_move.other()), "{fen}");
+        }
+    }
+
+    #[test]
+    fn bench_is_deterministic() {
+        let mut position_nodes: Vec<u128> = Vec::new();
+        let result = bench::run(2, |index, nodes| {
+            assert_eq!(position_nodes.len(), index);
+            position_nodes.push(nodes);
+        });
+        assert_eq!(BENCH_POSITIONS.len(), position_nodes.len());
+        assert_eq!(result.nodes, position_nodes.iter().sum());
+
+        assert_eq!(result.nodes, bench::run(2, |_, _| {}).nodes);
+    }
+
+    #[test]
+    fn bench_node_count_is_unchanged() {
+        // update this number only when the search or evaluation is changed on purpose
+        assert_eq!(793293, bench::run(BENCH_DEPTH, |_, _| {}).nodes);
+    }
+
+    #[test]
+    fn bench_stops_when_stop_flag_is_set() {
+        let stop = AtomicBool::new(true);
+        let mut num_positions = 0;
+        assert_eq!(None, bench::run_until_stopped(2, &stop, &Mutex::new(None), |_, _| num_positions += 1));
+        assert_eq!(0, num_positions);
+    }
+
+    #[test]
+    fn bench_result_formats_correctly() {
+        let result = BenchResult { nodes: 1500000, time: Duration::from_millis(1500) };
+        assert_eq!(1000000, result.nps());
+        assert_eq!("Total time (ms) : 1500\nNodes searched  : 1500000\nNodes/second    : 1000000", result.to_string());
+    }
+}
@@ -0,0 +1,111 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use ladybug::book::builder::BookBuilder;
+use ladybug::pgn;
+
+/// The options of the book-build binary.
+struct Options {
+    /// The PGN files containing the games.
+    pgn_files: Vec<PathBuf>,
+    /// The file the book is written to.
+    output_file: PathBuf,
+    /// The number of plies of each game that are added to the book.
+    max_plies: usize,
+    /// The minimum number of games a move must have been played in.
+    min_games: u32,
+    /// The minimum score of a move in percent.
+    min_score: f64,
+}
+
+/// Builds a Polyglot opening book from PGN games.
+///
+/// Usage: `book-build <pgn-file>... [--output <file>] [--plies <n>] [--min-games <n>] [--min-score <percent>]`
+///
+/// The first plies of every game with a known result are added to the book. Moves that were played in fewer games
+/// than the minimum or scored less than the minimum score for the side that played them are left out.
+fn main() {
+    let options = match parse_args(std::env::args().skip(1).collect()) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{message}");
+            eprintln!("Usage: book-build <pgn-file>... [--output <file>] [--plies <n>] [--min-games <n>] [--min-score <percent>]");
+            process::exit(1);
+        }
+    };
+
+    let mut builder = BookBuilder::new(options.max_plies, options.min_games, options.min_score / 100.0);
+    let mut num_games = 0;
+    let mut num_skipped = 0;
+    for pgn_file in options.pgn_files.iter() {
+        let reader = match pgn::open(pgn_file) {
+            Ok(reader) => reader,
+            Err(error) => {
+                eprintln!("Could not read {}: {error}", pgn_file.display());
+                process::exit(1);
+            }
+        };
+        println!("Reading games from {}...", pgn_file.display());
+        for game in reader {
+            match game {
+                Ok(game) if builder.add_game(&game) => num_games += 1,
+                Ok(_) => num_skipped += 1,
+                Err(error) => {
+                    eprintln!("Skipping game: {error}");
+                    num_skipped += 1;
+                }
+            }
+        }
+    }
+    println!("Added {num_games} games, skipped {num_skipped} games.");
+
+    let book = builder.build();
+    if let Err(error) = fs::write(&options.output_file, book.to_bytes()) {
+        eprintln!("Could not write {}: {error}", options.output_file.display());
+        process::exit(1);
+    }
+    println!("Book with {} entries written to {}.", book.len(), options.output_file.display());
+}
+
+/// Parses the command line arguments.
+fn parse_args(args: Vec<String>) -> Result<Options, String> {
+    let mut pgn_files: Vec<PathBuf> = Vec::new();
+    let mut output_file = PathBuf::from("book.bin");
+    let mut max_plies: usize = 20;
+    let mut min_games: u32 = 3;
+    let mut min_score: f64 = 0.0;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--output" => {
+                output_file = PathBuf::from(args.next().ok_or("Missing value for --output")?);
+            }
+            "--plies" => {
+                max_plies = args.next().and_then(|value| value.parse().ok()).ok_or("Invalid value for --plies")?;
+            }
+            "--min-games" => {
+                min_games = args.next().and_then(|value| value.parse().ok()).ok_or("Invalid value for --min-games")?;
+            }
+            "--min-score" => {
+                min_score = args.next().and_then(|value| value.parse().ok()).filter(|value| (0.0..=100.0).contains(value)).ok_or("Invalid value for --min-score")?;
+            }
+            other if !other.starts_with("--") => {
+                pgn_files.push(PathBuf::from(other));
+            }
+            other => return Err(format!("Unknown argument: {other}")),
+        }
+    }
+
+    if pgn_files.is_empty() {
+        return Err(String::from("Missing PGN file"));
+    }
+
+    Ok(Options {
+        pgn_files,
+        output_file,
+        max_plies,
+        min_games,
+        min_score,
+    })
+}
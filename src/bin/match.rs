@@ -0,0 +1,206 @@
+use std::fs;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+use std::process;
+use std::thread;
+use std::time::Duration;
+use ladybug::engine::EngineConfig;
+use ladybug::evaluation::parameters::Parameters;
+use ladybug::selfplay;
+use ladybug::selfplay::{Adjudication, MatchSettings, Opening, Player, TimeControl};
+use ladybug::selfplay::stats::{MatchStats, Sprt, SprtVerdict};
+
+/// The usage of the match binary.
+const USAGE: &str = "Usage: match [--engine1 <spec>] [--engine2 <spec>] [--games <n>] [--openings <file>] [--tc <base>+<inc>] [--concurrency <n>] \
+    [--pgn <file>] [--elo0 <elo>] [--elo1 <elo>] [--alpha <p>] [--beta <p>] [--draw-movenumber <n>] [--draw-movecount <n>] \
+    [--draw-score <cp>] [--resign-movecount <n>] [--resign-score <cp>]";
+
+/// The options of the match binary.
+struct Options {
+    /// The settings of the match.
+    settings: MatchSettings,
+    /// The file containing the openings, if any.
+    openings_file: Option<PathBuf>,
+    /// The file the games are written to, if any.
+    pgn_file: Option<PathBuf>,
+    /// The parameters of the SPRT.
+    sprt: Sprt,
+}
+
+/// Plays a match between two engine configurations to measure the Elo difference between them.
+///
+/// Usage: `match [--engine1 <spec>] [--engine2 <spec>] [--games <n>] [--openings <file>] [--tc <base>+<inc>] [--concurrency <n>] [--pgn <file>] ...`
+///
+/// An engine is specified as a comma separated list of `name=<name>` and `params=<file>`, where the
+/// parameter file has the format written by the tune binary, e.g. `--engine2 name=tuned,params=tuned_parameters.rs`.
+/// The openings are read from an EPD or PGN file, and each opening is played twice with swapped colors.
+/// The time control is given in seconds, e.g. `10+0.1`.
+///
+/// After every game, the score of the first engine, its Elo difference with a 95% confidence interval and the
+/// state of the SPRT are printed. The match ends as soon as the SPRT accepts one of its hypotheses.
+fn main() {
+    let options = match parse_args(std::env::args().skip(1).collect()) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{message}");
+            eprintln!("{USAGE}");
+            process::exit(1);
+        }
+    };
+
+    let openings: Vec<Opening> = match &options.openings_file {
+        Some(openings_file) => match selfplay::load_openings(openings_file) {
+            Ok(openings) => openings,
+            Err(error) => {
+                eprintln!("Could not read openings from {}: {error}", openings_file.display());
+                process::exit(1);
+            }
+        },
+        None => Vec::new(),
+    };
+
+    let mut pgn_writer = match &options.pgn_file {
+        Some(pgn_file) => match File::create(pgn_file) {
+            Ok(file) => Some(BufWriter::new(file)),
+            Err(error) => {
+                eprintln!("Could not create {}: {error}", pgn_file.display());
+                process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    let [first_name, second_name] = options.settings.players.clone().map(|player| player.name);
+    println!("Playing {} games of {first_name} vs {second_name}...", options.settings.num_games);
+
+    let mut stats = MatchStats::default();
+    let (lower_bound, upper_bound) = options.sprt.bounds();
+    selfplay::run_match(&options.settings, &openings, |game| {
+        let [white_name, black_name] = [game.white, 1 - game.white].map(|player| options.settings.players[player].name.as_str());
+        println!("Game {} ({white_name} vs {black_name}): {} {{{}}}", game.index + 1, game.termination.to_pgn(), game.termination);
+
+        if let Some(pgn_writer) = pgn_writer.as_mut() {
+            if let Err(error) = game.pgn.write_pgn(pgn_writer) {
+                eprintln!("Could not write game {}: {error}", game.index + 1);
+            }
+        }
+
+        stats.add(game.get_outcome());
+        let verdict = options.sprt.verdict(&stats);
+        println!("Score of {first_name} vs {second_name}: {stats}");
+        println!("SPRT ({} to {} Elo): LLR {:.2} ({lower_bound:.2}, {upper_bound:.2}), {verdict}", options.sprt.elo0, options.sprt.elo1, options.sprt.llr(&stats));
+        verdict == SprtVerdict::Continue
+    });
+
+    println!("Finished {} games: {stats}", stats.num_games());
+}
+
+/// Parses the specification of an engine, e.g. `name=tuned,params=tuned_parameters.rs`.
+fn parse_player(spec: &str) -> Result<Player, String> {
+    let mut player = Player { name: String::from(spec), config: EngineConfig::default() };
+    for option in spec.split(',') {
+        match option.split_once('=') {
+            Some(("name", name)) => player.name = String::from(name),
+            Some(("params", file)) => {
+                let source = fs::read_to_string(file).map_err(|error| format!("Could not read {file}: {error}"))?;
+                player.config.parameters = Some(Parameters::parse(&source).map_err(|error| format!("Invalid parameters in {file}: {error}"))?);
+            }
+            _ => return Err(format!("Invalid engine option: {option}")),
+        }
+    }
+    Ok(player)
+}
+
+/// Parses a time control in seconds, e.g. `10+0.1`.
+fn parse_time_control(value: &str) -> Option<TimeControl> {
+    let (base, increment) = value.split_once('+').unwrap_or((value, "0"));
+    Some(TimeControl {
+        base: Duration::try_from_secs_f64(base.parse().ok()?).ok()?,
+        increment: Duration::try_from_secs_f64(increment.parse().ok()?).ok()?,
+    })
+}
+
+/// Parses the command line arguments.
+fn parse_args(args: Vec<String>) -> Result<Options, String> {
+    let mut players = [String::from("name=Engine 1"), String::from("name=Engine 2")];
+    let mut num_games: usize = 100;
+    let mut openings_file: Option<PathBuf> = None;
+    let mut pgn_file: Option<PathBuf> = None;
+    let mut time_control = TimeControl { base: Duration::from_secs(10), increment: Duration::from_millis(100) };
+    let mut concurrency = thread::available_parallelism().map_or(1, |num_threads| num_threads.get());
+    let mut adjudication = Adjudication::default();
+    let mut sprt = Sprt::default();
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--engine1" => {
+                players[0] = args.next().ok_or("Missing value for --engine1")?;
+            }
+            "--engine2" => {
+                players[1] = args.next().ok_or("Missing value for --engine2")?;
+            }
+            "--games" => {
+                num_games = args.next().and_then(|value| value.parse().ok()).filter(|num_games| *num_games > 0).ok_or("Invalid value for --games")?;
+            }
+            "--openings" => {
+                openings_file = Some(PathBuf::from(args.next().ok_or("Missing value for --openings")?));
+            }
+            "--pgn" => {
+                pgn_file = Some(PathBuf::from(args.next().ok_or("Missing value for --pgn")?));
+            }
+            "--tc" => {
+                time_control = args.next().and_then(|value| parse_time_control(value.as_str())).ok_or("Invalid value for --tc")?;
+            }
+            "--concurrency" => {
+                concurrency = args.next().and_then(|value| value.parse().ok()).filter(|concurrency| *concurrency > 0).ok_or("Invalid value for --concurrency")?;
+            }
+            "--elo0" => {
+                sprt.elo0 = args.next().and_then(|value| value.parse().ok()).ok_or("Invalid value for --elo0")?;
+            }
+            "--elo1" => {
+                sprt.elo1 = args.next().and_then(|value| value.parse().ok()).ok_or("Invalid value for --elo1")?;
+            }
+            "--alpha" => {
+                sprt.alpha = args.next().and_then(|value| value.parse().ok()).filter(|alpha| *alpha > 0.0 && *alpha < 1.0).ok_or("Invalid value for --alpha")?;
+            }
+            "--beta" => {
+                sprt.beta = args.next().and_then(|value| value.parse().ok()).filter(|beta| *beta > 0.0 && *beta < 1.0).ok_or("Invalid value for --beta")?;
+            }
+            "--draw-movenumber" => {
+                adjudication.draw_move_number = args.next().and_then(|value| value.parse().ok()).ok_or("Invalid value for --draw-movenumber")?;
+            }
+            "--draw-movecount" => {
+                adjudication.draw_move_count = args.next().and_then(|value| value.parse().ok()).ok_or("Invalid value for --draw-movecount")?;
+            }
+            "--draw-score" => {
+                adjudication.draw_score = args.next().and_then(|value| value.parse().ok()).ok_or("Invalid value for --draw-score")?;
+            }
+            "--resign-movecount" => {
+                adjudication.resign_move_count = args.next().and_then(|value| value.parse().ok()).ok_or("Invalid value for --resign-movecount")?;
+            }
+            "--resign-score" => {
+                adjudication.resign_score = args.next().and_then(|value| value.parse().ok()).ok_or("Invalid value for --resign-score")?;
+            }
+            other => return Err(format!("Unknown argument: {other}")),
+        }
+    }
+
+    if sprt.elo0 >= sprt.elo1 {
+        return Err(String::from("--elo0 has to be lower than --elo1"));
+    }
+
+    Ok(Options {
+        settings: MatchSettings {
+            players: [parse_player(players[0].as_str())?, parse_player(players[1].as_str())?],
+            num_games,
+            time_control,
+            adjudication,
+            concurrency,
+        },
+        openings_file,
+        pgn_file,
+        sprt,
+    })
+}
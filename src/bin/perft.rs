@@ -0,0 +1,183 @@
+use std::io;
+use std::io::{BufRead, Write};
+use std::process;
+use std::sync::atomic::AtomicBool;
+use std::thread;
+use std::time::Instant;
+use ladybug::board::Board;
+use ladybug::board::fen::START_FEN;
+use ladybug::board::position::Position;
+use ladybug::move_gen::ply::Ply;
+use ladybug::search::perft;
+
+/// The options of the perft binary.
+struct Options {
+    /// The FEN of the position to start from.
+    fen: String,
+    /// The depth of the perft.
+    depth: u64,
+    /// Whether to print the detailed counters instead of a divide.
+    detailed: bool,
+    /// The total size of the perft hash tables in megabytes, or zero to disable them.
+    hash_mb: usize,
+    /// The number of threads.
+    threads: usize,
+    /// The number of plies after which the tree is split into work items for the threads.
+    split_depth: u64,
+    /// Whether to drill into the tree interactively.
+    interactive: bool,
+}
+
+/// Runs perft tests to debug the move generation.
+///
+/// Usage: `perft [--fen <fen>] [--depth <n>] [--hash <mb>] [--threads <n>] [--split-depth <n>] [--detailed | --interactive]`
+///
+/// By default, the number of leaf nodes below each legal move is printed (divide), followed by the total.
+/// With `--detailed`, the captures, en passant captures, castles, promotions, checks, discovered checks, double checks
+/// and checkmates are counted as well. With `--interactive`, moves can be entered to descend into their subtree,
+/// which makes it easy to find the position in which the move generation differs from another engine.
+///
+/// The tree is split into work items after `--split-depth` plies (2 by default), which are distributed across the threads.
+/// The detailed counters are always computed on a single thread.
+fn main() {
+    let options = match parse_args(std::env::args().skip(1).collect()) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{message}");
+            eprintln!("Usage: perft [--fen <fen>] [--depth <n>] [--hash <mb>] [--threads <n>] [--split-depth <n>] [--detailed | --interactive]");
+            process::exit(1);
+        }
+    };
+
+    let mut position = match Board::from_fen(options.fen.as_str()) {
+        Ok(board) => board.position,
+        Err(error) => {
+            eprintln!("Invalid FEN: {error}");
+            process::exit(1);
+        }
+    };
+
+    if options.detailed {
+        let time = Instant::now();
+        let stats = perft::count_detailed(&mut position, options.depth);
+        println!("{stats}");
+        println!("Searched {} nodes in {:?}", stats.nodes, time.elapsed());
+        return;
+    }
+
+    print_divide(position, options.depth, &options);
+    if options.interactive {
+        run_interactive(position, options.depth, &options);
+    }
+}
+
+/// Prints the number of leaf nodes below each legal move, followed by the total.
+fn print_divide(position: Position, depth: u64, options: &Options) {
+    let time = Instant::now();
+    let divide = perft::divide_parallel(position, depth, options.split_depth, options.threads, options.hash_mb, &AtomicBool::new(false)).unwrap();
+    for (ply, node_count) in divide.iter() {
+        println!("{}: {node_count}", ply.to_uci(position, false));
+    }
+    let node_count: u64 = divide.iter().map(|(_, node_count)| node_count).sum();
+    println!("Searched {node_count} nodes in {:?}", time.elapsed());
+}
+
+/// Reads moves from the standard input and prints the divide of the position after each of them.
+/// `undo` goes back to the previous position, and `quit` exits.
+fn run_interactive(mut position: Position, mut depth: u64, options: &Options) {
+    let mut history: Vec<Position> = Vec::new();
+    let stdin = io::stdin();
+    loop {
+        print!("perft {depth}> ");
+        let _ = io::stdout().flush();
+
+        let mut line = String::new();
+        match stdin.lock().read_line(&mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+
+        match line.trim() {
+            "" => continue,
+            "quit" => break,
+            "undo" => match history.pop() {
+                Some(previous_position) => {
+                    position = previous_position;
+                    depth += 1;
+                }
+                None => {
+                    println!("Already at the root position");
+                    continue;
+                }
+            },
+            move_str => {
+                if depth <= 1 {
+                    println!("Can not descend below depth 1");
+                    continue;
+                }
+                match Ply::from_string(move_str, position) {
+                    Ok(ply) => {
+                        history.push(position);
+                        position = position.make_move(ply);
+                        depth -= 1;
+                    }
+                    Err(error) => {
+                        println!("{error}");
+                        continue;
+                    }
+                }
+            }
+        }
+
+        print_divide(position, depth, options);
+    }
+}
+
+/// Parses the command line arguments.
+fn parse_args(args: Vec<String>) -> Result<Options, String> {
+    let mut fen = String::from(START_FEN);
+    let mut depth: u64 = 5;
+    let mut detailed = false;
+    let mut hash_mb: usize = 64;
+    let mut interactive = false;
+    let mut threads = thread::available_parallelism().map_or(1, |num_threads| num_threads.get());
+    let mut split_depth = perft::PERFT_SPLIT_DEPTH;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--fen" => {
+                fen = args.next().ok_or("Missing value for --fen")?;
+            }
+            "--depth" => {
+                depth = args.next().and_then(|value| value.parse().ok()).filter(|depth| *depth > 0).ok_or("Invalid value for --depth")?;
+            }
+            "--hash" => {
+                hash_mb = args.next().and_then(|value| value.parse().ok()).ok_or("Invalid value for --hash")?;
+            }
+            "--threads" => {
+                threads = args.next().and_then(|value| value.parse().ok()).filter(|threads| *threads > 0).ok_or("Invalid value for --threads")?;
+            }
+            "--split-depth" => {
+                split_depth = args.next().and_then(|value| value.parse().ok()).filter(|split_depth| *split_depth > 0).ok_or("Invalid value for --split-depth")?;
+            }
+            "--detailed" => detailed = true,
+            "--interactive" => interactive = true,
+            other => return Err(format!("Unknown argument: {other}")),
+        }
+    }
+
+    if detailed && interactive {
+        return Err(String::from("--detailed and --interactive can not be combined"));
+    }
+
+    Ok(Options {
+        fen,
+        depth,
+        detailed,
+        hash_mb,
+        threads,
+        split_depth,
+        interactive,
+    })
+}
@@ -0,0 +1,96 @@
+use std::path::PathBuf;
+use std::process;
+use ladybug::suite;
+use ladybug::suite::{SuiteLimit, SuiteRunner};
+
+/// The options of the suite binary.
+struct Options {
+    /// The EPD file containing the test positions.
+    suite_file: PathBuf,
+    /// The search limit for each position.
+    limit: SuiteLimit,
+}
+
+/// Runs an EPD test suite such as WAC or STS.
+///
+/// Usage: `suite <epd-file> [--depth <n> | --movetime <ms> | --nodes <n>]`
+///
+/// Every position is searched with the given limit (one second per position by default), and the result is printed as soon as it is known.
+/// Finally, the number of solved positions is printed as the total score.
+fn main() {
+    let options = match parse_args(std::env::args().skip(1).collect()) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{message}");
+            eprintln!("Usage: suite <epd-file> [--depth <n> | --movetime <ms> | --nodes <n>]");
+            process::exit(1);
+        }
+    };
+
+    let positions = match suite::load_suite(&options.suite_file) {
+        Ok(positions) => positions,
+        Err(error) => {
+            eprintln!("Could not read {}: {error}", options.suite_file.display());
+            process::exit(1);
+        }
+    };
+    println!("Running {} positions from {}...", positions.len(), options.suite_file.display());
+
+    let runner = SuiteRunner::new();
+    let mut num_passed = 0;
+    for (index, epd) in positions.iter().enumerate() {
+        let result = epd.as_ref()
+            .map_err(String::clone)
+            .and_then(|epd| runner.run_position(epd, index + 1, options.limit));
+        match result {
+            Ok(result) => {
+                let mut expected = String::from("");
+                if !result.best_moves.is_empty() {
+                    expected += format!(" bm {}", result.best_moves.join(" ")).as_str();
+                }
+                if !result.avoid_moves.is_empty() {
+                    expected += format!(" am {}", result.avoid_moves.join(" ")).as_str();
+                }
+                let verdict = if result.passed { "pass" } else { "FAIL" };
+                println!("{verdict} {}: played {},{expected}", result.id, result.played_move);
+                if result.passed {
+                    num_passed += 1;
+                }
+            }
+            Err(error) => println!("FAIL #{}: {error}", index + 1),
+        }
+    }
+
+    let percentage = if positions.is_empty() { 0.0 } else { 100.0 * num_passed as f64 / positions.len() as f64 };
+    println!("Score: {num_passed}/{} ({percentage:.1}%)", positions.len());
+}
+
+/// Parses the command line arguments.
+fn parse_args(args: Vec<String>) -> Result<Options, String> {
+    let mut suite_file: Option<PathBuf> = None;
+    let mut limit = SuiteLimit::MoveTime(1000);
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--depth" => {
+                limit = SuiteLimit::Depth(args.next().and_then(|value| value.parse().ok()).ok_or("Invalid value for --depth")?);
+            }
+            "--movetime" => {
+                limit = SuiteLimit::MoveTime(args.next().and_then(|value| value.parse().ok()).ok_or("Invalid value for --movetime")?);
+            }
+            "--nodes" => {
+                limit = SuiteLimit::Nodes(args.next().and_then(|value| value.parse().ok()).ok_or("Invalid value for --nodes")?);
+            }
+            other if suite_file.is_none() && !other.starts_with("--") => {
+                suite_file = Some(PathBuf::from(other));
+            }
+            other => return Err(format!("Unknown argument: {other}")),
+        }
+    }
+
+    Ok(Options {
+        suite_file: suite_file.ok_or("Missing suite file")?,
+        limit,
+    })
+}
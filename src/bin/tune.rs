@@ -0,0 +1,101 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use ladybug::evaluation::parameters::Parameters;
+use ladybug::evaluation::tuner;
+use ladybug::evaluation::tuner::Tuner;
+
+/// The options of the tune binary.
+struct Options {
+    /// The file containing the labeled training positions.
+    data_file: PathBuf,
+    /// The file the tuned parameters are written to.
+    output_file: PathBuf,
+    /// The maximum number of coordinate descent passes.
+    iterations: u32,
+    /// The amount by which a parameter is changed in each step.
+    step: i32,
+}
+
+/// Tunes the evaluation parameters with Texel's tuning method.
+///
+/// Usage: `tune <data-file> [--output <file>] [--iterations <n>] [--step <n>]`
+///
+/// After every pass over all parameters, the tuned parameters are written to the output file as Rust source (even if
+/// the tuning is aborted early). The file contains the piece values and piece-square tables of the `pst` module as well
+/// as the scores of the `pawn_structure`, `mobility` and `king_safety` modules, grouped by the module they belong to.
+/// It can also be loaded directly by the match binary with `params=<file>`.
+fn main() {
+    let options = match parse_args(std::env::args().skip(1).collect()) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{message}");
+            eprintln!("Usage: tune <data-file> [--output <file>] [--iterations <n>] [--step <n>]");
+            process::exit(1);
+        }
+    };
+
+    println!("Loading and resolving positions from {}...", options.data_file.display());
+    let entries = match tuner::load_entries(&options.data_file) {
+        Ok(entries) => entries,
+        Err(error) => {
+            eprintln!("Could not read {}: {error}", options.data_file.display());
+            process::exit(1);
+        }
+    };
+    println!("Loaded {} positions.", entries.len());
+
+    let mut tuner = Tuner::new(entries, Parameters::default(), options.step);
+    println!("K = {:.4}, initial error = {:.8}", tuner.k(), tuner.error());
+
+    for iteration in 1..=options.iterations {
+        let improved = tuner.run_iteration();
+        println!("Iteration {iteration}: error = {:.8}", tuner.error());
+
+        if let Err(error) = fs::write(&options.output_file, format!("{}", tuner.parameters())) {
+            eprintln!("Could not write {}: {error}", options.output_file.display());
+            process::exit(1);
+        }
+
+        if !improved {
+            println!("No further improvement possible.");
+            break;
+        }
+    }
+
+    println!("Tuned parameters written to {}.", options.output_file.display());
+}
+
+/// Parses the command line arguments.
+fn parse_args(args: Vec<String>) -> Result<Options, String> {
+    let mut data_file: Option<PathBuf> = None;
+    let mut output_file = PathBuf::from("tuned_parameters.rs");
+    let mut iterations: u32 = 100;
+    let mut step: i32 = 1;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--output" => {
+                output_file = PathBuf::from(args.next().ok_or("Missing value for --output")?);
+            }
+            "--iterations" => {
+                iterations = args.next().and_then(|value| value.parse().ok()).ok_or("Invalid value for --iterations")?;
+            }
+            "--step" => {
+                step = args.next().and_then(|value| value.parse().ok()).ok_or("Invalid value for --step")?;
+            }
+            other if data_file.is_none() && !other.starts_with("--") => {
+                data_file = Some(PathBuf::from(other));
+            }
+            other => return Err(format!("Unknown argument: {other}")),
+        }
+    }
+
+    Ok(Options {
+        data_file: data_file.ok_or("Missing data file")?,
+        output_file,
+        iterations,
+        step,
+    })
+}
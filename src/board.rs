@@ -2,8 +2,8 @@
 //! It contains the important position and bitboard submodules, as well other useful ones such as color, file, rank, and square.
 //! This module is the foundation on which the rest of the engine builds upon.
 
-use arrayvec::ArrayVec;
 use position::Position;
+use crate::board::fen::FenError;
 use crate::board::color::Color;
 use crate::board::piece::Piece;
 use crate::move_gen::ply::Ply;
@@ -17,6 +17,17 @@ pub mod castling_rights;
 pub mod piece;
 pub mod position;
 pub mod fen;
+pub mod epd;
+
+/// The state that is lost when a move is made in place on the board, which is needed to unmake the move again.
+/// Returned by `Board::make_move_in_place`, and kept on a stack by the caller until the move is unmade.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Undo {
+    /// The state needed to unmake the move on the position.
+    position_undo: position::Undo,
+    /// The halfmove clock before the move.
+    halfmove_clock: u32,
+}
 
 /// The board struct holds the current position of the board.
 /// It also keeps track of the full move counter, the halfmove clock (50 move rule),
@@ -47,39 +58,64 @@ impl Board {
     /// Constructs a new board from a FEN string.
     /// If the FEN could be parsed successfully, the result will contain the newly constructed board.
     /// Otherwise, it will contain an error.
-    pub fn from_fen(fen: &str) -> Result<Board, String> {
+    pub fn from_fen(fen: &str) -> Result<Board, FenError> {
         Self::parse_fen(fen)
     }
 
     /// Returns a new board that reflects the board state where the given move (ply) has been played.
     pub fn make_move(&self, ply: Ply) -> Board {
         let mut board = *self;
-        
-        board.position = board.position.make_move(ply);
-        
+        board.make_move_in_place(ply);
+        board
+    }
+
+    /// Plays the given move (ply) on this board instead of copying it, which is faster when searching a lot of positions.
+    /// Returns the state needed to take the move back with `unmake_move`.
+    pub fn make_move_in_place(&mut self, ply: Ply) -> Undo {
+        let undo = Undo {
+            position_undo: self.position.make_move_in_place(ply),
+            halfmove_clock: self.halfmove_clock,
+        };
+
         // update the halfmove clock
         if ply.piece != Piece::Pawn && ply.captured_piece.is_none() {
             // if the move is neither a pawn move nor a capture, increment the halfmove clock
-            board.halfmove_clock += 1;
+            self.halfmove_clock += 1;
         } else {
             // otherwise, reset it
-            board.halfmove_clock = 0;
+            self.halfmove_clock = 0;
         }
-        
-        // update the fullmove counter
-        if self.position.color_to_move == Color::Black {
-            board.fullmove_counter += 1;
+
+        // update the fullmove counter after Black's move
+        if self.position.color_to_move == Color::White {
+            self.fullmove_counter += 1;
         }
-        
-        board
+
+        undo
+    }
+
+    /// Takes back the move that was played with `make_move_in_place` and returned the given undo state.
+    /// Moves have to be unmade in the reverse order they were made in.
+    pub fn unmake_move(&mut self, undo: Undo) {
+        // the fullmove counter was incremented if Black made the move
+        if self.position.color_to_move == Color::White {
+            self.fullmove_counter -= 1;
+        }
+        self.halfmove_clock = undo.halfmove_clock;
+        self.position.unmake_move(undo.position_undo);
     }
     
-    /// Checks whether the position is a draw by either threefold repetition or the 50 move rule, based on the given board history.
-    pub fn is_draw(&self, board_history: &ArrayVec<u64, 1000>) -> bool {
+    /// Checks whether the position is a draw by either threefold repetition, the 50 move rule, or insufficient material, based on the given board history.
+    pub fn is_draw(&self, board_history: &[u64]) -> bool {
         // check for draw by 50 move role
         if self.halfmove_clock >= 100 {
             return true;
         }
+
+        // check for draw by insufficient material
+        if self.position.is_insufficient_material() {
+            return true;
+        }
         
         if board_history.is_empty() {
             return false;
@@ -113,17 +149,11 @@ mod tests {
     use crate::board::color::Color::{Black, White};
     use crate::board::piece::Piece;
     use crate::board::position::Position;
-    use crate::lookup::LOOKUP_TABLE;
-    use crate::lookup::lookup_table::LookupTable;
     use crate::move_gen::ply::Ply;
     use crate::zobrist;
 
     #[test]
     fn default_returns_board_with_default_values() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-        
         let board = Board::default();
         assert_eq!(Position::default(), board.position);
         assert_eq!(0, board.halfmove_clock);
@@ -132,10 +162,6 @@ mod tests {
 
     #[test]
     fn from_fen_with_valid_fen_returns_board() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // -----------------------------------------------------------------------------------------
         // position 1
         // -----------------------------------------------------------------------------------------
@@ -190,10 +216,6 @@ mod tests {
     
     #[test]
     fn test_make_move() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-        
         // g1-f3
         let board = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
         let board = board.make_move(Ply {
@@ -286,12 +308,27 @@ mod tests {
         assert_eq!(Board::from_fen("r1bqkb1r/pppppppp/2n2n2/3P4/8/2N5/PPP1PPPP/R1BQKB1R b KQkq - 0 5").unwrap(), board);
     }
     
+    #[test]
+    fn unmake_move_restores_board() {
+        let original_board = Board::from_fen("r1bqkb1r/pppppppp/2n2n2/8/3P4/2N5/PPP1PPPP/R1BQKB1R w KQkq - 4 4").unwrap();
+        let mut board = original_board;
+        let mut undo_stack = Vec::new();
+        for ply in ["c1g5", "f6e4", "c3e4", "e7e6"] {
+            let ply = Ply::from_string(ply, board.position).unwrap();
+            let expected_board = board.make_move(ply);
+            undo_stack.push(board.make_move_in_place(ply));
+            assert_eq!(expected_board, board);
+        }
+        assert_eq!(Board::from_fen("r1bqkb1r/pppp1ppp/2n1p3/6B1/3PN3/8/PPP1PPPP/R2QKB1R w KQkq - 0 6").unwrap(), board);
+
+        while let Some(undo) = undo_stack.pop() {
+            board.unmake_move(undo);
+        }
+        assert_eq!(original_board, board);
+    }
+
     #[test]
     fn test_is_draw() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-        
         let mut board_history: ArrayVec<u64, 1000> = ArrayVec::new();
         let board = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 3 1").unwrap();
         
@@ -304,10 +341,16 @@ mod tests {
         
         board_history.pop();
         
-        let mut board = Board::default();
+        let mut board = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
         board.halfmove_clock = 99;
         assert!(!board.is_draw(&board_history));
         board.halfmove_clock = 100;
         assert!(board.is_draw(&board_history));
+
+        // insufficient material
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/4KB2 w - - 0 1").unwrap();
+        assert!(board.is_draw(&board_history));
+        let board = Board::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1").unwrap();
+        assert!(!board.is_draw(&board_history));
     }
 }
@@ -16,6 +16,12 @@ pub struct Bitboard {
     pub value: u64,
 }
 
+/// A bitboard with all light squares set to 1.
+pub const LIGHT_SQUARES: Bitboard = Bitboard { value: 0x55aa55aa55aa55aa };
+
+/// A bitboard with all dark squares set to 1.
+pub const DARK_SQUARES: Bitboard = Bitboard { value: 0xaa55aa55aa55aa55 };
+
 impl Bitboard {
     /// Constructs a new bitboard from an u64.
     pub fn new(value: u64) -> Self {
@@ -287,4 +293,16 @@ mod tests {
         assert!(Bitboard::new(0x4000000000000000).get_bit(Square::from_file_rank(File::G, Rank::Eighth)));
         assert!(Bitboard::new(0x8000000000000000).get_bit(Square::from_file_rank(File::H, Rank::Eighth)));
     }
+
+    #[test]
+    fn square_colors_are_correct() {
+        assert!(DARK_SQUARES.get_bit(square::A1));
+        assert!(DARK_SQUARES.get_bit(square::H8));
+        assert!(DARK_SQUARES.get_bit(square::E5));
+        assert!(LIGHT_SQUARES.get_bit(square::H1));
+        assert!(LIGHT_SQUARES.get_bit(square::A8));
+        assert!(LIGHT_SQUARES.get_bit(square::D5));
+        assert_eq!(0, LIGHT_SQUARES.value & DARK_SQUARES.value);
+        assert_eq!(u64::MAX, LIGHT_SQUARES.value | DARK_SQUARES.value);
+    }
 }
\ No newline at end of file
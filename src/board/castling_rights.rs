@@ -1,4 +1,7 @@
 use std::fmt::{Display, Formatter};
+use crate::board::color::Color;
+use crate::board::file::File;
+use crate::board::square::Square;
 
 /// Contains information on what (if any) castling rights a player has.
 #[derive(Copy, Clone, PartialEq, Debug)]
@@ -9,6 +12,40 @@ pub enum CastlingRights {
     Both,
 }
 
+/// Contains the files of the rooks a player can castle with.
+///
+/// In standard chess, these are always the H and A files, while in [Chess960](https://www.chessprogramming.org/Chess960)
+/// they depend on the starting position.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct CastlingRooks {
+    /// The file of the rook used to castle kingside.
+    pub king_side: File,
+    /// The file of the rook used to castle queenside.
+    pub queen_side: File,
+}
+
+impl Default for CastlingRooks {
+    /// Returns the castling rooks of standard chess.
+    fn default() -> Self {
+        CastlingRooks {
+            king_side: File::H,
+            queen_side: File::A,
+        }
+    }
+}
+
+/// Returns the target squares of the king and the rook when the given color castles kingside or queenside.
+///
+/// These are the same in standard chess and Chess960: the king ends up on the G or C file, and the rook next to it on the F or D file.
+pub fn get_castling_targets(color: Color, king_side: bool) -> (Square, Square) {
+    let rank = color.back_rank();
+    if king_side {
+        (Square::from_file_rank(File::G, rank), Square::from_file_rank(File::F, rank))
+    } else {
+        (Square::from_file_rank(File::C, rank), Square::from_file_rank(File::D, rank))
+    }
+}
+
 /// Prints the castling rights as text.
 impl Display for CastlingRights {
     fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
@@ -23,7 +60,10 @@ impl Display for CastlingRights {
 
 #[cfg(test)]
 mod tests {
-    use crate::board::castling_rights::CastlingRights;
+    use crate::board::castling_rights::{CastlingRights, CastlingRooks, get_castling_targets};
+    use crate::board::color::Color;
+    use crate::board::file::File;
+    use crate::board::square;
 
     #[test]
     fn castling_rights_formats_correctly() {
@@ -32,4 +72,17 @@ mod tests {
         assert_eq!("QueenSide", format!("{}", CastlingRights::QueenSide));
         assert_eq!("Both", format!("{}", CastlingRights::Both));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn default_returns_standard_castling_rooks() {
+        assert_eq!(CastlingRooks { king_side: File::H, queen_side: File::A }, CastlingRooks::default());
+    }
+
+    #[test]
+    fn test_get_castling_targets() {
+        assert_eq!((square::G1, square::F1), get_castling_targets(Color::White, true));
+        assert_eq!((square::C1, square::D1), get_castling_targets(Color::White, false));
+        assert_eq!((square::G8, square::F8), get_castling_targets(Color::Black, true));
+        assert_eq!((square::C8, square::D8), get_castling_targets(Color::Black, false));
+    }
+}
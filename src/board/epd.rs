@@ -0,0 +1,231 @@
+use crate::board::Board;
+use crate::move_gen::ply::Ply;
+
+/// A position in the [Extended Position Description](https://www.chessprogramming.org/Extended_Position_Description) format,
+/// as used by test suites like WAC or STS.
+///
+/// An EPD record consists of the first four fields of a FEN, followed by a list of operations.
+/// Each operation has an opcode and any number of operands, and is terminated by a semicolon, e.g. `bm Qg6; id "WAC.001";`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Epd {
+    /// The board described by the record.
+    pub board: Board,
+    /// The operations of the record in the order they appeared, as pairs of opcode and operands.
+    pub operations: Vec<(String, Vec<String>)>,
+}
+
+impl Epd {
+    /// Parses an EPD record.
+    ///
+    /// The halfmove clock and fullmove counter are taken from the `hmvc` and `fmvn` operations if present.
+    /// Records that start with a full six-field FEN are accepted as well.
+    pub fn parse(epd: &str) -> Result<Epd, String> {
+        let fields: Vec<&str> = epd.split_whitespace().collect();
+        if fields.len() < 4 {
+            return Err(format!("invalid epd: {epd}"));
+        }
+
+        // some suites use full FENs, in which case the halfmove clock and fullmove counter follow the first four fields
+        let mut num_fen_fields = 4;
+        if fields.len() >= 6 && fields[4].parse::<u32>().is_ok() && fields[5].trim_end_matches(';').parse::<u32>().is_ok() {
+            num_fen_fields = 6;
+        }
+
+        // skip the position fields in the original string to keep the whitespace in quoted operands intact
+        let mut rest = epd.trim_start();
+        for _ in 0..num_fen_fields {
+            rest = rest.trim_start();
+            rest = &rest[rest.find(char::is_whitespace).unwrap_or(rest.len())..];
+        }
+        let operations = Self::parse_operations(rest)?;
+
+        let mut fen = fields[..4].join(" ");
+        if num_fen_fields == 6 {
+            fen += format!(" {} {}", fields[4], fields[5].trim_end_matches(';')).as_str();
+        } else {
+            let halfmove_clock = Self::find_operands(&operations, "hmvc").and_then(|operands| operands.first()).map_or("0", |operand| operand.as_str());
+            let fullmove_counter = Self::find_operands(&operations, "fmvn").and_then(|operands| operands.first()).map_or("1", |operand| operand.as_str());
+            fen += format!(" {halfmove_clock} {fullmove_counter}").as_str();
+        }
+
+        Ok(Epd {
+            board: Board::from_fen(fen.as_str()).map_err(|error| format!("invalid epd position: {error}"))?,
+            operations,
+        })
+    }
+
+    /// Returns the operands of the first operation with the given opcode.
+    pub fn get_operands(&self, opcode: &str) -> Option<&[String]> {
+        Self::find_operands(&self.operations, opcode)
+    }
+
+    /// Returns the id of the position as given by the `id` operation.
+    pub fn get_id(&self) -> Option<&str> {
+        self.get_operands("id").and_then(|operands| operands.first()).map(|id| id.as_str())
+    }
+
+    /// Returns the best moves as given by the `bm` operation.
+    pub fn get_best_moves(&self) -> Result<Vec<Ply>, String> {
+        self.get_moves("bm")
+    }
+
+    /// Returns the moves to avoid as given by the `am` operation.
+    pub fn get_avoid_moves(&self) -> Result<Vec<Ply>, String> {
+        self.get_moves("am")
+    }
+
+    /// Parses the operands of the operation with the given opcode as moves.
+    /// Moves are expected in SAN, but UCI notation is accepted as well.
+    /// Returns an empty list if there is no such operation.
+    pub fn get_moves(&self, opcode: &str) -> Result<Vec<Ply>, String> {
+        let position = self.board.position;
+        self.get_operands(opcode).unwrap_or_default().iter()
+            .map(|operand| Ply::from_san(operand, &position)
+                .or_else(|error| Ply::from_string(operand, position).map_err(|_| error))
+                .map_err(|error| format!("invalid move in {opcode} operation: {error}")))
+            .collect()
+    }
+
+    /// Builds an EPD record representing the position and its operations.
+    pub fn to_epd(&self) -> String {
+        let fen = self.board.to_fen();
+        let mut epd = fen.split_whitespace().take(4).collect::<Vec<&str>>().join(" ");
+        for (opcode, operands) in self.operations.iter() {
+            epd += format!(" {opcode}").as_str();
+            for operand in operands {
+                if operand.is_empty() || operand.contains(|c: char| c.is_whitespace() || c == ';') {
+                    epd += format!(" \"{operand}\"").as_str();
+                } else {
+                    epd += format!(" {operand}").as_str();
+                }
+            }
+            epd.push(';');
+        }
+        epd
+    }
+
+    /// Returns the operands of the first operation with the given opcode in the given list.
+    fn find_operands<'a>(operations: &'a [(String, Vec<String>)], opcode: &str) -> Option<&'a [String]> {
+        operations.iter()
+            .find(|(other_opcode, _)| other_opcode == opcode)
+            .map(|(_, operands)| operands.as_slice())
+    }
+
+    /// Parses the operations of an EPD record.
+    /// Operands in double quotes may contain whitespace and semicolons.
+    fn parse_operations(operations_str: &str) -> Result<Vec<(String, Vec<String>)>, String> {
+        let mut operations: Vec<(String, Vec<String>)> = Vec::new();
+        let mut tokens: Vec<String> = Vec::new();
+        let mut token = String::from("");
+        let mut in_quotes = false;
+        let mut is_quoted = false;
+
+        for c in operations_str.chars() {
+            match c {
+                '"' => {
+                    in_quotes = !in_quotes;
+                    is_quoted = true;
+                }
+                c if in_quotes => token.push(c),
+                c if c.is_whitespace() || c == ';' => {
+                    if !token.is_empty() || is_quoted {
+                        tokens.push(token.clone());
+                        token.clear();
+                        is_quoted = false;
+                    }
+                    if c == ';' {
+                        if tokens.is_empty() {
+                            return Err(String::from("empty epd operation"));
+                        }
+                        operations.push((tokens.remove(0), tokens.clone()));
+                        tokens.clear();
+                    }
+                }
+                c => token.push(c),
+            }
+        }
+
+        if in_quotes {
+            return Err(String::from("unterminated string in epd operation"));
+        }
+        if !token.is_empty() || is_quoted {
+            tokens.push(token);
+        }
+        // the semicolon of the last operation is often left out
+        if !tokens.is_empty() {
+            operations.push((tokens.remove(0), tokens));
+        }
+
+        Ok(operations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::epd::Epd;
+    use crate::board::square;
+
+    #[test]
+    fn parse_reads_position_and_operations() {
+        let epd = Epd::parse("2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id \"WAC.001\";").unwrap();
+        assert_eq!("2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - 0 1", epd.board.to_fen());
+        assert_eq!(2, epd.operations.len());
+        assert_eq!(Some("WAC.001"), epd.get_id());
+        assert_eq!(Some(&[String::from("Qg6")][..]), epd.get_operands("bm"));
+        assert_eq!(None, epd.get_operands("am"));
+
+        let best_moves = epd.get_best_moves().unwrap();
+        assert_eq!(1, best_moves.len());
+        assert_eq!((square::G3, square::G6), (best_moves[0].source, best_moves[0].target));
+        assert!(epd.get_avoid_moves().unwrap().is_empty());
+    }
+
+    #[test]
+    fn parse_reads_quoted_and_multiple_operands() {
+        let epd = Epd::parse("1k1r4/pp1b1R2/3q2pp/4p3/2B5/4Q3/PPP2B2/2K5 b - - bm Qd1+ Qd4; c0 \"Qd1+=10, Qd4=3; good\"; id \"STS.001\"").unwrap();
+        assert_eq!(2, epd.get_best_moves().unwrap().len());
+        assert_eq!(Some(&[String::from("Qd1+=10, Qd4=3; good")][..]), epd.get_operands("c0"));
+        assert_eq!(Some("STS.001"), epd.get_id());
+    }
+
+    #[test]
+    fn parse_reads_move_counters() {
+        let epd = Epd::parse("4k3/8/8/8/8/8/8/4K3 b - - hmvc 12; fmvn 40;").unwrap();
+        assert_eq!(12, epd.board.halfmove_clock);
+        assert_eq!(40, epd.board.fullmove_counter);
+
+        // full FENs are accepted as well
+        let epd = Epd::parse("4k3/8/8/8/8/8/8/4K3 b - - 3 7 am Kd8;").unwrap();
+        assert_eq!(3, epd.board.halfmove_clock);
+        assert_eq!(7, epd.board.fullmove_counter);
+        assert_eq!(square::D8, epd.get_avoid_moves().unwrap()[0].target);
+
+        let epd = Epd::parse("4k3/8/8/8/8/8/8/4K3 b - -").unwrap();
+        assert!(epd.operations.is_empty());
+        assert_eq!(1, epd.board.fullmove_counter);
+    }
+
+    #[test]
+    fn parse_with_invalid_input_returns_error() {
+        assert!(Epd::parse("").is_err());
+        assert!(Epd::parse("4k3/8/8/8/8/8/8/4K3 b -").is_err());
+        assert!(Epd::parse("4k3/8/8/8/8/8/8/4K3 x - - bm Kd8;").is_err());
+        assert!(Epd::parse("4k3/8/8/8/8/8/8/4K3 b - - id \"unterminated;").is_err());
+        assert!(Epd::parse("4k3/8/8/8/8/8/8/4K3 b - - ; bm Kd8;").is_err());
+        assert!(Epd::parse("4k3/8/8/8/8/8/8/4K3 b - - bm Ke1;").unwrap().get_best_moves().is_err());
+    }
+
+    #[test]
+    fn get_moves_accepts_uci_notation() {
+        let epd = Epd::parse("8/8/1Q6/8/7B/2R4N/5K1P/k7 w - - bm c3a3;").unwrap();
+        assert_eq!(square::A3, epd.get_best_moves().unwrap()[0].target);
+    }
+
+    #[test]
+    fn test_to_epd() {
+        let epd_str = "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id \"WAC 001\"; c0 \"\"; hmvc 0;";
+        let epd = Epd::parse(epd_str).unwrap();
+        assert_eq!(epd_str, epd.to_epd());
+        assert_eq!(epd, Epd::parse(epd.to_epd().as_str()).unwrap());
+    }
+}
@@ -1,6 +1,8 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
 use crate::board::bitboard::Bitboard;
 use crate::board::Board;
-use crate::board::castling_rights::CastlingRights;
+use crate::board::castling_rights::{CastlingRights, CastlingRooks};
 use crate::board::color::{Color, NUM_COLORS};
 use crate::board::color::Color::{Black, White};
 use crate::board::file::{File, NUM_FILES};
@@ -8,22 +10,83 @@ use crate::board::piece::Piece::{Bishop, King, Knight, Pawn, Queen, Rook};
 use crate::board::position::Position;
 use crate::board::rank::{NUM_RANKS, Rank};
 use crate::board::square::Square;
+use crate::zobrist;
+
+/// The FEN of the standard starting position.
+pub const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// An error that occurred while parsing a FEN.
+/// Besides syntax errors, positions that are not reachable in a legal game, and that the engine therefore can't handle, are rejected as well.
+#[derive(Clone, PartialEq, Debug)]
+pub enum FenError {
+    /// The FEN does not consist of four to six fields. Contains the number of fields found.
+    WrongFieldCount(usize),
+    /// The piece placement field does not consist of eight ranks separated by slashes.
+    WrongRankCount(String),
+    /// A rank of the piece placement field contains an invalid char or does not describe exactly eight squares.
+    InvalidRank(String),
+    /// The color to move is neither `w` nor `b`.
+    InvalidColorToMove(String),
+    /// The castling rights field is invalid.
+    InvalidCastlingRights(String),
+    /// The en passant field is neither `-` nor a valid square.
+    InvalidEnPassant(String),
+    /// The halfmove clock is not a non-negative number.
+    InvalidHalfmoveClock(String),
+    /// The fullmove counter is not a positive number.
+    InvalidFullmoveCounter(String),
+    /// A pawn stands on the first or eighth rank.
+    PawnOnBackRank(Square),
+    /// A side does not have exactly one king. Contains the color and the number of kings found.
+    InvalidKingCount(Color, usize),
+    /// The castling rights require a king or rook that is not on its home square.
+    UnavailableCastlingRights(String),
+    /// The en passant square could not have been created by a double pawn push of the side not to move.
+    ImpossibleEnPassant(Square),
+    /// The side not to move is in check, so its king could be captured.
+    OpponentInCheck,
+}
+
+/// Prints the error as text.
+impl Display for FenError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            FenError::WrongFieldCount(count) => write!(f, "expected 4 to 6 fields, found {count}"),
+            FenError::WrongRankCount(pieces) => write!(f, "expected 8 ranks in piece placement: {pieces}"),
+            FenError::InvalidRank(rank) => write!(f, "invalid rank in piece placement: {rank}"),
+            FenError::InvalidColorToMove(color) => write!(f, "invalid color to move: {color}"),
+            FenError::InvalidCastlingRights(castling_rights) => write!(f, "invalid castling rights: {castling_rights}"),
+            FenError::InvalidEnPassant(en_passant) => write!(f, "invalid en passant square: {en_passant}"),
+            FenError::InvalidHalfmoveClock(halfmove_clock) => write!(f, "invalid halfmove clock: {halfmove_clock}"),
+            FenError::InvalidFullmoveCounter(fullmove_counter) => write!(f, "invalid fullmove counter: {fullmove_counter}"),
+            FenError::PawnOnBackRank(square) => write!(f, "pawn on back rank: {square}"),
+            FenError::InvalidKingCount(color, count) => write!(f, "expected one {} king, found {count}", color.to_string().to_lowercase()),
+            FenError::UnavailableCastlingRights(castling_rights) => write!(f, "castling rights without king and rook on their home squares: {castling_rights}"),
+            FenError::ImpossibleEnPassant(square) => write!(f, "impossible en passant square: {square}"),
+            FenError::OpponentInCheck => write!(f, "the side not to move is in check"),
+        }
+    }
+}
+
+impl Error for FenError {}
 
 impl Board {
     /// Parses a [FEN](https://www.chessprogramming.org/Forsyth-Edwards_Notation) string and returns a result.
     /// If the FEN could be parsed successfully, the result will contain a board. Otherwise, it will contain an error.
-    pub(super) fn parse_fen(fen: &str) -> Result<Board, String> {
+    pub(super) fn parse_fen(fen: &str) -> Result<Board, FenError> {
         // split fen into its six parts
         let fen_parts = Self::split_fen(fen)?;
 
         // parse pieces
         let pieces = Self::parse_pieces(fen_parts.first().unwrap())?;
+        Self::validate_pieces(&pieces)?;
 
         // parse color to move
         let color_to_move = Self::parse_color_to_move(fen_parts.get(1).unwrap())?;
 
         // parse castling rights
-        let castling_rights = Self::parse_castling_rights(fen_parts.get(2).unwrap())?;
+        let (castling_rights, castling_rooks) = Self::parse_castling_rights(fen_parts.get(2).unwrap(), &pieces)?;
+        Self::validate_castling_rights(fen_parts.get(2).unwrap(), &pieces, castling_rights, castling_rooks)?;
 
         // parse en passant
         let en_passant = Self::parse_en_passant(fen_parts.get(3).unwrap())?;
@@ -35,12 +98,25 @@ impl Board {
         let fullmove_counter = Self::parse_fullmove_counter(fen_parts.last().unwrap())?;
 
         // create the position
-        let position = Position::new(
+        let mut position = Position::new(
             pieces,
             castling_rights,
-            en_passant,
+            None,
             color_to_move,
         );
+        position.castling_rooks = castling_rooks;
+        if position.is_in_check(color_to_move.other()) {
+            return Err(FenError::OpponentInCheck);
+        }
+
+        // the en passant square is only kept if it can actually be captured, just like after a double pawn push in `make_move`
+        if let Some(en_passant) = en_passant {
+            Self::validate_en_passant(en_passant, &position)?;
+            if position.is_en_passant_capturable(en_passant, color_to_move) {
+                position.en_passant = Some(en_passant);
+                position.hash = zobrist::get_hash(&position);
+            }
+        }
 
         // create the board
         let board = Board {
@@ -89,16 +165,26 @@ impl Board {
         }
 
         // castling rights
+        // the rooks are notated with K and Q if they are the outermost rooks on their side of the king (X-FEN), otherwise with their file (Shredder-FEN)
         let mut castling_rights_str_both = String::from("");
         for color_index in 0..NUM_COLORS {
+            let color = Color::from_index(color_index);
+            let castling_rooks = self.position.castling_rooks[color_index as usize];
+            let castling_rights = self.position.castling_rights[color_index as usize];
             let mut castling_rights_str = String::from("");
-            match self.position.castling_rights[color_index as usize] {
-                CastlingRights::NoRights => {}
-                CastlingRights::KingSide => castling_rights_str.push('K'),
-                CastlingRights::QueenSide => castling_rights_str.push('Q'),
-                CastlingRights::Both => castling_rights_str.push_str("KQ"),
+            if castling_rights == CastlingRights::KingSide || castling_rights == CastlingRights::Both {
+                match get_outermost_rook(&self.position.pieces, color, true) {
+                    Some(file) if file != castling_rooks.king_side => castling_rights_str.push(file_to_char(castling_rooks.king_side)),
+                    _other => castling_rights_str.push('K'),
+                }
             }
-            if Color::from_index(color_index) == Black {
+            if castling_rights == CastlingRights::QueenSide || castling_rights == CastlingRights::Both {
+                match get_outermost_rook(&self.position.pieces, color, false) {
+                    Some(file) if file != castling_rooks.queen_side => castling_rights_str.push(file_to_char(castling_rooks.queen_side)),
+                    _other => castling_rights_str.push('Q'),
+                }
+            }
+            if color == Black {
                 castling_rights_str = castling_rights_str.to_ascii_lowercase();
             }
             castling_rights_str_both += castling_rights_str.as_str();
@@ -127,7 +213,7 @@ impl Board {
 
     /// Takes a FEN and splits it into its 6 parts.
     /// If the FEN has more than 4 but less than 6 parts, default parameters will be added for the remaining parts.
-    fn split_fen(fen: &str) -> Result<Vec<String>, String> {
+    fn split_fen(fen: &str) -> Result<Vec<String>, FenError> {
         let mut fen_parts: Vec<String> = fen.split_whitespace().map(|s| s.to_string()).collect();
         match fen_parts.len() {
             4 => {
@@ -140,16 +226,16 @@ impl Board {
                 Ok(fen_parts)
             }
             6 => Ok(fen_parts),
-            _other => Err(String::from("Invalid FEN")),
+            other => Err(FenError::WrongFieldCount(other)),
         }
     }
 
     /// Parses the first part of the FEN (pieces).
-    fn parse_pieces(piece_fen: &str) -> Result<[[Bitboard; 6]; 2], String> {
+    pub(super) fn parse_pieces(piece_fen: &str) -> Result<[[Bitboard; 6]; 2], FenError> {
         let mut pieces = [[Bitboard::new(0); 6]; 2];
         let piece_parts: Vec<String> = piece_fen.split('/').map(|s| s.to_string()).collect();
         if piece_parts.len() != 8 {
-            return Err(String::from("Invalid FEN"));
+            return Err(FenError::WrongRankCount(String::from(piece_fen)));
         }
         for (rank_index, piece_str) in piece_parts.iter().enumerate() {
             let mut file_index: usize = 0;
@@ -172,10 +258,10 @@ impl Board {
                         let files_to_skip = char.to_digit(10);
                         match files_to_skip {
                             Some(files_to_skip) => file_index += files_to_skip as usize - 1,
-                            None => return Err(String::from("Invalid FEN")),
+                            None => return Err(FenError::InvalidRank(piece_str.clone())),
                         }
                     }
-                    _other => return Err(String::from("Invalid FEN")),
+                    _other => return Err(FenError::InvalidRank(piece_str.clone())),
                 }
                 if file_index > 7 {
                     // In a FEN string, pieces are specified using letters (P for a white pawn for example),
@@ -183,7 +269,7 @@ impl Board {
                     // If the file_index is larger than seven before the increment below,
                     // it means that the number of piece letters plus the sum of numbers used to notate empty squares was larger than 8.
                     // Since a chessboard only has 8 files, the FEN must be invalid.
-                    return Err(String::from("Invalid FEN"));
+                    return Err(FenError::InvalidRank(piece_str.clone()));
                 }
                 file_index += 1;
             }
@@ -191,88 +277,195 @@ impl Board {
         Ok(pieces)
     }
 
+    /// Validates that there are no pawns on the first and eighth rank, and that both sides have exactly one king.
+    fn validate_pieces(pieces: &[[Bitboard; 6]; 2]) -> Result<(), FenError> {
+        for color in [White, Black] {
+            let pawns = pieces[color.to_index() as usize][Pawn.to_index() as usize].get_active_bits();
+            if let Some(square) = pawns.iter().find(|square| square.get_rank() == Rank::First || square.get_rank() == Rank::Eighth) {
+                return Err(FenError::PawnOnBackRank(*square));
+            }
+        }
+        for color in [White, Black] {
+            let num_kings = pieces[color.to_index() as usize][King.to_index() as usize].get_active_bits().len();
+            if num_kings != 1 {
+                return Err(FenError::InvalidKingCount(color, num_kings));
+            }
+        }
+        Ok(())
+    }
+
     /// Parses the second part of the FEN (color to move).
-    fn parse_color_to_move(color_fen: &str) -> Result<Color, String> {
+    fn parse_color_to_move(color_fen: &str) -> Result<Color, FenError> {
         match color_fen {
             "w" => Ok(White),
             "b" => Ok(Black),
-            _other => Err(String::from("Invalid FEN")),
+            other => Err(FenError::InvalidColorToMove(String::from(other))),
         }
     }
 
-    /// Parses the third part of the FEN (castling rights).
-    fn parse_castling_rights(castling_rights_fen: &str) -> Result<[CastlingRights; 2], String> {
-        match castling_rights_fen {
-            "-" => Ok([CastlingRights::NoRights, CastlingRights::NoRights]),
-            "q" => Ok([CastlingRights::NoRights, CastlingRights::QueenSide]),
-            "k" => Ok([CastlingRights::NoRights, CastlingRights::KingSide]),
-            "kq" => Ok([CastlingRights::NoRights, CastlingRights::Both]),
-            "Q" => Ok([CastlingRights::QueenSide, CastlingRights::NoRights]),
-            "Qq" => Ok([CastlingRights::QueenSide, CastlingRights::QueenSide]),
-            "Qk" => Ok([CastlingRights::QueenSide, CastlingRights::KingSide]),
-            "Qkq" => Ok([CastlingRights::QueenSide, CastlingRights::Both]),
-            "K" => Ok([CastlingRights::KingSide, CastlingRights::NoRights]),
-            "Kq" => Ok([CastlingRights::KingSide, CastlingRights::QueenSide]),
-            "Kk" => Ok([CastlingRights::KingSide, CastlingRights::KingSide]),
-            "Kkq" => Ok([CastlingRights::KingSide, CastlingRights::Both]),
-            "KQ" => Ok([CastlingRights::Both, CastlingRights::NoRights]),
-            "KQq" => Ok([CastlingRights::Both, CastlingRights::QueenSide]),
-            "KQk" => Ok([CastlingRights::Both, CastlingRights::KingSide]),
-            "KQkq" => Ok([CastlingRights::Both, CastlingRights::Both]),
-            _other => Err(String::from("Invalid FEN")),
+    /// Parses the third part of the FEN (castling rights), and returns the castling rights and the files of the castling rooks.
+    ///
+    /// Besides the standard notation, both [X-FEN](https://en.wikipedia.org/wiki/X-FEN) and Shredder-FEN are supported for Chess960:
+    /// K and Q refer to the outermost rook on the respective side of the king, while the letters A to H refer to the rook on that file.
+    /// White's castling rights have to be specified before Black's, and the kingside before the queenside.
+    fn parse_castling_rights(castling_rights_fen: &str, pieces: &[[Bitboard; 6]; 2]) -> Result<([CastlingRights; 2], [CastlingRooks; 2]), FenError> {
+        let error = || FenError::InvalidCastlingRights(String::from(castling_rights_fen));
+        let mut castling_rights = [CastlingRights::NoRights; 2];
+        let mut castling_rooks = [CastlingRooks::default(); 2];
+        if castling_rights_fen == "-" {
+            return Ok((castling_rights, castling_rooks));
         }
+
+        let mut black_parsed = false;
+        for char in castling_rights_fen.chars() {
+            let color = if char.is_ascii_uppercase() { White } else { Black };
+            if color == White && black_parsed {
+                return Err(error());
+            }
+            black_parsed = color == Black;
+
+            // the king's file decides whether a rook file is on the kingside or queenside
+            let king_file = pieces[color.to_index() as usize][King.to_index() as usize].get_active_bits().iter()
+                .find(|square| square.get_rank() == color.back_rank())
+                .map(|square| square.get_file())
+                .unwrap_or(File::E);
+
+            let (rook_file, king_side) = match char.to_ascii_lowercase() {
+                'k' => (get_outermost_rook(pieces, color, true).unwrap_or(File::H), true),
+                'q' => (get_outermost_rook(pieces, color, false).unwrap_or(File::A), false),
+                'a'..='h' => {
+                    let file = File::from_char(&char).map_err(|_| error())?;
+                    (file, file.to_index() > king_file.to_index())
+                }
+                _other => return Err(error()),
+            };
+
+            let color_index = color.to_index() as usize;
+            castling_rights[color_index] = match (castling_rights[color_index], king_side) {
+                (CastlingRights::NoRights, true) => CastlingRights::KingSide,
+                (CastlingRights::NoRights, false) => CastlingRights::QueenSide,
+                (CastlingRights::KingSide, false) => CastlingRights::Both,
+                // the same side was specified twice, or the kingside after the queenside
+                _other => return Err(error()),
+            };
+            if king_side {
+                castling_rooks[color_index].king_side = rook_file;
+            } else {
+                castling_rooks[color_index].queen_side = rook_file;
+            }
+        }
+
+        Ok((castling_rights, castling_rooks))
+    }
+
+    /// Validates that the king and the castling rooks of each side with castling rights are on their home squares,
+    /// with the kingside rook on the right and the queenside rook on the left of the king.
+    fn validate_castling_rights(castling_rights_fen: &str, pieces: &[[Bitboard; 6]; 2], castling_rights: [CastlingRights; 2], castling_rooks: [CastlingRooks; 2]) -> Result<(), FenError> {
+        for color in [White, Black] {
+            let color_index = color.to_index() as usize;
+            let king_square = pieces[color_index][King.to_index() as usize].get_active_bits()[0];
+            let rooks = pieces[color_index][Rook.to_index() as usize];
+            let is_available = |rook_file: File, king_side: bool| {
+                king_square.get_rank() == color.back_rank() &&
+                    rooks.get_bit(Square::from_file_rank(rook_file, color.back_rank())) &&
+                    (rook_file.to_index() > king_square.get_file().to_index()) == king_side
+            };
+
+            let king_side = castling_rights[color_index] == CastlingRights::KingSide || castling_rights[color_index] == CastlingRights::Both;
+            let queen_side = castling_rights[color_index] == CastlingRights::QueenSide || castling_rights[color_index] == CastlingRights::Both;
+            if (king_side && !is_available(castling_rooks[color_index].king_side, true)) ||
+                (queen_side && !is_available(castling_rooks[color_index].queen_side, false)) {
+                return Err(FenError::UnavailableCastlingRights(String::from(castling_rights_fen)));
+            }
+        }
+        Ok(())
     }
 
     /// Parses the fourth part of the FEN (en passant).
-    fn parse_en_passant(en_passant_fen: &str) -> Result<Option<Square>, String> {
+    fn parse_en_passant(en_passant_fen: &str) -> Result<Option<Square>, FenError> {
         match en_passant_fen {
             "-" => Ok(None),
             other => {
                 Square::from_string(other)
                     .map(Some)
-                    .map_err(|_| String::from("Invalid FEN"))
+                    .map_err(|_| FenError::InvalidEnPassant(String::from(other)))
             }
         }
     }
 
+    /// Validates that the en passant square could have been created by a double pawn push of the side not to move:
+    /// it must be on the third or sixth rank, with the pushed pawn in front of it and both squares it passed empty.
+    fn validate_en_passant(en_passant: Square, position: &Position) -> Result<(), FenError> {
+        let color = position.color_to_move.other();
+        let file = en_passant.get_file();
+        let source = Square::from_file_rank(file, color.pawn_rank());
+        let target = Square::from_file_rank(file, color.double_pawn_push_target_rank());
+        let occupancies = position.get_occupancies();
+        if en_passant.get_rank() != position.color_to_move.en_passant_target_rank() ||
+            !position.pieces[color.to_index() as usize][Pawn.to_index() as usize].get_bit(target) ||
+            occupancies.get_bit(source) || occupancies.get_bit(en_passant) {
+            return Err(FenError::ImpossibleEnPassant(en_passant));
+        }
+        Ok(())
+    }
+
     /// Parses the fifth part of the FEN (halfmove clock).
-    fn parse_halfmove_clock(halfmove_clock_fen: &str) -> Result<u32, String> {
+    fn parse_halfmove_clock(halfmove_clock_fen: &str) -> Result<u32, FenError> {
         let halfmove_clock: Result<u32, _> = halfmove_clock_fen.parse();
         match halfmove_clock {
             Ok(halfmove_clock) => Ok(halfmove_clock),
-            Err(_) => Err(String::from("Invalid FEN")),
+            Err(_) => Err(FenError::InvalidHalfmoveClock(String::from(halfmove_clock_fen))),
         }
     }
 
     /// Parses the sixth part of the FEN (fullmove counter).
-    fn parse_fullmove_counter(fullmove_counter_fen: &str) -> Result<u32, String> {
+    fn parse_fullmove_counter(fullmove_counter_fen: &str) -> Result<u32, FenError> {
         let fullmove_counter: Result<u32, _> = fullmove_counter_fen.parse();
         match fullmove_counter {
             Ok(halfmove_clock) => match halfmove_clock {
-                0 => Err(String::from("Invalid FEN")), // The fullmove counter starts at 1, so it can't be 0.
+                0 => Err(FenError::InvalidFullmoveCounter(String::from(fullmove_counter_fen))), // The fullmove counter starts at 1, so it can't be 0.
                 other => Ok(other),
             }
-            Err(_) => Err(String::from("Invalid FEN")),
+            Err(_) => Err(FenError::InvalidFullmoveCounter(String::from(fullmove_counter_fen))),
         }
     }
 }
 
+/// Returns the file of the outermost rook of the given color on the kingside or queenside of its king, if there is any.
+/// Only rooks and kings on the back rank are considered.
+fn get_outermost_rook(pieces: &[[Bitboard; 6]; 2], color: Color, king_side: bool) -> Option<File> {
+    let back_rank = color.back_rank();
+    let king_file = pieces[color.to_index() as usize][King.to_index() as usize].get_active_bits().iter()
+        .find(|square| square.get_rank() == back_rank)?
+        .get_file();
+    let rook_files = pieces[color.to_index() as usize][Rook.to_index() as usize].get_active_bits().into_iter()
+        .filter(|square| square.get_rank() == back_rank)
+        .map(|square| square.get_file().to_index());
+    if king_side {
+        rook_files.filter(|file| *file > king_file.to_index()).max().map(File::from_index)
+    } else {
+        rook_files.filter(|file| *file < king_file.to_index()).min().map(File::from_index)
+    }
+}
+
+/// Returns the uppercase letter of the given file, as used in Shredder-FEN.
+fn file_to_char(file: File) -> char {
+    (b'A' + file.to_index()) as char
+}
+
 #[cfg(test)]
 mod tests {
     use crate::board::bitboard::Bitboard;
-    use crate::board::castling_rights::CastlingRights;
+    use crate::board::castling_rights::{CastlingRights, CastlingRooks};
     use crate::board::color::Color::{Black, White};
+    use crate::board::file::File;
     use crate::board::piece::Piece::{Bishop, King, Knight, Pawn, Queen, Rook};
     use crate::board::{Board, square};
-    use crate::lookup::LOOKUP_TABLE;
-    use crate::lookup::lookup_table::LookupTable;
+    use crate::board::fen::{FenError, START_FEN};
+    use crate::move_gen::ply::Ply;
 
     #[test]
     fn parse_fen_with_valid_fen_returns_board() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // -----------------------------------------------------------------------------------------
         // Test the parse_fen function with a lot of different positions to make sure it's working.
         // -----------------------------------------------------------------------------------------
@@ -341,20 +534,58 @@ mod tests {
         assert_eq!(bitboards, board.position.pieces);
         assert_eq!(White, board.position.color_to_move);
         assert_eq!([CastlingRights::Both, CastlingRights::NoRights], board.position.castling_rights);
-        assert_eq!(Some(square::A6), board.position.en_passant);
+        // no white pawn can capture on a6, so the en passant square is dropped
+        assert_eq!(None, board.position.en_passant);
         assert_eq!(0, board.halfmove_clock);
         assert_eq!(13, board.fullmove_counter);
     }
 
     #[test]
     fn parse_fen_with_invalid_fen_returns_error() {
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_fen(""));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_fen("Rust is awesome!"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQKQ - 0 1"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_fen("rnbqkbnr/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 1"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR B KQkq - 0 1"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_fen("rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_fen("rnbqkbnr/ppppp1ppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"));
+        assert_eq!(Err(FenError::WrongFieldCount(0)), Board::parse_fen(""));
+        assert_eq!(Err(FenError::WrongFieldCount(3)), Board::parse_fen("Rust is awesome!"));
+        assert_eq!(Err(FenError::InvalidCastlingRights(String::from("KQKQ"))), Board::parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQKQ - 0 1"));
+        assert_eq!(Err(FenError::WrongRankCount(String::from("rnbqkbnr/8/8/8/8/PPPPPPPP/RNBQKBNR"))), Board::parse_fen("rnbqkbnr/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 1"));
+        assert_eq!(Err(FenError::InvalidColorToMove(String::from("B"))), Board::parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR B KQkq - 0 1"));
+        assert_eq!(Err(FenError::InvalidRank(String::from("9"))), Board::parse_fen("rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"));
+        assert_eq!(Err(FenError::InvalidRank(String::from("ppppp1ppp"))), Board::parse_fen("rnbqkbnr/ppppp1ppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"));
+    }
+
+    #[test]
+    fn parse_fen_with_illegal_position_returns_error() {
+        assert_eq!(Err(FenError::PawnOnBackRank(square::H8)), Board::parse_fen("rnbqkbnP/pppppppp/8/8/8/8/PPPPPPP1/RNBQKBNR w KQq - 0 1"));
+        assert_eq!(Err(FenError::PawnOnBackRank(square::A1)), Board::parse_fen("4k3/8/8/8/8/8/8/p3K3 w - - 0 1"));
+        assert_eq!(Err(FenError::InvalidKingCount(Black, 0)), Board::parse_fen("8/8/8/8/8/8/8/4K3 w - - 0 1"));
+        assert_eq!(Err(FenError::InvalidKingCount(White, 2)), Board::parse_fen("4k3/8/8/8/8/8/8/3KK3 w - - 0 1"));
+        assert_eq!(Err(FenError::UnavailableCastlingRights(String::from("K"))), Board::parse_fen("4k3/8/8/8/8/8/8/4K3 w K - 0 1"));
+        assert_eq!(Err(FenError::UnavailableCastlingRights(String::from("KQkq"))), Board::parse_fen("r3k2r/8/8/8/8/8/4K3/R6R w KQkq - 0 1"));
+        assert_eq!(Err(FenError::UnavailableCastlingRights(String::from("Bk"))), Board::parse_fen("4k2r/8/8/8/8/8/8/R3K3 w Bk - 0 1"));
+        assert_eq!(Err(FenError::ImpossibleEnPassant(square::E6)), Board::parse_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e6 0 1"));
+        assert_eq!(Err(FenError::ImpossibleEnPassant(square::E3)), Board::parse_fen("4k3/8/8/8/8/8/8/4K3 b - e3 0 1"));
+        assert_eq!(Err(FenError::ImpossibleEnPassant(square::E3)), Board::parse_fen("4k3/8/8/8/3pP3/4N3/8/4K3 b - e3 0 1"));
+        assert_eq!(Err(FenError::OpponentInCheck), Board::parse_fen("4k3/8/8/8/8/8/8/4RK2 w - - 0 1"));
+    }
+
+    #[test]
+    fn parse_fen_normalizes_en_passant() {
+        // a black pawn on d4 can capture en passant, so the square is kept
+        let board = Board::parse_fen("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 3").unwrap();
+        assert_eq!(Some(square::E3), board.position.en_passant);
+
+        // no black pawn can capture en passant after 1. e4, so the square is dropped and the hash matches the position after e2e4
+        let board = Board::parse_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap();
+        let expected_board = Board::parse_fen(START_FEN).unwrap().make_move(Ply::from_string("e2e4", Board::parse_fen(START_FEN).unwrap().position).unwrap());
+        assert_eq!(None, board.position.en_passant);
+        assert_eq!(expected_board.position.hash, board.position.hash);
+        assert_eq!(Board::parse_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap(), board);
+    }
+
+    #[test]
+    fn fen_error_formats_correctly() {
+        assert_eq!("expected 4 to 6 fields, found 3", FenError::WrongFieldCount(3).to_string());
+        assert_eq!("pawn on back rank: h8", FenError::PawnOnBackRank(square::H8).to_string());
+        assert_eq!("expected one black king, found 0", FenError::InvalidKingCount(Black, 0).to_string());
+        assert_eq!("the side not to move is in check", FenError::OpponentInCheck.to_string());
     }
 
     #[test]
@@ -392,9 +623,9 @@ mod tests {
 
     #[test]
     fn split_fen_with_invalid_fen_returns_error() {
-        assert_eq!(Err(String::from("Invalid FEN")), Board::split_fen("rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::split_fen("one two three four five six seven"));
-        assert_ne!(Err(String::from("Invalid FEN")), Board::split_fen("rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"));
+        assert_eq!(Err(FenError::WrongFieldCount(3)), Board::split_fen("rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq"));
+        assert_eq!(Err(FenError::WrongFieldCount(7)), Board::split_fen("one two three four five six seven"));
+        assert!(Board::split_fen("rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2").is_ok());
     }
 
     #[test]
@@ -433,9 +664,9 @@ mod tests {
 
     #[test]
     fn parse_pieces_with_invalid_fen_returns_error() {
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_pieces("/rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R/"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_pieces("rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_pieces("rnbqk1bnr/8/8/8/8/8/8/8"));
+        assert_eq!(Err(FenError::WrongRankCount(String::from("/rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R/"))), Board::parse_pieces("/rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R/"));
+        assert_eq!(Err(FenError::WrongRankCount(String::from("rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP"))), Board::parse_pieces("rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP"));
+        assert_eq!(Err(FenError::InvalidRank(String::from("rnbqk1bnr"))), Board::parse_pieces("rnbqk1bnr/8/8/8/8/8/8/8"));
     }
 
     #[test]
@@ -446,37 +677,65 @@ mod tests {
 
     #[test]
     fn parse_color_with_invalid_fen_returns_error() {
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_color_to_move("W"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_color_to_move(""));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_color_to_move("nonsense"));
+        assert_eq!(Err(FenError::InvalidColorToMove(String::from("W"))), Board::parse_color_to_move("W"));
+        assert_eq!(Err(FenError::InvalidColorToMove(String::from(""))), Board::parse_color_to_move(""));
+        assert_eq!(Err(FenError::InvalidColorToMove(String::from("nonsense"))), Board::parse_color_to_move("nonsense"));
     }
 
     #[test]
     fn parse_castling_rights_with_valid_fen_returns_castling_rights() {
-        assert_eq!([CastlingRights::NoRights, CastlingRights::NoRights], Board::parse_castling_rights("-").unwrap());
-        assert_eq!([CastlingRights::NoRights, CastlingRights::QueenSide], Board::parse_castling_rights("q").unwrap());
-        assert_eq!([CastlingRights::NoRights, CastlingRights::KingSide], Board::parse_castling_rights("k").unwrap());
-        assert_eq!([CastlingRights::NoRights, CastlingRights::Both], Board::parse_castling_rights("kq").unwrap());
-        assert_eq!([CastlingRights::QueenSide, CastlingRights::NoRights], Board::parse_castling_rights("Q").unwrap());
-        assert_eq!([CastlingRights::QueenSide, CastlingRights::QueenSide], Board::parse_castling_rights("Qq").unwrap());
-        assert_eq!([CastlingRights::QueenSide, CastlingRights::KingSide], Board::parse_castling_rights("Qk").unwrap());
-        assert_eq!([CastlingRights::QueenSide, CastlingRights::Both], Board::parse_castling_rights("Qkq").unwrap());
-        assert_eq!([CastlingRights::KingSide, CastlingRights::NoRights], Board::parse_castling_rights("K").unwrap());
-        assert_eq!([CastlingRights::KingSide, CastlingRights::QueenSide], Board::parse_castling_rights("Kq").unwrap());
-        assert_eq!([CastlingRights::KingSide, CastlingRights::KingSide], Board::parse_castling_rights("Kk").unwrap());
-        assert_eq!([CastlingRights::KingSide, CastlingRights::Both], Board::parse_castling_rights("Kkq").unwrap());
-        assert_eq!([CastlingRights::Both, CastlingRights::NoRights], Board::parse_castling_rights("KQ").unwrap());
-        assert_eq!([CastlingRights::Both, CastlingRights::QueenSide], Board::parse_castling_rights("KQq").unwrap());
-        assert_eq!([CastlingRights::Both, CastlingRights::KingSide], Board::parse_castling_rights("KQk").unwrap());
-        assert_eq!([CastlingRights::Both, CastlingRights::Both], Board::parse_castling_rights("KQkq").unwrap());
+        let pieces = Board::parse_pieces("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR").unwrap();
+        assert_eq!([CastlingRights::NoRights, CastlingRights::NoRights], Board::parse_castling_rights("-", &pieces).unwrap().0);
+        assert_eq!([CastlingRights::NoRights, CastlingRights::QueenSide], Board::parse_castling_rights("q", &pieces).unwrap().0);
+        assert_eq!([CastlingRights::NoRights, CastlingRights::KingSide], Board::parse_castling_rights("k", &pieces).unwrap().0);
+        assert_eq!([CastlingRights::NoRights, CastlingRights::Both], Board::parse_castling_rights("kq", &pieces).unwrap().0);
+        assert_eq!([CastlingRights::QueenSide, CastlingRights::NoRights], Board::parse_castling_rights("Q", &pieces).unwrap().0);
+        assert_eq!([CastlingRights::QueenSide, CastlingRights::QueenSide], Board::parse_castling_rights("Qq", &pieces).unwrap().0);
+        assert_eq!([CastlingRights::QueenSide, CastlingRights::KingSide], Board::parse_castling_rights("Qk", &pieces).unwrap().0);
+        assert_eq!([CastlingRights::QueenSide, CastlingRights::Both], Board::parse_castling_rights("Qkq", &pieces).unwrap().0);
+        assert_eq!([CastlingRights::KingSide, CastlingRights::NoRights], Board::parse_castling_rights("K", &pieces).unwrap().0);
+        assert_eq!([CastlingRights::KingSide, CastlingRights::QueenSide], Board::parse_castling_rights("Kq", &pieces).unwrap().0);
+        assert_eq!([CastlingRights::KingSide, CastlingRights::KingSide], Board::parse_castling_rights("Kk", &pieces).unwrap().0);
+        assert_eq!([CastlingRights::KingSide, CastlingRights::Both], Board::parse_castling_rights("Kkq", &pieces).unwrap().0);
+        assert_eq!([CastlingRights::Both, CastlingRights::NoRights], Board::parse_castling_rights("KQ", &pieces).unwrap().0);
+        assert_eq!([CastlingRights::Both, CastlingRights::QueenSide], Board::parse_castling_rights("KQq", &pieces).unwrap().0);
+        assert_eq!([CastlingRights::Both, CastlingRights::KingSide], Board::parse_castling_rights("KQk", &pieces).unwrap().0);
+        assert_eq!([CastlingRights::Both, CastlingRights::Both], Board::parse_castling_rights("KQkq", &pieces).unwrap().0);
     }
 
     #[test]
     fn parse_castling_rights_with_invalid_fen_returns_error() {
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_castling_rights("KQkqq"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_castling_rights("kqKQ"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_castling_rights("nonsense"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_castling_rights("12345"));
+        let pieces = Board::parse_pieces("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR").unwrap();
+        assert_eq!(Err(FenError::InvalidCastlingRights(String::from("KQkqq"))), Board::parse_castling_rights("KQkqq", &pieces));
+        assert_eq!(Err(FenError::InvalidCastlingRights(String::from("kqKQ"))), Board::parse_castling_rights("kqKQ", &pieces));
+        assert_eq!(Err(FenError::InvalidCastlingRights(String::from("nonsense"))), Board::parse_castling_rights("nonsense", &pieces));
+        assert_eq!(Err(FenError::InvalidCastlingRights(String::from("12345"))), Board::parse_castling_rights("12345", &pieces));
+    }
+
+    #[test]
+    fn parse_castling_rights_with_chess960_fen_returns_castling_rooks() {
+        let pieces = Board::parse_pieces("rkrbbnnq/pppppppp/8/8/8/8/PPPPPPPP/RKRBBNNQ").unwrap();
+        let castling_rooks = CastlingRooks { king_side: File::C, queen_side: File::A };
+
+        // X-FEN
+        assert_eq!(([CastlingRights::Both; 2], [castling_rooks; 2]), Board::parse_castling_rights("KQkq", &pieces).unwrap());
+        // Shredder-FEN
+        assert_eq!(([CastlingRights::Both; 2], [castling_rooks; 2]), Board::parse_castling_rights("CAca", &pieces).unwrap());
+        let (castling_rights, castling_rooks) = Board::parse_castling_rights("Ca", &pieces).unwrap();
+        assert_eq!([CastlingRights::KingSide, CastlingRights::QueenSide], castling_rights);
+        assert_eq!(File::C, castling_rooks[0].king_side);
+        assert_eq!(File::A, castling_rooks[1].queen_side);
+
+        // two rooks on the same side of the king - K refers to the outermost one, the inner one needs its file
+        let pieces = Board::parse_pieces("4k3/8/8/8/8/8/8/R3K1RR").unwrap();
+        let (castling_rights, castling_rooks) = Board::parse_castling_rights("GQ", &pieces).unwrap();
+        assert_eq!(CastlingRights::Both, castling_rights[0]);
+        assert_eq!(CastlingRooks { king_side: File::G, queen_side: File::A }, castling_rooks[0]);
+        let (_, castling_rooks) = Board::parse_castling_rights("KQ", &pieces).unwrap();
+        assert_eq!(CastlingRooks { king_side: File::H, queen_side: File::A }, castling_rooks[0]);
+
+        // the same side twice
+        assert_eq!(Err(FenError::InvalidCastlingRights(String::from("GK"))), Board::parse_castling_rights("GK", &pieces));
     }
 
     #[test]
@@ -494,14 +753,14 @@ mod tests {
 
     #[test]
     fn parse_en_passant_with_invalid_fen_returns_error() {
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_en_passant(""));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_en_passant("12345"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_en_passant("Nonsense"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_en_passant("G5"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_en_passant("a9"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_en_passant("e0"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_en_passant("f-"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_en_passant("ab"));
+        assert_eq!(Err(FenError::InvalidEnPassant(String::from(""))), Board::parse_en_passant(""));
+        assert_eq!(Err(FenError::InvalidEnPassant(String::from("12345"))), Board::parse_en_passant("12345"));
+        assert_eq!(Err(FenError::InvalidEnPassant(String::from("Nonsense"))), Board::parse_en_passant("Nonsense"));
+        assert_eq!(Err(FenError::InvalidEnPassant(String::from("G5"))), Board::parse_en_passant("G5"));
+        assert_eq!(Err(FenError::InvalidEnPassant(String::from("a9"))), Board::parse_en_passant("a9"));
+        assert_eq!(Err(FenError::InvalidEnPassant(String::from("e0"))), Board::parse_en_passant("e0"));
+        assert_eq!(Err(FenError::InvalidEnPassant(String::from("f-"))), Board::parse_en_passant("f-"));
+        assert_eq!(Err(FenError::InvalidEnPassant(String::from("ab"))), Board::parse_en_passant("ab"));
     }
 
     #[test]
@@ -515,10 +774,10 @@ mod tests {
 
     #[test]
     fn parse_halfmove_clock_with_invalid_fen_returns_error() {
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_halfmove_clock("-5"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_halfmove_clock("Nonsense"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_halfmove_clock("a"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_halfmove_clock("I like Rust"));
+        assert_eq!(Err(FenError::InvalidHalfmoveClock(String::from("-5"))), Board::parse_halfmove_clock("-5"));
+        assert_eq!(Err(FenError::InvalidHalfmoveClock(String::from("Nonsense"))), Board::parse_halfmove_clock("Nonsense"));
+        assert_eq!(Err(FenError::InvalidHalfmoveClock(String::from("a"))), Board::parse_halfmove_clock("a"));
+        assert_eq!(Err(FenError::InvalidHalfmoveClock(String::from("I like Rust"))), Board::parse_halfmove_clock("I like Rust"));
     }
 
     #[test]
@@ -532,19 +791,15 @@ mod tests {
 
     #[test]
     fn parse_fullmove_counter_with_invalid_fen_returns_error() {
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_fullmove_counter("-5"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_fullmove_counter("Nonsense"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_fullmove_counter("a"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_fullmove_counter("I like Rust"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_fullmove_counter("0"));
+        assert_eq!(Err(FenError::InvalidFullmoveCounter(String::from("-5"))), Board::parse_fullmove_counter("-5"));
+        assert_eq!(Err(FenError::InvalidFullmoveCounter(String::from("Nonsense"))), Board::parse_fullmove_counter("Nonsense"));
+        assert_eq!(Err(FenError::InvalidFullmoveCounter(String::from("a"))), Board::parse_fullmove_counter("a"));
+        assert_eq!(Err(FenError::InvalidFullmoveCounter(String::from("I like Rust"))), Board::parse_fullmove_counter("I like Rust"));
+        assert_eq!(Err(FenError::InvalidFullmoveCounter(String::from("0"))), Board::parse_fullmove_counter("0"));
     }
 
     #[test]
     fn test_to_fen() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // position 1 (starting position)
         let board = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
         assert_eq!(board, Board::from_fen(board.to_fen().as_str()).unwrap());
@@ -578,11 +833,23 @@ mod tests {
         assert_eq!(board, Board::from_fen(board.to_fen().as_str()).unwrap());
 
         // position 9
-        let board = Board::from_fen("8/8/8/8/8/8/8/8 w - - 0 1").unwrap();
+        let board = Board::from_fen("8/8/8/8/8/8/8/4K2k w - - 0 1").unwrap();
         assert_eq!(board, Board::from_fen(board.to_fen().as_str()).unwrap());
 
         // position 10
-        let board = Board::from_fen("8/1k6/8/8/5K2/8/8/8 w - e3 0 1").unwrap();
+        let board = Board::from_fen("8/1k6/8/8/4pP2/8/8/4K3 b - f3 0 1").unwrap();
+        assert_eq!(board, Board::from_fen(board.to_fen().as_str()).unwrap());
+    }
+
+    #[test]
+    fn to_fen_with_chess960_position_returns_x_fen() {
+        // the outermost rooks are notated with K and Q
+        let board = Board::from_fen("rkrbbnnq/pppppppp/8/8/8/8/PPPPPPPP/RKRBBNNQ w CAca - 0 1").unwrap();
+        assert_eq!("rkrbbnnq/pppppppp/8/8/8/8/PPPPPPPP/RKRBBNNQ w KQkq - 0 1", board.to_fen());
+
+        // inner rooks are notated with their file
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/R3K1RR w GQ - 0 1").unwrap();
+        assert_eq!("4k3/8/8/8/8/8/8/R3K1RR w GQ - 0 1", board.to_fen());
         assert_eq!(board, Board::from_fen(board.to_fen().as_str()).unwrap());
     }
 }
\ No newline at end of file
@@ -1,4 +1,5 @@
 use std::fmt::{Display, Formatter};
+use crate::move_gen::ply::MoveParseError;
 
 /// Represents a file on a chessboard.
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -38,9 +39,8 @@ impl File {
     }
     
     /// Constructs a new file from a char.
-    pub fn from_char(char: &char) -> Result<File, String> {
-        let char = char.to_ascii_lowercase();
-        match char {
+    pub fn from_char(char: &char) -> Result<File, MoveParseError> {
+        match char.to_ascii_lowercase() {
             'a' => Ok(File::A),
             'b' => Ok(File::B),
             'c' => Ok(File::C),
@@ -49,7 +49,7 @@ impl File {
             'f' => Ok(File::F),
             'g' => Ok(File::G),
             'h' => Ok(File::H),
-            _other => Err(String::from("Invalid char string")),
+            _other => Err(MoveParseError::InvalidFile(*char)),
         }
     }
 
@@ -87,6 +87,7 @@ impl Display for File {
 mod tests {
     use crate::board::file::File;
     use crate::board::file::{NUM_FILES};
+    use crate::move_gen::ply::MoveParseError;
 
     #[test]
     fn to_index_returns_correct_index() {
@@ -146,9 +147,9 @@ mod tests {
     
     #[test]
     fn from_char_with_invalid_char_returns_error() {
-        assert_eq!(Err(String::from("Invalid char string")), File::from_char(&'1'));
-        assert_eq!(Err(String::from("Invalid char string")), File::from_char(&'k'));
-        assert_eq!(Err(String::from("Invalid char string")), File::from_char(&'-'));
+        assert_eq!(Err(MoveParseError::InvalidFile('1')), File::from_char(&'1'));
+        assert_eq!(Err(MoveParseError::InvalidFile('k')), File::from_char(&'k'));
+        assert_eq!(Err(MoveParseError::InvalidFile('-')), File::from_char(&'-'));
     }
     
     #[test]
@@ -1,13 +1,14 @@
 use std::fmt::{Display, Formatter};
-use crate::board::bitboard::Bitboard;
-use crate::board::castling_rights::CastlingRights;
+use crate::board::bitboard::{Bitboard, DARK_SQUARES, LIGHT_SQUARES};
+use crate::board::castling_rights::{CastlingRights, CastlingRooks, get_castling_targets};
 use crate::board::color::{Color, NUM_COLORS};
 use crate::board::file::{File, NUM_FILES};
 use crate::board::piece::{NUM_PIECES, Piece};
 use crate::board::piece::Piece::{King};
 use crate::board::rank::{NUM_RANKS, Rank};
-use crate::board::square;
 use crate::board::square::Square;
+use crate::evaluation::parameters::DEFAULT_PARAMETERS;
+use crate::evaluation::Score;
 use crate::lookup::LOOKUP_TABLE;
 use crate::move_gen::ply::Ply;
 use crate::zobrist;
@@ -24,6 +25,9 @@ pub struct Position {
     /// The castling rights for both White and Black.
     pub castling_rights: [CastlingRights; 2],
 
+    /// The files of the rooks White and Black can castle with.
+    pub castling_rooks: [CastlingRooks; 2],
+
     /// If en passant is possible, this Option contains the target square for the en passant move.
     pub en_passant: Option<Square>,
 
@@ -37,8 +41,27 @@ pub struct Position {
     /// The zobrist hash key of the position.
     pub hash: u64,
 
-    /// The attack_bbs for White's and Black's pieces.
-    attack_bb: [Bitboard; 2],
+    /// The material of White's and Black's pieces, based on the default piece values. Kings are not counted.
+    pub material: [i32; 2],
+
+    /// The middlegame and endgame PST scores of White's and Black's pieces, based on the default piece-square tables.
+    pub pst: [Score; 2],
+}
+
+/// The state that is lost when a move is made in place, which is needed to unmake the move again.
+/// Returned by `Position::make_move_in_place`, and kept on a stack by the caller until the move is unmade.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Undo {
+    /// The move that was made, including the captured piece.
+    ply: Ply,
+    /// Whether the move was a castling move.
+    is_castling: bool,
+    /// The castling rights before the move.
+    castling_rights: [CastlingRights; 2],
+    /// The en passant square before the move.
+    en_passant: Option<Square>,
+    /// The hash before the move.
+    hash: u64,
 }
 
 impl Default for Position {
@@ -48,13 +71,15 @@ impl Default for Position {
         let mut position = Self {
             pieces: [[Bitboard::new(0); 6]; 2],
             castling_rights: [CastlingRights::NoRights; 2],
+            castling_rooks: [CastlingRooks::default(); 2],
             en_passant: None,
             color_to_move: Color::White,
             hash: 0,
-            attack_bb: [Bitboard::new(0); 2],
+            material: [0; 2],
+            pst: [Score::default(); 2],
         };
         position.hash = zobrist::get_hash(&position);
-        position.initialize_attack_bb();
+        (position.material, position.pst) = position.get_material_and_pst();
         position
     }
 }
@@ -64,7 +89,7 @@ impl PartialEq for Position {
     /// This has to be done manually because only the fields necessary to uniquely encode a 
     /// chess position should be compared. The attack bitboards are irrelevant.
     fn eq(&self, other: &Self) -> bool {
-        self.pieces == other.pieces && self.castling_rights == other.castling_rights &&
+        self.pieces == other.pieces && self.castling_rights == other.castling_rights && self.get_active_castling_rooks() == other.get_active_castling_rooks() &&
             self.en_passant == other.en_passant && self.color_to_move == other.color_to_move
     }
 }
@@ -75,13 +100,15 @@ impl Position {
         let mut position = Self {
             pieces,
             castling_rights,
+            castling_rooks: [CastlingRooks::default(); 2],
             en_passant,
             color_to_move,
             hash: 0,
-            attack_bb: [Bitboard::new(0); 2],
+            material: [0; 2],
+            pst: [Score::default(); 2],
         };
         position.hash = zobrist::get_hash(&position);
-        position.initialize_attack_bb();
+        (position.material, position.pst) = position.get_material_and_pst();
         position
     }
 
@@ -89,13 +116,23 @@ impl Position {
     ///
     /// This method DOES NOT check if there already is another piece on that square,
     /// so use `get_piece` to check if the square is unoccupied first.
+    /// The material and PST scores are updated unconditionally, like the hash in `make_move`.
     pub fn set_piece(&mut self, piece: Piece, color: Color, square: Square) {
         self.pieces[color.to_index() as usize][piece.to_index() as usize].set_bit(square);
+        // update material and pst
+        self.material[color.to_index() as usize] += get_material_value(piece);
+        self.pst[color.to_index() as usize] += DEFAULT_PARAMETERS.get_pst_score(piece, square, color);
     }
 
     /// Removes a piece of the given color from the given square.
+    ///
+    /// This method DOES NOT check if the piece is actually on that square, so the material and PST scores
+    /// are only correct if it is.
     pub fn remove_piece(&mut self, piece: Piece, color: Color, square: Square) {
         self.pieces[color.to_index() as usize][piece.to_index() as usize].pop_bit(square);
+        // update material and pst
+        self.material[color.to_index() as usize] -= get_material_value(piece);
+        self.pst[color.to_index() as usize] -= DEFAULT_PARAMETERS.get_pst_score(piece, square, color);
     }
 
     /// Returns the piece and the piece's color on the specified square.
@@ -103,9 +140,8 @@ impl Position {
     pub fn get_piece(&self, square: Square) -> Option<(Piece, Color)> {
         for color_index in 0..NUM_COLORS {
             for piece_index in 0..NUM_PIECES {
-                match self.pieces[color_index as usize][piece_index as usize].get_bit(square) {
-                    true => return Some((Piece::from_index(piece_index), Color::from_index(color_index))),
-                    false => {}
+                if self.pieces[color_index as usize][piece_index as usize].get_bit(square) {
+                    return Some((Piece::from_index(piece_index), Color::from_index(color_index)));
                 }
             }
         }
@@ -136,8 +172,14 @@ impl Position {
     ///
     /// For example `get_attack_bb(Color::White)` will return a bitboard with all squares
     /// set that are attacked by any of White's pieces.
+    /// The attack bitboard is computed on demand, so prefer `is_square_attacked` to check single squares.
     pub fn get_attack_bb(&self, color: Color) -> Bitboard {
-        self.attack_bb[color.to_index() as usize]
+        let mut attack_bb = Bitboard::new(0);
+        // `or` the attack bitboards for all pieces of the given color
+        for piece_index in 0..NUM_PIECES {
+            attack_bb.value |= self.get_piece_attack_bb(Piece::from_index(piece_index), color).value;
+        }
+        attack_bb
     }
 
     /// Returns the attack bitboard for the given type of piece of the given color.
@@ -146,7 +188,7 @@ impl Position {
     /// set that are attacked by White's bishops.
     pub fn get_piece_attack_bb(&self, piece: Piece, color: Color) -> Bitboard {
         // get a reference to the lookup table
-        let lookup = LOOKUP_TABLE.get().unwrap();
+        let lookup = &*LOOKUP_TABLE;
         // the result attack_bb
         let mut attack_bb = Bitboard::new(0);
         // the bitboard for the given piece of the given color
@@ -173,7 +215,23 @@ impl Position {
 
     /// Returns whether the given square is attacked by a piece of the given color.
     pub fn is_square_attacked(&self, square: Square, color: Color) -> bool {
-        self.get_attack_bb(color).get_bit(square)
+        self.get_attackers(square, color).value != 0
+    }
+
+    /// Returns the pieces of the given color that attack the given square.
+    ///
+    /// Instead of computing all attacks of the given color, the attacks of each piece type are computed from the square itself,
+    /// since a piece on the square would attack exactly the squares the attacking pieces of the same type could be on.
+    pub fn get_attackers(&self, square: Square, color: Color) -> Bitboard {
+        let lookup = &*LOOKUP_TABLE;
+        let pieces = &self.pieces[color.to_index() as usize];
+        let blockers = self.get_occupancies();
+        let queens = pieces[Piece::Queen.to_index() as usize].value;
+        Bitboard::new(lookup.get_pawn_attacks(square, color.other()).value & pieces[Piece::Pawn.to_index() as usize].value |
+            lookup.get_knight_attacks(square).value & pieces[Piece::Knight.to_index() as usize].value |
+            lookup.get_king_attacks(square).value & pieces[King.to_index() as usize].value |
+            lookup.get_bishop_attacks(square, blockers).value & (pieces[Piece::Bishop.to_index() as usize].value | queens) |
+            lookup.get_rook_attacks(square, blockers).value & (pieces[Piece::Rook.to_index() as usize].value | queens))
     }
 
     /// Returns whether the king of the given color is in check
@@ -182,6 +240,12 @@ impl Position {
         self.is_square_attacked(king_square, color.other())
     }
 
+    /// Returns whether a pawn of the given color attacks the given en passant square, which means that it could capture en passant.
+    pub fn is_en_passant_capturable(&self, en_passant: Square, color: Color) -> bool {
+        let attacker_squares = LOOKUP_TABLE.get_pawn_attacks(en_passant, color.other());
+        attacker_squares.value & self.pieces[color.to_index() as usize][Piece::Pawn.to_index() as usize].value != 0
+    }
+
     /// Returns whether the position is legal.
     /// Specifically, it validates that:
     /// - both sides have exactly 1 king
@@ -192,53 +256,91 @@ impl Position {
             !self.is_in_check(self.color_to_move.other())
     }
 
+    /// Returns the files of the castling rooks for the kingside and queenside of both colors, or `None` for sides without castling rights.
+    fn get_active_castling_rooks(&self) -> [(Option<File>, Option<File>); 2] {
+        [Color::White, Color::Black].map(|color| {
+            let castling_rights = self.castling_rights[color.to_index() as usize];
+            let castling_rooks = self.castling_rooks[color.to_index() as usize];
+            (
+                (castling_rights == CastlingRights::KingSide || castling_rights == CastlingRights::Both).then_some(castling_rooks.king_side),
+                (castling_rights == CastlingRights::QueenSide || castling_rights == CastlingRights::Both).then_some(castling_rooks.queen_side),
+            )
+        })
+    }
+
+    /// Returns whether the given move (ply) is a castling move.
+    ///
+    /// Castling moves are encoded as the king capturing its own rook, which works for both standard chess and Chess960.
+    pub fn is_castling(&self, ply: Ply) -> bool {
+        ply.piece == King && self.pieces[self.color_to_move.to_index() as usize][Piece::Rook.to_index() as usize].get_bit(ply.target)
+    }
+
     /// Returns a new position that reflects the board state where the given move (ply) has been played.
     pub fn make_move(&self, ply: Ply) -> Position {
         let mut position = *self;
+        position.make_move_in_place(ply);
+        position
+    }
+
+    /// Plays the given move (ply) on this position instead of copying it, which is faster when searching a lot of positions.
+    /// Returns the state needed to take the move back with `unmake_move`.
+    pub fn make_move_in_place(&mut self, ply: Ply) -> Undo {
+        let color = self.color_to_move;
+        let previous_castling_rights = self.castling_rights;
+        let previous_en_passant = self.en_passant;
+        let is_castling = self.is_castling(ply);
+        let is_en_passant = ply.piece == Piece::Pawn && previous_en_passant == Some(ply.target);
+        let undo = Undo {
+            ply,
+            is_castling,
+            castling_rights: previous_castling_rights,
+            en_passant: previous_en_passant,
+            hash: self.hash,
+        };
 
         // -----------------------------------------------------------------------------------------------------------------------
         // remove piece from old position
         // -----------------------------------------------------------------------------------------------------------------------
-        position.remove_piece(ply.piece, self.color_to_move, ply.source);
+        self.remove_piece(ply.piece, color, ply.source);
         // update hash
-        position.hash ^= zobrist::random::get_random_piece(ply.piece, self.color_to_move, ply.source);
+        self.hash ^= zobrist::random::get_random_piece(ply.piece, color, ply.source);
 
         // -----------------------------------------------------------------------------------------------------------------------
         // remove capture piece
         // -----------------------------------------------------------------------------------------------------------------------
         if let Some(piece) = ply.captured_piece {
-            position.remove_piece(piece, self.color_to_move.other(), ply.target);
-            // update hash
-            position.hash ^= zobrist::random::get_random_piece(piece, self.color_to_move.other(), ply.target);
+            // the pawn captured en passant is not on the target square, it is removed below
+            if !is_en_passant {
+                self.remove_piece(piece, color.other(), ply.target);
+                // update hash
+                self.hash ^= zobrist::random::get_random_piece(piece, color.other(), ply.target);
+            }
 
             // if the captured piece was a rook on its starting square, remove castling rights of opponent for the rooks side (queenside or kingside)
-            if ply.captured_piece == Some(Piece::Rook) && ply.target.get_rank() == position.color_to_move.other().back_rank() {
-                match ply.target.get_file() {
-                    File::A => {
-                        // remove queenside castling rights
-                        if self.castling_rights[self.color_to_move.other().to_index() as usize] == CastlingRights::KingSide || self.castling_rights[self.color_to_move.other().to_index() as usize] == CastlingRights::Both {
-                            position.castling_rights[self.color_to_move.other().to_index() as usize] = CastlingRights::KingSide;
-                        } else {
-                            position.castling_rights[self.color_to_move.other().to_index() as usize] = CastlingRights::NoRights;
-                        }
-                        // update hash
-                        if self.castling_rights[self.color_to_move.other().to_index() as usize] == CastlingRights::QueenSide || self.castling_rights[self.color_to_move.other().to_index() as usize] == CastlingRights::Both {
-                            position.hash ^= zobrist::random::get_random_castling(CastlingRights::QueenSide, self.color_to_move.other());
-                        }
+            let castling_rooks = self.castling_rooks[color.other().to_index() as usize];
+            if ply.captured_piece == Some(Piece::Rook) && ply.target.get_rank() == color.other().back_rank() {
+                if ply.target.get_file() == castling_rooks.queen_side {
+                    // remove queenside castling rights
+                    if previous_castling_rights[color.other().to_index() as usize] == CastlingRights::KingSide || previous_castling_rights[color.other().to_index() as usize] == CastlingRights::Both {
+                        self.castling_rights[color.other().to_index() as usize] = CastlingRights::KingSide;
+                    } else {
+                        self.castling_rights[color.other().to_index() as usize] = CastlingRights::NoRights;
+                    }
+                    // update hash
+                    if previous_castling_rights[color.other().to_index() as usize] == CastlingRights::QueenSide || previous_castling_rights[color.other().to_index() as usize] == CastlingRights::Both {
+                        self.hash ^= zobrist::random::get_random_castling(CastlingRights::QueenSide, color.other());
+                    }
+                } else if ply.target.get_file() == castling_rooks.king_side {
+                    // remove kingside castling rights
+                    if previous_castling_rights[color.other().to_index() as usize] == CastlingRights::QueenSide || previous_castling_rights[color.other().to_index() as usize] == CastlingRights::Both {
+                        self.castling_rights[color.other().to_index() as usize] = CastlingRights::QueenSide;
+                    } else {
+                        self.castling_rights[color.other().to_index() as usize] = CastlingRights::NoRights;
                     }
-                    File::H => {
-                        // remove kingside castling rights
-                        if self.castling_rights[self.color_to_move.other().to_index() as usize] == CastlingRights::QueenSide || self.castling_rights[self.color_to_move.other().to_index() as usize] == CastlingRights::Both {
-                            position.castling_rights[self.color_to_move.other().to_index() as usize] = CastlingRights::QueenSide;
-                        } else {
-                            position.castling_rights[self.color_to_move.other().to_index() as usize] = CastlingRights::NoRights;
-                        }
-                        // update hash
-                        if self.castling_rights[self.color_to_move.other().to_index() as usize] == CastlingRights::KingSide || self.castling_rights[self.color_to_move.other().to_index() as usize] == CastlingRights::Both {
-                            position.hash ^= zobrist::random::get_random_castling(CastlingRights::KingSide, self.color_to_move.other());
-                        }
+                    // update hash
+                    if previous_castling_rights[color.other().to_index() as usize] == CastlingRights::KingSide || previous_castling_rights[color.other().to_index() as usize] == CastlingRights::Both {
+                        self.hash ^= zobrist::random::get_random_castling(CastlingRights::KingSide, color.other());
                     }
-                    _other => {}
                 }
             }
         }
@@ -247,86 +349,49 @@ impl Position {
         // set piece on new position
         // -----------------------------------------------------------------------------------------------------------------------
         match ply.promotion_piece {
+            // move is castling - the king is set below, since its target square is not the target square of the ply
+            _ if is_castling => {}
             // move is a promotion - set promotion piece
             Some(piece) => {
-                position.set_piece(piece, self.color_to_move, ply.target);
+                self.set_piece(piece, color, ply.target);
                 // update hash
-                position.hash ^= zobrist::random::get_random_piece(piece, self.color_to_move, ply.target);
+                self.hash ^= zobrist::random::get_random_piece(piece, color, ply.target);
             }
             // move is not a promotion - set piece specified in ply
             None => {
-                position.set_piece(ply.piece, self.color_to_move, ply.target);
+                self.set_piece(ply.piece, color, ply.target);
                 // update hash
-                position.hash ^= zobrist::random::get_random_piece(ply.piece, self.color_to_move, ply.target);
+                self.hash ^= zobrist::random::get_random_piece(ply.piece, color, ply.target);
             }
         }
 
         // -----------------------------------------------------------------------------------------------------------------------
-        // in case of castling, set the rook
+        // in case of castling, set the king and the rook
         // -----------------------------------------------------------------------------------------------------------------------
-        let mut rook_source: Option<Square> = None;
-        let mut rook_target: Option<Square> = None;
-        if ply.piece == Piece::King {
-            match (ply.source, ply.target) {
-                // black castles queenside
-                (square::E8, square::C8) => {
-                    // remove rook from old position
-                    position.remove_piece(Piece::Rook, self.color_to_move, square::A8);
-                    rook_source = Some(square::A8);
-                    // set rook on new position
-                    position.set_piece(Piece::Rook, self.color_to_move, square::D8);
-                    rook_target = Some(square::D8);
-                }
-                // black castles kingside
-                (square::E8, square::G8) => {
-                    // remove rook from old position
-                    position.remove_piece(Piece::Rook, self.color_to_move, square::H8);
-                    rook_source = Some(square::H8);
-                    // set rook on new position
-                    position.set_piece(Piece::Rook, self.color_to_move, square::F8);
-                    rook_target = Some(square::F8);
-                }
-                // white castles queenside
-                (square::E1, square::C1) => {
-                    // remove rook from old position
-                    position.remove_piece(Piece::Rook, self.color_to_move, square::A1);
-                    rook_source = Some(square::A1);
-                    // set rook on new position
-                    position.set_piece(Piece::Rook, self.color_to_move, square::D1);
-                    rook_target = Some(square::D1);
-                }
-                // white castles kingside
-                (square::E1, square::G1) => {
-                    // remove rook from old position
-                    position.remove_piece(Piece::Rook, self.color_to_move, square::H1);
-                    rook_source = Some(square::H1);
-                    // set rook on new position
-                    position.set_piece(Piece::Rook, self.color_to_move, square::F1);
-                    rook_target = Some(square::F1);
-                }
-                _other => {}
-            }
-            if let Some(square) = rook_source {
-                // update hash
-                position.hash ^= zobrist::random::get_random_piece(Piece::Rook, self.color_to_move, square);
-            }
-            if let Some(square) = rook_target {
-                // update hash
-                position.hash ^= zobrist::random::get_random_piece(Piece::Rook, self.color_to_move, square);
-            }
+        if is_castling {
+            let (king_target, rook_target) = get_castling_targets(color, ply.target.get_file().to_index() > ply.source.get_file().to_index());
+            // remove rook from old position
+            self.remove_piece(Piece::Rook, color, ply.target);
+            // update hash
+            self.hash ^= zobrist::random::get_random_piece(Piece::Rook, color, ply.target);
+            // set king on new position
+            self.set_piece(King, color, king_target);
+            // update hash
+            self.hash ^= zobrist::random::get_random_piece(King, color, king_target);
+            // set rook on new position
+            self.set_piece(Piece::Rook, color, rook_target);
+            // update hash
+            self.hash ^= zobrist::random::get_random_piece(Piece::Rook, color, rook_target);
         }
 
         // -----------------------------------------------------------------------------------------------------------------------
         // in case of en passant, remove opponent pawn from 4th or 5th rank
         // -----------------------------------------------------------------------------------------------------------------------
-        if let Some(square) = self.en_passant {
-            if ply.piece == Piece::Pawn && square == ply.target {
-                position.remove_piece(Piece::Pawn, self.color_to_move.other(), Square::from_file_rank(ply.target.get_file(), self.color_to_move.other().double_pawn_push_target_rank()));
-                // update hash
-                position.hash ^= zobrist::random::get_random_piece(Piece::Pawn, self.color_to_move.other(), Square::from_file_rank(ply.target.get_file(), self.color_to_move.other().double_pawn_push_target_rank()));
-                // move is en passant - restore wrongly removed pawn hash (on en passant target square)
-                position.hash ^= zobrist::random::get_random_piece(Piece::Pawn, self.color_to_move.other(), ply.target);
-            }
+        if is_en_passant {
+            let captured_square = Square::from_file_rank(ply.target.get_file(), color.other().double_pawn_push_target_rank());
+            self.remove_piece(Piece::Pawn, color.other(), captured_square);
+            // update hash
+            self.hash ^= zobrist::random::get_random_piece(Piece::Pawn, color.other(), captured_square);
         }
 
         // -----------------------------------------------------------------------------------------------------------------------
@@ -334,30 +399,30 @@ impl Position {
         // -----------------------------------------------------------------------------------------------------------------------
         if ply.piece == King {
             // move is a king move - no rights
-            position.castling_rights[self.color_to_move.to_index() as usize] = CastlingRights::NoRights;
+            self.castling_rights[color.to_index() as usize] = CastlingRights::NoRights;
             // update hash
-            position.hash ^= zobrist::random::get_random_castling(self.castling_rights[self.color_to_move.to_index() as usize], self.color_to_move);
-        } else if ply.piece == Piece::Rook && ply.source == Square::from_file_rank(File::A, self.color_to_move.back_rank()) {
-            // move is A file rook move - remove queenside rights
-            match self.castling_rights[self.color_to_move.to_index() as usize] {
-                CastlingRights::Both => position.castling_rights[self.color_to_move.to_index() as usize] = CastlingRights::KingSide,
-                CastlingRights::KingSide => position.castling_rights[self.color_to_move.to_index() as usize] = CastlingRights::KingSide,
-                _other => position.castling_rights[self.color_to_move.to_index() as usize] = CastlingRights::NoRights,
+            self.hash ^= zobrist::random::get_random_castling(previous_castling_rights[color.to_index() as usize], color);
+        } else if ply.piece == Piece::Rook && ply.source == Square::from_file_rank(self.castling_rooks[color.to_index() as usize].queen_side, color.back_rank()) {
+            // move is queenside rook move - remove queenside rights
+            match previous_castling_rights[color.to_index() as usize] {
+                CastlingRights::Both => self.castling_rights[color.to_index() as usize] = CastlingRights::KingSide,
+                CastlingRights::KingSide => self.castling_rights[color.to_index() as usize] = CastlingRights::KingSide,
+                _other => self.castling_rights[color.to_index() as usize] = CastlingRights::NoRights,
             }
             // update hash
-            if self.castling_rights[self.color_to_move as usize] == CastlingRights::QueenSide || self.castling_rights[self.color_to_move as usize] == CastlingRights::Both {
-                position.hash ^= zobrist::random::get_random_castling(CastlingRights::QueenSide, self.color_to_move);
+            if previous_castling_rights[color as usize] == CastlingRights::QueenSide || previous_castling_rights[color as usize] == CastlingRights::Both {
+                self.hash ^= zobrist::random::get_random_castling(CastlingRights::QueenSide, color);
             }
-        } else if ply.piece == Piece::Rook && ply.source == Square::from_file_rank(File::H, self.color_to_move.back_rank()) {
-            // move is H file rook move - remove kingside rights
-            match self.castling_rights[self.color_to_move.to_index() as usize] {
-                CastlingRights::Both => position.castling_rights[self.color_to_move.to_index() as usize] = CastlingRights::QueenSide,
-                CastlingRights::QueenSide => position.castling_rights[self.color_to_move.to_index() as usize] = CastlingRights::QueenSide,
-                _other => position.castling_rights[self.color_to_move.to_index() as usize] = CastlingRights::NoRights,
+        } else if ply.piece == Piece::Rook && ply.source == Square::from_file_rank(self.castling_rooks[color.to_index() as usize].king_side, color.back_rank()) {
+            // move is kingside rook move - remove kingside rights
+            match previous_castling_rights[color.to_index() as usize] {
+                CastlingRights::Both => self.castling_rights[color.to_index() as usize] = CastlingRights::QueenSide,
+                CastlingRights::QueenSide => self.castling_rights[color.to_index() as usize] = CastlingRights::QueenSide,
+                _other => self.castling_rights[color.to_index() as usize] = CastlingRights::NoRights,
             }
             // update hash
-            if self.castling_rights[self.color_to_move as usize] == CastlingRights::KingSide || self.castling_rights[self.color_to_move as usize] == CastlingRights::Both {
-                position.hash ^= zobrist::random::get_random_castling(CastlingRights::KingSide, self.color_to_move);
+            if previous_castling_rights[color as usize] == CastlingRights::KingSide || previous_castling_rights[color as usize] == CastlingRights::Both {
+                self.hash ^= zobrist::random::get_random_castling(CastlingRights::KingSide, color);
             }
         }
 
@@ -365,51 +430,125 @@ impl Position {
         // update en_passant
         // -----------------------------------------------------------------------------------------------------------------------
         // update hash
-        if let Some(square) = self.en_passant {
+        if let Some(square) = previous_en_passant {
             // if the previous position had an en passant square set, remove it from the hash
-            position.hash ^= zobrist::random::get_random_en_passant(Some(square.get_file()));
+            self.hash ^= zobrist::random::get_random_en_passant(Some(square.get_file()));
         }
-        if ply.piece == Piece::Pawn && ply.source.get_rank() == self.color_to_move.pawn_rank() &&
-            ply.target.get_rank() == self.color_to_move.double_pawn_push_target_rank() {
-            position.en_passant = Some(Square::from_file_rank(ply.source.get_file(), self.color_to_move.other().en_passant_target_rank()));
+        let en_passant = Square::from_file_rank(ply.source.get_file(), color.other().en_passant_target_rank());
+        // the en passant square is only set if an opposing pawn could capture en passant, so that transpositions have the same hash
+        if ply.piece == Piece::Pawn && ply.source.get_rank() == color.pawn_rank() &&
+            ply.target.get_rank() == color.double_pawn_push_target_rank() &&
+            self.is_en_passant_capturable(en_passant, color.other()) {
+            self.en_passant = Some(en_passant);
             // update hash
-            position.hash ^= zobrist::random::get_random_en_passant(Some(ply.source.get_file()));
+            self.hash ^= zobrist::random::get_random_en_passant(Some(ply.source.get_file()));
         } else {
-            position.en_passant = None;
+            self.en_passant = None;
         }
 
         // -----------------------------------------------------------------------------------------------------------------------
         // update color_to_move
         // -----------------------------------------------------------------------------------------------------------------------
-        position.color_to_move = self.color_to_move.other();
+        self.color_to_move = color.other();
         // update hash
-        position.hash ^= zobrist::random::get_random_turn(Color::White);
+        self.hash ^= zobrist::random::get_random_turn(Color::White);
 
-        // initialize the attack bitboards for the new position
-        position.initialize_attack_bb();
-
-        position
+        undo
     }
 
-    /// Initializes the attack bitboards for both colors.
-    pub fn initialize_attack_bb(&mut self) {
-        // calculate attack_bb for both colors
-        for color_index in 0..NUM_COLORS {
-            // the result attack_bb for the color
-            let mut attack_bb = Bitboard::new(0);
-            // `or` the attack bitboards for all pieces of the given color
-            for piece_index in 0..NUM_PIECES {
-                attack_bb.value |= self.get_piece_attack_bb(Piece::from_index(piece_index), Color::from_index(color_index)).value;
+
+    /// Takes back the move that was played with `make_move_in_place` and returned the given undo state.
+    /// Moves have to be unmade in the reverse order they were made in.
+    pub fn unmake_move(&mut self, undo: Undo) {
+        let ply = undo.ply;
+        let color = self.color_to_move.other();
+        self.color_to_move = color;
+
+        if undo.is_castling {
+            // take back the king and the rook, the rook stands on the target square of the ply before castling
+            let (king_target, rook_target) = get_castling_targets(color, ply.target.get_file().to_index() > ply.source.get_file().to_index());
+            self.remove_piece(Piece::Rook, color, rook_target);
+            self.remove_piece(King, color, king_target);
+            self.set_piece(Piece::Rook, color, ply.target);
+            self.set_piece(King, color, ply.source);
+        } else {
+            // move the piece back, and replace a promoted piece by a pawn
+            self.remove_piece(ply.promotion_piece.unwrap_or(ply.piece), color, ply.target);
+            self.set_piece(ply.piece, color, ply.source);
+
+            // restore the captured piece, which is behind the target square in case of en passant
+            if let Some(piece) = ply.captured_piece {
+                if ply.piece == Piece::Pawn && undo.en_passant == Some(ply.target) {
+                    self.set_piece(Piece::Pawn, color.other(), Square::from_file_rank(ply.target.get_file(), color.other().double_pawn_push_target_rank()));
+                } else {
+                    self.set_piece(piece, color.other(), ply.target);
+                }
             }
-            // add the calculated bb to the result array
-            self.attack_bb[color_index as usize] = attack_bb;
         }
+
+        self.castling_rights = undo.castling_rights;
+        self.en_passant = undo.en_passant;
+        self.hash = undo.hash;
     }
 
     /// Returns the number of pieces of the given type and color.
     pub fn get_num_pieces(&self, piece: Piece, color: Color) -> u8 {
         self.pieces[color.to_index() as usize][piece.to_index() as usize].get_num_active_bits()
     }
+
+    /// Returns whether neither side has enough material left to checkmate the opponent.
+    ///
+    /// This is the case for KvK, KNvK, KBvK, and positions where all remaining bishops are on squares of the same color.
+    pub fn is_insufficient_material(&self) -> bool {
+        let mut knights = 0;
+        let mut bishops = Bitboard::new(0);
+        for color_index in 0..NUM_COLORS as usize {
+            // any pawn, rook or queen is enough to checkmate
+            if self.pieces[color_index][Piece::Pawn.to_index() as usize].value != 0 ||
+                self.pieces[color_index][Piece::Rook.to_index() as usize].value != 0 ||
+                self.pieces[color_index][Piece::Queen.to_index() as usize].value != 0 {
+                return false;
+            }
+            knights += self.pieces[color_index][Piece::Knight.to_index() as usize].get_num_active_bits();
+            bishops.value |= self.pieces[color_index][Piece::Bishop.to_index() as usize].value;
+        }
+
+        match knights {
+            // only bishops left - checkmate is impossible if they are all on the same square color
+            0 => bishops.value & LIGHT_SQUARES.value == 0 || bishops.value & DARK_SQUARES.value == 0,
+            // a single knight can not checkmate
+            1 => bishops.value == 0,
+            _ => false,
+        }
+    }
+
+    /// Calculates the material and the PST scores for both colors from scratch.
+    ///
+    /// The position keeps these values up to date incrementally, so this is only needed when
+    /// constructing a new position, or to verify the incrementally updated values.
+    pub fn get_material_and_pst(&self) -> ([i32; 2], [Score; 2]) {
+        let mut material = [0; 2];
+        let mut pst = [Score::default(); 2];
+        for color in [Color::White, Color::Black] {
+            for piece_index in 0..NUM_PIECES {
+                let piece = Piece::from_index(piece_index);
+                for square in self.pieces[color.to_index() as usize][piece_index as usize].get_active_bits() {
+                    material[color.to_index() as usize] += get_material_value(piece);
+                    pst[color.to_index() as usize] += DEFAULT_PARAMETERS.get_pst_score(piece, square, color);
+                }
+            }
+        }
+        (material, pst)
+    }
+}
+
+/// Returns the default material value of the given piece.
+/// Kings are worth nothing, since both sides always have exactly one.
+fn get_material_value(piece: Piece) -> i32 {
+    match piece {
+        King => 0,
+        _ => DEFAULT_PARAMETERS.piece_values[piece.to_index() as usize],
+    }
 }
 
 /// Prints the position with '.' marking empty squares, capital letters marking white pieces,
@@ -422,12 +561,12 @@ impl Display for Position {
             output += format!("{}  ", rank + 1).as_str();
             for file in 0..NUM_FILES {
                 let piece = self.get_piece(Square::from_file_rank(File::from_index(file), Rank::from_index(rank)));
-                if piece.is_none() {
-                    output += ".  ";
-                } else {
-                    let (piece, color) = piece.unwrap();
-                    output.push(piece.to_char(color));
-                    output += "  ";
+                match piece {
+                    None => output += ".  ",
+                    Some((piece, color)) => {
+                        output.push(piece.to_char(color));
+                        output += "  ";
+                    }
                 }
             }
             output += "\n";
@@ -453,17 +592,14 @@ mod tests {
     use crate::board::piece::Piece;
     use crate::board::piece::Piece::{Bishop, King, Knight, Pawn, Queen, Rook};
     use crate::board::position::Position;
-    use crate::board::square::{A1, A3, E1, E4, F2, F3, G3, H7, H8};
-    use crate::lookup::LOOKUP_TABLE;
-    use crate::lookup::lookup_table::LookupTable;
+    use crate::board::square::{A1, A3, E1, E3, E4, F2, F3, G3, H7, H8};
+    use crate::evaluation::Score;
+    use crate::move_gen;
     use crate::move_gen::ply::Ply;
+    use crate::zobrist;
 
     #[test]
     fn default_returns_position_with_default_values() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         let position = Position::default();
         assert_eq!([[Bitboard::new(0); 6]; 2], position.pieces);
         assert_eq!([NoRights; 2], position.castling_rights);
@@ -512,10 +648,6 @@ mod tests {
 
     #[test]
     fn test_remove_piece() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         let mut position = Board::parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
         position.remove_piece(Piece::Pawn, Color::Black, square::E2);
         assert_eq!(position, Board::parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position);
@@ -535,10 +667,6 @@ mod tests {
 
     #[test]
     fn get_piece_returns_piece_on_specified_square() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         let mut position = Position::default();
         position.set_piece(Knight, Black, E4);
         position.set_piece(King, White, H8);
@@ -557,10 +685,6 @@ mod tests {
 
     #[test]
     fn get_occupancy_returns_occupancy_bb() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // position 1 (starting position)
         let position = Board::parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
         assert_eq!(0xffff, position.get_occupancy(White).value);
@@ -579,10 +703,6 @@ mod tests {
 
     #[test]
     fn get_occupancies_returns_occupancy_bb_for_both_colors() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // position 1 (starting position)
         let position = Board::parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
         assert_eq!(0xffff00000000ffff, position.get_occupancies().value);
@@ -598,25 +718,17 @@ mod tests {
 
     #[test]
     fn position_formats_correctly() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         let position = Board::parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
         let expected_output = "8  r  n  b  q  k  b  n  r  \n7  p  p  p  p  p  p  p  p  \n6  .  .  .  .  .  .  .  .  \n5  .  .  .  .  .  .  .  .  \n4  .  .  .  .  .  .  .  .  \n3  .  .  .  .  .  .  .  .  \n2  P  P  P  P  P  P  P  P  \n1  R  N  B  Q  K  B  N  R  \n   a  b  c  d  e  f  g  h\n\nMove: White\nCastling: Both - Both\nEn Passant: None\n";
         assert_eq!(expected_output, format!("{}", position));
 
-        let position = Board::parse_fen("r1bq1rk1/1pp1bppp/p1n2n2/4p3/2PpP1P1/P2P1Q1P/1P1N1P2/R1B1KBNR b KQ g3 0 9").unwrap().position;
-        let expected_output = "8  r  .  b  q  .  r  k  .  \n7  .  p  p  .  b  p  p  p  \n6  p  .  n  .  .  n  .  .  \n5  .  .  .  .  p  .  .  .  \n4  .  .  P  p  P  .  P  .  \n3  P  .  .  P  .  Q  .  P  \n2  .  P  .  N  .  P  .  .  \n1  R  .  B  .  K  B  N  R  \n   a  b  c  d  e  f  g  h\n\nMove: Black\nCastling: Both - NoRights\nEn Passant: g3\n";
+        let position = Board::parse_fen("r1bq1rk1/1pp1bppp/p1n2n2/4p3/2PpP1P1/P2P1Q1P/1P1N1P2/R1B1KBNR b KQ e3 0 9").unwrap().position;
+        let expected_output = "8  r  .  b  q  .  r  k  .  \n7  .  p  p  .  b  p  p  p  \n6  p  .  n  .  .  n  .  .  \n5  .  .  .  .  p  .  .  .  \n4  .  .  P  p  P  .  P  .  \n3  P  .  .  P  .  Q  .  P  \n2  .  P  .  N  .  P  .  .  \n1  R  .  B  .  K  B  N  R  \n   a  b  c  d  e  f  g  h\n\nMove: Black\nCastling: Both - NoRights\nEn Passant: e3\n";
         assert_eq!(expected_output, format!("{}", position));
     }
 
     #[test]
     fn test_get_attack_bb() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // -----------------------------------------------------------------------------------------
         // position 1 (starting position)
         // -----------------------------------------------------------------------------------------
@@ -644,10 +756,6 @@ mod tests {
 
     #[test]
     fn test_get_piece_attack_bb() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // -----------------------------------------------------------------------------------------
         // position 1 (starting position)
         // -----------------------------------------------------------------------------------------
@@ -708,10 +816,6 @@ mod tests {
 
     #[test]
     fn test_is_square_attacked() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         let position = Board::from_fen("5rk1/pppr1pp1/7p/3q4/3P4/P3R1PP/1P2Q1PK/8 w - - 5 33").unwrap().position;
 
         assert!(position.is_square_attacked(square::B3, White));
@@ -728,11 +832,25 @@ mod tests {
     }
 
     #[test]
-    fn test_is_in_check() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
+    fn test_get_attackers() {
+        let position = Board::from_fen("5rk1/pppr1pp1/7p/3q4/3P4/P3R1PP/1P2Q1PK/8 w - - 5 33").unwrap().position;
+
+        assert_eq!(Bitboard::from_square(E3), position.get_attackers(square::B3, White));
+        assert_eq!(Bitboard::from_square(square::D5), position.get_attackers(square::B3, Black));
+        assert_eq!(Bitboard::from_square(square::E2), position.get_attackers(E1, White));
+        assert_eq!(Bitboard::new(0), position.get_attackers(square::A4, White));
+
+        // the rook on d7 is blocked by the queen
+        assert_eq!(Bitboard::from_square(square::D5), position.get_attackers(square::D4, Black));
 
+        let mut attackers = Bitboard::from_square(E3);
+        attackers.set_bit(square::E2);
+        attackers.set_bit(square::G2);
+        assert_eq!(attackers, position.get_attackers(F3, White));
+    }
+
+    #[test]
+    fn test_is_in_check() {
         // position 1
         let position = Board::from_fen("5rk1/pppr1pp1/7p/3q4/3P4/P3R1PP/1P2Q1PK/8 w - - 5 33").unwrap().position;
         assert!(!position.is_in_check(Color::White));
@@ -764,17 +882,13 @@ mod tests {
         assert!(!position.is_in_check(Color::Black));
 
         // position 7
-        let position = Board::from_fen("r1b1kbnr/1pp3pp/p1n5/4Bp2/2P4q/1P2PP2/P2P2PP/RN1QKB1R b KQkq - 1 8").unwrap().position;
+        let position = Board::from_fen("r1b1kbnr/1pp3pp/p1n5/4Bp2/2P4q/1P2PP2/P2P2PP/RN1QKB1R w KQkq - 1 8").unwrap().position;
         assert!(position.is_in_check(Color::White));
         assert!(!position.is_in_check(Color::Black));
     }
 
     #[test]
     fn is_legal_with_legal_position_returns_true() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // position 1
         assert!(Board::from_fen("5rk1/pppr1pp1/7p/3q4/3P4/P3R1PP/1P2Q1PK/8 w - - 5 33").unwrap().position.is_legal());
 
@@ -796,38 +910,35 @@ mod tests {
 
     #[test]
     fn is_legal_with_illegal_position_returns_false() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
+        // illegal positions are rejected when parsing a FEN, so they have to be constructed directly
+        let illegal_position = |pieces: &str, color_to_move: Color| {
+            Position::new(Board::parse_pieces(pieces).unwrap(), [NoRights; 2], None, color_to_move)
+        };
 
         // position 1
-        assert!(!Board::from_fen("8/8/7Q/8/6p1/5pBk/R4K2/8 w - - 0 67").unwrap().position.is_legal());
+        assert!(!illegal_position("8/8/7Q/8/6p1/5pBk/R4K2/8", White).is_legal());
 
         // position 2
-        assert!(!Board::from_fen("rn2k2r/2pq1ppp/p2b4/1p4B1/2bP4/5N2/PP3PPP/R2QR1K1 w kq - 1 14").unwrap().position.is_legal());
+        assert!(!illegal_position("rn2k2r/2pq1ppp/p2b4/1p4B1/2bP4/5N2/PP3PPP/R2QR1K1", White).is_legal());
 
         // position 3
-        assert!(!Board::from_fen("8/ppp3kp/2b5/5P2/3P2N1/P2B4/1r3K2/8 b - - 0 28").unwrap().position.is_legal());
+        assert!(!illegal_position("8/ppp3kp/2b5/5P2/3P2N1/P2B4/1r3K2/8", Black).is_legal());
 
         // position 4
-        assert!(!Board::from_fen("7Q/ppp2Qkp/2b5/4BP2/3P2N1/P2B4/1r6/8 w - - 0 28").unwrap().position.is_legal());
+        assert!(!illegal_position("7Q/ppp2Qkp/2b5/4BP2/3P2N1/P2B4/1r6/8", White).is_legal());
 
         // position 5
-        assert!(!Board::from_fen("7Q/ppp2Qkp/2b5/4BP2/3P2N1/P2B4/1r6/8 b - - 0 28").unwrap().position.is_legal());
+        assert!(!illegal_position("7Q/ppp2Qkp/2b5/4BP2/3P2N1/P2B4/1r6/8", Black).is_legal());
 
         // position 6
-        assert!(!Board::from_fen("2kR3r/pp5p/5p1b/2p5/8/4N3/PqP1NPPP/5RK1 w - - 2 19").unwrap().position.is_legal());
+        assert!(!illegal_position("2kR3r/pp5p/5p1b/2p5/8/4N3/PqP1NPPP/5RK1", White).is_legal());
 
         // position 7
-        assert!(!Board::from_fen("r1b1kbnr/1pp3pp/p1n5/4Bp2/2P4q/1P2PP2/P2P2PP/RN1QKB1R b KQkq - 1 8").unwrap().position.is_legal());
+        assert!(!illegal_position("r1b1kbnr/1pp3pp/p1n5/4Bp2/2P4q/1P2PP2/P2P2PP/RN1QKB1R", Black).is_legal());
     }
 
     #[test]
     fn test_make_move() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // position 1
 
         // e2-e4
@@ -1083,10 +1194,10 @@ mod tests {
         println!("{position}");
         assert_eq!(Board::from_fen("r3k1r1/pppqnp1P/2n5/3p4/P7/5N1R/1PPPKPP1/RNB2B2 b q a3 0 12").unwrap().position, position);
 
-        // e8-C8
+        // e8-a8 (castling queenside)
         let position = position.make_move(Ply {
             source: square::E8,
-            target: square::C8,
+            target: square::A8,
             piece: Piece::King,
             captured_piece: None,
             promotion_piece: None,
@@ -1173,24 +1284,44 @@ mod tests {
 
         // position 2
 
-        // e2-e4
+        // g2-g3
         let position = Board::from_fen("r1b1kbnr/1pp3pp/p1n5/4Bp2/2P4q/1P2P3/P2P1PPP/RN1QKB1R w KQkq - 1 8").unwrap().position.make_move(Ply {
-            source: square::F2,
-            target: square::F3,
+            source: square::G2,
+            target: square::G3,
             piece: Piece::Pawn,
             captured_piece: None,
             promotion_piece: None,
         });
         println!("{position}");
-        assert_eq!(Board::from_fen("r1b1kbnr/1pp3pp/p1n5/4Bp2/2P4q/1P2PP2/P2P2PP/RN1QKB1R b KQkq - 1 8").unwrap().position, position);
+        assert_eq!(Board::from_fen("r1b1kbnr/1pp3pp/p1n5/4Bp2/2P4q/1P2P1P1/P2P1P1P/RN1QKB1R b KQkq - 1 8").unwrap().position, position);
     }
 
     #[test]
-    fn test_get_num_pieces() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
+    fn unmake_move_restores_position() {
+        // castling, a promotion with capture and an en passant capture, unmade in reverse order
+        let original_position = Board::from_fen("r3k2r/1P6/8/8/3pP3/8/8/R3K2R b KQkq e3 0 1").unwrap().position;
+        let mut position = original_position;
+        let mut undo_stack = Vec::new();
+        for ply in ["d4e3", "e1c1", "e8g8", "b7a8q"] {
+            let ply = Ply::from_string(ply, position).unwrap();
+            let expected_position = position.make_move(ply);
+            undo_stack.push(position.make_move_in_place(ply));
+            assert_eq!(expected_position, position);
+            assert_eq!(expected_position.hash, position.hash);
+        }
+        assert_eq!(Board::from_fen("Q4rk1/8/8/8/8/4p3/8/2KR3R b - - 0 3").unwrap().position, position);
 
+        while let Some(undo) = undo_stack.pop() {
+            position.unmake_move(undo);
+        }
+        assert_eq!(original_position, position);
+        assert_eq!(original_position.hash, position.hash);
+        assert_eq!(original_position.material, position.material);
+        assert_eq!(original_position.pst, position.pst);
+    }
+
+    #[test]
+    fn test_get_num_pieces() {
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
         assert_eq!(8, position.get_num_pieces(Piece::Pawn, Color::White));
         assert_eq!(8, position.get_num_pieces(Piece::Pawn, Color::Black));
@@ -1219,4 +1350,83 @@ mod tests {
         assert_eq!(1, position.get_num_pieces(Piece::King, Color::White));
         assert_eq!(1, position.get_num_pieces(Piece::King, Color::Black));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_is_insufficient_material() {
+        // KvK, KNvK, KBvK, KBvKB with same colored bishops
+        assert!(Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap().position.is_insufficient_material());
+        assert!(Board::from_fen("4k3/8/8/8/8/8/8/4KN2 w - - 0 1").unwrap().position.is_insufficient_material());
+        assert!(Board::from_fen("4kb2/8/8/8/8/8/8/4K3 w - - 0 1").unwrap().position.is_insufficient_material());
+        assert!(Board::from_fen("4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1").unwrap().position.is_insufficient_material());
+
+        // KBvKB with opposite colored bishops, KNvKN, KBNvK, KPvK
+        assert!(!Board::from_fen("4k1b1/8/8/8/8/8/8/2B1K3 w - - 0 1").unwrap().position.is_insufficient_material());
+        assert!(!Board::from_fen("4kn2/8/8/8/8/8/8/4KN2 w - - 0 1").unwrap().position.is_insufficient_material());
+        assert!(!Board::from_fen("4k3/8/8/8/8/8/8/2B1KN2 w - - 0 1").unwrap().position.is_insufficient_material());
+        assert!(!Board::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1").unwrap().position.is_insufficient_material());
+        assert!(!Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position.is_insufficient_material());
+    }
+
+    #[test]
+    fn test_get_material_and_pst() {
+        let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
+        assert_eq!([4050, 4050], position.material);
+        assert_eq!(position.pst[0], position.pst[1]);
+
+        let mut position = Position::default();
+        position.set_piece(Knight, White, A1);
+        assert_eq!([320, 0], position.material);
+        assert_eq!(Score::new(-50, -50), position.pst[White.to_index() as usize]);
+        position.remove_piece(Knight, White, A1);
+        assert_eq!([0, 0], position.material);
+        assert_eq!(Score::default(), position.pst[White.to_index() as usize]);
+    }
+
+    #[test]
+    fn test_make_move_chess960_castling() {
+        let position = Board::from_fen("1k6/8/8/8/8/8/8/RK4R1 w GA - 0 1").unwrap().position;
+
+        // b1-g1 (castling kingside, the king ends up on the rook's square)
+        let ply = Ply { source: square::B1, target: square::G1, piece: King, captured_piece: None, promotion_piece: None };
+        assert!(position.is_castling(ply));
+        let new_position = position.make_move(ply);
+        assert_eq!(Board::from_fen("1k6/8/8/8/8/8/8/R4RK1 b - - 1 1").unwrap().position, new_position);
+        assert_eq!(zobrist::get_hash(&new_position), new_position.hash);
+
+        // b1-a1 (castling queenside)
+        let ply = Ply { source: square::B1, target: square::A1, piece: King, captured_piece: None, promotion_piece: None };
+        let new_position = position.make_move(ply);
+        assert_eq!(Board::from_fen("1k6/8/8/8/8/8/8/2KR2R1 b - - 1 1").unwrap().position, new_position);
+        assert_eq!(zobrist::get_hash(&new_position), new_position.hash);
+
+        // moving the inner rook removes the castling rights for its side, even though it is not on the A or H file
+        let ply = Ply { source: square::G1, target: square::G2, piece: Rook, captured_piece: None, promotion_piece: None };
+        assert!(!position.is_castling(ply));
+        let new_position = position.make_move(ply);
+        assert_eq!(Board::from_fen("1k6/8/8/8/8/8/6R1/RK6 b A - 1 1").unwrap().position, new_position);
+        assert_eq!(zobrist::get_hash(&new_position), new_position.hash);
+    }
+
+    #[test]
+    fn make_move_keeps_material_and_pst_up_to_date() {
+        /// Plays all moves up to the given depth and verifies the incrementally updated values in every position.
+        fn verify(position: Position, depth: u8) {
+            assert_eq!((position.material, position.pst), position.get_material_and_pst());
+            if depth == 0 {
+                return;
+            }
+            let move_list = move_gen::generate_moves(position);
+            for index in 0..move_list.len() {
+                let new_position = position.make_move(move_list.get(index));
+                if new_position.is_legal() {
+                    verify(new_position, depth - 1);
+                }
+            }
+        }
+
+        // castling, en passant, captures and promotions
+        verify(Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap().position, 2);
+        verify(Board::from_fen("n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1").unwrap().position, 2);
+        verify(Board::from_fen("8/8/8/2k5/2pP4/8/B7/4K3 b - d3 0 3").unwrap().position, 2);
+    }
+}
@@ -1,6 +1,7 @@
 use std::fmt::{Display, Formatter};
 use crate::board::file::File;
 use crate::board::rank::Rank;
+use crate::move_gen::ply::MoveParseError;
 
 /// A square on the chessboard, represented by an index ranging from 0 to 63.
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
@@ -23,41 +24,27 @@ impl Square {
     }
 
     /// Constructs a new square from a string.
-    pub fn from_string(square_str: &str) -> Result<Self, String> {
-        // square_str can not be longer than 2
-        if square_str.len() != 2 {
-            return Err(String::from("Invalid square string"));
-        }
-        
-        // get chars
+    pub fn from_string(square_str: &str) -> Result<Self, MoveParseError> {
+        let error = || MoveParseError::InvalidSquare(String::from(square_str));
+
+        // square_str must consist of exactly two chars
         let chars: Vec<char> = square_str.chars().collect();
-        
+        if chars.len() != 2 {
+            return Err(error());
+        }
+
         // first char must be ascii a-h
-        match chars[0] {
-            file_char if file_char.is_ascii_lowercase() && ('a'..='h').contains(&file_char) => {}
-            _other => return Err(String::from("Invalid square string")) 
+        if !('a'..='h').contains(&chars[0]) {
+            return Err(error());
         }
-        
+        let file = File::from_char(&chars[0]).map_err(|_| error())?;
+
         // second char must be a number 1-8
-        match chars[1] {
-            rank_char if rank_char.is_numeric() && ('1'..='8').contains(&rank_char) => {}
-            _other => return Err(String::from("Invalid square string"))
+        if !('1'..='8').contains(&chars[1]) {
+            return Err(error());
         }
+        let rank = Rank::from_index(chars[1] as u8 - b'1');
 
-        // get file
-        let file = File::from_char(&chars[0]);
-        if file.is_err() {
-            return Err(String::from("Invalid square string"));
-        }
-        let file = file.unwrap();
-        
-        // get rank
-        let rank = chars[1].to_digit(10);
-        if rank.is_none() {
-            return Err(String::from("Invalid square string"));
-        }
-        let rank = Rank::from_index(rank.unwrap() as u8 - 1);
-        
         // get square
         Ok(Square::from_file_rank(file, rank))
     }
@@ -187,13 +174,13 @@ mod tests {
     
     #[test]
     fn from_string_with_invalid_string_returns_error() {
-        assert_eq!(Err(String::from("Invalid square string")), Square::from_string("ab2"));
-        assert_eq!(Err(String::from("Invalid square string")), Square::from_string("123"));
-        assert_eq!(Err(String::from("Invalid square string")), Square::from_string("h9"));
-        assert_eq!(Err(String::from("Invalid square string")), Square::from_string("j1"));
-        assert_eq!(Err(String::from("Invalid square string")), Square::from_string("nonsense"));
-        assert_eq!(Err(String::from("Invalid square string")), Square::from_string("2e"));
-        assert_eq!(Err(String::from("Invalid square string")), Square::from_string("G9"));
+        assert_eq!(Err(MoveParseError::InvalidSquare(String::from("ab2"))), Square::from_string("ab2"));
+        assert_eq!(Err(MoveParseError::InvalidSquare(String::from("123"))), Square::from_string("123"));
+        assert_eq!(Err(MoveParseError::InvalidSquare(String::from("h9"))), Square::from_string("h9"));
+        assert_eq!(Err(MoveParseError::InvalidSquare(String::from("j1"))), Square::from_string("j1"));
+        assert_eq!(Err(MoveParseError::InvalidSquare(String::from("nonsense"))), Square::from_string("nonsense"));
+        assert_eq!(Err(MoveParseError::InvalidSquare(String::from("2e"))), Square::from_string("2e"));
+        assert_eq!(Err(MoveParseError::InvalidSquare(String::from("G9"))), Square::from_string("G9"));
     }
 
     #[test]
@@ -0,0 +1,312 @@
+//! The book module provides support for opening books in the [Polyglot](http://hgm.nubati.net/book_format.html) format.
+//!
+//! A Polyglot book is a file of 16 byte entries, sorted by the Zobrist key of the position they belong to.
+//! Ladybug's Zobrist keys are the Polyglot keys, so the entries of a position can be found with a binary search over its hash.
+//!
+//! Moves are stored as source and target squares plus an optional promotion piece.
+//! Castling is stored as the king capturing its own rook (e.g. `e1h1`), which happens to be Ladybug's own castling encoding.
+
+pub mod builder;
+
+use std::fs;
+use crate::board::piece::Piece;
+use crate::board::position::Position;
+use crate::board::square::Square;
+use crate::move_gen;
+use crate::move_gen::ply::Ply;
+
+/// The size of a book entry in bytes.
+const ENTRY_SIZE: usize = 16;
+
+/// An entry of a Polyglot book, recommending a move for a position.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct BookEntry {
+    /// The Zobrist key of the position.
+    pub key: u64,
+    /// The move in Polyglot encoding.
+    pub raw_move: u16,
+    /// The weight of the move. Moves with a higher weight are played more often.
+    pub weight: u16,
+    /// Learning data, which is not used by Ladybug.
+    pub learn: u32,
+}
+
+/// A Polyglot opening book.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Book {
+    /// The entries of the book, sorted by their key.
+    entries: Vec<BookEntry>,
+}
+
+impl BookEntry {
+    /// Decodes an entry from its 16 big-endian bytes.
+    fn from_bytes(bytes: &[u8]) -> BookEntry {
+        BookEntry {
+            key: u64::from_be_bytes(bytes[0..8].try_into().unwrap()),
+            raw_move: u16::from_be_bytes(bytes[8..10].try_into().unwrap()),
+            weight: u16::from_be_bytes(bytes[10..12].try_into().unwrap()),
+            learn: u32::from_be_bytes(bytes[12..16].try_into().unwrap()),
+        }
+    }
+
+    /// Encodes the entry as 16 big-endian bytes, as it is stored in a book file.
+    pub fn to_bytes(&self) -> [u8; ENTRY_SIZE] {
+        let mut bytes = [0; ENTRY_SIZE];
+        bytes[0..8].copy_from_slice(&self.key.to_be_bytes());
+        bytes[8..10].copy_from_slice(&self.raw_move.to_be_bytes());
+        bytes[10..12].copy_from_slice(&self.weight.to_be_bytes());
+        bytes[12..16].copy_from_slice(&self.learn.to_be_bytes());
+        bytes
+    }
+}
+
+impl Book {
+    /// Reads the book file at the given path.
+    pub fn open(path: &str) -> Result<Book, String> {
+        let bytes = fs::read(path).map_err(|_| format!("Could not read book file {path}"))?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Constructs a book from the contents of a book file.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Book, String> {
+        if !bytes.len().is_multiple_of(ENTRY_SIZE) {
+            return Err(format!("Invalid book file size of {} bytes", bytes.len()));
+        }
+        Ok(Self::from_entries(bytes.chunks_exact(ENTRY_SIZE).map(BookEntry::from_bytes).collect()))
+    }
+
+    /// Constructs a book from the given entries.
+    /// The entries are sorted by their key, keeping the order of entries with equal keys.
+    pub fn from_entries(mut entries: Vec<BookEntry>) -> Book {
+        entries.sort_by_key(|entry| entry.key);
+        Book { entries }
+    }
+
+    /// Returns the number of entries in the book.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if the book has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns all entries of the book, sorted by their key.
+    pub fn get_entries(&self) -> &[BookEntry] {
+        &self.entries
+    }
+
+    /// Returns the contents of a book file containing all entries of the book.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.entries.iter().flat_map(|entry| entry.to_bytes()).collect()
+    }
+
+    /// Returns the entries with the given key.
+    pub fn get_entries_for_key(&self, key: u64) -> &[BookEntry] {
+        let start = self.entries.partition_point(|entry| entry.key < key);
+        let end = self.entries.partition_point(|entry| entry.key <= key);
+        &self.entries[start..end]
+    }
+
+    /// Returns the legal book moves for the given position together with their weights.
+    /// Entries whose move is not legal in the position (e.g. because of a hash collision) are skipped.
+    pub fn get_moves(&self, position: &Position) -> Vec<(Ply, u16)> {
+        self.get_entries_for_key(position.hash).iter()
+            .filter_map(|entry| decode_move(entry.raw_move, position).map(|ply| (ply, entry.weight)))
+            .collect()
+    }
+
+    /// Returns the book move with the highest weight for the given position.
+    /// If several moves share the highest weight, the first one is returned.
+    pub fn get_best_move(&self, position: &Position) -> Option<Ply> {
+        let mut best_move: Option<(Ply, u16)> = None;
+        for (ply, weight) in self.get_moves(position) {
+            if best_move.is_none_or(|(_, best_weight)| weight > best_weight) {
+                best_move = Some((ply, weight));
+            }
+        }
+        best_move.map(|(ply, _)| ply)
+    }
+
+    /// Returns a book move for the given position, chosen with a probability proportional to its weight.
+    ///
+    /// The given random number decides which move is chosen. If all moves have a weight of zero, the first one is returned.
+    pub fn get_random_move(&self, position: &Position, random: u64) -> Option<Ply> {
+        let moves = self.get_moves(position);
+        let total_weight: u64 = moves.iter().map(|(_, weight)| *weight as u64).sum();
+        if total_weight == 0 {
+            return moves.first().map(|(ply, _)| *ply);
+        }
+
+        let mut remaining = random % total_weight;
+        for (ply, weight) in moves {
+            if remaining < weight as u64 {
+                return Some(ply);
+            }
+            remaining -= weight as u64;
+        }
+        unreachable!()
+    }
+}
+
+/// Decodes a move in Polyglot encoding into a legal ply for the given position.
+///
+/// The bits 0-2 contain the target file, 3-5 the target rank, 6-8 the source file, 9-11 the source rank
+/// and 12-14 the promotion piece (1 = knight, 2 = bishop, 3 = rook, 4 = queen).
+/// Returns `None` if the move is not legal in the position.
+pub fn decode_move(raw_move: u16, position: &Position) -> Option<Ply> {
+    let target = Square::new((raw_move & 0x3f) as u8);
+    let source = Square::new(((raw_move >> 6) & 0x3f) as u8);
+    let promotion_piece = match (raw_move >> 12) & 0x7 {
+        0 => None,
+        index @ 1..=4 => Some(Piece::from_index(index as u8)),
+        _ => return None,
+    };
+
+    // castling is encoded as the king capturing its own rook in both Polyglot and Ladybug, so no special handling is needed
+    let move_list = move_gen::generate_moves(*position);
+    (0..move_list.len())
+        .map(|index| move_list.get(index))
+        .find(|ply| ply.source == source && ply.target == target && ply.promotion_piece == promotion_piece)
+}
+
+/// Encodes the given ply as a move in Polyglot encoding.
+pub fn encode_move(ply: Ply) -> u16 {
+    let promotion = ply.promotion_piece.map_or(0, |piece| piece.to_index() as u16);
+    (promotion << 12) | ((ply.source.index as u16) << 6) | ply.target.index as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::board::fen::START_FEN;
+    use crate::board::piece::Piece;
+    use crate::board::square;
+    use crate::book;
+    use crate::book::{Book, BookEntry};
+    use crate::move_gen::ply::Ply;
+
+    /// Plays the given moves from the start position and returns the resulting board.
+    fn play(moves: &[&str]) -> Board {
+        let mut board = Board::from_fen(START_FEN).unwrap();
+        for move_str in moves {
+            board = board.make_move(Ply::from_string(move_str, board.position).unwrap());
+        }
+        board
+    }
+
+    /// Constructs a book entry for the given board and move.
+    fn entry(board: &Board, move_str: &str, weight: u16) -> BookEntry {
+        let ply = Ply::from_string(move_str, board.position).unwrap();
+        BookEntry { key: board.position.hash, raw_move: book::encode_move(ply), weight, learn: 0 }
+    }
+
+    #[test]
+    fn hashes_match_polyglot_reference_positions() {
+        // the reference keys from the Polyglot book format specification
+        let references: [(&[&str], u64); 9] = [
+            (&[], 0x463b96181691fc9c),
+            (&["e2e4"], 0x823c9b50fd114196),
+            (&["e2e4", "d7d5"], 0x0756b94461c50fb0),
+            (&["e2e4", "d7d5", "e4e5"], 0x662fafb965db29d4),
+            (&["e2e4", "d7d5", "e4e5", "f7f5"], 0x22a48b5a8e47ff78),
+            (&["e2e4", "d7d5", "e4e5", "f7f5", "e1e2"], 0x652a607ca3f242c1),
+            (&["e2e4", "d7d5", "e4e5", "f7f5", "e1e2", "e8f7"], 0x00fdd303c946bdd9),
+            (&["a2a4", "b7b5", "h2h4", "b5b4", "c2c4"], 0x3c8123ea7b067637),
+            (&["a2a4", "b7b5", "h2h4", "b5b4", "c2c4", "b4c3", "a1a3"], 0x5c3f9b829b279560),
+        ];
+
+        for (moves, key) in references {
+            let board = play(moves);
+            assert_eq!(key, board.position.hash, "{moves:?}");
+            assert_eq!(key, Board::from_fen(board.to_fen().as_str()).unwrap().position.hash, "{moves:?}");
+        }
+    }
+
+    #[test]
+    fn test_decode_move() {
+        let board = Board::from_fen(START_FEN).unwrap();
+        let ply = book::decode_move(0x031c, &board.position).unwrap();
+        assert_eq!((square::E2, square::E4), (ply.source, ply.target));
+        assert_eq!(None, book::decode_move(0x0324, &board.position));
+
+        // castling is stored as the king capturing its own rook
+        let board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        let ply = book::decode_move(0x0107, &board.position).unwrap();
+        assert!(board.position.is_castling(ply));
+        assert_eq!("e1g1", ply.to_uci(board.position, false));
+        let ply = book::decode_move(0x0100, &board.position).unwrap();
+        assert_eq!("e1c1", ply.to_uci(board.position, false));
+
+        // promotions
+        let board = Board::from_fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let ply = book::decode_move(0x4c79, &board.position).unwrap();
+        assert_eq!(Some(Piece::Queen), ply.promotion_piece);
+        let ply = book::decode_move(0x1c79, &board.position).unwrap();
+        assert_eq!(Some(Piece::Knight), ply.promotion_piece);
+        assert_eq!(None, book::decode_move(0x0c79, &board.position));
+    }
+
+    #[test]
+    fn test_encode_move() {
+        let board = Board::from_fen("r3k2r/1P6/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        for (move_str, raw_move) in [("e1g1", 0x0107), ("e1c1", 0x0100), ("b7a8q", 0x4c78), ("a1a7", 0x0030)] {
+            let ply = Ply::from_string(move_str, board.position).unwrap();
+            assert_eq!(raw_move, book::encode_move(ply), "{move_str}");
+            assert_eq!(Some(ply), book::decode_move(raw_move, &board.position));
+        }
+    }
+
+    #[test]
+    fn get_moves_finds_entries_by_hash() {
+        let start = Board::from_fen(START_FEN).unwrap();
+        let e4 = play(&["e2e4"]);
+        let book = Book::from_entries(vec![
+            entry(&e4, "e7e5", 5),
+            entry(&start, "e2e4", 10),
+            entry(&start, "d2d4", 30),
+            entry(&e4, "c7c5", 20),
+            BookEntry { key: start.position.hash, raw_move: 0x0324, weight: 100, learn: 0 },
+        ]);
+        assert_eq!(5, book.len());
+
+        let moves = book.get_moves(&start.position);
+        assert_eq!(2, moves.len());
+        assert_eq!("e2e4", moves[0].0.to_uci(start.position, false));
+        assert_eq!(10, moves[0].1);
+        assert_eq!("d2d4", book.get_best_move(&start.position).unwrap().to_uci(start.position, false));
+        assert_eq!("c7c5", book.get_best_move(&e4.position).unwrap().to_uci(e4.position, false));
+        assert_eq!(None, book.get_best_move(&play(&["d2d4"]).position));
+    }
+
+    #[test]
+    fn get_random_move_respects_weights() {
+        let start = Board::from_fen(START_FEN).unwrap();
+        let book = Book::from_entries(vec![entry(&start, "e2e4", 10), entry(&start, "d2d4", 30), entry(&start, "g1f3", 0)]);
+
+        let random_move = |random: u64| book.get_random_move(&start.position, random).unwrap().to_uci(start.position, false);
+        assert_eq!("e2e4", random_move(0));
+        assert_eq!("e2e4", random_move(9));
+        assert_eq!("d2d4", random_move(10));
+        assert_eq!("d2d4", random_move(39));
+        assert_eq!("e2e4", random_move(40));
+
+        let book = Book::from_entries(vec![entry(&start, "g1f3", 0)]);
+        assert_eq!("g1f3", book.get_random_move(&start.position, 7).unwrap().to_uci(start.position, false));
+        assert_eq!(None, Book::default().get_random_move(&start.position, 7));
+    }
+
+    #[test]
+    fn from_bytes_reads_big_endian_entries() {
+        let start = Board::from_fen(START_FEN).unwrap();
+        let entries = [entry(&start, "e2e4", 10), entry(&start, "d2d4", 30)];
+        let bytes: Vec<u8> = entries.iter().flat_map(|entry| entry.to_bytes()).collect();
+        assert_eq!([0x46, 0x3b, 0x96, 0x18, 0x16, 0x91, 0xfc, 0x9c, 0x03, 0x1c, 0x00, 0x0a, 0, 0, 0, 0], bytes[0..16]);
+
+        let book = Book::from_bytes(&bytes).unwrap();
+        assert_eq!(entries, book.get_entries());
+        assert!(Book::from_bytes(&bytes[1..]).is_err());
+        assert!(Book::open("/this/path/does/not/exist.bin").is_err());
+    }
+}
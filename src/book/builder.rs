@@ -0,0 +1,217 @@
+//! This module builds Polyglot opening books from PGN games.
+//!
+//! Every game is replayed up to a ply limit, and the moves played in each position are counted together with the
+//! results they led to. Moves that were played too rarely or scored too badly are left out of the book.
+//! The remaining moves are weighted like Polyglot does it, with two points for a win and one point for a draw.
+
+use std::collections::HashMap;
+use crate::board::color::Color;
+use crate::book;
+use crate::book::{Book, BookEntry};
+use crate::pgn::PgnGame;
+
+/// The statistics of a move played in a position, from the point of view of the side that played it.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct MoveStats {
+    /// The number of games the move was played in.
+    pub games: u32,
+    /// The number of these games that were won.
+    pub wins: u32,
+    /// The number of these games that were drawn.
+    pub draws: u32,
+}
+
+/// Collects move statistics from games and builds a book from them.
+pub struct BookBuilder {
+    /// The number of plies of each game that are added to the book.
+    max_plies: usize,
+    /// The minimum number of games a move must have been played in.
+    min_games: u32,
+    /// The minimum score (between 0 and 1) a move must have achieved.
+    min_score: f64,
+    /// The statistics of each move, indexed by the position key and the move in Polyglot encoding.
+    stats: HashMap<(u64, u16), MoveStats>,
+}
+
+impl MoveStats {
+    /// Returns the score of the move between 0 and 1, counting wins as 1 and draws as 0.5.
+    pub fn score(&self) -> f64 {
+        if self.games == 0 {
+            return 0.0;
+        }
+        (self.wins as f64 + 0.5 * self.draws as f64) / self.games as f64
+    }
+
+    /// Returns the Polyglot weight of the move, which is two points for a win and one point for a draw.
+    pub fn weight(&self) -> u32 {
+        2 * self.wins + self.draws
+    }
+}
+
+impl BookBuilder {
+    /// Constructs a new builder with the given ply limit, minimum number of games and minimum score.
+    pub fn new(max_plies: usize, min_games: u32, min_score: f64) -> Self {
+        Self {
+            max_plies,
+            min_games,
+            min_score,
+            stats: HashMap::new(),
+        }
+    }
+
+    /// Adds the mainline of the given game to the statistics.
+    /// Games without a known result are skipped, in which case false is returned.
+    pub fn add_game(&mut self, game: &PgnGame) -> bool {
+        let winner = match game.result() {
+            "1-0" => Some(Color::White),
+            "0-1" => Some(Color::Black),
+            "1/2-1/2" => None,
+            _other => return false,
+        };
+
+        let mut board = game.start;
+        for pgn_move in game.moves.iter().take(self.max_plies) {
+            let color = board.position.color_to_move;
+            let stats = self.stats.entry((board.position.hash, book::encode_move(pgn_move.ply))).or_default();
+            stats.games += 1;
+            match winner {
+                Some(winner) if winner == color => stats.wins += 1,
+                Some(_) => {}
+                None => stats.draws += 1,
+            }
+            board = board.make_move(pgn_move.ply);
+        }
+        true
+    }
+
+    /// Returns the statistics of the given move in the position with the given key.
+    pub fn get_stats(&self, key: u64, raw_move: u16) -> Option<MoveStats> {
+        self.stats.get(&(key, raw_move)).copied()
+    }
+
+    /// Builds a book from all moves that pass the filters.
+    ///
+    /// The entries are sorted by key and then by descending weight, like in books written by other Polyglot tools.
+    /// If the largest weight of a position does not fit into 16 bits, the weights of all moves in that position are
+    /// scaled down proportionally. Moves that end up with a weight of 0 are never played, so they are left out.
+    pub fn build(&self) -> Book {
+        let mut moves: Vec<(u64, u16, u32)> = self.stats.iter()
+            .filter(|(_, stats)| stats.games >= self.min_games && stats.score() >= self.min_score)
+            .map(|((key, raw_move), stats)| (*key, *raw_move, stats.weight()))
+            .collect();
+        moves.sort_by(|a, b| a.0.cmp(&b.0).then(b.2.cmp(&a.2)).then(a.1.cmp(&b.1)));
+
+        let mut entries: Vec<BookEntry> = Vec::new();
+        for position_moves in moves.chunk_by(|a, b| a.0 == b.0) {
+            // the moves are sorted by descending weight, so the first move has the largest weight of the position
+            let max_weight = position_moves[0].2;
+            let scale = |weight: u32| if max_weight > u16::MAX as u32 { (weight as u64 * u16::MAX as u64 / max_weight as u64) as u16 } else { weight as u16 };
+            entries.extend(position_moves.iter()
+                .map(|(key, raw_move, weight)| BookEntry { key: *key, raw_move: *raw_move, weight: scale(*weight), learn: 0 })
+                .filter(|entry| entry.weight > 0));
+        }
+        Book::from_entries(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::board::fen::START_FEN;
+    use crate::book;
+    use crate::book::Book;
+    use crate::book::builder::{BookBuilder, MoveStats};
+    use crate::move_gen::ply::Ply;
+    use crate::pgn::PgnGame;
+
+    /// Returns the key of the position after the given moves from the start position and the encoded next move.
+    fn key_and_move(moves: &[&str], next_move: &str) -> (u64, u16) {
+        let mut board = Board::from_fen(START_FEN).unwrap();
+        for move_str in moves {
+            board = board.make_move(Ply::from_string(move_str, board.position).unwrap());
+        }
+        (board.position.hash, book::encode_move(Ply::from_string(next_move, board.position).unwrap()))
+    }
+
+    #[test]
+    fn add_game_collects_statistics() {
+        let mut builder = BookBuilder::new(3, 1, 0.0);
+        assert!(builder.add_game(&PgnGame::parse("[Result \"1-0\"]\n\n1. e4 e5 2. Nf3 Nc6 1-0").unwrap()));
+        assert!(builder.add_game(&PgnGame::parse("[Result \"1/2-1/2\"]\n\n1. e4 c5 1/2-1/2").unwrap()));
+        assert!(builder.add_game(&PgnGame::parse("[Result \"0-1\"]\n\n1. e4 e5 0-1").unwrap()));
+        assert!(!builder.add_game(&PgnGame::parse("[Result \"*\"]\n\n1. d4 *").unwrap()));
+
+        let (key, raw_move) = key_and_move(&[], "e2e4");
+        assert_eq!(Some(MoveStats { games: 3, wins: 1, draws: 1 }), builder.get_stats(key, raw_move));
+        let (key, raw_move) = key_and_move(&["e2e4"], "e7e5");
+        assert_eq!(Some(MoveStats { games: 2, wins: 1, draws: 0 }), builder.get_stats(key, raw_move));
+        let (key, raw_move) = key_and_move(&["e2e4", "e7e5"], "g1f3");
+        assert_eq!(Some(MoveStats { games: 1, wins: 1, draws: 0 }), builder.get_stats(key, raw_move));
+
+        // moves beyond the ply limit and of games without a result are not counted
+        let (key, raw_move) = key_and_move(&["e2e4", "e7e5", "g1f3"], "b8c6");
+        assert_eq!(None, builder.get_stats(key, raw_move));
+        let (key, raw_move) = key_and_move(&[], "d2d4");
+        assert_eq!(None, builder.get_stats(key, raw_move));
+    }
+
+    #[test]
+    fn build_filters_and_sorts_entries() {
+        let mut builder = BookBuilder::new(2, 2, 0.5);
+        for pgn in ["1. e4 e5 1-0", "1. e4 e5 1-0", "1. e4 c5 0-1", "1. d4 d5 1/2-1/2", "1. d4 d5 1/2-1/2", "1. c4 e5 1-0"] {
+            let result = pgn.rsplit(' ').next().unwrap();
+            builder.add_game(&PgnGame::parse(format!("[Result \"{result}\"]\n\n{pgn}").as_str()).unwrap());
+        }
+
+        // only e4 (4 points in 3 games), d4 (2 points in 2 games) and d5 remain, e5 scored too badly and c5 and c4 were played too rarely
+        let book = builder.build();
+        assert_eq!(3, book.len());
+        let start = Board::from_fen(START_FEN).unwrap();
+        let moves: Vec<(String, u16)> = book.get_moves(&start.position).iter().map(|(ply, weight)| (ply.to_uci(start.position, false), *weight)).collect();
+        assert_eq!(vec![(String::from("e2e4"), 4), (String::from("d2d4"), 2)], moves);
+
+        let entries = book.get_entries();
+        assert!(entries.windows(2).all(|pair| pair[0].key <= pair[1].key));
+        let bytes: Vec<u8> = book.to_bytes();
+        assert_eq!(book, Book::from_bytes(&bytes).unwrap());
+    }
+
+    #[test]
+    fn build_scales_large_weights() {
+        let mut builder = BookBuilder::new(2, 1, 0.0);
+        let game = PgnGame::parse("[Result \"1-0\"]\n\n1. e4 1-0").unwrap();
+        for _ in 0..40000 {
+            builder.add_game(&game);
+        }
+        let game = PgnGame::parse("[Result \"1/2-1/2\"]\n\n1. d4 1/2-1/2").unwrap();
+        for _ in 0..20000 {
+            builder.add_game(&game);
+        }
+
+        // the weights of the position after 1. e4 are not scaled, since they fit into 16 bits
+        let game = PgnGame::parse("[Result \"0-1\"]\n\n1. e4 e5 0-1").unwrap();
+        builder.add_game(&game);
+
+        let book = builder.build();
+        let start = Board::from_fen(START_FEN).unwrap();
+        let weights: Vec<u16> = book.get_moves(&start.position).iter().map(|(_, weight)| *weight).collect();
+        assert_eq!(vec![u16::MAX, 16383], weights);
+        let (key, _) = key_and_move(&["e2e4"], "e7e5");
+        assert_eq!(vec![2], book.get_entries().iter().filter(|entry| entry.key == key).map(|entry| entry.weight).collect::<Vec<u16>>());
+    }
+
+    #[test]
+    fn build_drops_entries_without_weight() {
+        let mut builder = BookBuilder::new(2, 1, 0.0);
+        builder.add_game(&PgnGame::parse("[Result \"1-0\"]\n\n1. e4 e5 1-0").unwrap());
+        builder.add_game(&PgnGame::parse("[Result \"0-1\"]\n\n1. d4 d5 0-1").unwrap());
+
+        // the losing moves e5 and d4 have a weight of 0
+        let book = builder.build();
+        assert_eq!(2, book.len());
+        assert!(book.get_entries().iter().all(|entry| entry.weight > 0));
+        let start = Board::from_fen(START_FEN).unwrap();
+        let moves: Vec<String> = book.get_moves(&start.position).iter().map(|(ply, _)| ply.to_uci(start.position, false)).collect();
+        assert_eq!(vec![String::from("e2e4")], moves);
+    }
+}
@@ -0,0 +1,311 @@
+//! The engine module provides a synchronous API to Ladybug's search, which can be embedded without any channels or UCI strings.
+//!
+//! An [`Engine`] is given a position with [`Engine::set_position`] and searched with [`Engine::search`], which blocks until
+//! one of the [`SearchLimits`] is reached and returns a [`SearchResult`]. The progress of each completed iteration can be
+//! observed with [`Engine::search_with_progress`], and a running search can be aborted from another thread via a [`CancelHandle`].
+//!
+//! ```no_run
+//! use ladybug::board::Board;
+//! use ladybug::board::fen::START_FEN;
+//! use ladybug::engine::{Engine, EngineConfig, SearchLimits};
+//!
+//! let mut engine = Engine::new(EngineConfig::default());
+//! engine.set_position(Board::from_fen(START_FEN).unwrap(), Default::default());
+//! let result = engine.search(SearchLimits { depth: Some(5), ..Default::default() });
+//! println!("best move: {:?}, score: {}", result.best_move, result.score);
+//! ```
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use crate::board::Board;
+use crate::board::position::Position;
+use crate::evaluation;
+use crate::evaluation::parameters::Parameters;
+use crate::move_gen::ply::Ply;
+use crate::search::{MAX_PLY, SearchInfo};
+
+/// The configuration of an engine.
+#[derive(Clone, Debug, Default)]
+pub struct EngineConfig {
+    /// The evaluation parameters to search with, or `None` to use the built-in parameters.
+    ///
+    /// Custom parameters are evaluated from scratch in every node, which is slower than the incrementally updated
+    /// built-in evaluation.
+    pub parameters: Option<Parameters>,
+}
+
+/// The limits of a search. The search stops as soon as any of the given limits is reached.
+/// If no limit is given, the search runs until the maximum depth is reached or it is cancelled.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct SearchLimits {
+    /// The maximum depth to search.
+    pub depth: Option<u64>,
+    /// The maximum time to search.
+    pub move_time: Option<Duration>,
+    /// The maximum number of nodes to search.
+    pub nodes: Option<u64>,
+}
+
+/// The result of a search, or of a single iteration when reported as progress.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct SearchResult {
+    /// The best move, or `None` if the position has no legal moves.
+    pub best_move: Option<Ply>,
+    /// The expected reply to the best move, if the principal variation contains one.
+    pub ponder_move: Option<Ply>,
+    /// The score of the best move in centipawns, from the point of view of the side to move.
+    pub score: i32,
+    /// The principal variation, starting with the best move.
+    pub pv: Vec<Ply>,
+    /// The depth of the last completed iteration.
+    pub depth: u64,
+    /// The maximum depth reached by the quiescence search in the last completed iteration.
+    pub seldepth: u64,
+    /// The number of nodes searched in all completed iterations.
+    pub nodes: u128,
+    /// The time elapsed since the start of the search.
+    pub time: Duration,
+}
+
+/// Aborts a running search from another thread.
+///
+/// A cancel is honoured by the running search, or by the next search if none is running, so that a cancel issued right
+/// before a search starts is not lost. The cancel is reset when that search finishes or a new position is set.
+#[derive(Clone, Debug)]
+pub struct CancelHandle {
+    /// The flag shared with the engine.
+    flag: Arc<AtomicBool>,
+}
+
+/// A synchronous chess engine, which searches the position it was given until the search limits are reached.
+pub struct Engine {
+    /// The position to search.
+    pub(crate) board: Board,
+    /// The hashes of all positions that occurred before the position to search, used to detect repetitions.
+    pub(crate) board_history: Vec<u64>,
+    /// Used to measure the total expired time across all iterations during search.
+    pub(crate) total_time: Option<Instant>,
+    /// The maximum number of nodes to search, if any.
+    pub(crate) node_limit: Option<u128>,
+    /// The number of nodes evaluated during all completed iterations of the current search.
+    pub(crate) total_node_count: u128,
+    /// Flag to signal that the search should stop immediately.
+    pub(crate) stop: bool,
+    /// Flag set by the cancel handles to abort the current search.
+    pub(crate) cancelled: Arc<AtomicBool>,
+    /// Contains information collected and used during the search.
+    pub(crate) search_info: SearchInfo,
+    /// The custom evaluation parameters, if configured.
+    pub(crate) parameters: Option<Parameters>,
+}
+
+impl CancelHandle {
+    /// Aborts the running search. The search returns the result of the last completed iteration.
+    pub fn cancel(&self) {
+        self.flag.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Engine {
+    /// Constructs a new engine with the given configuration. The position to search is empty until one is set.
+    pub fn new(config: EngineConfig) -> Self {
+        Self {
+            board: Board::default(),
+            board_history: Vec::new(),
+            total_time: None,
+            node_limit: None,
+            total_node_count: 0,
+            stop: true,
+            cancelled: Arc::new(AtomicBool::new(false)),
+            search_info: SearchInfo::default(),
+            parameters: config.parameters,
+        }
+    }
+
+    /// Sets the position to search, together with the hashes of the positions that occurred before it in the game.
+    /// A cancel that was issued since the last search finished is reset.
+    pub fn set_position(&mut self, board: Board, board_history: Vec<u64>) {
+        self.board = board;
+        self.board_history = board_history;
+        self.cancelled.store(false, Ordering::Relaxed);
+    }
+
+    /// Returns the position to search.
+    pub fn get_board(&self) -> Board {
+        self.board
+    }
+
+    /// Returns a handle which can be used to cancel searches of this engine from another thread.
+    pub fn cancel_handle(&self) -> CancelHandle {
+        CancelHandle { flag: Arc::clone(&self.cancelled) }
+    }
+
+    /// Searches the current position until one of the given limits is reached and returns the result.
+    pub fn search(&mut self, limits: SearchLimits) -> SearchResult {
+        self.search_with_progress(limits, |_| {})
+    }
+
+    /// Searches the current position like [`Engine::search`], and calls the given function with the intermediate result
+    /// after every completed iteration.
+    pub fn search_with_progress(&mut self, limits: SearchLimits, mut on_iteration: impl FnMut(&SearchResult)) -> SearchResult {
+        // without a depth limit, search until the max depth is reached
+        let depth_limit = limits.depth.unwrap_or(MAX_PLY as u64);
+
+        // without a time limit, use a default limit of 72 hours
+        let time_limit = limits.move_time.unwrap_or(Duration::from_secs(72 * 60 * 60));
+
+        self.node_limit = limits.nodes.map(|nodes| nodes as u128);
+        let result = self.iterative_search(depth_limit, time_limit, &mut on_iteration);
+        self.node_limit = None;
+        self.cancelled.store(false, Ordering::Relaxed);
+        result
+    }
+
+    /// Returns the static evaluation of the given position, using the configured evaluation parameters.
+    pub(crate) fn evaluate(&self, position: Position) -> i32 {
+        match &self.parameters {
+            Some(parameters) => evaluation::evaluate_with_parameters(position, parameters),
+            None => evaluation::evaluate(position),
+        }
+    }
+
+    /// Checks whether the current search has to stop, because the time or node limit is reached or it was cancelled.
+    pub(crate) fn is_limit_reached(&self, time_limit: Duration) -> bool {
+        self.total_time.is_some_and(|instant| instant.elapsed() > time_limit)
+            || self.node_limit.is_some_and(|node_limit| self.total_node_count + self.search_info.node_count >= node_limit)
+            || self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::thread;
+    use std::time::Duration;
+    use crate::board::Board;
+    use crate::board::fen::START_FEN;
+    use crate::engine::{Engine, EngineConfig, SearchLimits};
+    use crate::move_gen::ply::Ply;
+
+    #[test]
+    fn search_finds_mate_in_one() {
+        let mut engine = Engine::new(EngineConfig::default());
+        let board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        engine.set_position(board, Vec::new());
+
+        let result = engine.search(SearchLimits { depth: Some(3), ..Default::default() });
+        assert_eq!(Some(Ply::from_string("a1a8", board.position).unwrap()), result.best_move);
+        assert_eq!(3, result.depth);
+        assert_eq!(result.best_move, result.pv.first().copied());
+        assert!(result.score > 100000);
+        assert!(result.nodes > 0);
+    }
+
+    #[test]
+    fn search_returns_pv_and_ponder_move() {
+        let mut engine = Engine::new(EngineConfig::default());
+        let board = Board::from_fen(START_FEN).unwrap();
+        engine.set_position(board, Vec::new());
+
+        let result = engine.search(SearchLimits { depth: Some(4), ..Default::default() });
+        assert_eq!(4, result.pv.len());
+        assert_eq!(result.pv.get(1).copied(), result.ponder_move);
+
+        // the pv consists of legal moves
+        let mut position = board.position;
+        for ply in result.pv.iter() {
+            position = position.make_move(Ply::from_string(ply.to_uci(position, false).as_str(), position).unwrap());
+        }
+    }
+
+    #[test]
+    fn search_without_legal_moves_has_no_best_move() {
+        let mut engine = Engine::new(EngineConfig::default());
+        engine.set_position(Board::from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1").unwrap(), Vec::new());
+
+        let result = engine.search(SearchLimits { depth: Some(3), ..Default::default() });
+        assert_eq!(None, result.best_move);
+        assert_eq!(None, result.ponder_move);
+        assert!(result.pv.is_empty());
+    }
+
+    #[test]
+    fn search_reports_progress_per_iteration() {
+        let mut engine = Engine::new(EngineConfig::default());
+        engine.set_position(Board::from_fen(START_FEN).unwrap(), Vec::new());
+
+        let mut depths: Vec<u64> = Vec::new();
+        let mut nodes: Vec<u128> = Vec::new();
+        let result = engine.search_with_progress(SearchLimits { depth: Some(4), ..Default::default() }, |progress| {
+            depths.push(progress.depth);
+            nodes.push(progress.nodes);
+        });
+        assert_eq!(vec![1, 2, 3, 4], depths);
+        assert!(nodes.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(Some(&result.nodes), nodes.last());
+    }
+
+    #[test]
+    fn search_respects_node_limit() {
+        let mut engine = Engine::new(EngineConfig::default());
+        engine.set_position(Board::from_fen(START_FEN).unwrap(), Vec::new());
+
+        let result = engine.search(SearchLimits { nodes: Some(5000), ..Default::default() });
+        assert!(result.best_move.is_some());
+        assert!(result.nodes <= 5000);
+    }
+
+    #[test]
+    fn search_can_be_cancelled() {
+        let mut engine = Engine::new(EngineConfig::default());
+        let board = Board::from_fen(START_FEN).unwrap();
+        engine.set_position(board, Vec::new());
+
+        // keep cancelling until the search returns, in case the first cancellation happens before the search started
+        let cancel_handle = engine.cancel_handle();
+        let finished = Arc::new(AtomicBool::new(false));
+        let finished_copy = Arc::clone(&finished);
+        let canceller = thread::spawn(move || {
+            while !finished_copy.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_millis(100));
+                cancel_handle.cancel();
+            }
+        });
+        let result = engine.search(SearchLimits::default());
+        finished.store(true, Ordering::Relaxed);
+        canceller.join().unwrap();
+
+        // the search stops long before the max depth and still returns a legal move
+        assert!(result.depth < 20);
+        assert!(result.best_move.is_some());
+
+        // the next search is not affected by the cancellation, even if the canceller cancelled once more after the search returned
+        engine.set_position(board, Vec::new());
+        let result = engine.search(SearchLimits { depth: Some(2), ..Default::default() });
+        assert_eq!(2, result.depth);
+    }
+
+    #[test]
+    fn cancel_before_search_is_honoured() {
+        let mut engine = Engine::new(EngineConfig::default());
+        engine.set_position(Board::from_fen(START_FEN).unwrap(), Vec::new());
+
+        // the search stops before completing the first iteration, but still returns a legal move
+        engine.cancel_handle().cancel();
+        let result = engine.search(SearchLimits { depth: Some(5), ..Default::default() });
+        assert_eq!(0, result.depth);
+        assert!(result.best_move.is_some());
+
+        // the cancel is reset once the search finished
+        let result = engine.search(SearchLimits { depth: Some(2), ..Default::default() });
+        assert_eq!(2, result.depth);
+
+        // setting a new position resets a pending cancel as well
+        engine.cancel_handle().cancel();
+        engine.set_position(Board::from_fen(START_FEN).unwrap(), Vec::new());
+        let result = engine.search(SearchLimits { depth: Some(2), ..Default::default() });
+        assert_eq!(2, result.depth);
+    }
+}
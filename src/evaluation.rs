@@ -1,74 +1,402 @@
+use std::fmt::{Display, Formatter};
+use std::ops::{Add, AddAssign, Mul, Sub, SubAssign};
 use crate::board::color::{Color, NUM_COLORS};
-use crate::board::piece::{NUM_PIECES, Piece};
+use crate::board::piece::Piece;
 use crate::board::position::Position;
+use crate::evaluation::parameters::{DEFAULT_PARAMETERS, Parameters};
+use crate::evaluation::scale_factor::SCALE_FACTOR_NORMAL;
 
 pub mod pst;
+pub mod parameters;
+pub mod tuner;
+pub mod pawn_structure;
+pub mod mobility;
+pub mod king_safety;
+pub mod scale_factor;
+
 /// The highest possible value.
 pub const POSITIVE_INFINITY: i32 = i32::MAX - 1;
 /// The lowest possible value.
 pub const NEGATIVE_INFINITY: i32 = i32::MIN + 1;
 
+/// The game phase of a position with all pieces still on the board.
+/// A phase of 0 means that only kings and pawns are left.
+pub const MAX_PHASE: i32 = 24;
+
+/// The amount each piece contributes to the game phase.
+const PHASE_WEIGHTS: [i32; 6] = [0, 1, 1, 2, 4, 0];
+
+/// The number of evaluation terms.
+pub const NUM_TERMS: usize = 5;
+
+/// The names of the evaluation terms, in the order of their indices.
+const TERM_NAMES: [&str; NUM_TERMS] = ["Material", "PST", "Pawn structure", "Mobility", "King safety"];
+
+/// The JSON keys of the evaluation terms, in the order of their indices.
+const TERM_KEYS: [&str; NUM_TERMS] = ["material", "pst", "pawn_structure", "mobility", "king_safety"];
+
+/// A pair of middlegame and endgame scores.
+///
+/// Most evaluation terms are worth a different amount in the middlegame than in the endgame.
+/// The final score is interpolated between the two, depending on the game phase (see [Tapered Eval](https://www.chessprogramming.org/Tapered_Eval)).
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct Score {
+    /// The middlegame score.
+    pub mg: i32,
+    /// The endgame score.
+    pub eg: i32,
+}
+
+/// The terms the static evaluation consists of.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Term {
+    Material = 0,
+    Pst = 1,
+    PawnStructure = 2,
+    Mobility = 3,
+    KingSafety = 4,
+}
+
+/// The static evaluation of a position, broken down into its terms.
+///
+/// All scores are from White's point of view.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Breakdown {
+    /// The scores of all terms for White and Black.
+    pub scores: [[Score; NUM_COLORS as usize]; NUM_TERMS],
+    /// The game phase of the position, ranging from 0 (endgame) to `MAX_PHASE` (middlegame).
+    pub phase: i32,
+    /// The factor the total evaluation is scaled with in drawish endgames, ranging from 0 (dead draw) to `SCALE_FACTOR_NORMAL`.
+    pub scale_factor: i32,
+}
+
+impl Score {
+    /// Constructs a new score from a middlegame and an endgame score.
+    pub const fn new(mg: i32, eg: i32) -> Self {
+        Self { mg, eg }
+    }
+
+    /// Interpolates between the middlegame and the endgame score, depending on the given game phase.
+    pub fn taper(&self, phase: i32) -> i32 {
+        (self.mg * phase + self.eg * (MAX_PHASE - phase)) / MAX_PHASE
+    }
+}
+
+impl Add for Score {
+    type Output = Score;
+
+    fn add(self, other: Score) -> Score {
+        Score::new(self.mg + other.mg, self.eg + other.eg)
+    }
+}
+
+impl AddAssign for Score {
+    fn add_assign(&mut self, other: Score) {
+        self.mg += other.mg;
+        self.eg += other.eg;
+    }
+}
+
+impl Sub for Score {
+    type Output = Score;
+
+    fn sub(self, other: Score) -> Score {
+        Score::new(self.mg - other.mg, self.eg - other.eg)
+    }
+}
+
+impl SubAssign for Score {
+    fn sub_assign(&mut self, other: Score) {
+        self.mg -= other.mg;
+        self.eg -= other.eg;
+    }
+}
+
+impl Mul<i32> for Score {
+    type Output = Score;
+
+    fn mul(self, factor: i32) -> Score {
+        Score::new(self.mg * factor, self.eg * factor)
+    }
+}
+
+impl Term {
+    /// Returns all terms, in the order of their indices.
+    pub fn all() -> [Term; NUM_TERMS] {
+        [Term::Material, Term::Pst, Term::PawnStructure, Term::Mobility, Term::KingSafety]
+    }
+
+    /// Returns the index of the term.
+    pub fn to_index(&self) -> usize {
+        *self as usize
+    }
+}
+
+impl Breakdown {
+    /// Returns the tapered score of the given term for the given color.
+    pub fn get(&self, term: Term, color: Color) -> i32 {
+        self.scores[term.to_index()][color.to_index() as usize].taper(self.phase)
+    }
+
+    /// Returns the tapered score of the given term from White's point of view.
+    pub fn get_total(&self, term: Term) -> i32 {
+        let scores = self.scores[term.to_index()];
+        (scores[Color::White.to_index() as usize] - scores[Color::Black.to_index() as usize]).taper(self.phase)
+    }
+
+    /// Returns the static evaluation from White's point of view, scaled by the scale factor.
+    pub fn total(&self) -> i32 {
+        self.unscaled_total() * self.scale_factor / SCALE_FACTOR_NORMAL
+    }
+
+    /// Returns the static evaluation from White's point of view, without applying the scale factor.
+    fn unscaled_total(&self) -> i32 {
+        Term::all().iter().map(|term| self.get_total(*term)).sum()
+    }
+
+    /// Returns the breakdown as a single line of JSON.
+    pub fn to_json(&self) -> String {
+        let mut json = format!("{{\"phase\":{}", self.phase);
+        for term in Term::all() {
+            json += format!(
+                ",\"{}\":{{\"white\":{},\"black\":{},\"total\":{}}}",
+                TERM_KEYS[term.to_index()],
+                self.get(term, Color::White),
+                self.get(term, Color::Black),
+                self.get_total(term),
+            ).as_str();
+        }
+        json += format!(",\"scale_factor\":{},\"total\":{}}}", self.scale_factor, self.total()).as_str();
+        json
+    }
+}
+
+/// Prints the breakdown as a table with a white, black and total column for every term.
+impl Display for Breakdown {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let mut output: String = String::from("");
+        output += format!("{:<16}{:>10}{:>10}{:>10}\n", "Term", "White", "Black", "Total").as_str();
+        for term in Term::all() {
+            output += format!(
+                "{:<16}{:>10}{:>10}{:>10}\n",
+                TERM_NAMES[term.to_index()],
+                self.get(term, Color::White),
+                self.get(term, Color::Black),
+                self.get_total(term),
+            ).as_str();
+        }
+        output += format!("{:<16}{:>10}{:>10}{:>10}\n", "Total", "", "", self.total()).as_str();
+        output += format!("Phase: {} / {MAX_PHASE}\n", self.phase).as_str();
+        output += format!("Scale factor: {} / {SCALE_FACTOR_NORMAL}", self.scale_factor).as_str();
+        write!(f, "{}", output)
+    }
+}
 
 /// Returns the static evaluation for the given position.
 ///
 /// The evaluation is always done from the point of view of the side whose turn it is.
-/// E.g. if it is Black's turn, and black is up a queen, the evaluation will return +900, 
+/// E.g. if it is Black's turn, and black is up a queen, the evaluation will return +900,
 /// even though chess players usually refer to such a position, from White's point of view, as -9.
+///
+/// Material and PST scores are taken from the values the position keeps up to date incrementally.
 pub fn evaluate(position: Position) -> i32 {
-    evaluate_material(position)
+    debug_assert_eq!(
+        (position.material, position.pst),
+        position.get_material_and_pst(),
+        "incrementally updated material and PST scores are out of sync for {}", position
+    );
+
+    let material = position.material.map(|material| Score::new(material, material));
+    let score = build_breakdown(position, &DEFAULT_PARAMETERS, material, position.pst).total();
+    // if it is Black's move, negate the score so that the evaluation is from Black's perspective
+    match position.color_to_move {
+        Color::White => score,
+        Color::Black => -score,
+    }
 }
 
-/// Returns the purely materialistic evaluation of the position.
-fn evaluate_material(position: Position) -> i32 {
-    let mut material_score: i32 = 0;
-    for color_index in 0..NUM_COLORS {
-        for piece_index in 0..NUM_PIECES {
-            let active_bits = position.pieces[color_index as usize][piece_index as usize].get_active_bits();
-            for square in active_bits {
-                match Color::from_index(color_index) {
-                    Color::White => material_score += pst::get_piece_value(Piece::from_index(piece_index), square, Color::from_index(color_index)),
-                    Color::Black => material_score -= pst::get_piece_value(Piece::from_index(piece_index), square, Color::from_index(color_index)),
-                }
-            }
+/// Returns the static evaluation for the given position, using the given evaluation parameters.
+///
+/// Like `evaluate`, the evaluation is done from the point of view of the side whose turn it is.
+pub fn evaluate_with_parameters(position: Position, parameters: &Parameters) -> i32 {
+    let score = get_breakdown(position, parameters).total();
+    // if it is Black's move, negate the score so that the evaluation is from Black's perspective
+    match position.color_to_move {
+        Color::White => score,
+        Color::Black => -score,
+    }
+}
+
+/// Returns the static evaluation for the given position, broken down into its terms.
+pub fn get_breakdown(position: Position, parameters: &Parameters) -> Breakdown {
+    let material = [Color::White, Color::Black].map(|color| evaluate_material(position, color, parameters));
+    let pst = [Color::White, Color::Black].map(|color| evaluate_pst(position, color, parameters));
+    build_breakdown(position, parameters, material, pst)
+}
+
+/// Builds the breakdown from the given material and PST scores, evaluating all other terms.
+fn build_breakdown(position: Position, parameters: &Parameters, material: [Score; NUM_COLORS as usize], pst: [Score; NUM_COLORS as usize]) -> Breakdown {
+    let mut scores = [[Score::default(); NUM_COLORS as usize]; NUM_TERMS];
+    scores[Term::Material.to_index()] = material;
+    scores[Term::Pst.to_index()] = pst;
+    for color in [Color::White, Color::Black] {
+        let color_index = color.to_index() as usize;
+        scores[Term::PawnStructure.to_index()][color_index] = pawn_structure::evaluate_pawn_structure(position, color, parameters);
+        scores[Term::Mobility.to_index()][color_index] = mobility::evaluate_mobility(position, color, parameters);
+        scores[Term::KingSafety.to_index()][color_index] = king_safety::evaluate_king_safety(position, color, parameters);
+    }
+    let mut breakdown = Breakdown {
+        scores,
+        phase: get_phase(position),
+        scale_factor: SCALE_FACTOR_NORMAL,
+    };
+    // scale the evaluation towards a draw if the side that is ahead has trouble converting its advantage
+    let strong_side = if breakdown.unscaled_total() >= 0 { Color::White } else { Color::Black };
+    breakdown.scale_factor = scale_factor::get_scale_factor(position, strong_side);
+    breakdown
+}
+
+/// Returns the game phase of the position, ranging from 0 (only kings and pawns left) to `MAX_PHASE` (all pieces on the board).
+pub fn get_phase(position: Position) -> i32 {
+    let mut phase = 0;
+    for color in [Color::White, Color::Black] {
+        for piece in [Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen] {
+            phase += position.get_num_pieces(piece, color) as i32 * PHASE_WEIGHTS[piece.to_index() as usize];
         }
     }
-    // if it is Black's move, negate the material score so that the evaluation is from Black's perspective
-    if position.color_to_move == Color::Black {
-        material_score = -material_score;
+    // promotions can push the phase above the maximum
+    phase.min(MAX_PHASE)
+}
+
+/// Returns the material score of the given color.
+/// The king is not counted, since both sides always have exactly one.
+fn evaluate_material(position: Position, color: Color, parameters: &Parameters) -> Score {
+    let mut material_score: i32 = 0;
+    for piece in [Piece::Pawn, Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen] {
+        material_score += position.get_num_pieces(piece, color) as i32 * parameters.piece_values[piece.to_index() as usize];
     }
-    material_score
+    Score::new(material_score, material_score)
+}
+
+/// Returns the piece-square table score of the given color.
+fn evaluate_pst(position: Position, color: Color, parameters: &Parameters) -> Score {
+    let mut pst_score = Score::default();
+    for piece in [Piece::Pawn, Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen, Piece::King] {
+        for square in position.pieces[color.to_index() as usize][piece.to_index() as usize].get_active_bits() {
+            pst_score += parameters.get_pst_score(piece, square, color);
+        }
+    }
+    pst_score
 }
 
 #[cfg(test)]
 mod tests {
     use crate::board::Board;
-    use crate::evaluation::{evaluate_material};
-    use crate::lookup::LOOKUP_TABLE;
-    use crate::lookup::lookup_table::LookupTable;
+    use crate::board::color::Color::{Black, White};
+    use crate::evaluation::{evaluate, get_breakdown, get_phase, MAX_PHASE, Score, Term};
+    use crate::evaluation::parameters::DEFAULT_PARAMETERS;
+    use crate::evaluation::scale_factor::SCALE_FACTOR_NORMAL;
 
     #[test]
     fn test_evaluate_material() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
-        assert_eq!(0, evaluate_material(position));
+        assert_eq!(0, evaluate(position));
 
         // White is missing a queen - White to move
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNB1KBNR w KQkq - 0 1").unwrap().position;
-        assert!(evaluate_material(position) < -800);
+        assert!(evaluate(position) < -800);
 
         // White is missing a queen - Black to move
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNB1KBNR b KQkq - 0 1").unwrap().position;
-        assert!(evaluate_material(position) > 800);
+        assert!(evaluate(position) > 800);
 
         // Black is missing a knight - White to move
         let position = Board::from_fen("rnbqkb1r/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
-        assert!(evaluate_material(position) > 200);
+        assert!(evaluate(position) > 200);
 
         // Black is missing a knight - Black to move
         let position = Board::from_fen("rnbqkb1r/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1").unwrap().position;
-        assert!(evaluate_material(position) < -200);
+        assert!(evaluate(position) < -200);
+    }
+
+    #[test]
+    fn test_score() {
+        assert_eq!(Score::new(30, 50), Score::new(10, 20) + Score::new(20, 30));
+        assert_eq!(Score::new(-10, -10), Score::new(10, 20) - Score::new(20, 30));
+        assert_eq!(Score::new(30, -60), Score::new(10, -20) * 3);
+
+        let score = Score::new(100, 200);
+        assert_eq!(100, score.taper(MAX_PHASE));
+        assert_eq!(200, score.taper(0));
+        assert_eq!(150, score.taper(MAX_PHASE / 2));
+    }
+
+    #[test]
+    fn test_get_phase() {
+        let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
+        assert_eq!(MAX_PHASE, get_phase(position));
+
+        let position = Board::from_fen("4k3/pppppppp/8/8/8/8/PPPPPPPP/4K3 w - - 0 1").unwrap().position;
+        assert_eq!(0, get_phase(position));
+
+        let position = Board::from_fen("3rk3/8/8/8/8/8/8/2B1K1N1 w - - 0 1").unwrap().position;
+        assert_eq!(4, get_phase(position));
+
+        // more than the maximum phase because of promotions
+        let position = Board::from_fen("QQQQ4/8/8/7k/8/8/8/QQQK4 w - - 0 1").unwrap().position;
+        assert_eq!(MAX_PHASE, get_phase(position));
+    }
+
+    #[test]
+    fn test_get_breakdown() {
+        // the starting position is symmetrical
+        let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
+        let breakdown = get_breakdown(position, &DEFAULT_PARAMETERS);
+        for term in Term::all() {
+            assert_eq!(breakdown.get(term, White), breakdown.get(term, Black));
+            assert_eq!(0, breakdown.get_total(term));
+        }
+        assert_eq!(4050, breakdown.get(Term::Material, White));
+        assert_eq!(0, breakdown.total());
+
+        // the breakdown is always from White's point of view, regardless of the color to move
+        let position = Board::from_fen("rnb1kbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1").unwrap().position;
+        let breakdown = get_breakdown(position, &DEFAULT_PARAMETERS);
+        assert_eq!(950, breakdown.get_total(Term::Material));
+        assert!(breakdown.total() > 900);
+        assert_eq!(-breakdown.total(), evaluate(position));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn breakdown_formats_correctly() {
+        let position = Board::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1").unwrap().position;
+        let breakdown = get_breakdown(position, &DEFAULT_PARAMETERS);
+
+        let output = format!("{breakdown}");
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(9, lines.len());
+        assert_eq!("Term                 White     Black     Total", lines[0]);
+        assert_eq!("Material               100         0       100", lines[1]);
+        assert_eq!(format!("Total                                    {:>5}", breakdown.total()), lines[6]);
+        assert_eq!("Phase: 0 / 24", lines[7]);
+        assert_eq!("Scale factor: 64 / 64", lines[8]);
+
+        let json = breakdown.to_json();
+        assert!(json.starts_with("{\"phase\":0,\"material\":{\"white\":100,\"black\":0,\"total\":100},\"pst\":{"));
+        assert!(json.ends_with(format!(",\"scale_factor\":64,\"total\":{}}}", breakdown.total()).as_str()));
+    }
+
+    #[test]
+    fn test_scale_factor() {
+        // insufficient material is always evaluated as a draw
+        let position = Board::from_fen("4k3/8/8/8/8/8/8/2B1K3 w - - 0 1").unwrap().position;
+        assert_eq!(0, evaluate(position));
+
+        // Black is a pawn up in an opposite-colored bishop ending, which is scaled down
+        let position = Board::from_fen("4k1b1/ppp5/8/8/8/8/PP6/2B1K3 b - - 0 1").unwrap().position;
+        let breakdown = get_breakdown(position, &DEFAULT_PARAMETERS);
+        assert!(breakdown.scale_factor < SCALE_FACTOR_NORMAL);
+        assert!(evaluate(position) > 0);
+        assert!(evaluate(position) < breakdown.get_total(Term::Material).abs());
+    }
+}
@@ -0,0 +1,60 @@
+//! This module evaluates [king safety](https://www.chessprogramming.org/King_Safety).
+//!
+//! The king gets a bonus for every friendly pawn shielding it, and a penalty for every enemy attack on the squares around it.
+//! King safety only matters in the middlegame, so the endgame values are zero by default.
+
+use crate::board::color::Color;
+use crate::board::piece::Piece;
+use crate::board::position::Position;
+use crate::evaluation::parameters::Parameters;
+use crate::evaluation::Score;
+use crate::lookup::LOOKUP_TABLE;
+
+/// The bonus for every friendly pawn directly in front of the king or diagonally in front of it.
+pub const PAWN_SHIELD: Score = Score::new(10, 0);
+
+/// The penalty for every square around the king that is attacked by the opponent.
+pub const KING_ZONE_ATTACK: Score = Score::new(-8, 0);
+
+/// Returns the king safety score of the given color.
+pub fn evaluate_king_safety(position: Position, color: Color, parameters: &Parameters) -> Score {
+    let king_bb = position.pieces[color.to_index() as usize][Piece::King.to_index() as usize];
+    let Some(&king_square) = king_bb.get_active_bits().first() else {
+        return Score::default();
+    };
+
+    let lookup = &*LOOKUP_TABLE;
+    let king_zone = lookup.get_king_attacks(king_square).value;
+
+    // the pawn shield consists of the three squares in front of the king
+    let shield_zone = match color {
+        Color::White => king_zone & u64::MAX.checked_shl(8 * (king_square.get_rank().to_index() as u32 + 1)).unwrap_or(0),
+        Color::Black => king_zone & u64::MAX.checked_shr(8 * (8 - king_square.get_rank().to_index() as u32)).unwrap_or(0),
+    };
+    let own_pawns = position.pieces[color.to_index() as usize][Piece::Pawn.to_index() as usize].value;
+    let num_shield_pawns = (own_pawns & shield_zone).count_ones() as i32;
+
+    let num_attacked_squares = (king_zone & position.get_attack_bb(color.other()).value).count_ones() as i32;
+
+    parameters.pawn_shield * num_shield_pawns + parameters.king_zone_attack * num_attacked_squares
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::board::color::Color::{Black, White};
+    use crate::evaluation::king_safety::{evaluate_king_safety, KING_ZONE_ATTACK, PAWN_SHIELD};
+    use crate::evaluation::parameters::DEFAULT_PARAMETERS;
+
+    #[test]
+    fn test_evaluate_king_safety() {
+        // castled king with an intact pawn shield
+        let position = Board::from_fen("6k1/5ppp/8/8/8/8/5PPP/6K1 w - - 0 1").unwrap().position;
+        assert_eq!(PAWN_SHIELD * 3, evaluate_king_safety(position, White, &DEFAULT_PARAMETERS));
+        assert_eq!(PAWN_SHIELD * 3, evaluate_king_safety(position, Black, &DEFAULT_PARAMETERS));
+
+        // the rook on a7 attacks f7, g7 and h7 next to the black king
+        let position = Board::from_fen("6k1/R7/8/8/8/8/8/6K1 b - - 0 1").unwrap().position;
+        assert_eq!(KING_ZONE_ATTACK * 3, evaluate_king_safety(position, Black, &DEFAULT_PARAMETERS));
+    }
+}
@@ -0,0 +1,66 @@
+//! This module evaluates the [mobility](https://www.chessprogramming.org/Mobility) of the pieces.
+//!
+//! Every square a piece can move to is worth a small bonus, unless it is occupied by a friendly piece
+//! or attacked by an enemy pawn.
+
+use crate::board::color::Color;
+use crate::board::piece::{NUM_PIECES, Piece};
+use crate::board::position::Position;
+use crate::evaluation::parameters::Parameters;
+use crate::evaluation::Score;
+use crate::lookup::LOOKUP_TABLE;
+
+/// The bonus for every square a piece can move to, indexed by the piece.
+/// Pawns and kings are not considered.
+pub const MOBILITY: [Score; NUM_PIECES as usize] = [
+    Score::new(0, 0),
+    Score::new(4, 4),
+    Score::new(5, 5),
+    Score::new(2, 4),
+    Score::new(1, 2),
+    Score::new(0, 0),
+];
+
+/// Returns the mobility score of the given color.
+pub fn evaluate_mobility(position: Position, color: Color, parameters: &Parameters) -> Score {
+    let lookup = &*LOOKUP_TABLE;
+    let blockers = position.get_occupancies();
+    let safe_squares = !(position.get_occupancy(color).value | position.get_piece_attack_bb(Piece::Pawn, color.other()).value);
+
+    let mut score = Score::default();
+    for piece in [Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen] {
+        for square in position.pieces[color.to_index() as usize][piece.to_index() as usize].get_active_bits() {
+            let attacks = match piece {
+                Piece::Knight => lookup.get_knight_attacks(square),
+                Piece::Bishop => lookup.get_bishop_attacks(square, blockers),
+                Piece::Rook => lookup.get_rook_attacks(square, blockers),
+                Piece::Queen => lookup.get_queen_attacks(square, blockers),
+                _ => unreachable!(),
+            };
+            let num_moves = (attacks.value & safe_squares).count_ones() as i32;
+            score += parameters.mobility[piece.to_index() as usize] * num_moves;
+        }
+    }
+    score
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::board::color::Color::{Black, White};
+    use crate::board::piece::Piece;
+    use crate::evaluation::mobility::{evaluate_mobility, MOBILITY};
+    use crate::evaluation::parameters::DEFAULT_PARAMETERS;
+
+    #[test]
+    fn test_evaluate_mobility() {
+        // only the knights can move in the starting position
+        let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
+        assert_eq!(MOBILITY[Piece::Knight.to_index() as usize] * 4, evaluate_mobility(position, White, &DEFAULT_PARAMETERS));
+        assert_eq!(MOBILITY[Piece::Knight.to_index() as usize] * 4, evaluate_mobility(position, Black, &DEFAULT_PARAMETERS));
+
+        // the knight on e4 can not go to c5 and g5, since they are attacked by black pawns
+        let position = Board::from_fen("4k3/8/3p3p/8/4N3/8/8/4K3 w - - 0 1").unwrap().position;
+        assert_eq!(MOBILITY[Piece::Knight.to_index() as usize] * 6, evaluate_mobility(position, White, &DEFAULT_PARAMETERS));
+    }
+}
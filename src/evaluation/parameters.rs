@@ -0,0 +1,316 @@
+//! This module bundles all evaluation parameters into a single struct.
+//!
+//! The engine itself always evaluates with `DEFAULT_PARAMETERS`, which are built from the constants in the evaluation submodules.
+//! Keeping the parameters in one place allows the tuner to adjust them one by one and to write the result back out as Rust source.
+
+use std::fmt::{Display, Formatter};
+use crate::board::color::Color;
+use crate::board::piece::{NUM_PIECES, Piece};
+use crate::board::square::Square;
+use crate::evaluation::{king_safety, mobility, pawn_structure, pst, Score};
+
+/// The names of the piece-square tables, in the order of the piece indices.
+const PST_NAMES: [&str; NUM_PIECES as usize] = [
+    "PAWN_VALUES",
+    "KNIGHT_VALUES",
+    "BISHOP_VALUES",
+    "ROOK_VALUES",
+    "QUEEN_VALUES",
+    "KING_VALUES",
+];
+
+/// The names of the endgame piece-square tables, in the order of the piece indices.
+const PST_ENDGAME_NAMES: [&str; NUM_PIECES as usize] = [
+    "PAWN_ENDGAME_VALUES",
+    "KNIGHT_ENDGAME_VALUES",
+    "BISHOP_ENDGAME_VALUES",
+    "ROOK_ENDGAME_VALUES",
+    "QUEEN_ENDGAME_VALUES",
+    "KING_ENDGAME_VALUES",
+];
+
+/// The pieces names used in the doc comments of the piece-square tables.
+const PIECE_NAMES: [&str; NUM_PIECES as usize] = [
+    "pawns",
+    "knights",
+    "bishops",
+    "rooks",
+    "queens",
+    "kings",
+];
+
+/// The number of values in the parameters, which can be accessed one by one via `Parameters::value_mut`.
+pub const NUM_VALUES: usize = NUM_PIECES as usize * (1 + 2 * 64) + 2 * (4 + 8 + NUM_PIECES as usize);
+
+/// The parameters the engine is currently evaluating with.
+pub const DEFAULT_PARAMETERS: Parameters = Parameters {
+    piece_values: pst::PIECE_VALUES,
+    pst: pst::PST,
+    pst_endgame: pst::PST_ENDGAME,
+    doubled_pawn: pawn_structure::DOUBLED_PAWN,
+    isolated_pawn: pawn_structure::ISOLATED_PAWN,
+    passed_pawn: pawn_structure::PASSED_PAWN,
+    mobility: mobility::MOBILITY,
+    pawn_shield: king_safety::PAWN_SHIELD,
+    king_zone_attack: king_safety::KING_ZONE_ATTACK,
+};
+
+/// Contains all parameters used by the static evaluation.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Parameters {
+    /// The base values of the pieces.
+    pub piece_values: [i32; NUM_PIECES as usize],
+    /// The middlegame piece-square tables for all pieces, laid out from White's point of view with A8 at index 0.
+    pub pst: [[i32; 64]; NUM_PIECES as usize],
+    /// The endgame piece-square tables for all pieces, laid out like `pst`.
+    pub pst_endgame: [[i32; 64]; NUM_PIECES as usize],
+    /// The penalty for doubled pawns.
+    pub doubled_pawn: Score,
+    /// The penalty for isolated pawns.
+    pub isolated_pawn: Score,
+    /// The bonus for passed pawns, indexed by the relative rank.
+    pub passed_pawn: [Score; 8],
+    /// The bonus per reachable square, indexed by the piece.
+    pub mobility: [Score; NUM_PIECES as usize],
+    /// The bonus per pawn shielding the king.
+    pub pawn_shield: Score,
+    /// The penalty per attacked square around the king.
+    pub king_zone_attack: Score,
+}
+
+impl Default for Parameters {
+    /// Default constructor for Parameters.
+    /// Returns the parameters the engine is currently evaluating with.
+    fn default() -> Self {
+        DEFAULT_PARAMETERS
+    }
+}
+
+impl Parameters {
+    /// Returns the relative middlegame value of the given piece, based on its color and square.
+    pub fn get_piece_value(&self, piece: Piece, square: Square, color: Color) -> i32 {
+        self.piece_values[piece.to_index() as usize] + self.get_pst_score(piece, square, color).mg
+    }
+
+    /// Returns the middlegame and endgame piece-square table score of the given piece, based on its color and square.
+    pub fn get_pst_score(&self, piece: Piece, square: Square, color: Color) -> Score {
+        let square_index = match color {
+            Color::White => square.index ^ 56,
+            Color::Black => square.index,
+        } as usize;
+        let piece_index = piece.to_index() as usize;
+        Score::new(self.pst[piece_index][square_index], self.pst_endgame[piece_index][square_index])
+    }
+
+    /// Returns a mutable reference to the value with the given index, so that the values can be adjusted one by one.
+    ///
+    /// The values are indexed in the order they are printed: piece values, middlegame PSTs, endgame PSTs and finally
+    /// the middlegame and endgame halves of all scores. The index must be smaller than `NUM_VALUES`.
+    pub fn value_mut(&mut self, index: usize) -> &mut i32 {
+        const NUM_PST_VALUES: usize = NUM_PIECES as usize * 64;
+        let mut index = index;
+        if index < NUM_PIECES as usize {
+            return &mut self.piece_values[index];
+        }
+        index -= NUM_PIECES as usize;
+        if index < NUM_PST_VALUES {
+            return &mut self.pst[index / 64][index % 64];
+        }
+        index -= NUM_PST_VALUES;
+        if index < NUM_PST_VALUES {
+            return &mut self.pst_endgame[index / 64][index % 64];
+        }
+        index -= NUM_PST_VALUES;
+        let score = self.score_mut(index / 2);
+        match index % 2 {
+            0 => &mut score.mg,
+            _ => &mut score.eg,
+        }
+    }
+
+    /// Returns a mutable reference to the score with the given index, in the order the scores are printed.
+    fn score_mut(&mut self, index: usize) -> &mut Score {
+        const MOBILITY_START: usize = 2 + 8;
+        const KING_SAFETY_START: usize = MOBILITY_START + NUM_PIECES as usize;
+        match index {
+            0 => &mut self.doubled_pawn,
+            1 => &mut self.isolated_pawn,
+            2..MOBILITY_START => &mut self.passed_pawn[index - 2],
+            MOBILITY_START..KING_SAFETY_START => &mut self.mobility[index - MOBILITY_START],
+            KING_SAFETY_START => &mut self.pawn_shield,
+            _ => &mut self.king_zone_attack,
+        }
+    }
+
+    /// Parses parameters from Rust source in the format they are printed in, e.g. a file written by the tuner.
+    ///
+    /// Comments and the declarations of the constants are skipped, and the values are read in the order they are printed.
+    pub fn parse(source: &str) -> Result<Parameters, String> {
+        let mut values = source.lines()
+            .map(|line| line.split_once("//").map_or(line, |(code, _)| code))
+            // skip everything up to the equals sign, so that array lengths are not read as values
+            .map(|line| line.split_once('=').map_or(line, |(_, value)| value))
+            .flat_map(|line| line.split(|c: char| !c.is_ascii_digit() && c != '-'))
+            .filter(|token| !token.is_empty())
+            .map(|token| token.parse::<i32>().map_err(|_| format!("invalid value: {token}")));
+
+        let mut parameters = DEFAULT_PARAMETERS;
+        for index in 0..NUM_VALUES {
+            *parameters.value_mut(index) = values.next().ok_or("too few values")??;
+        }
+        if values.next().is_some() {
+            return Err(String::from("too many values"));
+        }
+        Ok(parameters)
+    }
+}
+
+/// Prints the parameters as Rust source, in the same format as the constants in the evaluation submodules.
+/// The constants are grouped by the module they belong to.
+impl Display for Parameters {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let mut output: String = String::from("");
+
+        output += "// pst.rs\n\n";
+        output += "/// The base values of the pieces.\n";
+        output += format!("pub const PIECE_VALUES: [i32; {NUM_PIECES}] = [\n").as_str();
+        for value in self.piece_values {
+            output += format!("    {value},\n").as_str();
+        }
+        output += "];\n";
+
+        for piece_index in 0..NUM_PIECES as usize {
+            output += format_pst(&format!("Middlegame PST for {}.", PIECE_NAMES[piece_index]), PST_NAMES[piece_index], &self.pst[piece_index]).as_str();
+        }
+        for piece_index in 0..NUM_PIECES as usize {
+            output += format_pst(&format!("Endgame PST for {}.", PIECE_NAMES[piece_index]), PST_ENDGAME_NAMES[piece_index], &self.pst_endgame[piece_index]).as_str();
+        }
+
+        output += "\n// pawn_structure.rs\n\n";
+        output += format!("pub const DOUBLED_PAWN: Score = {};\n", format_score(self.doubled_pawn)).as_str();
+        output += format!("pub const ISOLATED_PAWN: Score = {};\n", format_score(self.isolated_pawn)).as_str();
+        output += format_scores("PASSED_PAWN", "8", &self.passed_pawn).as_str();
+
+        output += "\n// mobility.rs\n\n";
+        output += format_scores("MOBILITY", "NUM_PIECES as usize", &self.mobility).as_str();
+
+        output += "\n// king_safety.rs\n\n";
+        output += format!("pub const PAWN_SHIELD: Score = {};\n", format_score(self.pawn_shield)).as_str();
+        output += format!("pub const KING_ZONE_ATTACK: Score = {};\n", format_score(self.king_zone_attack)).as_str();
+
+        write!(f, "{}", output)
+    }
+}
+
+/// Formats a piece-square table as a Rust constant.
+fn format_pst(doc: &str, name: &str, values: &[i32; 64]) -> String {
+    let mut output = format!("\n/// {doc}\nconst {name}: [i32; 64] = [\n");
+    for row in values.chunks(8) {
+        output += "   ";
+        for value in row {
+            output += format!("{value:4},").as_str();
+        }
+        output += "\n";
+    }
+    output += "];\n";
+    output
+}
+
+/// Formats an array of scores as a Rust constant.
+fn format_scores(name: &str, length: &str, scores: &[Score]) -> String {
+    let mut output = format!("pub const {name}: [Score; {length}] = [\n");
+    for score in scores {
+        output += format!("    {},\n", format_score(*score)).as_str();
+    }
+    output += "];\n";
+    output
+}
+
+/// Formats a score as a Rust expression.
+fn format_score(score: Score) -> String {
+    format!("Score::new({}, {})", score.mg, score.eg)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::color::Color::{Black, White};
+    use crate::board::piece::Piece::{King, Knight, Pawn};
+    use crate::board::square;
+    use crate::evaluation::parameters::{DEFAULT_PARAMETERS, NUM_VALUES, Parameters};
+    use crate::evaluation::{pst, Score};
+
+    #[test]
+    fn default_returns_default_parameters() {
+        let parameters = Parameters::default();
+        assert_eq!(DEFAULT_PARAMETERS, parameters);
+        assert_eq!(pst::PIECE_VALUES, parameters.piece_values);
+        assert_eq!(pst::PST, parameters.pst);
+        assert_eq!(pst::PST_ENDGAME, parameters.pst_endgame);
+    }
+
+    #[test]
+    fn test_get_piece_value() {
+        let parameters = Parameters::default();
+        assert_eq!(120, parameters.get_piece_value(Pawn, square::E4, White));
+        assert_eq!(120, parameters.get_piece_value(Pawn, square::E5, Black));
+        assert_eq!(270, parameters.get_piece_value(Knight, square::A1, White));
+        assert_eq!(335, parameters.get_piece_value(Knight, square::D6, Black));
+    }
+
+    #[test]
+    fn test_get_pst_score() {
+        let parameters = Parameters::default();
+        assert_eq!(Score::new(20, 20), parameters.get_pst_score(Pawn, square::E4, White));
+        assert_eq!(Score::new(30, -30), parameters.get_pst_score(King, square::G1, White));
+        assert_eq!(Score::new(-40, 40), parameters.get_pst_score(King, square::E5, Black));
+    }
+
+    #[test]
+    fn test_value_mut() {
+        let mut parameters = Parameters::default();
+        assert_eq!(6 + 2 * 6 * 64 + 2 * (4 + 8 + 6), NUM_VALUES);
+
+        *parameters.value_mut(1) = 300;
+        *parameters.value_mut(6 + 64) = -45;
+        *parameters.value_mut(6 + 6 * 64 + 5 * 64 + 63) = -55;
+        assert_eq!(300, parameters.piece_values[1]);
+        assert_eq!(-45, parameters.pst[1][0]);
+        assert_eq!(-55, parameters.pst_endgame[5][63]);
+
+        *parameters.value_mut(6 + 2 * 6 * 64 + 1) = -25;
+        *parameters.value_mut(6 + 2 * 6 * 64 + 2 * 2) = 12;
+        *parameters.value_mut(6 + 2 * 6 * 64 + 2 * (2 + 8) + 3) = 6;
+        *parameters.value_mut(NUM_VALUES - 1) = 7;
+        assert_eq!(-25, parameters.doubled_pawn.eg);
+        assert_eq!(12, parameters.passed_pawn[0].mg);
+        assert_eq!(6, parameters.mobility[1].eg);
+        assert_eq!(7, parameters.king_zone_attack.eg);
+    }
+
+    #[test]
+    fn parameters_are_parsed_from_rust_source() {
+        let mut parameters = Parameters::default();
+        parameters.piece_values[4] = 950;
+        parameters.pst_endgame[5][63] = -55;
+        parameters.passed_pawn[6] = Score::new(75, 140);
+        parameters.king_zone_attack = Score::new(-9, -1);
+        assert_eq!(Ok(parameters), Parameters::parse(&format!("{parameters}")));
+        assert_eq!(Ok(parameters), Parameters::parse(&format!("{parameters}").replace("\n", " // tuned at 2026-10-18, 40 iterations\n")));
+
+        let source = format!("{}", Parameters::default());
+        assert_eq!(Err(String::from("too few values")), Parameters::parse(&source[..source.len() - 30]));
+        assert_eq!(Err(String::from("too many values")), Parameters::parse(&format!("{source}const EXTRA: i32 = 1;\n")));
+        assert_eq!(Err(String::from("invalid value: 1-0")), Parameters::parse(&source.replacen("100,", "1-0,", 1)));
+    }
+
+    #[test]
+    fn parameters_format_as_rust_source() {
+        let output = format!("{}", Parameters::default());
+        assert!(output.starts_with("// pst.rs\n\n/// The base values of the pieces.\npub const PIECE_VALUES: [i32; 6] = [\n    100,\n    320,\n"));
+        assert!(output.contains("/// Middlegame PST for knights.\nconst KNIGHT_VALUES: [i32; 64] = [\n    -50, -40, -30, -30, -30, -30, -40, -50,\n"));
+        assert!(output.contains("/// Endgame PST for kings.\nconst KING_ENDGAME_VALUES: [i32; 64] = [\n    -50, -40, -30, -20, -20, -30, -40, -50,\n"));
+        assert!(output.contains("// pawn_structure.rs\n\npub const DOUBLED_PAWN: Score = Score::new(-10, -20);\n"));
+        assert!(output.contains("pub const MOBILITY: [Score; NUM_PIECES as usize] = [\n    Score::new(0, 0),\n    Score::new(4, 4),\n"));
+        assert!(output.ends_with("pub const KING_ZONE_ATTACK: Score = Score::new(-8, 0);\n"));
+    }
+}
@@ -0,0 +1,115 @@
+//! This module evaluates the [pawn structure](https://www.chessprogramming.org/Pawn_Structure).
+//!
+//! Doubled and isolated pawns are penalized, while passed pawns get a bonus depending on how far they have advanced.
+
+use crate::board::bitboard::Bitboard;
+use crate::board::color::Color;
+use crate::board::piece::Piece;
+use crate::board::position::Position;
+use crate::evaluation::parameters::Parameters;
+use crate::evaluation::Score;
+
+/// The penalty for every pawn that is on the same file as another pawn of the same color.
+pub const DOUBLED_PAWN: Score = Score::new(-10, -20);
+
+/// The penalty for every pawn that has no pawns of the same color on its adjacent files.
+pub const ISOLATED_PAWN: Score = Score::new(-10, -15);
+
+/// The bonus for a passed pawn, indexed by the number of ranks the pawn has advanced from its own back rank.
+pub const PASSED_PAWN: [Score; 8] = [
+    Score::new(0, 0),
+    Score::new(5, 10),
+    Score::new(5, 15),
+    Score::new(10, 25),
+    Score::new(20, 45),
+    Score::new(35, 75),
+    Score::new(60, 120),
+    Score::new(0, 0),
+];
+
+/// A bitboard with all bits on the A file set to 1.
+const A_FILE: u64 = 0x0101010101010101;
+
+/// Returns the pawn structure score of the given color.
+pub fn evaluate_pawn_structure(position: Position, color: Color, parameters: &Parameters) -> Score {
+    let mut score = Score::default();
+    let own_pawns = position.pieces[color.to_index() as usize][Piece::Pawn.to_index() as usize];
+    let enemy_pawns = position.pieces[color.other().to_index() as usize][Piece::Pawn.to_index() as usize];
+
+    for square in own_pawns.get_active_bits() {
+        let file_index = square.get_file().to_index();
+        let rank_index = square.get_rank().to_index();
+        let file_bb = A_FILE << file_index;
+        let adjacent_files_bb = get_adjacent_files(file_index);
+
+        // doubled pawns: every pawn that shares its file with another friendly pawn is penalized
+        if Bitboard::new(own_pawns.value & file_bb).get_num_active_bits() > 1 {
+            score += parameters.doubled_pawn;
+        }
+
+        // isolated pawns: no friendly pawns on the adjacent files
+        if own_pawns.value & adjacent_files_bb == 0 {
+            score += parameters.isolated_pawn;
+        }
+
+        // passed pawns: no enemy pawns in front of the pawn on its own or the adjacent files
+        let front_span = get_front_span(rank_index, color) & (file_bb | adjacent_files_bb);
+        if enemy_pawns.value & front_span == 0 {
+            let relative_rank = match color {
+                Color::White => rank_index,
+                Color::Black => 7 - rank_index,
+            };
+            score += parameters.passed_pawn[relative_rank as usize];
+        }
+    }
+
+    score
+}
+
+/// Returns a bitboard with all squares on the files next to the given file set to 1.
+fn get_adjacent_files(file_index: u8) -> u64 {
+    let mut adjacent_files_bb = 0;
+    if file_index > 0 {
+        adjacent_files_bb |= A_FILE << (file_index - 1);
+    }
+    if file_index < 7 {
+        adjacent_files_bb |= A_FILE << (file_index + 1);
+    }
+    adjacent_files_bb
+}
+
+/// Returns a bitboard with all squares on the ranks in front of the given rank set to 1, from the point of view of the given color.
+fn get_front_span(rank_index: u8, color: Color) -> u64 {
+    match color {
+        Color::White if rank_index < 7 => u64::MAX << (8 * (rank_index + 1)),
+        Color::Black if rank_index > 0 => u64::MAX >> (8 * (8 - rank_index)),
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::board::color::Color::{Black, White};
+    use crate::evaluation::parameters::DEFAULT_PARAMETERS;
+    use crate::evaluation::pawn_structure::{DOUBLED_PAWN, evaluate_pawn_structure, ISOLATED_PAWN, PASSED_PAWN};
+    use crate::evaluation::Score;
+
+    #[test]
+    fn test_evaluate_pawn_structure() {
+        // no weaknesses and no passed pawns in the starting position
+        let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
+        assert_eq!(Score::default(), evaluate_pawn_structure(position, White, &DEFAULT_PARAMETERS));
+        assert_eq!(Score::default(), evaluate_pawn_structure(position, Black, &DEFAULT_PARAMETERS));
+
+        // White has doubled, isolated pawns on the e file, which are blocked by Black's e pawn
+        let position = Board::from_fen("4k3/4p3/8/8/4P3/4P3/8/4K3 w - - 0 1").unwrap().position;
+        assert_eq!((DOUBLED_PAWN + ISOLATED_PAWN) * 2, evaluate_pawn_structure(position, White, &DEFAULT_PARAMETERS));
+        assert_eq!(ISOLATED_PAWN, evaluate_pawn_structure(position, Black, &DEFAULT_PARAMETERS));
+
+        // both sides have a protected passed pawn
+        let position = Board::from_fen("4k3/8/2p5/1p6/6P1/7P/8/4K3 w - - 0 1").unwrap().position;
+        assert_eq!(PASSED_PAWN[3] + PASSED_PAWN[2], evaluate_pawn_structure(position, White, &DEFAULT_PARAMETERS));
+        assert_eq!(PASSED_PAWN[3] + PASSED_PAWN[2], evaluate_pawn_structure(position, Black, &DEFAULT_PARAMETERS));
+    }
+}
@@ -5,24 +5,24 @@
 //! This is necessary to encourage the engine to place its piece's on positionally advantageous squares.
 //! Otherwise, it would just shuffle pieces around aimlessly.
 //!
+//! Each piece has a middlegame and an endgame table, which are interpolated depending on the game phase.
+//!
 //! The values here are directly taken from the [Simplified Evaluation Function](https://www.chessprogramming.org/Simplified_Evaluation_Function#See_also).
+//! It only provides a separate endgame table for the king, so the endgame tables of all other pieces start out as copies of their middlegame tables.
 
 
 use crate::board::color::Color;
 use crate::board::piece::Piece;
 use crate::board::square::Square;
+use crate::evaluation::parameters::DEFAULT_PARAMETERS;
 
-/// Returns the relative value of the given piece, based on its color and square.
+/// Returns the relative middlegame value of the given piece, based on its color and square.
 pub fn get_piece_value(piece: Piece, square: Square, color: Color) -> i32 {
-    let square_index = match color {
-        Color::White => square.index ^ 56,
-        Color::Black => square.index,
-    };
-    PIECE_VALUES[piece.to_index() as usize] + PST[piece.to_index() as usize][square_index as usize]
+    DEFAULT_PARAMETERS.get_piece_value(piece, square, color)
 }
 
-/// The PST for all pieces.
-const PST: [[i32; 64]; 6] = [
+/// The middlegame PST for all pieces.
+pub const PST: [[i32; 64]; 6] = [
     PAWN_VALUES,
     KNIGHT_VALUES,
     BISHOP_VALUES,
@@ -31,6 +31,16 @@ const PST: [[i32; 64]; 6] = [
     KING_VALUES,
 ];
 
+/// The endgame PST for all pieces.
+pub const PST_ENDGAME: [[i32; 64]; 6] = [
+    PAWN_ENDGAME_VALUES,
+    KNIGHT_ENDGAME_VALUES,
+    BISHOP_ENDGAME_VALUES,
+    ROOK_ENDGAME_VALUES,
+    QUEEN_ENDGAME_VALUES,
+    KING_ENDGAME_VALUES,
+];
+
 /// The base values of the pieces.
 pub const PIECE_VALUES: [i32; 6] = [
     100,
@@ -41,7 +51,7 @@ pub const PIECE_VALUES: [i32; 6] = [
     20000,
 ];
 
-/// PST for pawns.
+/// Middlegame PST for pawns.
 const PAWN_VALUES: [i32; 64] = [
     0,  0,  0,  0,  0,  0,  0,  0,
     50, 50, 50, 50, 50, 50, 50, 50,
@@ -53,7 +63,7 @@ const PAWN_VALUES: [i32; 64] = [
     0,  0,  0,  0,  0,  0,  0,  0
 ];
 
-/// PST for knights.
+/// Middlegame PST for knights.
 const KNIGHT_VALUES: [i32; 64] = [
     -50,-40,-30,-30,-30,-30,-40,-50,
     -40,-20,  0,  0,  0,  0,-20,-40,
@@ -65,7 +75,7 @@ const KNIGHT_VALUES: [i32; 64] = [
     -50,-40,-30,-30,-30,-30,-40,-50,
 ];
 
-/// PST for bishops.
+/// Middlegame PST for bishops.
 const BISHOP_VALUES: [i32; 64] = [
     -20,-10,-10,-10,-10,-10,-10,-20,
     -10,  0,  0,  0,  0,  0,  0,-10,
@@ -77,7 +87,7 @@ const BISHOP_VALUES: [i32; 64] = [
     -20,-10,-10,-10,-10,-10,-10,-20,
 ];
 
-/// PST for rooks.
+/// Middlegame PST for rooks.
 const ROOK_VALUES: [i32; 64] = [
     0,  0,  0,  0,  0,  0,  0,  0,
     5, 10, 10, 10, 10, 10, 10,  5,
@@ -89,7 +99,7 @@ const ROOK_VALUES: [i32; 64] = [
     0,  0,  0,  5,  5,  0,  0,  0
 ];
 
-/// PST for queens.
+/// Middlegame PST for queens.
 const QUEEN_VALUES: [i32; 64] = [
     -20,-10,-10, -5, -5,-10,-10,-20,
     -10,  0,  0,  0,  0,  0,  0,-10,
@@ -101,7 +111,7 @@ const QUEEN_VALUES: [i32; 64] = [
     -20,-10,-10, -5, -5,-10,-10,-20
 ];
 
-/// PST for kings.
+/// Middlegame PST for kings.
 const KING_VALUES: [i32; 64] = [
     -30,-40,-40,-50,-50,-40,-40,-30,
     -30,-40,-40,-50,-50,-40,-40,-30,
@@ -111,4 +121,76 @@ const KING_VALUES: [i32; 64] = [
     -10,-20,-20,-20,-20,-20,-20,-10,
     20, 20,  0,  0,  0,  0, 20, 20,
     20, 30, 10,  0,  0, 10, 30, 20
-];
\ No newline at end of file
+];
+
+/// Endgame PST for pawns.
+const PAWN_ENDGAME_VALUES: [i32; 64] = [
+    0,  0,  0,  0,  0,  0,  0,  0,
+    50, 50, 50, 50, 50, 50, 50, 50,
+    10, 10, 20, 30, 30, 20, 10, 10,
+    5,  5, 10, 25, 25, 10,  5,  5,
+    0,  0,  0, 20, 20,  0,  0,  0,
+    5, -5,-10,  0,  0,-10, -5,  5,
+    5, 10, 10,-20,-20, 10, 10,  5,
+    0,  0,  0,  0,  0,  0,  0,  0
+];
+
+/// Endgame PST for knights.
+const KNIGHT_ENDGAME_VALUES: [i32; 64] = [
+    -50,-40,-30,-30,-30,-30,-40,-50,
+    -40,-20,  0,  0,  0,  0,-20,-40,
+    -30,  0, 10, 15, 15, 10,  0,-30,
+    -30,  5, 15, 20, 20, 15,  5,-30,
+    -30,  0, 15, 20, 20, 15,  0,-30,
+    -30,  5, 10, 15, 15, 10,  5,-30,
+    -40,-20,  0,  5,  5,  0,-20,-40,
+    -50,-40,-30,-30,-30,-30,-40,-50,
+];
+
+/// Endgame PST for bishops.
+const BISHOP_ENDGAME_VALUES: [i32; 64] = [
+    -20,-10,-10,-10,-10,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5, 10, 10,  5,  0,-10,
+    -10,  5,  5, 10, 10,  5,  5,-10,
+    -10,  0, 10, 10, 10, 10,  0,-10,
+    -10, 10, 10, 10, 10, 10, 10,-10,
+    -10,  5,  0,  0,  0,  0,  5,-10,
+    -20,-10,-10,-10,-10,-10,-10,-20,
+];
+
+/// Endgame PST for rooks.
+const ROOK_ENDGAME_VALUES: [i32; 64] = [
+    0,  0,  0,  0,  0,  0,  0,  0,
+    5, 10, 10, 10, 10, 10, 10,  5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    0,  0,  0,  5,  5,  0,  0,  0
+];
+
+/// Endgame PST for queens.
+const QUEEN_ENDGAME_VALUES: [i32; 64] = [
+    -20,-10,-10, -5, -5,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5,  5,  5,  5,  0,-10,
+    -5,  0,  5,  5,  5,  5,  0, -5,
+    0,  0,  5,  5,  5,  5,  0, -5,
+    -10,  5,  5,  5,  5,  5,  0,-10,
+    -10,  0,  5,  0,  0,  0,  0,-10,
+    -20,-10,-10, -5, -5,-10,-10,-20
+];
+
+/// Endgame PST for kings.
+const KING_ENDGAME_VALUES: [i32; 64] = [
+    -50,-40,-30,-20,-20,-30,-40,-50,
+    -30,-20,-10,  0,  0,-10,-20,-30,
+    -30,-10, 20, 30, 30, 20,-10,-30,
+    -30,-10, 30, 40, 40, 30,-10,-30,
+    -30,-10, 30, 40, 40, 30,-10,-30,
+    -30,-10, 20, 30, 30, 20,-10,-30,
+    -30,-30,  0,  0,  0,  0,-30,-30,
+    -50,-30,-30,-30,-30,-30,-30,-50
+];
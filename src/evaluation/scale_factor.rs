@@ -0,0 +1,173 @@
+//! This module calculates endgame scale factors.
+//!
+//! Some endgames are much more drawish than the material balance suggests, e.g. opposite-colored bishop endings.
+//! In these, the evaluation is scaled towards zero, so that the engine does not trade into them when ahead,
+//! and seeks them out when behind.
+
+use crate::board::bitboard::{Bitboard, DARK_SQUARES, LIGHT_SQUARES};
+use crate::board::color::Color;
+use crate::board::file::File;
+use crate::board::piece::Piece;
+use crate::board::position::Position;
+use crate::board::square::Square;
+
+/// The scale factor for positions that are evaluated normally.
+pub const SCALE_FACTOR_NORMAL: i32 = 64;
+
+/// The scale factor for positions that are dead draws.
+pub const SCALE_FACTOR_DRAW: i32 = 0;
+
+/// The scale factor for endings where both sides only have a bishop of opposite colors, apart from pawns.
+const OPPOSITE_BISHOPS: i32 = 16;
+
+/// The scale factor for positions with opposite-colored bishops and other pieces.
+const OPPOSITE_BISHOPS_WITH_PIECES: i32 = 48;
+
+/// The scale factor for KR vs KB.
+const ROOK_VS_BISHOP: i32 = 16;
+
+/// A bitboard with all bits on the A file set to 1.
+const A_FILE: u64 = 0x0101010101010101;
+
+/// A bitboard with all bits on the H file set to 1.
+const H_FILE: u64 = 0x8080808080808080;
+
+/// Returns the factor the evaluation is scaled with, ranging from `SCALE_FACTOR_DRAW` to `SCALE_FACTOR_NORMAL`.
+///
+/// The strong side is the side the unscaled evaluation favors.
+pub fn get_scale_factor(position: Position, strong_side: Color) -> i32 {
+    if position.is_insufficient_material() {
+        return SCALE_FACTOR_DRAW;
+    }
+
+    let weak_side = strong_side.other();
+    let strong_pieces = position.pieces[strong_side.to_index() as usize];
+    let weak_pieces = position.pieces[weak_side.to_index() as usize];
+    let strong_bishops = strong_pieces[Piece::Bishop.to_index() as usize];
+    let weak_bishops = weak_pieces[Piece::Bishop.to_index() as usize];
+
+    // rook pawns with a bishop that does not control the promotion square
+    if has_only(position, strong_side, &[Piece::Bishop, Piece::Pawn]) && strong_bishops.value != 0 && has_only(position, weak_side, &[]) {
+        if let Some(scale_factor) = get_wrong_bishop_scale_factor(position, strong_side) {
+            return scale_factor;
+        }
+    }
+
+    // KR vs KB
+    if has_only(position, strong_side, &[Piece::Rook]) && has_only(position, weak_side, &[Piece::Bishop]) &&
+        position.get_num_pieces(Piece::Rook, strong_side) == 1 && position.get_num_pieces(Piece::Bishop, weak_side) == 1 {
+        return ROOK_VS_BISHOP;
+    }
+
+    // opposite-colored bishops
+    if position.get_num_pieces(Piece::Bishop, strong_side) == 1 && position.get_num_pieces(Piece::Bishop, weak_side) == 1 &&
+        is_on_same_colored_squares(strong_bishops) && (strong_bishops.value | weak_bishops.value) & LIGHT_SQUARES.value != 0 &&
+        (strong_bishops.value | weak_bishops.value) & DARK_SQUARES.value != 0 {
+        return if has_only(position, strong_side, &[Piece::Bishop, Piece::Pawn]) && has_only(position, weak_side, &[Piece::Bishop, Piece::Pawn]) {
+            OPPOSITE_BISHOPS
+        } else {
+            OPPOSITE_BISHOPS_WITH_PIECES
+        };
+    }
+
+    SCALE_FACTOR_NORMAL
+}
+
+/// Returns the draw scale factor if all pawns of the strong side are rook pawns on the same file,
+/// the bishops can not control the promotion square, and the defending king has reached the corner.
+fn get_wrong_bishop_scale_factor(position: Position, strong_side: Color) -> Option<i32> {
+    let pawns = position.pieces[strong_side.to_index() as usize][Piece::Pawn.to_index() as usize].value;
+    let bishops = position.pieces[strong_side.to_index() as usize][Piece::Bishop.to_index() as usize];
+
+    let file = if pawns != 0 && pawns & !A_FILE == 0 {
+        File::A
+    } else if pawns != 0 && pawns & !H_FILE == 0 {
+        File::H
+    } else {
+        return None;
+    };
+    let promotion_square = Square::from_file_rank(file, strong_side.promotion_rank());
+
+    // the bishops have to be unable to reach the promotion square
+    if !is_on_same_colored_squares(bishops) || bishops.value & get_square_color(promotion_square).value != 0 {
+        return None;
+    }
+
+    // the defending king has to be in front of the pawn, next to or on the promotion square
+    let weak_king = position.pieces[strong_side.other().to_index() as usize][Piece::King.to_index() as usize].get_active_bits();
+    let king_square = weak_king.first()?;
+    let file_distance = (king_square.get_file().to_index() as i8 - promotion_square.get_file().to_index() as i8).abs();
+    let rank_distance = (king_square.get_rank().to_index() as i8 - promotion_square.get_rank().to_index() as i8).abs();
+    if file_distance <= 1 && rank_distance <= 1 {
+        Some(SCALE_FACTOR_DRAW)
+    } else {
+        None
+    }
+}
+
+/// Returns whether the given color has no pieces besides the king and the given pieces.
+fn has_only(position: Position, color: Color, pieces: &[Piece]) -> bool {
+    [Piece::Pawn, Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen].iter()
+        .filter(|piece| !pieces.contains(piece))
+        .all(|piece| position.get_num_pieces(*piece, color) == 0)
+}
+
+/// Returns whether all set squares of the bitboard have the same color.
+fn is_on_same_colored_squares(bitboard: Bitboard) -> bool {
+    bitboard.value & LIGHT_SQUARES.value == 0 || bitboard.value & DARK_SQUARES.value == 0
+}
+
+/// Returns a bitboard containing all squares of the same color as the given square.
+fn get_square_color(square: Square) -> Bitboard {
+    if LIGHT_SQUARES.get_bit(square) {
+        LIGHT_SQUARES
+    } else {
+        DARK_SQUARES
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::board::color::Color::{Black, White};
+    use crate::evaluation::scale_factor::{get_scale_factor, OPPOSITE_BISHOPS, OPPOSITE_BISHOPS_WITH_PIECES, ROOK_VS_BISHOP, SCALE_FACTOR_DRAW, SCALE_FACTOR_NORMAL};
+
+    #[test]
+    fn test_get_scale_factor() {
+        // normal positions
+        let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
+        assert_eq!(SCALE_FACTOR_NORMAL, get_scale_factor(position, White));
+        let position = Board::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1").unwrap().position;
+        assert_eq!(SCALE_FACTOR_NORMAL, get_scale_factor(position, White));
+
+        // insufficient material
+        let position = Board::from_fen("4k3/8/8/8/8/8/8/4KN2 w - - 0 1").unwrap().position;
+        assert_eq!(SCALE_FACTOR_DRAW, get_scale_factor(position, White));
+
+        // rook pawn and wrong bishop - only a draw if the defending king reaches the corner
+        let position = Board::from_fen("7k/8/8/8/8/7P/8/4KB2 w - - 0 1").unwrap().position;
+        assert_eq!(SCALE_FACTOR_DRAW, get_scale_factor(position, White));
+        let position = Board::from_fen("8/8/8/2k5/8/7P/8/4KB2 w - - 0 1").unwrap().position;
+        assert_eq!(SCALE_FACTOR_NORMAL, get_scale_factor(position, White));
+        let position = Board::from_fen("7k/8/8/8/8/7P/8/4K1B1 w - - 0 1").unwrap().position;
+        assert_eq!(SCALE_FACTOR_NORMAL, get_scale_factor(position, White));
+        let position = Board::from_fen("4k1b1/8/p7/8/8/8/8/K7 b - - 0 1").unwrap().position;
+        assert_eq!(SCALE_FACTOR_DRAW, get_scale_factor(position, Black));
+        let position = Board::from_fen("k7/8/8/P7/8/8/8/2B1K3 w - - 0 1").unwrap().position;
+        assert_eq!(SCALE_FACTOR_DRAW, get_scale_factor(position, White));
+        let position = Board::from_fen("k7/8/8/P7/8/8/8/1B2K3 w - - 0 1").unwrap().position;
+        assert_eq!(SCALE_FACTOR_NORMAL, get_scale_factor(position, White));
+
+        // KR vs KB
+        let position = Board::from_fen("4kb2/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap().position;
+        assert_eq!(ROOK_VS_BISHOP, get_scale_factor(position, White));
+
+        // opposite-colored bishops
+        let position = Board::from_fen("4k1b1/pp6/8/8/8/8/PPP5/2B1K3 w - - 0 1").unwrap().position;
+        assert_eq!(OPPOSITE_BISHOPS, get_scale_factor(position, White));
+        let position = Board::from_fen("r3k1b1/pp6/8/8/8/8/PPP5/2B1K2R w - - 0 1").unwrap().position;
+        assert_eq!(OPPOSITE_BISHOPS_WITH_PIECES, get_scale_factor(position, White));
+        let position = Board::from_fen("4kb2/pp6/8/8/8/8/PPP5/2B1K3 w - - 0 1").unwrap().position;
+        assert_eq!(SCALE_FACTOR_NORMAL, get_scale_factor(position, White));
+    }
+}
@@ -0,0 +1,341 @@
+//! This module implements [Texel's Tuning Method](https://www.chessprogramming.org/Texel%27s_Tuning_Method).
+//!
+//! The tuner reads positions labeled with the result of the game they were taken from, resolves them with a
+//! quiescence search, and then adjusts the evaluation parameters one by one (coordinate descent) to minimize
+//! the mean squared error between the game results and the sigmoid of the static evaluation.
+
+use std::cmp::Reverse;
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use crate::board::Board;
+use crate::board::color::Color;
+use crate::board::position::Position;
+use crate::evaluation;
+use crate::evaluation::parameters::{NUM_VALUES, Parameters};
+use crate::move_gen;
+use crate::move_gen::ply::Ply;
+
+/// A single training position together with the result of the game it was taken from.
+#[derive(Copy, Clone, Debug)]
+pub struct TuningEntry {
+    /// The quiet position arising from the quiescence search of the original position.
+    pub position: Position,
+    /// The result of the game from White's point of view (1.0 for a win, 0.5 for a draw, 0.0 for a loss).
+    pub result: f64,
+}
+
+/// The tuner holds the training data and the parameters that are being optimized.
+pub struct Tuner {
+    /// The training positions.
+    entries: Vec<TuningEntry>,
+    /// The parameters that are being optimized.
+    parameters: Parameters,
+    /// The scaling constant of the sigmoid function.
+    k: f64,
+    /// The amount by which a parameter is changed in each step.
+    step: i32,
+    /// The mean squared error of the current parameters.
+    error: f64,
+}
+
+impl TuningEntry {
+    /// Constructs a new tuning entry.
+    /// The given position is resolved with a quiescence search, so that only quiet positions are used for tuning.
+    pub fn new(position: Position, result: f64) -> Self {
+        Self {
+            position: resolve(position),
+            result,
+        }
+    }
+}
+
+impl Tuner {
+    /// Constructs a new tuner for the given entries, starting with the given parameters.
+    /// The scaling constant K is computed so that the error of the starting parameters is minimal.
+    pub fn new(entries: Vec<TuningEntry>, parameters: Parameters, step: i32) -> Self {
+        let k = find_k(&entries, &parameters);
+        let error = mean_squared_error(&entries, &parameters, k);
+        Self {
+            entries,
+            parameters,
+            k,
+            step,
+            error,
+        }
+    }
+
+    /// Returns the current parameters.
+    pub fn parameters(&self) -> Parameters {
+        self.parameters
+    }
+
+    /// Returns the scaling constant K.
+    pub fn k(&self) -> f64 {
+        self.k
+    }
+
+    /// Returns the mean squared error of the current parameters.
+    pub fn error(&self) -> f64 {
+        self.error
+    }
+
+    /// Performs one pass of coordinate descent over all parameters.
+    ///
+    /// Every parameter is first increased by the step size. If that does not reduce the error, it is decreased instead.
+    /// If neither reduces the error, the parameter keeps its value.
+    /// Returns true if at least one parameter was changed.
+    pub fn run_iteration(&mut self) -> bool {
+        let mut improved = false;
+        for parameter_index in 0..NUM_VALUES {
+            let original_value = *self.parameters.value_mut(parameter_index);
+
+            // try increasing the parameter
+            *self.parameters.value_mut(parameter_index) = original_value + self.step;
+            let error = mean_squared_error(&self.entries, &self.parameters, self.k);
+            if error < self.error {
+                self.error = error;
+                improved = true;
+                continue;
+            }
+
+            // try decreasing the parameter
+            *self.parameters.value_mut(parameter_index) = original_value - self.step;
+            let error = mean_squared_error(&self.entries, &self.parameters, self.k);
+            if error < self.error {
+                self.error = error;
+                improved = true;
+                continue;
+            }
+
+            // neither direction helped - restore the original value
+            *self.parameters.value_mut(parameter_index) = original_value;
+        }
+
+        improved
+    }
+}
+
+/// Reads the training data from the given file.
+///
+/// Every line must start with a FEN or EPD position, followed by the game result.
+/// Lines that can not be parsed are skipped.
+pub fn load_entries(path: &Path) -> io::Result<Vec<TuningEntry>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut entries: Vec<TuningEntry> = Vec::new();
+    for line in reader.lines() {
+        if let Some(entry) = parse_entry(line?.as_str()) {
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+/// Parses a line of training data.
+///
+/// The position can be given as a full FEN or as the first four fields of an EPD record.
+/// The result is accepted in the most common formats, for example `1-0`, `"1/2-1/2";`, `c9 "0-1";` or `[0.5]`.
+pub fn parse_entry(line: &str) -> Option<TuningEntry> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() < 5 {
+        return None;
+    }
+
+    // the first four fields are always part of the position, the halfmove clock and fullmove counter are optional
+    let mut num_fen_fields = 4;
+    while num_fen_fields < 6 && num_fen_fields < fields.len() - 1 && fields[num_fen_fields].parse::<u32>().is_ok() {
+        num_fen_fields += 1;
+    }
+
+    let board = Board::from_fen(fields[..num_fen_fields].join(" ").as_str()).ok()?;
+    if !board.position.is_legal() {
+        return None;
+    }
+
+    let result = fields[num_fen_fields..].iter().find_map(|field| parse_result(field))?;
+
+    Some(TuningEntry::new(board.position, result))
+}
+
+/// Parses a game result from White's point of view.
+fn parse_result(result_str: &str) -> Option<f64> {
+    let trimmed_str = result_str.trim_matches(|c| c == '"' || c == ';' || c == '[' || c == ']');
+    match trimmed_str {
+        "1-0" => Some(1.0),
+        "0-1" => Some(0.0),
+        "1/2-1/2" => Some(0.5),
+        // numeric results must either be bracketed or contain a decimal point, otherwise they can't be told apart from move counters
+        other if result_str.starts_with('[') || other.contains('.') => match other.parse::<f64>() {
+            Ok(result) if (0.0..=1.0).contains(&result) => Some(result),
+            _other => None,
+        }
+        _other => None,
+    }
+}
+
+/// Returns the quiet position at the end of the principal variation of a quiescence search of the given position.
+pub fn resolve(position: Position) -> Position {
+    let (_score, quiet_position) = quiescence_search(position, evaluation::NEGATIVE_INFINITY, evaluation::POSITIVE_INFINITY, &Parameters::default());
+    quiet_position
+}
+
+/// A quiescence search that, in addition to the score, returns the position at the end of the principal variation.
+fn quiescence_search(position: Position, mut alpha: i32, beta: i32, parameters: &Parameters) -> (i32, Position) {
+    // establish the lower bound of the score with the static evaluation
+    let standing_pat = evaluation::evaluate_with_parameters(position, parameters);
+    if standing_pat >= beta {
+        return (beta, position);
+    }
+    if standing_pat > alpha {
+        alpha = standing_pat;
+    }
+
+    // the position at the end of the principal variation
+    let mut quiet_position = position;
+
+    // generate all legal capture moves and sort them by MVV-LVA
+    let capture_list = move_gen::generate_moves(position).get_captures();
+    let mut captures: Vec<Ply> = (0..capture_list.len()).map(|i| capture_list.get(i)).collect();
+    captures.sort_by_key(|ply| Reverse(ply.score()));
+
+    for ply in captures {
+        let (score, leaf) = quiescence_search(position.make_move(ply), -beta, -alpha, parameters);
+        let score = -score;
+        if score >= beta {
+            return (beta, leaf);
+        }
+        if score > alpha {
+            alpha = score;
+            quiet_position = leaf;
+        }
+    }
+
+    (alpha, quiet_position)
+}
+
+/// Maps an evaluation (from White's point of view) to an expected game result between 0.0 and 1.0.
+pub fn sigmoid(score: i32, k: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-k * score as f64 / 400.0))
+}
+
+/// Returns the mean squared error between the game results and the expected results of the static evaluation.
+pub fn mean_squared_error(entries: &[TuningEntry], parameters: &Parameters, k: f64) -> f64 {
+    if entries.is_empty() {
+        return 0.0;
+    }
+    let total_error: f64 = entries.iter().map(|entry| {
+        // the evaluation is done from the point of view of the side to move, but the results are from White's point of view
+        let mut score = evaluation::evaluate_with_parameters(entry.position, parameters);
+        if entry.position.color_to_move == Color::Black {
+            score = -score;
+        }
+        (entry.result - sigmoid(score, k)).powi(2)
+    }).sum();
+    total_error / entries.len() as f64
+}
+
+/// Finds the scaling constant K that minimizes the error for the given parameters.
+///
+/// The search starts with a coarse scan and then repeatedly scans a smaller interval around the best value.
+pub fn find_k(entries: &[TuningEntry], parameters: &Parameters) -> f64 {
+    let mut best_k = 1.0;
+    let mut best_error = mean_squared_error(entries, parameters, best_k);
+    let mut start = 0.0;
+    let mut end = 3.0;
+    let mut step = 0.1;
+    for _ in 0..4 {
+        let mut k = start;
+        while k <= end {
+            let error = mean_squared_error(entries, parameters, k);
+            if error < best_error {
+                best_error = error;
+                best_k = k;
+            }
+            k += step;
+        }
+        start = best_k - step;
+        end = best_k + step;
+        step /= 10.0;
+    }
+    best_k
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::evaluation::parameters::Parameters;
+    use crate::evaluation::tuner::{find_k, mean_squared_error, parse_entry, parse_result, resolve, sigmoid, Tuner};
+
+    #[test]
+    fn test_parse_result() {
+        assert_eq!(Some(1.0), parse_result("1-0"));
+        assert_eq!(Some(0.0), parse_result("0-1"));
+        assert_eq!(Some(0.5), parse_result("1/2-1/2"));
+        assert_eq!(Some(0.5), parse_result("\"1/2-1/2\";"));
+        assert_eq!(Some(1.0), parse_result("[1.0]"));
+        assert_eq!(Some(0.0), parse_result("[0]"));
+        assert_eq!(None, parse_result("c9"));
+        assert_eq!(None, parse_result("2.0"));
+        assert_eq!(None, parse_result("*"));
+        assert_eq!(None, parse_result("1"));
+        assert_eq!(Some(1.0), parse_result("1.0"));
+    }
+
+    #[test]
+    fn test_parse_entry() {
+        let entry = parse_entry("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 [0.5]").unwrap();
+        assert_eq!(Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position, entry.position);
+        assert_eq!(0.5, entry.result);
+
+        let entry = parse_entry("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - c9 \"1-0\";").unwrap();
+        assert_eq!(1.0, entry.result);
+
+        let entry = parse_entry("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0-1").unwrap();
+        assert_eq!(0.0, entry.result);
+
+        assert!(parse_entry("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -").is_none());
+        assert!(parse_entry("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").is_none());
+        assert!(parse_entry("this is not a valid entry").is_none());
+    }
+
+    #[test]
+    fn test_resolve() {
+        // quiet positions stay the same
+        let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
+        assert_eq!(position, resolve(position));
+
+        // the hanging queen is captured
+        let position = Board::from_fen("rnb1kbnr/pppp1ppp/8/6q1/3P4/2N5/PPP1PPPP/R1BQKBNR w KQkq - 0 1").unwrap().position;
+        assert_eq!(Board::from_fen("rnb1kbnr/pppp1ppp/8/6B1/3P4/2N5/PPP1PPPP/R2QKBNR b KQkq - 0 1").unwrap().position, resolve(position));
+    }
+
+    #[test]
+    fn test_sigmoid() {
+        assert_eq!(0.5, sigmoid(0, 1.0));
+        assert!(sigmoid(400, 1.0) > 0.9);
+        assert!(sigmoid(-400, 1.0) < 0.1);
+        assert!((sigmoid(100, 1.0) + sigmoid(-100, 1.0) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_tuner_reduces_error() {
+        let entries = vec![
+            parse_entry("rnb1kbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 [1.0]").unwrap(),
+            parse_entry("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNB1KBNR b KQkq - 0 1 [0.0]").unwrap(),
+            parse_entry("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1 [0.5]").unwrap(),
+            parse_entry("4k3/4p3/8/8/8/8/8/4K3 w - - 0 1 [0.5]").unwrap(),
+        ];
+        let parameters = Parameters::default();
+        let k = find_k(&entries, &parameters);
+        assert!(k > 0.0);
+
+        let mut tuner = Tuner::new(entries.clone(), parameters, 10);
+        assert_eq!(mean_squared_error(&entries, &parameters, k), tuner.error());
+
+        let error_before = tuner.error();
+        assert!(tuner.run_iteration());
+        assert!(tuner.error() < error_before);
+        assert_eq!(tuner.error(), mean_squared_error(&entries, &tuner.parameters(), tuner.k()));
+    }
+}
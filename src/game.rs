@@ -0,0 +1,248 @@
+//! The game module keeps track of a game of chess: the moves that were played, the boards they led to and how the game ended.
+//!
+//! A [`Game`] is shared by all protocol front ends. It grows without a fixed limit, can take back moves, and
+//! detects the end of the game as a [`GameResult`].
+
+use std::fmt::{Display, Formatter};
+use crate::board::Board;
+use crate::board::color::Color;
+use crate::move_gen;
+use crate::move_gen::ply::Ply;
+
+/// The reason a game ended.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum GameResult {
+    /// The side to move is checkmated. Contains the winner.
+    Checkmate(Color),
+    /// The side to move has no legal moves, but is not in check.
+    Stalemate,
+    /// The current position occurred for the third time.
+    ThreefoldRepetition,
+    /// No pawn was moved and no piece was captured in the last 50 moves.
+    FiftyMoveRule,
+    /// Neither side has enough material left to checkmate.
+    InsufficientMaterial,
+}
+
+/// A game of chess, consisting of the board it started from and the moves that were played.
+#[derive(Clone, Debug)]
+pub struct Game {
+    /// The boards of the game, starting with the initial board. The last board is the current one.
+    boards: Vec<Board>,
+    /// The moves of the game. The move at index `i` was played on the board at index `i`.
+    moves: Vec<Ply>,
+}
+
+impl GameResult {
+    /// Returns the winner, or `None` if the game is drawn.
+    pub fn get_winner(&self) -> Option<Color> {
+        match self {
+            GameResult::Checkmate(winner) => Some(*winner),
+            _other => None,
+        }
+    }
+
+    /// Returns the result as it is written in PGN, i.e. `1-0`, `0-1` or `1/2-1/2`.
+    pub fn to_pgn(&self) -> &'static str {
+        get_pgn_result(self.get_winner())
+    }
+}
+
+/// Prints the reason the game ended, e.g. "White mates".
+impl Display for GameResult {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            GameResult::Checkmate(winner) => write!(f, "{winner} mates"),
+            GameResult::Stalemate => write!(f, "Stalemate"),
+            GameResult::ThreefoldRepetition => write!(f, "Threefold repetition"),
+            GameResult::FiftyMoveRule => write!(f, "Fifty move rule"),
+            GameResult::InsufficientMaterial => write!(f, "Insufficient material"),
+        }
+    }
+}
+
+impl Default for Game {
+    /// Default constructor for `Game`. The game starts from an empty board until a position is set up.
+    fn default() -> Self {
+        Self::new(Board::default())
+    }
+}
+
+impl Game {
+    /// Constructs a new game starting from the given board.
+    pub fn new(start: Board) -> Self {
+        Self {
+            boards: vec![start],
+            moves: Vec::new(),
+        }
+    }
+
+    /// Returns the current board.
+    pub fn get_board(&self) -> Board {
+        *self.boards.last().unwrap()
+    }
+
+    /// Returns the board the game started from.
+    pub fn get_start_board(&self) -> Board {
+        self.boards[0]
+    }
+
+    /// Returns the moves that were played.
+    pub fn get_moves(&self) -> &[Ply] {
+        &self.moves
+    }
+
+    /// Returns the hashes of all positions before the current one, which the search needs to detect repetitions.
+    pub fn get_board_history(&self) -> Vec<u64> {
+        self.boards[..self.boards.len() - 1].iter().map(|board| board.position.hash).collect()
+    }
+
+    /// Plays the given move, which has to be legal in the current position.
+    pub fn make_move(&mut self, ply: Ply) {
+        self.boards.push(self.get_board().make_move(ply));
+        self.moves.push(ply);
+    }
+
+    /// Takes back the last move and returns it, or returns `None` if no move was played.
+    pub fn undo(&mut self) -> Option<Ply> {
+        let ply = self.moves.pop()?;
+        self.boards.pop();
+        Some(ply)
+    }
+
+    /// Takes back the given number of moves, as far as possible, and returns the number of moves taken back.
+    pub fn take_back(&mut self, num_moves: usize) -> usize {
+        (0..num_moves).take_while(|_| self.undo().is_some()).count()
+    }
+
+    /// Returns how the game ended, or `None` if it is still going on.
+    pub fn get_result(&self) -> Option<GameResult> {
+        let board = self.get_board();
+        let color_to_move = board.position.color_to_move;
+        if move_gen::generate_moves(board.position).is_empty() {
+            return Some(match board.position.is_in_check(color_to_move) {
+                true => GameResult::Checkmate(color_to_move.other()),
+                false => GameResult::Stalemate,
+            });
+        }
+        if board.halfmove_clock >= 100 {
+            return Some(GameResult::FiftyMoveRule);
+        }
+        if board.position.is_insufficient_material() {
+            return Some(GameResult::InsufficientMaterial);
+        }
+        if self.is_threefold_repetition() {
+            return Some(GameResult::ThreefoldRepetition);
+        }
+        None
+    }
+
+    /// Checks whether the current position occurred at least three times.
+    /// Only the positions since the last capture or pawn move have to be compared.
+    fn is_threefold_repetition(&self) -> bool {
+        let board = self.get_board();
+        let num_occurrences = self.boards.iter()
+            .rev()
+            .take(board.halfmove_clock as usize + 1)
+            .filter(|previous_board| previous_board.position.hash == board.position.hash)
+            .count();
+        num_occurrences >= 3
+    }
+}
+
+/// Returns the result of a game with the given winner as it is written in PGN, i.e. `1-0`, `0-1` or `1/2-1/2`.
+/// A winner of `None` means that the game is drawn.
+pub fn get_pgn_result(winner: Option<Color>) -> &'static str {
+    match winner {
+        Some(Color::White) => "1-0",
+        Some(Color::Black) => "0-1",
+        None => "1/2-1/2",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::board::color::Color;
+    use crate::board::fen::START_FEN;
+    use crate::game::{Game, GameResult};
+    use crate::move_gen::ply::Ply;
+
+    /// Plays the given moves in the given game.
+    fn play(game: &mut Game, moves: &[&str]) {
+        for move_str in moves {
+            game.make_move(Ply::from_string(move_str, game.get_board().position).unwrap());
+        }
+    }
+
+    #[test]
+    fn make_move_and_undo() {
+        let start = Board::from_fen(START_FEN).unwrap();
+        let mut game = Game::new(start);
+        play(&mut game, &["e2e4", "e7e5", "g1f3"]);
+        assert_eq!("rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2", game.get_board().to_fen());
+        assert_eq!(3, game.get_moves().len());
+        assert_eq!(3, game.get_board_history().len());
+        assert_eq!(start.position.hash, game.get_board_history()[0]);
+
+        let ply = game.undo().unwrap();
+        assert_eq!("g1f3", ply.to_uci(game.get_board().position, false));
+        assert_eq!("rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2", game.get_board().to_fen());
+
+        assert_eq!(2, game.take_back(5));
+        assert_eq!(start, game.get_board());
+        assert_eq!(start, game.get_start_board());
+        assert!(game.get_moves().is_empty());
+        assert!(game.get_board_history().is_empty());
+        assert_eq!(None, game.undo());
+    }
+
+    #[test]
+    fn game_grows_beyond_1000_plies() {
+        let mut game = Game::new(Board::from_fen(START_FEN).unwrap());
+        for _ in 0..300 {
+            play(&mut game, &["g1f3", "g8f6", "f3g1", "f6g8"]);
+        }
+        assert_eq!(1200, game.get_moves().len());
+        assert_eq!(1200, game.get_board_history().len());
+    }
+
+    #[test]
+    fn get_result_detects_mate_and_stalemate() {
+        let mut game = Game::new(Board::from_fen(START_FEN).unwrap());
+        play(&mut game, &["f2f3", "e7e5", "g2g4"]);
+        assert_eq!(None, game.get_result());
+        play(&mut game, &["d8h4"]);
+        assert_eq!(Some(GameResult::Checkmate(Color::Black)), game.get_result());
+
+        let game = Game::new(Board::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap());
+        assert_eq!(Some(GameResult::Stalemate), game.get_result());
+    }
+
+    #[test]
+    fn get_result_detects_draws() {
+        let mut game = Game::new(Board::from_fen(START_FEN).unwrap());
+        play(&mut game, &["g1f3", "g8f6", "f3g1", "f6g8"]);
+        assert_eq!(None, game.get_result());
+        play(&mut game, &["g1f3", "g8f6", "f3g1"]);
+        assert_eq!(None, game.get_result());
+        play(&mut game, &["f6g8"]);
+        assert_eq!(Some(GameResult::ThreefoldRepetition), game.get_result());
+
+        let game = Game::new(Board::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 100 80").unwrap());
+        assert_eq!(Some(GameResult::FiftyMoveRule), game.get_result());
+
+        let game = Game::new(Board::from_fen("4k3/8/8/8/8/8/8/4KN2 w - - 0 1").unwrap());
+        assert_eq!(Some(GameResult::InsufficientMaterial), game.get_result());
+    }
+
+    #[test]
+    fn game_result_formats_correctly() {
+        assert_eq!("0-1", GameResult::Checkmate(Color::Black).to_pgn());
+        assert_eq!("1-0", GameResult::Checkmate(Color::White).to_pgn());
+        assert_eq!("1/2-1/2", GameResult::Stalemate.to_pgn());
+        assert_eq!("White mates", GameResult::Checkmate(Color::White).to_string());
+        assert_eq!("Threefold repetition", GameResult::ThreefoldRepetition.to_string());
+        assert_eq!(None, GameResult::FiftyMoveRule.get_winner());
+    }
+}
@@ -1,55 +1,61 @@
 use std::sync::mpsc::{Receiver, Sender};
-use arrayvec::ArrayVec;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::bench;
 use crate::board::Board;
 use crate::board::color::Color;
+use crate::board::fen::START_FEN;
+use crate::book::Book;
+use crate::engine::SearchLimits;
+use crate::game::Game;
+use crate::evaluation;
+use crate::evaluation::parameters::DEFAULT_PARAMETERS;
 use crate::move_gen::ply::Ply;
-use crate::search::SearchCommand;
+use crate::search::{SearchCommand, SearchEvent};
 use crate::uci;
-use crate::uci::{UciCommand};
+use crate::uci::{UciCommand, UciError};
+use crate::xboard;
+use crate::ladybug::xboard_game::XboardState;
+
+mod xboard_game;
 
 /// The main character in this project!
 /// The Ladybug struct acts as the UCI client and can receive and handle UCI commands.
 pub struct Ladybug {
     /// The current game on which all searches and commands will be performed on.
     game: Game,
-    /// The current state of Ladybug.
-    state: State,
     /// Used to send commands to the search thread.
     search_command_sender: Sender<SearchCommand>,
     /// Used to send output to the console.
     console_output_sender: Sender<String>,
     /// Used to receive input from both the console and the search thread.
     input_receiver: Receiver<Message>,
-}
-
-/// Represents a game of chess, containing the current board as well as the board history.
-pub struct Game {
-    /// The current board.
-    board: Board,
-    /// Contains the hashes of all positions that have been on the board before.
-    board_history: ArrayVec<u64, 1000>,
+    /// The opening book set via the `BookFile` option.
+    book: Option<Book>,
+    /// Whether moves are played from the opening book, as set via the `OwnBook` option.
+    own_book: bool,
+    /// Whether Chess960 is enabled, in which case castling moves are printed as king captures rook and the opening book is not used.
+    chess960: bool,
+    /// The protocol spoken with the GUI, which is detected from the first command.
+    protocol: Option<Protocol>,
+    /// The state of the game when playing via the xboard protocol.
+    xboard: XboardState,
 }
 
 /// The two types of messages Ladybug can receive.
 pub enum Message {
+    /// A line of input from the console.
     ConsoleMessage(String),
-    SearchMessage(String),
-}
-
-/// The two possible states of Ladybug.
-enum State {
-    Idle,
-    Busy,
+    /// An event from the search thread, which is printed as UCI output.
+    SearchMessage(Box<SearchEvent>),
 }
 
-impl Default for Game {
-    /// Default constructor for Game.
-    fn default() -> Self {
-        Game {
-            board: Board::default(),
-            board_history: ArrayVec::new(),
-        }
-    }
+/// The protocols Ladybug can speak.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Protocol {
+    /// The [Universal Chess Interface](https://www.chessprogramming.org/UCI).
+    Uci,
+    /// The [Chess Engine Communication Protocol](https://www.chessprogramming.org/Chess_Engine_Communication_Protocol) of xboard and WinBoard.
+    Xboard,
 }
 
 impl Ladybug {
@@ -57,10 +63,14 @@ impl Ladybug {
     pub fn new(search_command_sender: Sender<SearchCommand>, console_output_sender: Sender<String>, input_receiver: Receiver<Message>) -> Self {
         Self {
             game: Game::default(),
-            state: State::Idle,
             search_command_sender,
             console_output_sender,
             input_receiver,
+            book: None,
+            own_book: false,
+            chess960: false,
+            protocol: None,
+            xboard: XboardState::default(),
         }
     }
     
@@ -79,46 +89,65 @@ impl Ladybug {
             let message = input.unwrap();
             
             match message {
-                // print search messages to the console
-                Message::SearchMessage(msg) => {
-                    self.send_console(msg);
-                }
-                // parse console messages as uci and delegate them to the respective handler methods
+                // print search events to the console
+                Message::SearchMessage(event) => self.handle_search_event(*event),
+                // detect the protocol from the first command and parse console messages accordingly
                 Message::ConsoleMessage(msg) => {
-                    // try to parse the uci command
-                    let uci_command = uci::parse_uci(msg);
-
-                    let uci_command = match uci_command {
-                        // if the uci command cannot be parsed, send the error message to the output thread
-                        Err(message) => {
-                            self.send_console(message);
-                            continue;
-                        }
-                        Ok(command) => command
+                    if self.protocol.is_none() && !msg.trim().is_empty() {
+                        self.protocol = Some(if xboard::is_xboard_handshake(msg.as_str()) { Protocol::Xboard } else { Protocol::Uci });
+                    }
+                    let keep_running = match self.protocol {
+                        Some(Protocol::Xboard) => self.handle_xboard_command(msg),
+                        _ => self.handle_uci_command(msg),
                     };
-
-                    // delegate the handling of the uci command to the respective method
-                    match uci_command {
-                        UciCommand::Uci => self.handle_uci(),
-                        UciCommand::IsReady => self.handle_is_ready(),
-                        UciCommand::UciNewGame => self.hande_uci_new_game(),
-                        UciCommand::Position(args) => self.handle_position(args),
-                        UciCommand::GoClockTime(args) => self.handle_go_clock_time(args),
-                        UciCommand::GoMoveTime(time) => self.handle_go_move_time(time),
-                        UciCommand::GoDepth(depth) => self.handle_depth(depth),
-                        UciCommand::GoPerft(depth) => self.handle_go_perft(depth),
-                        UciCommand::Quit => {
-                            self.handle_quit();
-                            break;
-                        }
-                        UciCommand::Help => self.handle_help(),
-                        UciCommand::Display => self.handle_display()
+                    if !keep_running {
+                        break;
                     }
                 }
             }
         }
     }
 
+    /// Parses the given input as a UCI command and delegates it to the respective handler method.
+    /// Returns false if Ladybug has to quit.
+    fn handle_uci_command(&mut self, input: String) -> bool {
+        // try to parse the uci command
+        let uci_command = match uci::parse_uci(input) {
+            // if the uci command cannot be parsed, send the error message to the output thread
+            Err(error) => {
+                self.send_error(error);
+                return true;
+            }
+            Ok(command) => command
+        };
+
+        // delegate the handling of the uci command to the respective method
+        match uci_command {
+            UciCommand::Uci => self.handle_uci(),
+            UciCommand::IsReady => self.handle_is_ready(),
+            UciCommand::UciNewGame => self.hande_uci_new_game(),
+            UciCommand::Position(args) => self.handle_position(args),
+            UciCommand::GoClockTime(args) => self.handle_go_clock_time(args),
+            UciCommand::GoMoveTime(time) => self.handle_go_move_time(time),
+            UciCommand::GoDepth(depth) => self.handle_depth(depth),
+            UciCommand::GoNodes(nodes) => self.handle_go_nodes(nodes),
+            UciCommand::GoInfinite => self.handle_go_infinite(),
+            UciCommand::GoPerft(depth) => self.handle_go_perft(depth),
+            UciCommand::Stop => self.handle_stop(),
+            UciCommand::Quit => {
+                self.handle_quit();
+                return false;
+            }
+            UciCommand::Help => self.handle_help(),
+            UciCommand::Display => self.handle_display(),
+            UciCommand::Eval => self.handle_eval(false),
+            UciCommand::EvalJson => self.handle_eval(true),
+            UciCommand::SetOption(name, value) => self.handle_set_option(name, value),
+            UciCommand::Bench(depth) => self.handle_bench(depth),
+        }
+        true
+    }
+
     /// Sends the given string to the output thread.
     fn send_console(&self, output: String) {
         let send_result = self.console_output_sender.send(output);
@@ -129,6 +158,43 @@ impl Ladybug {
         }
     }
 
+    /// Sends the given error to the output thread as an info string.
+    fn send_error(&self, error: UciError) {
+        self.send_console(format!("info string {error}"));
+    }
+
+    /// Formats the given search event as output of the current protocol and sends it to the output thread.
+    fn handle_search_event(&mut self, event: SearchEvent) {
+        if self.protocol == Some(Protocol::Xboard) {
+            self.handle_xboard_search_event(event);
+            return;
+        }
+
+        let output = match event {
+            SearchEvent::NoLegalMoves => String::from("info string no legal moves"),
+            SearchEvent::InsufficientMaterial => String::from("info string draw by insufficient material"),
+            SearchEvent::IterationCompleted { position, depth, seldepth, score, nodes, nps, time, pv } => {
+                let mut output = format!("info depth {depth} seldepth {seldepth} score cp {score} nodes {nodes} time {} nps {nps} pv", time.as_millis());
+                let mut pv_position = position;
+                for ply in pv {
+                    output += format!(" {}", ply.to_uci(pv_position, self.chess960)).as_str();
+                    pv_position = pv_position.make_move(ply);
+                }
+                output
+            }
+            SearchEvent::BestMove { position, best_move, ponder_move: _ } => format!("bestmove {}", best_move.to_uci(position, self.chess960)),
+            SearchEvent::PerftDivide { position, ply, nodes } => format!("{}: {nodes}", ply.to_uci(position, self.chess960)),
+            SearchEvent::PerftDone { nodes, time } => format!("Searched {nodes} nodes in {time:?}"),
+            SearchEvent::PerftStopped => String::from("info string perft stopped"),
+            SearchEvent::BenchPosition { index, nodes } => format!("Position {}/{}: {nodes} nodes", index + 1, bench::BENCH_POSITIONS.len()),
+            SearchEvent::BenchDone(result) => format!("{result}"),
+            SearchEvent::BenchStopped => String::from("info string bench stopped"),
+        };
+        for line in output.lines() {
+            self.send_console(String::from(line));
+        }
+    }
+
     /// Sends the given search command to the search thread.
     fn send_search(&self, search_command: SearchCommand) {
         let send_result = self.search_command_sender.send(search_command);
@@ -143,6 +209,9 @@ impl Ladybug {
     fn handle_uci(&self) {
         self.send_console("id name Ladybug 0.5.0".to_string());
         self.send_console("id author Felix O.".to_string());
+        self.send_console(String::from("option name OwnBook type check default false"));
+        self.send_console(String::from("option name BookFile type string default <empty>"));
+        self.send_console(String::from("option name UCI_Chess960 type check default false"));
         self.send_console(String::from("uciok"));
     }
 
@@ -162,7 +231,7 @@ impl Ladybug {
         self.game = Game::default();
         
         if args.is_empty() {
-            self.send_console(String::from("info string unknown command"));
+            self.send_error(UciError::MissingArgument(String::from("position")));
             return;
         }
 
@@ -171,7 +240,7 @@ impl Ladybug {
         // build the fen string from the provided args
         match args[0].as_str() {
             "startpos" => {
-                fen += "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+                fen += START_FEN;
             }
             "fen" => {
                 for (index, arg) in args.iter().enumerate() {
@@ -185,25 +254,26 @@ impl Ladybug {
                     fen += arg.as_str();
                 }
             }
-            _other => {
-                self.send_console(String::from("info string unknown command"));
+            other => {
+                self.send_error(UciError::UnknownArgument(String::from(other)));
                 return;
             }
         };
 
         // try to parse the fen
-        let board = Board::from_fen(fen.as_str());
-        if board.is_err() {
-            self.send_console(String::from("info string invalid fen"));
-            return;
-        }
-        let mut board = board.unwrap();
+        let board = match Board::from_fen(fen.as_str()) {
+            Ok(board) => board,
+            Err(error) => {
+                self.send_error(UciError::InvalidFen(error));
+                return;
+            }
+        };
 
         // split the args vector to only contain the moves
         let moves_index = args.iter().position(|r| r == "moves");
         if moves_index.is_none() {
             // command contains no moves - finish
-            self.game.board = board;
+            self.game = Game::new(board);
             return;
         }
         let moves_index = moves_index.unwrap() + 1;
@@ -213,29 +283,31 @@ impl Ladybug {
         let (_, moves) = args.split_at(moves_index);
 
         // loop over moves strings and try to make the moves on the board
+        let mut game = Game::new(board);
         for move_string in moves {
-            let ply = Ply::from_string(move_string, board.position);
+            let ply = Ply::from_string(move_string, game.get_board().position);
             match ply {
-                Some(ply) => {
-                    self.game.board_history.push(board.position.hash);
-                    board = board.make_move(ply)
-                }
-                None => {
-                    // reset the game
+                Ok(ply) => game.make_move(ply),
+                Err(error) => {
+                    // reset the game instead of keeping the moves before the invalid one
                     self.game = Game::default();
-                    self.send_console(String::from("info string invalid moves"));
+                    self.send_error(UciError::InvalidMove(error));
                     return;
                 }
             }
         }
 
-        self.game.board = board;
+        self.game = game;
     }
 
     /// Handles the "go wtime <time> btime <time>" command.
     fn handle_go_clock_time(&self, mut args: Vec<String>) {
-        if  args.len() != 4 && args.len() != 8 {
-            self.send_console(String::from("info string unknown command"));
+        if args.len() > 8 {
+            self.send_error(UciError::UnexpectedArgument(args[8].clone()));
+            return;
+        }
+        if args.len() != 4 && args.len() != 8 {
+            self.send_error(UciError::MissingArgument(String::from("go wtime")));
             return;
         }
         
@@ -246,46 +318,52 @@ impl Ladybug {
             args.push("0".to_string());
         }
         
-        if args[0] != "wtime" || args[2] != "btime" || args[4] != "winc" || args[6] != "binc" {
-            self.send_console(String::from("info string unknown command"));
-            return;
-        }
-        
-        let w_time = args[1].parse::<u64>();
-        let b_time = args[3].parse::<u64>();
-        let w_inc = args[5].parse::<u64>();
-        let b_inc = args[7].parse::<u64>();
-        
-        if w_time.is_err() || b_time.is_err() || w_inc.is_err() || b_inc.is_err() {
-            self.send_console(String::from("info string unknown command"));
-            return;
+        let mut values = [0; 4];
+        for (index, name) in ["wtime", "btime", "winc", "binc"].iter().enumerate() {
+            if args[2 * index] != *name {
+                self.send_error(UciError::UnknownArgument(args[2 * index].clone()));
+                return;
+            }
+            match args[2 * index + 1].parse::<u64>() {
+                Ok(value) => values[index] = value,
+                Err(_) => {
+                    self.send_error(UciError::InvalidValue(name.to_string(), args[2 * index + 1].clone()));
+                    return;
+                }
+            }
         }
+        let [w_time, b_time, w_inc, b_inc] = values;
         
-        let time =  match self.game.board.position.color_to_move{
-            Color::White => w_time.unwrap(),
-            Color::Black => b_time.unwrap(),
+        let time =  match self.game.get_board().position.color_to_move{
+            Color::White => w_time,
+            Color::Black => b_time,
         };
 
 
-        let increment =  match self.game.board.position.color_to_move{
-            Color::White => w_inc.unwrap(),
-            Color::Black => b_inc.unwrap(),
+        let increment =  match self.game.get_board().position.color_to_move{
+            Color::White => w_inc,
+            Color::Black => b_inc,
         };
         
         let time = (time / 40) + increment;
-        
-        self.send_search(SearchCommand::SearchTime(self.game.board, self.game.board_history.clone(), time));
+
+        if self.send_book_move() {
+            return;
+        }
+        self.send_search(SearchCommand::SearchTime(self.game.get_board(), self.game.get_board_history(), time));
     }
 
     /// Handles the "go movetime <time>" command.
     fn handle_go_move_time(&self, time: String)  {
-        let time = time.parse::<u64>();
-        match time {
+        match time.parse::<u64>() {
             Err(_) => {
-                self.send_console(String::from("info string unknown command"));
+                self.send_error(UciError::InvalidValue(String::from("movetime"), time));
             }
             Ok(time) => {
-                self.send_search(SearchCommand::SearchTime(self.game.board, self.game.board_history.clone(), time));
+                if self.send_book_move() {
+                    return;
+                }
+                self.send_search(SearchCommand::SearchTime(self.game.get_board(), self.game.get_board_history(), time));
             }
         }
     }
@@ -295,12 +373,62 @@ impl Ladybug {
         let depth = depth_str.parse::<u64>();
         match depth {
             Err(_) => {
-                self.send_console(String::from("info string unknown command"));
+                self.send_error(UciError::InvalidValue(String::from("depth"), depth_str));
             }
             Ok(depth) => {
-                self.send_search(SearchCommand::SearchDepth(self.game.board, self.game.board_history.clone(), depth));
+                if self.send_book_move() {
+                    return;
+                }
+                self.send_search(SearchCommand::SearchDepth(self.game.get_board(), self.game.get_board_history(), depth));
+            }
+        }
+    }
+
+    /// Handles the "go nodes <nodes>" command.
+    fn handle_go_nodes(&self, nodes_str: String) {
+        let nodes = nodes_str.parse::<u64>();
+        match nodes {
+            Err(_) => {
+                self.send_error(UciError::InvalidValue(String::from("nodes"), nodes_str));
             }
+            Ok(nodes) => {
+                if self.send_book_move() {
+                    return;
+                }
+                self.send_search(SearchCommand::SearchNodes(self.game.get_board(), self.game.get_board_history(), nodes));
+            }
+        }
+    }
+
+    /// Handles the "go infinite" command.
+    fn handle_go_infinite(&self) {
+        if self.send_book_move() {
+            return;
         }
+        self.send_search(SearchCommand::SearchLimits(self.game.get_board(), self.game.get_board_history(), SearchLimits::default()));
+    }
+
+    /// Plays a move from the opening book if `OwnBook` is enabled and the current position is in the book.
+    /// Returns true if a book move was sent, in which case no search must be started.
+    fn send_book_move(&self) -> bool {
+        match self.get_book_move() {
+            Some(ply) => {
+                self.send_console(format!("bestmove {}", ply.to_uci(self.game.get_board().position, false)));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns a random move from the opening book for the current position, if `OwnBook` is enabled and the position is in the book.
+    fn get_book_move(&self) -> Option<Ply> {
+        let (Some(book), true, false) = (&self.book, self.own_book, self.chess960) else {
+            return None;
+        };
+
+        // the current time is random enough to vary the chosen book moves between games
+        let random = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_nanos() as u64);
+        book.get_random_move(&self.game.get_board().position, random)
     }
 
     /// Handles the "go perft <depth>" command.
@@ -308,14 +436,19 @@ impl Ladybug {
         let depth = depth_str.parse::<u64>();
         match depth {
             Err(_) => {
-                self.send_console(String::from("info string unknown command"));
+                self.send_error(UciError::InvalidValue(String::from("depth"), depth_str));
             }
             Ok(depth) => {
-                self.send_search(SearchCommand::Perft(self.game.board.position, depth));
+                self.send_search(SearchCommand::Perft(self.game.get_board().position, depth));
             }
         }
     }
 
+    /// Handles the "stop" command.
+    fn handle_stop(&self) {
+        self.send_search(SearchCommand::Stop);
+    }
+
     /// Handles the "quit" command.
     fn handle_quit(&self) {
         self.send_console(String::from("quit"));
@@ -329,36 +462,107 @@ impl Ladybug {
         self.send_console(String::from("isready                                                 : Synchronize Ladybug with the GUI"));
         self.send_console(String::from("ucinewgame                                              : Reset the internal board state"));
         self.send_console(String::from("position fen <fen> moves <moves>                        : Setup the board position"));
+        self.send_console(String::from("setoption name <name> value <value>                     : Set an engine option"));
         self.send_console(String::from("go wtime <time> btime <time> winc <time> binc <time>    : Start searching"));
         self.send_console(String::from("go movetime <time>                                      : Search for the specified time"));
         self.send_console(String::from("go depth <depth>                                        : Search to the specified depth"));
+        self.send_console(String::from("go nodes <nodes>                                        : Search the specified number of nodes"));
+        self.send_console(String::from("go infinite                                             : Search until stopped"));
         self.send_console(String::from("go perft <depth>                                        : Perform a perft test"));
+        self.send_console(String::from("stop                                                    : Abort a running search, perft test or bench"));
         self.send_console(String::from("display                                                 : Print the fen of the current position"));
+        self.send_console(String::from("eval [--json]                                           : Print the static evaluation of the current position"));
+        self.send_console(String::from("bench [depth]                                           : Search the bench positions and print the node count"));
         self.send_console(String::from("quit                                                    : Quit Ladybug"));
     }
 
     /// Handles the "display" command.
     fn handle_display(&self) {
-        self.send_console(self.game.board.to_fen());
+        self.send_console(self.game.get_board().to_fen());
+    }
+
+    /// Handles the "setoption" command.
+    fn handle_set_option(&mut self, name: String, value: String) {
+        match name.to_lowercase().as_str() {
+            "ownbook" => match value.to_lowercase().as_str() {
+                "true" => self.own_book = true,
+                "false" => self.own_book = false,
+                _other => self.send_error(UciError::InvalidValue(name, value)),
+            },
+            "bookfile" => {
+                if value.is_empty() || value == "<empty>" {
+                    self.book = None;
+                    return;
+                }
+                match Book::open(value.as_str()) {
+                    Ok(book) => {
+                        self.send_console(format!("info string found {} book entries", book.len()));
+                        self.book = Some(book);
+                    }
+                    Err(message) => {
+                        self.send_console(format!("info string {}", message.to_lowercase()));
+                        self.book = None;
+                    }
+                }
+            }
+            "uci_chess960" => match value.to_lowercase().as_str() {
+                "true" => self.chess960 = true,
+                "false" => self.chess960 = false,
+                _other => self.send_error(UciError::InvalidValue(name, value)),
+            },
+            _other => self.send_error(UciError::UnknownOption(name)),
+        }
+    }
+
+    /// Handles the "eval" command.
+    /// Prints the static evaluation of the current position, broken down into its terms, either as a table or as JSON.
+    fn handle_eval(&self, json: bool) {
+        let breakdown = evaluation::get_breakdown(self.game.get_board().position, &DEFAULT_PARAMETERS);
+        if json {
+            self.send_console(breakdown.to_json());
+        } else {
+            for line in format!("{breakdown}").lines() {
+                self.send_console(String::from(line));
+            }
+        }
+    }
+
+    /// Handles the "bench" command.
+    /// Searches the bench positions to the given depth, or to the default depth if none is given, and prints the node count.
+    /// The bench runs on the search thread, so that it can be stopped like a search.
+    fn handle_bench(&self, depth: Option<String>) {
+        let depth = match depth {
+            None => bench::BENCH_DEPTH,
+            Some(depth) => match depth.parse::<u64>() {
+                Ok(depth) if depth > 0 => depth,
+                _ => {
+                    self.send_error(UciError::InvalidValue(String::from("depth"), depth));
+                    return;
+                }
+            },
+        };
+        self.send_search(SearchCommand::Bench(depth));
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::fs;
     use std::sync::mpsc;
     use std::sync::mpsc::{Receiver, Sender};
     use std::{thread};
     use std::time::Duration;
+    use crate::board::Board;
+    use crate::board::fen::START_FEN;
+    use crate::book;
+    use crate::book::BookEntry;
     use crate::ladybug::{Ladybug, Message};
+    use crate::move_gen::ply::Ply;
     use crate::ladybug::Message::ConsoleMessage;
-    use crate::lookup::LOOKUP_TABLE;
-    use crate::lookup::lookup_table::LookupTable;
     use crate::search::{Search, SearchCommand};
 
     /// Creates a new Ladybug thread and returns the input_sender and output_receiver.
     fn setup() -> (Sender<Message>, Receiver<String>) {
-        initialize_lookup_table();
-
         // create search_command_sender and search_command_receiver so that the ladybug thread can send commands to the search thread
         let (search_command_sender, search_command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
 
@@ -383,31 +587,43 @@ mod tests {
         (message_sender, output_receiver)
     }
 
-    /// helper function to initialize the lookup table
-    fn initialize_lookup_table() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-    }
-
     #[test]
     fn test_ladybug_with_invalid_uci_input_prints_error_message() {
         let (input_sender, output_receiver) = setup();
 
         let _ = input_sender.send(ConsoleMessage(String::from("Not Uci")));
-        assert_eq!("info string unknown command", output_receiver.recv().unwrap());
+        assert_eq!("info string unknown command: Not", output_receiver.recv().unwrap());
 
         let _ = input_sender.send(ConsoleMessage(String::from("       ")));
-        assert_eq!("info string unknown command", output_receiver.recv().unwrap());
+        assert_eq!("info string empty command", output_receiver.recv().unwrap());
 
         let _ = input_sender.send(ConsoleMessage(String::from("123456789")));
-        assert_eq!("info string unknown command", output_receiver.recv().unwrap());
+        assert_eq!("info string unknown command: 123456789", output_receiver.recv().unwrap());
 
         let _ = input_sender.send(ConsoleMessage(String::from("position test")));
-        assert_eq!("info string unknown command", output_receiver.recv().unwrap());
+        assert_eq!("info string unknown argument: test", output_receiver.recv().unwrap());
 
         let _ = input_sender.send(ConsoleMessage(String::from("position fen this is invalid fen")));
-        assert_eq!("info string invalid fen", output_receiver.recv().unwrap());
+        assert_eq!("info string invalid fen: expected 8 ranks in piece placement: this", output_receiver.recv().unwrap());
+
+        let _ = input_sender.send(ConsoleMessage(String::from("position fen 4k3/8/8/8/8/8/8/4K3 x - - 0 1")));
+        assert_eq!("info string invalid fen: invalid color to move: x", output_receiver.recv().unwrap());
+
+        let _ = input_sender.send(ConsoleMessage(String::from("position startpos moves e2e4 e7e5 e1e3")));
+        assert_eq!("info string illegal move: e1e3", output_receiver.recv().unwrap());
+
+        // the previous game is not kept after an invalid position command
+        let _ = input_sender.send(ConsoleMessage(String::from("display")));
+        assert_eq!("8/8/8/8/8/8/8/8 w - - 0 1", output_receiver.recv().unwrap());
+
+        let _ = input_sender.send(ConsoleMessage(String::from("position startpos moves e2e4 e7e9")));
+        assert_eq!("info string invalid square: e9", output_receiver.recv().unwrap());
+
+        let _ = input_sender.send(ConsoleMessage(String::from("go depth ten")));
+        assert_eq!("info string invalid value for depth: ten", output_receiver.recv().unwrap());
+
+        let _ = input_sender.send(ConsoleMessage(String::from("go wtime 1000 btime -5")));
+        assert_eq!("info string invalid value for btime: -5", output_receiver.recv().unwrap());
     }
 
     #[test]
@@ -417,6 +633,9 @@ mod tests {
         let _ = input_sender.send(ConsoleMessage(String::from("uci")));
         assert_eq!("id name Ladybug 0.5.0", output_receiver.recv().unwrap());
         assert_eq!("id author Felix O.", output_receiver.recv().unwrap());
+        assert_eq!("option name OwnBook type check default false", output_receiver.recv().unwrap());
+        assert_eq!("option name BookFile type string default <empty>", output_receiver.recv().unwrap());
+        assert_eq!("option name UCI_Chess960 type check default false", output_receiver.recv().unwrap());
         assert_eq!("uciok", output_receiver.recv().unwrap());
     }
 
@@ -455,7 +674,8 @@ mod tests {
 
         let _ = input_sender.send(ConsoleMessage(String::from("position startpos moves e2e4 c7c5 c2c3 b8c6 d2d4")));
         let _ = input_sender.send(ConsoleMessage(String::from("display")));
-        assert_eq!("r1bqkbnr/pp1ppppp/2n5/2p5/3PP3/2P5/PP3PPP/RNBQKBNR b KQkq d3 0 3", output_receiver.recv().unwrap());
+        // no black pawn can capture d4 en passant, so there is no en passant square
+        assert_eq!("r1bqkbnr/pp1ppppp/2n5/2p5/3PP3/2P5/PP3PPP/RNBQKBNR b KQkq - 0 3", output_receiver.recv().unwrap());
 
         let _ = input_sender.send(ConsoleMessage(String::from("position fen r1bqkbnr/pp1ppppp/2n5/2p5/3PP3/2P5/PP3PPP/RNBQKBNR b KQkq d3 0 3 moves c5d4 h2h4 d4c3 g1f3 c3b2 f1b5 b2c1q")));
         let _ = input_sender.send(ConsoleMessage(String::from("display")));
@@ -505,6 +725,22 @@ mod tests {
         assert!(output.iter().any(|r| r.contains("bestmove")));
     }
 
+    #[test]
+    fn test_ladybug_reports_insufficient_material() {
+        let (input_sender, output_receiver) = setup();
+
+        let _ = input_sender.send(ConsoleMessage(String::from("position fen 4k3/8/8/8/8/8/8/2B1K3 w - - 0 1")));
+        let _ = input_sender.send(ConsoleMessage(String::from("go depth 2")));
+        assert_eq!("info string draw by insufficient material", output_receiver.recv().unwrap());
+
+        // a move is still returned
+        let mut output = output_receiver.recv().unwrap();
+        while !output.starts_with("bestmove") {
+            assert!(output.contains("score cp 0"));
+            output = output_receiver.recv().unwrap();
+        }
+    }
+
     #[test]
     fn test_ladybug_for_go_move_time() {
         let (input_sender, output_receiver) = setup();
@@ -539,23 +775,118 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_ladybug_for_go_nodes() {
+        let (input_sender, output_receiver) = setup();
+
+        let _ = input_sender.send(ConsoleMessage(String::from("position startpos")));
+        let _ = input_sender.send(ConsoleMessage(String::from("go nodes 5000")));
+
+        // the nodes of all completed iterations must not exceed the limit
+        let mut total_nodes = 0;
+        loop {
+            let output = output_receiver.recv().unwrap();
+            if output.starts_with("info depth") {
+                let parts: Vec<&str> = output.split_whitespace().collect();
+                let nodes_index = parts.iter().position(|part| *part == "nodes").unwrap() + 1;
+                total_nodes += parts[nodes_index].parse::<u64>().unwrap();
+            }
+            if output.starts_with("bestmove") {
+                break;
+            }
+        }
+        assert!(total_nodes > 0);
+        assert!(total_nodes <= 5000);
+    }
+
     #[test]
     fn test_ladybug_for_go_perft() {
         let (input_sender, output_receiver) = setup();
 
         let _ = input_sender.send(ConsoleMessage(String::from("position startpos")));
         let _ = input_sender.send(ConsoleMessage(String::from("go perft 1")));
-        
-        thread::sleep(Duration::from_millis(50));
 
-        // collect all messages that have accumulated in the channel
+        // collect all messages until the perft is finished
         let mut output: Vec<String> = Vec::new();
-        while let Ok(output_str) = output_receiver.try_recv() {
+        while let Ok(output_str) = output_receiver.recv_timeout(Duration::from_secs(5)) {
+            let is_finished = output_str.contains("Searched");
             output.push(output_str);
+            if is_finished {
+                break;
+            }
         }
 
+        assert_eq!(21, output.len());
         assert!(output.iter().any(|r| r.contains("Searched")));
     }
+
+    #[test]
+    fn test_ladybug_for_stop() {
+        let (input_sender, output_receiver) = setup();
+
+        // a perft this deep would run for days, so it can only finish by being stopped
+        let _ = input_sender.send(ConsoleMessage(String::from("position startpos")));
+        let _ = input_sender.send(ConsoleMessage(String::from("go perft 12")));
+        thread::sleep(Duration::from_millis(50));
+        let _ = input_sender.send(ConsoleMessage(String::from("stop")));
+        assert_eq!("info string perft stopped", output_receiver.recv_timeout(Duration::from_secs(10)).unwrap());
+
+        // the search accepts new commands afterwards
+        let _ = input_sender.send(ConsoleMessage(String::from("go depth 1")));
+        assert!(output_receiver.recv_timeout(Duration::from_secs(10)).unwrap().starts_with("info depth 1"));
+    }
+
+    #[test]
+    fn test_ladybug_for_stop_during_go_infinite() {
+        let (input_sender, output_receiver) = setup();
+
+        // an infinite search only finishes by being stopped, and still sends the best move found so far
+        let _ = input_sender.send(ConsoleMessage(String::from("position startpos")));
+        let _ = input_sender.send(ConsoleMessage(String::from("go infinite")));
+        assert!(output_receiver.recv_timeout(Duration::from_secs(10)).unwrap().starts_with("info depth 1"));
+        let _ = input_sender.send(ConsoleMessage(String::from("stop")));
+        loop {
+            let output = output_receiver.recv_timeout(Duration::from_secs(10)).unwrap();
+            if output.starts_with("bestmove") {
+                break;
+            }
+            assert!(output.starts_with("info depth"), "{output}");
+        }
+
+        // the search accepts new commands afterwards
+        let _ = input_sender.send(ConsoleMessage(String::from("go depth 1")));
+        assert!(output_receiver.recv_timeout(Duration::from_secs(10)).unwrap().starts_with("info depth 1"));
+    }
+
+    #[test]
+    fn test_ladybug_for_chess960_castling_notation() {
+        let (input_sender, output_receiver) = setup();
+
+        // the castling moves can be given in both notations
+        let _ = input_sender.send(ConsoleMessage(String::from("position fen 1k6/8/8/8/8/8/8/RK5R w HA - 0 1 moves b1h1")));
+        let _ = input_sender.send(ConsoleMessage(String::from("display")));
+        assert_eq!("1k6/8/8/8/8/8/8/R4RK1 b - - 1 1", output_receiver.recv().unwrap());
+        let _ = input_sender.send(ConsoleMessage(String::from("position fen 1k6/8/8/8/8/8/8/RK5R w HA - 0 1 moves b1g1")));
+        let _ = input_sender.send(ConsoleMessage(String::from("display")));
+        assert_eq!("1k6/8/8/8/8/8/8/R4RK1 b - - 1 1", output_receiver.recv().unwrap());
+
+        // castling is printed as the king moving to its target square by default, and as the king capturing the rook in Chess960 mode
+        let _ = input_sender.send(ConsoleMessage(String::from("position fen 1k6/8/8/8/8/8/8/RK5R w HA - 0 1")));
+        for (option, expected, unexpected) in [("false", "b1g1: 1", "b1h1: 1"), ("true", "b1h1: 1", "b1g1: 1")] {
+            let _ = input_sender.send(ConsoleMessage(format!("setoption name UCI_Chess960 value {option}")));
+            let _ = input_sender.send(ConsoleMessage(String::from("go perft 1")));
+            let mut output: Vec<String> = Vec::new();
+            loop {
+                let output_str = output_receiver.recv().unwrap();
+                if output_str.starts_with("Searched") {
+                    break;
+                }
+                output.push(output_str);
+            }
+            assert!(output.iter().any(|r| r == expected));
+            assert!(!output.iter().any(|r| r == unexpected));
+        }
+    }
     
     #[test]
     fn test_ladybug_for_quit() {
@@ -576,14 +907,34 @@ mod tests {
         assert_eq!("isready                                                 : Synchronize Ladybug with the GUI", output_receiver.recv().unwrap());
         assert_eq!("ucinewgame                                              : Reset the internal board state", output_receiver.recv().unwrap());
         assert_eq!("position fen <fen> moves <moves>                        : Setup the board position", output_receiver.recv().unwrap());
+        assert_eq!("setoption name <name> value <value>                     : Set an engine option", output_receiver.recv().unwrap());
         assert_eq!("go wtime <time> btime <time> winc <time> binc <time>    : Start searching", output_receiver.recv().unwrap());
         assert_eq!("go movetime <time>                                      : Search for the specified time", output_receiver.recv().unwrap());
         assert_eq!("go depth <depth>                                        : Search to the specified depth", output_receiver.recv().unwrap());
+        assert_eq!("go nodes <nodes>                                        : Search the specified number of nodes", output_receiver.recv().unwrap());
+        assert_eq!("go infinite                                             : Search until stopped", output_receiver.recv().unwrap());
         assert_eq!("go perft <depth>                                        : Perform a perft test", output_receiver.recv().unwrap());
+        assert_eq!("stop                                                    : Abort a running search, perft test or bench", output_receiver.recv().unwrap());
         assert_eq!("display                                                 : Print the fen of the current position", output_receiver.recv().unwrap());
+        assert_eq!("eval [--json]                                           : Print the static evaluation of the current position", output_receiver.recv().unwrap());
+        assert_eq!("bench [depth]                                           : Search the bench positions and print the node count", output_receiver.recv().unwrap());
         assert_eq!("quit                                                    : Quit Ladybug", output_receiver.recv().unwrap());
     }
 
+    #[test]
+    fn test_ladybug_for_position_with_more_than_1000_moves() {
+        let (input_sender, output_receiver) = setup();
+
+        let moves = vec!["g1f3 g8f6 f3g1 f6g8"; 260].join(" ");
+        let _ = input_sender.send(ConsoleMessage(format!("position startpos moves {moves} e2e4")));
+        let _ = input_sender.send(ConsoleMessage(String::from("display")));
+        assert_eq!("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 521", output_receiver.recv().unwrap());
+
+        let _ = input_sender.send(ConsoleMessage(String::from("go depth 1")));
+        let output = output_receiver.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert!(output.starts_with("info depth 1"));
+    }
+
     #[test]
     fn test_ladybug_for_display() {
         let (input_sender, output_receiver) = setup();
@@ -596,5 +947,188 @@ mod tests {
         let _ = input_sender.send(ConsoleMessage(String::from("display")));
         assert_eq!("r1bqk1nr/ppp1bBpp/3p4/n7/3PP3/1Q3N2/P4PPP/RNB1K2R b KQkq - 0 9", output_receiver.recv().unwrap());
     }
-}
 
+    #[test]
+    fn test_ladybug_for_setoption() {
+        let (input_sender, output_receiver) = setup();
+
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name Unknown value 1")));
+        assert_eq!("info string unknown option: Unknown", output_receiver.recv().unwrap());
+
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name UCI_Chess960 value maybe")));
+        assert_eq!("info string invalid value for UCI_Chess960: maybe", output_receiver.recv().unwrap());
+
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name OwnBook value maybe")));
+        assert_eq!("info string invalid value for OwnBook: maybe", output_receiver.recv().unwrap());
+
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name BookFile value /this/path/does/not/exist.bin")));
+        assert_eq!("info string could not read book file /this/path/does/not/exist.bin", output_receiver.recv().unwrap());
+    }
+
+    #[test]
+    fn test_ladybug_for_own_book() {
+        let (input_sender, output_receiver) = setup();
+
+        // a book containing only 1. e4 for the start position
+        let board = Board::from_fen(START_FEN).unwrap();
+        let ply = Ply::from_string("e2e4", board.position).unwrap();
+        let entry = BookEntry { key: board.position.hash, raw_move: book::encode_move(ply), weight: 1, learn: 0 };
+        let path = std::env::temp_dir().join("ladybug_test_own_book.bin");
+        fs::write(&path, entry.to_bytes()).unwrap();
+
+        let _ = input_sender.send(ConsoleMessage(format!("setoption name BookFile value {}", path.display())));
+        assert_eq!("info string found 1 book entries", output_receiver.recv().unwrap());
+        fs::remove_file(&path).unwrap();
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name OwnBook value true")));
+
+        // in the book, the move is played without searching
+        let _ = input_sender.send(ConsoleMessage(String::from("position startpos")));
+        let _ = input_sender.send(ConsoleMessage(String::from("go depth 1")));
+        assert_eq!("bestmove e2e4", output_receiver.recv().unwrap());
+
+        // out of the book, Ladybug searches as usual
+        let _ = input_sender.send(ConsoleMessage(String::from("position startpos moves e2e4")));
+        let _ = input_sender.send(ConsoleMessage(String::from("go depth 1")));
+        assert!(output_receiver.recv().unwrap().starts_with("info depth 1"));
+    }
+
+    #[test]
+    fn test_ladybug_for_xboard_handshake() {
+        let (input_sender, output_receiver) = setup();
+
+        // the protocol is detected from the first command
+        let _ = input_sender.send(ConsoleMessage(String::from("xboard")));
+        let _ = input_sender.send(ConsoleMessage(String::from("protover 2")));
+        let features = output_receiver.recv().unwrap();
+        assert!(features.starts_with("feature myname=\"Ladybug 0.5.0\""));
+        assert!(features.contains("usermove=1"));
+        assert!(features.ends_with("done=1"));
+
+        let _ = input_sender.send(ConsoleMessage(String::from("accepted usermove")));
+        let _ = input_sender.send(ConsoleMessage(String::from("ping 3")));
+        assert_eq!("pong 3", output_receiver.recv().unwrap());
+
+        // UCI commands are unknown in xboard mode
+        let _ = input_sender.send(ConsoleMessage(String::from("isready")));
+        assert_eq!("Error (unknown command): isready", output_receiver.recv().unwrap());
+        let _ = input_sender.send(ConsoleMessage(String::from("sd ten")));
+        assert_eq!("Error (invalid value for sd: ten): sd ten", output_receiver.recv().unwrap());
+    }
+
+    #[test]
+    fn test_ladybug_for_uci_is_not_switched_to_xboard() {
+        let (input_sender, output_receiver) = setup();
+
+        let _ = input_sender.send(ConsoleMessage(String::from("isready")));
+        assert_eq!("readyok", output_receiver.recv().unwrap());
+        let _ = input_sender.send(ConsoleMessage(String::from("xboard")));
+        assert_eq!("info string unknown command: xboard", output_receiver.recv().unwrap());
+    }
+
+    #[test]
+    fn test_ladybug_for_xboard_game() {
+        let (input_sender, output_receiver) = setup();
+
+        let _ = input_sender.send(ConsoleMessage(String::from("xboard")));
+        let _ = input_sender.send(ConsoleMessage(String::from("new")));
+        let _ = input_sender.send(ConsoleMessage(String::from("sd 2")));
+        let _ = input_sender.send(ConsoleMessage(String::from("usermove e2e5")));
+        assert_eq!("Illegal move: e2e5", output_receiver.recv().unwrap());
+
+        // Ladybug plays Black and answers the move of the user
+        let _ = input_sender.send(ConsoleMessage(String::from("usermove e2e4")));
+        let output = output_receiver.recv_timeout(Duration::from_secs(10)).unwrap();
+        let board = Board::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap();
+        assert!(Ply::from_string(output.strip_prefix("move ").unwrap(), board.position).is_ok());
+
+        // in force mode, moves are only played on the board
+        let _ = input_sender.send(ConsoleMessage(String::from("force")));
+        let _ = input_sender.send(ConsoleMessage(String::from("usermove g1f3")));
+        let _ = input_sender.send(ConsoleMessage(String::from("ping 1")));
+        assert_eq!("pong 1", output_receiver.recv().unwrap());
+    }
+
+    #[test]
+    fn test_ladybug_for_xboard_move_now() {
+        let (input_sender, output_receiver) = setup();
+
+        // with 1000 seconds per move, the answer can only come from "?"
+        let _ = input_sender.send(ConsoleMessage(String::from("xboard")));
+        let _ = input_sender.send(ConsoleMessage(String::from("new")));
+        let _ = input_sender.send(ConsoleMessage(String::from("st 1000")));
+        let _ = input_sender.send(ConsoleMessage(String::from("usermove e2e4")));
+        thread::sleep(Duration::from_millis(200));
+        let _ = input_sender.send(ConsoleMessage(String::from("?")));
+        let output = output_receiver.recv_timeout(Duration::from_secs(10)).unwrap();
+        let board = Board::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap();
+        assert!(Ply::from_string(output.strip_prefix("move ").unwrap(), board.position).is_ok());
+    }
+
+    #[test]
+    fn test_ladybug_for_xboard_thinking_output_and_result() {
+        let (input_sender, output_receiver) = setup();
+
+        let _ = input_sender.send(ConsoleMessage(String::from("xboard")));
+        let _ = input_sender.send(ConsoleMessage(String::from("setboard 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1")));
+        let _ = input_sender.send(ConsoleMessage(String::from("sd 3")));
+        let _ = input_sender.send(ConsoleMessage(String::from("post")));
+        let _ = input_sender.send(ConsoleMessage(String::from("go")));
+
+        // thinking output contains the depth, score, time, nodes and pv
+        for depth in 1..=3 {
+            let output = output_receiver.recv_timeout(Duration::from_secs(10)).unwrap();
+            let parts: Vec<&str> = output.split_whitespace().collect();
+            assert_eq!(depth.to_string(), parts[0]);
+            assert!(parts[1..4].iter().all(|part| part.parse::<i64>().is_ok()));
+            assert_eq!("a1a8", parts[4]);
+        }
+        assert_eq!("move a1a8", output_receiver.recv_timeout(Duration::from_secs(10)).unwrap());
+        assert_eq!("1-0 {White mates}", output_receiver.recv().unwrap());
+    }
+
+    #[test]
+    fn test_ladybug_for_bench() {
+        let (input_sender, output_receiver) = setup();
+
+        let _ = input_sender.send(ConsoleMessage(String::from("bench 1")));
+        assert_eq!("Position 1/50: 20 nodes", output_receiver.recv().unwrap());
+        for _ in 1..50 {
+            assert!(output_receiver.recv().unwrap().starts_with("Position "));
+        }
+        assert!(output_receiver.recv().unwrap().starts_with("Total time (ms) : "));
+        assert!(output_receiver.recv().unwrap().starts_with("Nodes searched  : "));
+        assert!(output_receiver.recv().unwrap().starts_with("Nodes/second    : "));
+
+        let _ = input_sender.send(ConsoleMessage(String::from("bench 0")));
+        assert_eq!("info string invalid value for depth: 0", output_receiver.recv().unwrap());
+
+        // a deep bench does not block other commands and can be stopped
+        let _ = input_sender.send(ConsoleMessage(String::from("bench 30")));
+        let _ = input_sender.send(ConsoleMessage(String::from("isready")));
+        assert_eq!("readyok", output_receiver.recv_timeout(Duration::from_secs(10)).unwrap());
+        let _ = input_sender.send(ConsoleMessage(String::from("stop")));
+        let mut output = output_receiver.recv_timeout(Duration::from_secs(10)).unwrap();
+        while output.starts_with("Position ") {
+            output = output_receiver.recv_timeout(Duration::from_secs(10)).unwrap();
+        }
+        assert_eq!("info string bench stopped", output);
+    }
+
+    #[test]
+    fn test_ladybug_for_eval() {
+        let (input_sender, output_receiver) = setup();
+
+        let _ = input_sender.send(ConsoleMessage(String::from("position fen 4k3/8/8/8/8/8/4P3/4K3 w - - 0 1")));
+        let _ = input_sender.send(ConsoleMessage(String::from("eval")));
+        assert_eq!("Term                 White     Black     Total", output_receiver.recv().unwrap());
+        assert_eq!("Material               100         0       100", output_receiver.recv().unwrap());
+        for _ in 0..5 {
+            let _ = output_receiver.recv().unwrap();
+        }
+        assert_eq!("Phase: 0 / 24", output_receiver.recv().unwrap());
+        assert_eq!("Scale factor: 64 / 64", output_receiver.recv().unwrap());
+
+        let _ = input_sender.send(ConsoleMessage(String::from("eval --json")));
+        assert!(output_receiver.recv().unwrap().starts_with("{\"phase\":0,\"material\":{\"white\":100,\"black\":0,\"total\":100},"));
+    }
+}
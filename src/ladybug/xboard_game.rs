@@ -0,0 +1,355 @@
+use std::time::Duration;
+use crate::board::Board;
+use crate::board::color::Color;
+use crate::board::fen::START_FEN;
+use crate::engine::SearchLimits;
+use crate::game::Game;
+use crate::ladybug::Ladybug;
+use crate::move_gen::ply::Ply;
+use crate::search::{SearchCommand, SearchEvent};
+use crate::xboard;
+use crate::xboard::{XboardCommand, XboardError};
+
+/// The features Ladybug supports, sent in response to the `protover` command.
+const FEATURES: &str = "feature myname=\"Ladybug 0.5.0\" ping=1 setboard=1 usermove=1 time=1 draw=0 sigint=0 sigterm=0 reuse=1 analyze=0 colors=0 variants=\"normal\" done=1";
+
+/// The number of moves the remaining time is divided by if the time control has no fixed number of moves per session.
+const DEFAULT_MOVES_TO_GO: u32 = 40;
+
+/// The state of a game played via the xboard protocol.
+pub struct XboardState {
+    /// Whether Ladybug is in force mode, in which she only updates the board and never starts thinking.
+    force: bool,
+    /// The color Ladybug plays.
+    engine_color: Color,
+    /// Whether thinking output is sent during the search.
+    post: bool,
+    /// The number of moves per session of the time control, or zero if all moves have to be played in the base time.
+    moves_per_session: u32,
+    /// The number of plies the game had when the time control started, from which the moves until the next time control are counted.
+    session_start_ply: usize,
+    /// The base time of the time control.
+    base_time: Duration,
+    /// The time added to the clock after each move.
+    increment: Duration,
+    /// The fixed time per move, if set with `st`.
+    move_time: Option<Duration>,
+    /// The maximum depth to search, if set with `sd`.
+    depth_limit: Option<u64>,
+    /// The remaining time on Ladybug's clock, if sent by the GUI.
+    engine_time: Option<Duration>,
+}
+
+impl Default for XboardState {
+    /// Default constructor for `XboardState`. Until the GUI sends a time control, 40 moves in 5 minutes are assumed.
+    fn default() -> Self {
+        Self {
+            force: false,
+            engine_color: Color::Black,
+            post: false,
+            moves_per_session: 40,
+            session_start_ply: 0,
+            base_time: Duration::from_secs(5 * 60),
+            increment: Duration::ZERO,
+            move_time: None,
+            depth_limit: None,
+            engine_time: None,
+        }
+    }
+}
+
+impl Ladybug {
+    /// Parses the given input as an xboard command and delegates it to the respective handler method.
+    /// Returns false if Ladybug has to quit.
+    pub(super) fn handle_xboard_command(&mut self, input: String) -> bool {
+        let xboard_command = match xboard::parse_xboard(input.clone()) {
+            // empty lines are ignored
+            Err(XboardError::EmptyCommand) => return true,
+            Err(error) => {
+                self.send_console(format!("Error ({error}): {}", input.trim()));
+                return true;
+            }
+            Ok(command) => command,
+        };
+
+        match xboard_command {
+            XboardCommand::Xboard | XboardCommand::Accepted(_) | XboardCommand::Rejected(_) | XboardCommand::Ignore(_) => {}
+            XboardCommand::Protover(_) => self.send_console(String::from(FEATURES)),
+            XboardCommand::New => self.handle_xboard_new(),
+            XboardCommand::UserMove(move_string) => self.handle_xboard_user_move(move_string),
+            XboardCommand::Go => {
+                self.xboard.force = false;
+                self.xboard.engine_color = self.game.get_board().position.color_to_move;
+                self.start_xboard_search();
+            }
+            XboardCommand::Force => {
+                self.xboard.force = true;
+                self.stop_xboard_search();
+            }
+            // the stopped search still sends its best move, which is played like any other
+            XboardCommand::MoveNow => self.stop_xboard_search(),
+            XboardCommand::Level(moves_per_session, base_time, increment) => {
+                self.xboard.moves_per_session = moves_per_session;
+                self.xboard.session_start_ply = self.game.get_moves().len();
+                self.xboard.base_time = base_time;
+                self.xboard.increment = increment;
+                self.xboard.move_time = None;
+            }
+            XboardCommand::MoveTime(time) => self.xboard.move_time = Some(time),
+            XboardCommand::Depth(depth) => self.xboard.depth_limit = Some(depth),
+            XboardCommand::Time(time) => self.xboard.engine_time = Some(time),
+            // the opponent's clock is not used for time management
+            XboardCommand::OpponentTime(_) => {}
+            XboardCommand::Undo => {
+                self.stop_xboard_search();
+                self.game.undo();
+            }
+            XboardCommand::Remove => {
+                self.stop_xboard_search();
+                self.game.take_back(2);
+            }
+            XboardCommand::SetBoard(fen) => self.handle_xboard_set_board(fen),
+            // the game is over, so Ladybug must not move anymore
+            XboardCommand::Result(_) => {
+                self.xboard.force = true;
+                self.stop_xboard_search();
+            }
+            XboardCommand::Post => self.xboard.post = true,
+            XboardCommand::NoPost => self.xboard.post = false,
+            XboardCommand::Ping(number) => self.send_console(format!("pong {number}")),
+            XboardCommand::Quit => {
+                self.handle_quit();
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Formats the given search event as xboard output. When the search is finished, the best move is played.
+    pub(super) fn handle_xboard_search_event(&mut self, event: SearchEvent) {
+        match event {
+            SearchEvent::IterationCompleted { position, depth, score, nodes, time, pv, .. } if self.xboard.post => {
+                // thinking output consists of the depth, the score, the time in centiseconds, the nodes and the pv
+                let mut output = format!("{depth} {score} {} {nodes}", time.as_millis() / 10);
+                let mut pv_position = position;
+                for ply in pv {
+                    output += format!(" {}", ply.to_uci(pv_position, false)).as_str();
+                    pv_position = pv_position.make_move(ply);
+                }
+                self.send_console(output);
+            }
+            // the game may have changed during the search, e.g. by "force" or "undo", in which case the move is outdated
+            SearchEvent::BestMove { position, best_move, .. } if position == self.game.get_board().position && !self.xboard.force && position.color_to_move == self.xboard.engine_color => {
+                self.play_xboard_engine_move(best_move);
+            }
+            SearchEvent::PerftDivide { position, ply, nodes } => self.send_console(format!("{}: {nodes}", ply.to_uci(position, false))),
+            SearchEvent::PerftDone { nodes, time } => self.send_console(format!("Searched {nodes} nodes in {time:?}")),
+            _other => {}
+        }
+    }
+
+    /// Handles the "new" command.
+    fn handle_xboard_new(&mut self) {
+        self.stop_xboard_search();
+        self.game = Game::new(Board::from_fen(START_FEN).unwrap());
+        self.xboard.session_start_ply = 0;
+        self.xboard.force = false;
+        self.xboard.engine_color = Color::Black;
+        self.xboard.depth_limit = None;
+        self.xboard.engine_time = None;
+    }
+
+    /// Handles the "usermove" command.
+    fn handle_xboard_user_move(&mut self, move_string: String) {
+        let ply = match Ply::from_string(move_string.as_str(), self.game.get_board().position) {
+            Ok(ply) => ply,
+            Err(_) => {
+                self.send_console(format!("Illegal move: {move_string}"));
+                return;
+            }
+        };
+        self.game.make_move(ply);
+
+        if !self.xboard.force && self.game.get_board().position.color_to_move == self.xboard.engine_color {
+            self.start_xboard_search();
+        }
+    }
+
+    /// Handles the "setboard" command.
+    fn handle_xboard_set_board(&mut self, fen: String) {
+        match Board::from_fen(fen.as_str()) {
+            Ok(board) => {
+                self.stop_xboard_search();
+                self.game = Game::new(board);
+                self.xboard.session_start_ply = 0;
+            }
+            Err(error) => self.send_console(format!("tellusererror Illegal position: {error}")),
+        }
+    }
+
+    /// Starts searching the current position with the current time control, unless the game is over.
+    /// If the position is in the opening book, the book move is played right away.
+    fn start_xboard_search(&mut self) {
+        if let Some(result) = self.game.get_result() {
+            self.send_console(format!("{} {{{result}}}", result.to_pgn()));
+            return;
+        }
+
+        if let Some(ply) = self.get_book_move() {
+            self.play_xboard_engine_move(ply);
+            return;
+        }
+
+        let limits = SearchLimits {
+            depth: self.xboard.depth_limit,
+            move_time: Some(self.get_xboard_move_time()),
+            nodes: None,
+        };
+        self.send_search(SearchCommand::SearchLimits(self.game.get_board(), self.game.get_board_history(), limits));
+    }
+
+    /// Stops a running search. If no search is running, the search thread ignores the command.
+    ///
+    /// The best move of a search that was stopped because the game changed is not played, see `handle_xboard_search_event`.
+    fn stop_xboard_search(&self) {
+        self.send_search(SearchCommand::Stop);
+    }
+
+    /// Returns the time Ladybug may spend on the current move.
+    ///
+    /// With a fixed time per move, that time is used. Otherwise, the remaining time is divided by the number of moves
+    /// until the next time control (or 40 moves if there is none), and the increment is added.
+    /// The moves are counted from the start of the time control rather than taken from the move number of the position,
+    /// which may be arbitrary after `setboard`.
+    fn get_xboard_move_time(&self) -> Duration {
+        if let Some(move_time) = self.xboard.move_time {
+            return move_time;
+        }
+
+        let remaining_time = self.xboard.engine_time.unwrap_or(self.xboard.base_time);
+        let moves_to_go = match self.xboard.moves_per_session {
+            0 => DEFAULT_MOVES_TO_GO,
+            moves_per_session => {
+                let moves_played = (self.game.get_moves().len().saturating_sub(self.xboard.session_start_ply) / 2) as u32;
+                moves_per_session - moves_played % moves_per_session
+            }
+        };
+        remaining_time / moves_to_go + self.xboard.increment
+    }
+
+    /// Sends the given move of Ladybug to the GUI and plays it on the board.
+    /// If the game is over after the move, the result is sent as well.
+    fn play_xboard_engine_move(&mut self, ply: Ply) {
+        self.send_console(format!("move {}", ply.to_uci(self.game.get_board().position, false)));
+        self.game.make_move(ply);
+        if let Some(result) = self.game.get_result() {
+            self.send_console(format!("{} {{{result}}}", result.to_pgn()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::sync::mpsc::{Receiver, Sender};
+    use std::time::Duration;
+    use crate::board::fen::START_FEN;
+    use crate::ladybug::{Ladybug, Message};
+    use crate::search::SearchCommand;
+
+    /// Creates a Ladybug instance in xboard mode without a search thread, together with the receivers of her search commands and output.
+    fn setup() -> (Ladybug, Receiver<SearchCommand>, Receiver<String>) {
+        let (search_command_sender, search_command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
+        let (_message_sender, message_receiver): (Sender<Message>, Receiver<Message>) = mpsc::channel();
+        let (output_sender, output_receiver): (Sender<String>, Receiver<String>) = mpsc::channel();
+        let mut ladybug = Ladybug::new(search_command_sender, output_sender, message_receiver);
+        ladybug.handle_xboard_command(String::from("xboard"));
+        (ladybug, search_command_receiver, output_receiver)
+    }
+
+    #[test]
+    fn undo_and_remove_take_back_moves() {
+        let (mut ladybug, _search_command_receiver, _output_receiver) = setup();
+        ladybug.handle_xboard_command(String::from("new"));
+        ladybug.handle_xboard_command(String::from("force"));
+        for move_string in ["e2e4", "e7e5", "g1f3"] {
+            ladybug.handle_xboard_command(format!("usermove {move_string}"));
+        }
+        assert_eq!(3, ladybug.game.get_board_history().len());
+
+        ladybug.handle_xboard_command(String::from("undo"));
+        assert_eq!("rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2", ladybug.game.get_board().to_fen());
+        ladybug.handle_xboard_command(String::from("remove"));
+        assert_eq!(START_FEN, ladybug.game.get_board().to_fen());
+        assert!(ladybug.game.get_board_history().is_empty());
+
+        // taking back more moves than were played keeps the start position
+        ladybug.handle_xboard_command(String::from("undo"));
+        assert_eq!(START_FEN, ladybug.game.get_board().to_fen());
+    }
+
+    #[test]
+    fn setboard_sets_position() {
+        let (mut ladybug, _search_command_receiver, output_receiver) = setup();
+        ladybug.handle_xboard_command(String::from("setboard 4k3/8/8/8/8/8/4P3/4K3 b - - 0 1"));
+        assert_eq!("4k3/8/8/8/8/8/4P3/4K3 b - - 0 1", ladybug.game.get_board().to_fen());
+
+        ladybug.handle_xboard_command(String::from("setboard 4k3/8/8/8/8/8/4P3/4K3 x - - 0 1"));
+        assert_eq!("tellusererror Illegal position: invalid color to move: x", output_receiver.recv().unwrap());
+        assert_eq!("4k3/8/8/8/8/8/4P3/4K3 b - - 0 1", ladybug.game.get_board().to_fen());
+    }
+
+    #[test]
+    fn move_time_follows_time_control() {
+        let (mut ladybug, search_command_receiver, _output_receiver) = setup();
+        ladybug.handle_xboard_command(String::from("new"));
+
+        // 40 moves in 5 minutes by default
+        assert_eq!(Duration::from_millis(7500), ladybug.get_xboard_move_time());
+
+        // the remaining time is divided by the moves left until the time control, counted from the start of the time control
+        ladybug.handle_xboard_command(String::from("level 30 10 0"));
+        ladybug.handle_xboard_command(String::from("time 6000"));
+        ladybug.handle_xboard_command(String::from("setboard rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 11"));
+        assert_eq!(Duration::from_secs(2), ladybug.get_xboard_move_time());
+        ladybug.handle_xboard_command(String::from("force"));
+        for move_string in ["e2e4", "e7e5", "g1f3", "b8c6", "f1b5", "a7a6", "b5a4", "g8f6", "e1g1", "f8e7"] {
+            ladybug.handle_xboard_command(format!("usermove {move_string}"));
+        }
+        assert_eq!(Duration::from_millis(2400), ladybug.get_xboard_move_time());
+        ladybug.handle_xboard_command(String::from("new"));
+        ladybug.handle_xboard_command(String::from("time 6000"));
+        ladybug.handle_xboard_command(String::from("setboard rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 11"));
+
+        // incremental time controls divide the remaining time by 40 and add the increment
+        ladybug.handle_xboard_command(String::from("level 0 2:30 1.5"));
+        assert_eq!(Duration::from_millis(3000), ladybug.get_xboard_move_time());
+
+        // a fixed time per move overrides the clock
+        ladybug.handle_xboard_command(String::from("st 2"));
+        assert_eq!(Duration::from_secs(2), ladybug.get_xboard_move_time());
+
+        // the search is started with the move time and depth limit
+        ladybug.handle_xboard_command(String::from("sd 4"));
+        ladybug.handle_xboard_command(String::from("usermove e2e4"));
+        match search_command_receiver.try_iter().find(|command| !matches!(command, SearchCommand::Stop)).unwrap() {
+            SearchCommand::SearchLimits(board, board_history, limits) => {
+                assert_eq!("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 11", board.to_fen());
+                assert_eq!(1, board_history.len());
+                assert_eq!(Some(4), limits.depth);
+                assert_eq!(Some(Duration::from_secs(2)), limits.move_time);
+            }
+            _other => panic!("unexpected search command"),
+        }
+    }
+
+    #[test]
+    fn changing_the_game_stops_the_search() {
+        let (mut ladybug, search_command_receiver, _output_receiver) = setup();
+        for command in ["new", "force", "usermove e2e4", "undo", "usermove e2e4", "usermove e7e5", "remove", "setboard 4k3/8/8/8/8/8/4P3/4K3 b - - 0 1", "?", "result 1/2-1/2"] {
+            ladybug.handle_xboard_command(String::from(command));
+            let is_stopped = search_command_receiver.try_iter().any(|command| matches!(command, SearchCommand::Stop));
+            assert_eq!(!command.starts_with("usermove"), is_stopped, "{command}");
+        }
+    }
+}
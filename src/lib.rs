@@ -3,6 +3,14 @@ pub mod board;
 pub mod lookup;
 pub mod move_gen;
 pub mod uci;
+pub mod xboard;
 pub mod search;
+pub mod engine;
 pub mod evaluation;
 pub mod zobrist;
+pub mod book;
+pub mod pgn;
+pub mod game;
+pub mod suite;
+pub mod selfplay;
+pub mod bench;
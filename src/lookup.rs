@@ -2,7 +2,7 @@
 //! The submodule `lookup_table` contains a struct to store these tables, while the generation logic
 //! is provided by functions in submodules such as `pawn_attacks` or `knight_attacks`.
 
-use std::sync::OnceLock;
+use std::sync::LazyLock;
 use crate::board::bitboard::Bitboard;
 use crate::lookup::lookup_table::LookupTable;
 
@@ -14,13 +14,17 @@ pub mod bishop_occupancies;
 pub mod rook_occupancies;
 pub mod bishop_attacks;
 pub mod rook_attacks;
+pub mod magic_bitboards;
 
-/// This static `OnceLock` variable will contain the `LookupTable` instance.
+/// This static `LazyLock` variable contains the `LookupTable` instance.
 /// Because calculating the lookup table is so expensive, it should only be done once.
-/// 
-/// The `OnceLock` type allows us to use its `set()` method to set the lookup table exactly once, and after that,
-/// the lookup table can be obtained by calling the `get()` method.
-pub static LOOKUP_TABLE: OnceLock<LookupTable> = OnceLock::new();
+///
+/// The `LazyLock` type initializes the lookup table on first access, so it can be used without any setup.
+pub static LOOKUP_TABLE: LazyLock<LookupTable> = LazyLock::new(|| {
+    let mut lookup = LookupTable::default();
+    lookup.initialize_tables();
+    lookup
+});
 
 // ---------------------------------------------------------------
 // Constants used to mask the attack bitboards for various pieces
@@ -43,7 +47,15 @@ mod tests {
     use crate::board::file::{File, NUM_FILES};
     use crate::board::rank::{NUM_RANKS, Rank};
     use crate::board::square::Square;
-    use crate::lookup::{NOT_A_B_FILES, NOT_A_FILE, NOT_G_H_FILES, NOT_H_FILE};
+    use crate::board::bitboard::Bitboard;
+    use crate::board::square::{A1, D4};
+    use crate::lookup::{LOOKUP_TABLE, NOT_A_B_FILES, NOT_A_FILE, NOT_G_H_FILES, NOT_H_FILE};
+
+    #[test]
+    fn lookup_table_is_initialized_on_first_access() {
+        assert_eq!(0x20400, LOOKUP_TABLE.get_knight_attacks(A1).value);
+        assert_eq!(0x8080808f7080808, LOOKUP_TABLE.get_rook_attacks(D4, Bitboard::new(0)).value);
+    }
 
     #[test]
     fn not_a_file_squares_on_a_file_not_set() {
@@ -1,5 +1,3 @@
-#![allow(dead_code)]
-
 use crate::board::bitboard::Bitboard;
 use crate::board::file::{File};
 use crate::board::rank::{Rank};
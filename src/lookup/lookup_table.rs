@@ -1,6 +1,7 @@
 use crate::board::bitboard::Bitboard;
 use crate::board::color::Color;
 use crate::board::square::Square;
+use crate::lookup::magic_bitboards::Magic;
 
 /// This is the lookup table for the move generator.
 #[derive(Debug)]
@@ -8,6 +9,10 @@ pub struct LookupTable {
     pawn_attacks: [[Bitboard; 64]; 2],
     knight_attacks: [Bitboard; 64],
     king_attacks: [Bitboard; 64],
+    bishop_magics: [Magic; 64],
+    bishop_attacks: Vec<Bitboard>,
+    rook_magics: [Magic; 64],
+    rook_attacks: Vec<Bitboard>,
 }
 
 impl Default for LookupTable {
@@ -18,6 +23,10 @@ impl Default for LookupTable {
             pawn_attacks: [[Bitboard::new(0); 64]; 2],
             knight_attacks: [Bitboard::new(0); 64],
             king_attacks: [Bitboard::new(0); 64],
+            bishop_magics: [Magic::default(); 64],
+            bishop_attacks: Vec::new(),
+            rook_magics: [Magic::default(); 64],
+            rook_attacks: Vec::new(),
         }
     }
 }
@@ -28,6 +37,8 @@ impl LookupTable {
         self.pawn_attacks = Self::generate_pawn_attacks();
         self.knight_attacks = Self::generate_knight_attacks();
         self.king_attacks = Self::generate_king_attacks();
+        (self.bishop_magics, self.bishop_attacks) = Self::generate_bishop_magics();
+        (self.rook_magics, self.rook_attacks) = Self::generate_rook_magics();
     }
 
     /// Returns the attack bitboard for a pawn of the specified color on the specified square.
@@ -42,12 +53,12 @@ impl LookupTable {
 
     /// Returns the attack bitboard for a bishop on the specified square and the specified blocker bitboard.
     pub fn get_bishop_attacks(&self, square: Square, blockers: Bitboard) -> Bitboard {
-        Self::get_bishop_attack_bb(square, blockers)
+        self.bishop_attacks[self.bishop_magics[square.index as usize].get_index(blockers)]
     }
 
     /// Returns the attack bitboard for a rook on the specified square and the specified blocker bitboard.
     pub fn get_rook_attacks(&self, square: Square, blockers: Bitboard) -> Bitboard {
-        Self::get_rook_attack_bb(square, blockers)
+        self.rook_attacks[self.rook_magics[square.index as usize].get_index(blockers)]
     }
 
     /// Returns the attack bitboard for a queen on the specified square and the specified blocker bitboard.
@@ -74,6 +85,8 @@ mod tests {
         assert_eq!([[Bitboard::new(0); 64]; 2], lookup_table.pawn_attacks);
         assert_eq!([Bitboard::new(0); 64], lookup_table.knight_attacks);
         assert_eq!([Bitboard::new(0); 64], lookup_table.king_attacks);
+        assert!(lookup_table.bishop_attacks.is_empty());
+        assert!(lookup_table.rook_attacks.is_empty());
     }
 
     #[test]
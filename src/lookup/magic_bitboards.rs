@@ -0,0 +1,276 @@
+//! This module implements [magic bitboards](https://www.chessprogramming.org/Magic_Bitboards) for sliding pieces.
+//!
+//! For every square, the relevant blockers of a bishop or rook (its occupancy mask) are multiplied by a magic number.
+//! The upper bits of the product form a unique index into a table of precomputed attack bitboards,
+//! so that slider attacks can be looked up instead of walking every ray square by square.
+//!
+//! The magic numbers were found with `find_magic` and are stored as fixed tables, so they don't have to be searched for at startup.
+//!
+//! With the `pext` feature enabled on a target supporting BMI2, the index is computed with the `pext` instruction instead,
+//! which extracts the masked blocker bits directly. Both backends use the same masks, offsets and table sizes.
+
+use crate::board::bitboard::Bitboard;
+use crate::board::square::{NUM_SQUARES, Square};
+use crate::lookup::lookup_table::LookupTable;
+#[cfg(all(feature = "pext", target_arch = "x86_64", target_feature = "bmi2"))]
+use std::arch::x86_64::_pext_u64;
+
+/// The magic entry for one square.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct Magic {
+    /// The relevant occupancy mask of the square.
+    pub mask: u64,
+    /// The magic number.
+    pub magic: u64,
+    /// The amount the product of the masked blockers and the magic number is shifted to the right.
+    pub shift: u8,
+    /// The offset of the square's attack bitboards in the flat attack table.
+    pub offset: usize,
+}
+
+impl Magic {
+    /// Returns the index of the attack bitboard for the given blockers in the flat attack table.
+    pub fn get_index(&self, blockers: Bitboard) -> usize {
+        #[cfg(all(feature = "pext", target_arch = "x86_64", target_feature = "bmi2"))]
+        return self.get_pext_index(blockers);
+        #[cfg(not(all(feature = "pext", target_arch = "x86_64", target_feature = "bmi2")))]
+        return self.get_magic_index(blockers);
+    }
+
+    /// Returns the index of the attack bitboard for the given blockers, using the magic number.
+    pub fn get_magic_index(&self, blockers: Bitboard) -> usize {
+        self.offset + ((blockers.value & self.mask).wrapping_mul(self.magic) >> self.shift) as usize
+    }
+
+    /// Returns the index of the attack bitboard for the given blockers, using the BMI2 `pext` instruction.
+    #[cfg(all(feature = "pext", target_arch = "x86_64", target_feature = "bmi2"))]
+    pub fn get_pext_index(&self, blockers: Bitboard) -> usize {
+        // SAFETY: this is only compiled if the target supports BMI2
+        self.offset + unsafe { _pext_u64(blockers.value, self.mask) } as usize
+    }
+}
+
+impl LookupTable {
+    /// Generates the magic entries and the flat attack table for rooks.
+    pub(super) fn generate_rook_magics() -> ([Magic; 64], Vec<Bitboard>) {
+        Self::generate_magics(&ROOK_MAGICS, Self::generate_rook_occupancies(), Self::get_rook_attack_bb)
+    }
+
+    /// Generates the magic entries and the flat attack table for bishops.
+    pub(super) fn generate_bishop_magics() -> ([Magic; 64], Vec<Bitboard>) {
+        Self::generate_magics(&BISHOP_MAGICS, Self::generate_bishop_occupancies(), Self::get_bishop_attack_bb)
+    }
+
+    /// Generates the magic entries for all squares, and fills the flat attack table using the given ray-walking attack function.
+    fn generate_magics(magic_numbers: &[u64; 64], occupancies: [Bitboard; 64], get_attack_bb: fn(Square, Bitboard) -> Bitboard) -> ([Magic; 64], Vec<Bitboard>) {
+        let mut magics = [Magic::default(); 64];
+        let mut attacks: Vec<Bitboard> = Vec::new();
+
+        for square_index in 0..NUM_SQUARES {
+            let mask = occupancies[square_index as usize].value;
+            let num_bits = mask.count_ones() as u8;
+            let magic = Magic {
+                mask,
+                magic: magic_numbers[square_index as usize],
+                shift: 64 - num_bits,
+                offset: attacks.len(),
+            };
+
+            // fill the attack bitboards for all blocker subsets of the mask
+            attacks.resize(attacks.len() + (1 << num_bits), Bitboard::new(0));
+            for blockers in get_blocker_subsets(mask) {
+                attacks[magic.get_index(Bitboard::new(blockers))] = get_attack_bb(Square::new(square_index), Bitboard::new(blockers));
+            }
+
+            magics[square_index as usize] = magic;
+        }
+
+        (magics, attacks)
+    }
+}
+
+/// Returns all subsets of the given mask, using the [Carry-Rippler](https://www.chessprogramming.org/Traversing_Subsets_of_a_Set) trick.
+fn get_blocker_subsets(mask: u64) -> Vec<u64> {
+    let mut subsets = Vec::with_capacity(1 << mask.count_ones());
+    let mut subset: u64 = 0;
+    loop {
+        subsets.push(subset);
+        subset = subset.wrapping_sub(mask) & mask;
+        if subset == 0 {
+            return subsets;
+        }
+    }
+}
+
+/// Searches for a magic number for the given square, which maps every blocker subset of the mask to an index without destructive collisions.
+///
+/// Candidates are random numbers with few bits set, generated with a xorshift generator seeded with the given seed.
+pub fn find_magic(square: Square, mask: u64, get_attack_bb: fn(Square, Bitboard) -> Bitboard, seed: u64) -> u64 {
+    let num_bits = mask.count_ones();
+    let subsets = get_blocker_subsets(mask);
+    let attacks: Vec<u64> = subsets.iter().map(|blockers| get_attack_bb(square, Bitboard::new(*blockers)).value).collect();
+
+    let mut state = seed;
+    let mut next_random = move || {
+        state ^= state >> 12;
+        state ^= state << 25;
+        state ^= state >> 27;
+        state.wrapping_mul(0x2545f4914f6cdd1d)
+    };
+
+    let mut table: Vec<Option<u64>> = vec![None; 1 << num_bits];
+    loop {
+        let magic = next_random() & next_random() & next_random();
+        // the upper byte of the product has to contain enough bits for the magic to be any good
+        if (mask.wrapping_mul(magic) & 0xff00000000000000).count_ones() < 6 {
+            continue;
+        }
+
+        table.iter_mut().for_each(|entry| *entry = None);
+        let mut collision = false;
+        for (blockers, attack) in subsets.iter().zip(attacks.iter()) {
+            let index = (blockers.wrapping_mul(magic) >> (64 - num_bits)) as usize;
+            match table[index] {
+                None => table[index] = Some(*attack),
+                // different blockers may share an index, as long as they result in the same attacks
+                Some(existing) if existing == *attack => {}
+                Some(_) => {
+                    collision = true;
+                    break;
+                }
+            }
+        }
+
+        if !collision {
+            return magic;
+        }
+    }
+}
+
+/// The magic numbers for rooks, indexed by square.
+const ROOK_MAGICS: [u64; 64] = [
+    0x1080004008801020, 0x2240001000200440, 0x4580100020028088, 0x0100082010000500,
+    0x1001002010088040, 0x0200041008932a00, 0x0c00020801104084, 0x0100048044290002,
+    0x0220800040002884, 0x0004402000401002, 0x2002002212004280, 0x0050800803100080,
+    0x8005000c50280100, 0x0202000200040810, 0x0129001100940200, 0x4420802080004100,
+    0x0240208000400090, 0x4010044040082000, 0x0230808010002008, 0x6008210008100101,
+    0x9004808004000801, 0xc004808002000400, 0x0016040088420110, 0x0807020000842041,
+    0x0808802480004008, 0x0000200180400088, 0x0801004100102000, 0x0050005b00102100,
+    0x1000080080800400, 0x0132000200040810, 0x50000b3400100208, 0x1240412200004084,
+    0x0002400083800220, 0x0041201000c00040, 0x0000801002802001, 0x4300801000800800,
+    0x0208000811000500, 0x0006008422000930, 0xc280429004000108, 0x000000a102000044,
+    0x0614882040028000, 0x0010002000404000, 0x8010002408002000, 0x0e2010010021000a,
+    0x010800c500090010, 0x0404020004008080, 0x3c01040200010100, 0x0350104081020004,
+    0x0080410080220200, 0x02c0401000200340, 0x13a0001001480140, 0x0004080410008080,
+    0x5040080004008080, 0x040200081c300a00, 0x040a000804013200, 0x3001800041000080,
+    0x0020402080010011, 0x0200288440001101, 0x0408082000401101, 0x4092002008100442,
+    0x405b002800100403, 0x2806008401081042, 0x1800008821101214, 0x00c0040020408102,
+];
+
+/// The magic numbers for bishops, indexed by square.
+const BISHOP_MAGICS: [u64; 64] = [
+    0x1009480088004500, 0x0120223402419104, 0x0244810401008024, 0x0004404280002180,
+    0x0042021040800000, 0x2202015008008004, 0x0002011198400a10, 0x0005004616034004,
+    0x1080404324010210, 0x9100a24882088200, 0x0080190821010220, 0x4800882080283822,
+    0x4600111140000000, 0x80480088044200d0, 0x24002c01441e20c0, 0x28000101188a2003,
+    0x80b002092a101443, 0x806800029208020a, 0x2108012482040008, 0x0804200802410022,
+    0x0044000194200001, 0x0010201c10041008, 0x0001040084300201, 0x0000200200840408,
+    0x806008c011020800, 0xe0046000100200c0, 0x8810480010128210, 0x1001004084004200,
+    0x0070030001200808, 0x0008008000406004, 0x0000810812251024, 0x0008420401049208,
+    0x0001055000206000, 0x2002012000100210, 0x5022030400408820, 0x0220020084080080,
+    0x1040008020460020, 0x01020283000e0063, 0x0010010046310404, 0x0008010042003202,
+    0x4104022104009100, 0x40024804048030a1, 0x180e084048021012, 0x0000208401200c01,
+    0x4003110602000420, 0x5002808102000100, 0x0008018400800400, 0x0030020c90208308,
+    0x2000411048210002, 0x2001008844222410, 0x020882008404882b, 0x1201040842020100,
+    0x0842034042820400, 0x52050820d8008100, 0x0049201480820001, 0x102c010424018501,
+    0x8488262402201000, 0x0022070411010820, 0x0134000442180448, 0x0010181000420224,
+    0x200008104005010c, 0x08070a081010a080, 0x5100041410040328, 0x2042040108110901,
+];
+
+#[cfg(test)]
+mod tests {
+    use crate::board::bitboard::Bitboard;
+    use crate::board::square::{B7, D4, NUM_SQUARES, Square};
+    use crate::lookup::lookup_table::LookupTable;
+    use crate::lookup::magic_bitboards::{find_magic, get_blocker_subsets};
+
+    #[test]
+    fn test_get_blocker_subsets() {
+        assert_eq!(vec![0], get_blocker_subsets(0));
+        assert_eq!(vec![0, 0x2, 0x80, 0x82], get_blocker_subsets(0x82));
+        assert_eq!(1 << 12, get_blocker_subsets(LookupTable::generate_rook_occupancies()[0].value).len());
+    }
+
+    #[test]
+    fn magic_attacks_match_ray_walking_attacks() {
+        let (rook_magics, rook_attacks) = LookupTable::generate_rook_magics();
+        let (bishop_magics, bishop_attacks) = LookupTable::generate_bishop_magics();
+        assert_eq!(102400, rook_attacks.len());
+        assert_eq!(5248, bishop_attacks.len());
+
+        // verify the magic lookup against the ray-walking functions for every square and every blocker subset
+        for square_index in 0..NUM_SQUARES {
+            let square = Square::new(square_index);
+            let rook_magic = rook_magics[square_index as usize];
+            for blockers in get_blocker_subsets(rook_magic.mask) {
+                assert_eq!(LookupTable::get_rook_attack_bb(square, Bitboard::new(blockers)), rook_attacks[rook_magic.get_index(Bitboard::new(blockers))]);
+            }
+            let bishop_magic = bishop_magics[square_index as usize];
+            for blockers in get_blocker_subsets(bishop_magic.mask) {
+                assert_eq!(LookupTable::get_bishop_attack_bb(square, Bitboard::new(blockers)), bishop_attacks[bishop_magic.get_index(Bitboard::new(blockers))]);
+            }
+        }
+    }
+
+    #[test]
+    fn blockers_outside_the_mask_are_ignored() {
+        let (rook_magics, rook_attacks) = LookupTable::generate_rook_magics();
+        // blockers on the board edges and the rook's own square don't change the index
+        let blockers = Bitboard::new(0xff818181818181ff | 0x8000000);
+        let magic = rook_magics[D4.index as usize];
+        assert_eq!(LookupTable::get_rook_attack_bb(D4, blockers), rook_attacks[magic.get_index(blockers)]);
+    }
+
+    #[test]
+    #[cfg(all(feature = "pext", target_arch = "x86_64", target_feature = "bmi2"))]
+    fn pext_attacks_match_magic_attacks() {
+        let (rook_magics, rook_attacks) = LookupTable::generate_rook_magics();
+        let (bishop_magics, bishop_attacks) = LookupTable::generate_bishop_magics();
+
+        // the tables are filled using pext indices, so look up the same blockers with both backends in a table filled using magic indices
+        for (magics, attacks, get_attack_bb) in [
+            (rook_magics, &rook_attacks, LookupTable::get_rook_attack_bb as fn(Square, Bitboard) -> Bitboard),
+            (bishop_magics, &bishop_attacks, LookupTable::get_bishop_attack_bb),
+        ] {
+            let mut magic_attacks = vec![Bitboard::new(0); attacks.len()];
+            for square_index in 0..NUM_SQUARES {
+                let magic = magics[square_index as usize];
+                for blockers in get_blocker_subsets(magic.mask) {
+                    magic_attacks[magic.get_magic_index(Bitboard::new(blockers))] = get_attack_bb(Square::new(square_index), Bitboard::new(blockers));
+                }
+            }
+            for square_index in 0..NUM_SQUARES {
+                let magic = magics[square_index as usize];
+                for blockers in get_blocker_subsets(magic.mask) {
+                    assert_eq!(magic_attacks[magic.get_magic_index(Bitboard::new(blockers))], attacks[magic.get_pext_index(Bitboard::new(blockers))]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn find_magic_finds_valid_magic() {
+        let mask = LookupTable::generate_bishop_occupancies()[B7.index as usize].value;
+        let magic = find_magic(B7, mask, LookupTable::get_bishop_attack_bb, 0x9e3779b97f4a7c15);
+        let shift = 64 - mask.count_ones();
+
+        // no two blocker subsets with different attacks may share an index
+        let mut table = vec![None; 1 << mask.count_ones()];
+        for blockers in get_blocker_subsets(mask) {
+            let index = (blockers.wrapping_mul(magic) >> shift) as usize;
+            let attacks = LookupTable::get_bishop_attack_bb(B7, Bitboard::new(blockers));
+            assert!(table[index].is_none() || table[index] == Some(attacks));
+            table[index] = Some(attacks);
+        }
+    }
+}
@@ -1,21 +1,28 @@
 use std::sync::mpsc;
 use std::sync::mpsc::{Receiver, Sender};
-use std::{io, thread};
+use std::{io, process, thread};
+use std::sync::LazyLock;
+use ladybug::bench;
 use ladybug::ladybug::{Ladybug, Message};
 use ladybug::lookup::LOOKUP_TABLE;
-use ladybug::lookup::lookup_table::LookupTable;
 use ladybug::search::{Search, SearchCommand};
 
 /// Initializes the lookup table, spawns the input and output threads, and starts running Ladybug.
+///
+/// If started as `ladybug bench [depth]`, Ladybug runs the bench and exits instead.
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().is_some_and(|arg| arg == "bench") {
+        run_bench(args.get(1));
+        return;
+    }
+
     println!("\nLadybug 0.5.0\n");
 
     print!("Initializing tables... ");
 
-    // initialize the lookup table
-    let mut lookup = LookupTable::default();
-    lookup.initialize_tables();
-    let _ = LOOKUP_TABLE.set(lookup);
+    // initialize the lookup table now rather than on the first move generation
+    LazyLock::force(&LOOKUP_TABLE);
 
     println!("Done!");
 
@@ -52,6 +59,24 @@ fn main() {
     ladybug.run();
 }
 
+/// Runs the bench to the given depth, or to the default depth if none is given, and prints the results.
+fn run_bench(depth: Option<&String>) {
+    let depth = match depth.map(|depth| depth.parse::<u64>()) {
+        None => bench::BENCH_DEPTH,
+        Some(Ok(depth)) if depth > 0 => depth,
+        Some(_) => {
+            eprintln!("Invalid depth: {}", depth.unwrap());
+            eprintln!("Usage: ladybug bench [depth]");
+            process::exit(1);
+        }
+    };
+
+    let result = bench::run(depth, |index, nodes| {
+        println!("Position {}/{}: {nodes} nodes", index + 1, bench::BENCH_POSITIONS.len());
+    });
+    println!("{result}");
+}
+
 /// Reads input from Stdin and sends it to Ladybug.
 pub fn read_input(sender: Sender<Message>) {
     loop {
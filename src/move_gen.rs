@@ -8,6 +8,7 @@ use crate::move_gen::slider_moves::generate_slider_moves;
 
 pub mod ply;
 pub mod move_list;
+pub mod san;
 mod pawn_moves;
 mod slider_moves;
 mod leaper_moves;
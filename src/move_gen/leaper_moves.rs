@@ -1,9 +1,7 @@
-use crate::board::castling_rights::CastlingRights;
-use crate::board::color::{Color};
+use crate::board::castling_rights::{CastlingRights, get_castling_targets};
 use crate::board::file::File;
 use crate::board::piece::Piece;
 use crate::board::position::Position;
-use crate::board::rank::Rank;
 use crate::board::square::Square;
 use crate::lookup::LOOKUP_TABLE;
 use crate::move_gen::move_list::MoveList;
@@ -19,7 +17,7 @@ pub fn generate_leaper_moves(position: Position, move_list: &mut MoveList) {
 /// Generates all legal leaper moves (knights and kings) for a given leaper piece type in the given position.
 fn generate_leaper_moves_by_piece(position: Position, piece: Piece, move_list: &mut MoveList) {
     // get a reference to the lookup table
-    let lookup = LOOKUP_TABLE.get().unwrap();
+    let lookup = &*LOOKUP_TABLE;
 
     // get all squares with the piece type on it
     let active_squares = position.pieces[position.color_to_move.to_index() as usize][piece.to_index() as usize].get_active_bits();
@@ -47,7 +45,7 @@ fn generate_leaper_moves_by_piece(position: Position, piece: Piece, move_list: &
             // get the type of the attacked piece
             let attacked_piece = position.get_piece(target_square).map(|(piece, _color)| piece);
 
-            let ply = Ply { source: source_square, target: target_square, piece: piece, captured_piece: attacked_piece, promotion_piece: None };
+            let ply = Ply { source: source_square, target: target_square, piece, captured_piece: attacked_piece, promotion_piece: None };
             if position.make_move(ply).is_legal() {
                 move_list.push(ply);
             }
@@ -56,6 +54,8 @@ fn generate_leaper_moves_by_piece(position: Position, piece: Piece, move_list: &
 }
 
 /// Generates all legal castling moves for the given position.
+///
+/// Castling moves are encoded as the king capturing its own rook, so that they work for both standard chess and Chess960.
 fn generate_castling_moves(position: Position, move_list: &mut MoveList) {
     let castling_rights = position.castling_rights[position.color_to_move as usize];
 
@@ -70,10 +70,13 @@ fn generate_castling_moves(position: Position, move_list: &mut MoveList) {
     }
 
     // get castling rank
-    let rank = match position.color_to_move {
-        Color::White => Rank::First,
-        Color::Black => Rank::Eighth,
-    };
+    let rank = position.color_to_move.back_rank();
+
+    // get the king square - the king has to be on the castling rank
+    let king_square = position.pieces[position.color_to_move.to_index() as usize][Piece::King.to_index() as usize].get_active_bits()[0];
+    if king_square.get_rank() != rank {
+        return;
+    }
 
     // get attack bb for opponent
     let attack_bb = position.get_attack_bb(position.color_to_move.other());
@@ -81,47 +84,57 @@ fn generate_castling_moves(position: Position, move_list: &mut MoveList) {
     // get occupancies
     let occupancies = position.get_occupancies();
 
-    // queenside
-    if (castling_rights == CastlingRights::QueenSide || castling_rights == CastlingRights::Both) // color to move has castling rights for queenside
-        && !occupancies.get_bit(Square::from_file_rank(File::B, rank))  // B file square is unoccupied
-        && !occupancies.get_bit(Square::from_file_rank(File::C, rank)) // C file square is unoccupied
-        && !occupancies.get_bit(Square::from_file_rank(File::D, rank)) // D file square is unoccupied
-        && !attack_bb.get_bit(Square::from_file_rank(File::C, rank))  // C file square is not attacked
-        && !attack_bb.get_bit(Square::from_file_rank(File::D, rank)) // D file square is not attacked
-        && position.pieces[position.color_to_move.to_index() as usize][Piece::Rook.to_index() as usize].get_bit(Square::from_file_rank(File::A, rank)) // Rook is still on A file square
-    {
-        let ply = Ply { source: Square::from_file_rank(File::E, rank), target: Square::from_file_rank(File::C, rank), piece: Piece::King, captured_piece: None, promotion_piece: None };
-        move_list.push(ply);
-    }
+    let castling_rooks = position.castling_rooks[position.color_to_move.to_index() as usize];
+    let sides = [
+        (castling_rights == CastlingRights::QueenSide || castling_rights == CastlingRights::Both, castling_rooks.queen_side, false),
+        (castling_rights == CastlingRights::KingSide || castling_rights == CastlingRights::Both, castling_rooks.king_side, true),
+    ];
+
+    for (has_rights, rook_file, king_side) in sides {
+        let rook_square = Square::from_file_rank(rook_file, rank);
+
+        // the color to move has castling rights for this side, and the rook is still on its square
+        if !has_rights || !position.pieces[position.color_to_move.to_index() as usize][Piece::Rook.to_index() as usize].get_bit(rook_square) {
+            continue;
+        }
+
+        let (king_target, rook_target) = get_castling_targets(position.color_to_move, king_side);
+
+        // all squares between the king and its target square and between the rook and its target square have to be unoccupied,
+        // except for the squares of the king and the rook themselves
+        let is_unoccupied = |from: Square, to: Square| get_squares_between(from, to).iter()
+            .all(|square| *square == king_square || *square == rook_square || !occupancies.get_bit(*square));
+
+        // none of the squares the king passes may be attacked
+        let is_safe = get_squares_between(king_square, king_target).iter().all(|square| !attack_bb.get_bit(*square));
 
-    // kingside
-    if (castling_rights == CastlingRights::KingSide || castling_rights == CastlingRights::Both) // color to move has castling rights for kingside
-        && !occupancies.get_bit(Square::from_file_rank(File::F, rank))  // F file square is unoccupied
-        && !occupancies.get_bit(Square::from_file_rank(File::G, rank)) // G file square is unoccupied
-        && !attack_bb.get_bit(Square::from_file_rank(File::F, rank))  // F file square is not attacked
-        && !attack_bb.get_bit(Square::from_file_rank(File::G, rank)) // G file square is not attacked
-        && position.pieces[position.color_to_move.to_index() as usize][Piece::Rook.to_index() as usize].get_bit(Square::from_file_rank(File::H, rank)) // Rook is still on H file square
-    {
-        let ply = Ply { source: Square::from_file_rank(File::E, rank), target: Square::from_file_rank(File::G, rank), piece: Piece::King, captured_piece: None, promotion_piece: None };
-        move_list.push(ply);
+        if is_unoccupied(king_square, king_target) && is_unoccupied(rook_square, rook_target) && is_safe {
+            let ply = Ply { source: king_square, target: rook_square, piece: Piece::King, captured_piece: None, promotion_piece: None };
+            // in Chess960, the rook may have shielded the king from an attack along the castling rank
+            if position.make_move(ply).is_legal() {
+                move_list.push(ply);
+            }
+        }
     }
 }
 
+/// Returns all squares on the rank of the given squares from one square to the other, including both.
+fn get_squares_between(from: Square, to: Square) -> Vec<Square> {
+    let min_file = from.get_file().to_index().min(to.get_file().to_index());
+    let max_file = from.get_file().to_index().max(to.get_file().to_index());
+    (min_file..=max_file).map(|file| Square::from_file_rank(File::from_index(file), from.get_rank())).collect()
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::board::Board;
+    use crate::board::{Board, square};
     use crate::board::piece::Piece;
-    use crate::lookup::LOOKUP_TABLE;
-    use crate::lookup::lookup_table::LookupTable;
     use crate::move_gen::leaper_moves::{generate_castling_moves, generate_leaper_moves, generate_leaper_moves_by_piece};
     use crate::move_gen::move_list::MoveList;
+    use crate::move_gen::ply::Ply;
 
     #[test]
     fn test_generate_leaper_moves() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // position 1 (starting position)
 
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
@@ -195,10 +208,6 @@ mod tests {
 
     #[test]
     fn test_generate_leaper_moves_by_piece_for_knights() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // position 1 (starting position)
 
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
@@ -272,10 +281,6 @@ mod tests {
 
     #[test]
     fn test_generate_leaper_moves_by_piece_for_kings() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // position 1 (starting position)
 
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
@@ -349,10 +354,6 @@ mod tests {
 
     #[test]
     fn test_generate_castling_moves() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // position 1 (starting position)
 
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
@@ -423,4 +424,33 @@ mod tests {
         generate_castling_moves(position, &mut move_list);
         assert_eq!(2, move_list.len());
     }
+
+    #[test]
+    fn test_generate_castling_moves_chess960() {
+        // king on b1 with rooks on a1 and g1
+        let position = Board::from_fen("1k6/8/8/8/8/8/8/RK4R1 w GA - 0 1").unwrap().position;
+        let mut move_list = MoveList::default();
+        generate_castling_moves(position, &mut move_list);
+        assert_eq!(2, move_list.len());
+        assert_eq!(Ply { source: square::B1, target: square::A1, piece: Piece::King, captured_piece: None, promotion_piece: None }, move_list.get(0));
+        assert_eq!(Ply { source: square::B1, target: square::G1, piece: Piece::King, captured_piece: None, promotion_piece: None }, move_list.get(1));
+
+        // the king's target square is occupied
+        let position = Board::from_fen("1k6/8/8/8/8/8/8/RK4NR w HA - 0 1").unwrap().position;
+        let mut move_list = MoveList::default();
+        generate_castling_moves(position, &mut move_list);
+        assert_eq!(1, move_list.len());
+
+        // the rook on b1 shields the king on c1 from the rook on a1, so castling queenside would leave the king in check
+        let position = Board::from_fen("k7/8/8/8/8/8/8/rRK5 w B - 0 1").unwrap().position;
+        let mut move_list = MoveList::default();
+        generate_castling_moves(position, &mut move_list);
+        assert_eq!(0, move_list.len());
+
+        // the square the king passes is attacked
+        let position = Board::from_fen("1k3r2/8/8/8/8/8/8/RK5R w HA - 0 1").unwrap().position;
+        let mut move_list = MoveList::default();
+        generate_castling_moves(position, &mut move_list);
+        assert_eq!(1, move_list.len());
+    }
 }
\ No newline at end of file
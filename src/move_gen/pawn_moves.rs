@@ -75,7 +75,7 @@ fn generate_quiet_pawn_moves(position: Position, move_list: &mut MoveList) {
 /// Generates all legal attacking pawn moves for the given position.
 fn generate_attacking_pawn_moves(position: Position, move_list: &mut MoveList) {
     // get a reference to the lookup table
-    let lookup = LOOKUP_TABLE.get().unwrap();
+    let lookup = &*LOOKUP_TABLE;
 
     // get opposite color occupancy
     let occupancy = position.get_occupancy(position.color_to_move.other());
@@ -160,17 +160,11 @@ fn generate_en_passant_moves(position: Position, move_list: &mut MoveList) {
 #[cfg(test)]
 mod tests {
     use crate::board::{Board, square};
-    use crate::lookup::LOOKUP_TABLE;
-    use crate::lookup::lookup_table::LookupTable;
     use crate::move_gen::move_list::MoveList;
     use crate::move_gen::pawn_moves;
 
     #[test]
     fn test_generate_quiet_pawn_moves() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // position 1 (starting position)
 
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
@@ -251,10 +245,6 @@ mod tests {
 
     #[test]
     fn test_generate_attacking_pawn_moves() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // position 1 (starting position)
 
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
@@ -335,10 +325,6 @@ mod tests {
 
     #[test]
     fn test_generate_en_passant_moves() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // position 1 (starting position)
 
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
@@ -430,10 +416,6 @@ mod tests {
 
     #[test]
     fn test_generate_pawn_moves() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // position 1 (starting position)
 
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
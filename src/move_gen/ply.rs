@@ -1,4 +1,6 @@
+use std::error::Error;
 use std::fmt::{Display, Formatter};
+use crate::board::castling_rights::get_castling_targets;
 use crate::board::color::Color;
 use crate::board::piece::Piece;
 use crate::board::position::Position;
@@ -24,6 +26,25 @@ const SHIFT_PROMOTION_PIECE: u32 = 11;
 
 
 
+/// An error that occurred while parsing a move.
+#[derive(Clone, PartialEq, Debug)]
+pub enum MoveParseError {
+    /// The move string does not have the length of a move in UCI notation.
+    InvalidLength(String),
+    /// The square string is not a valid square.
+    InvalidSquare(String),
+    /// The char is not a valid file.
+    InvalidFile(char),
+    /// The char is not a valid promotion piece.
+    InvalidPromotionPiece(char),
+    /// The move string is not valid SAN.
+    InvalidSan(String),
+    /// The move is well-formed, but not legal in the given position.
+    IllegalMove(String),
+    /// The move matches more than one legal move in the given position.
+    AmbiguousMove(String),
+}
+
 /// This struct represents a halfmove, also known as [ply](https://www.chessprogramming.org/Ply).
 ///
 /// In the comments, I will often refer to a ply as a move, even though a move technically involves
@@ -63,13 +84,30 @@ impl Display for Ply {
         let mut output: String = String::from("");
         output += format!("{}", self.source).as_str();
         output += format!("{}", self.target).as_str();
-        if self.promotion_piece.is_some() {
-            output.push(self.promotion_piece.unwrap().to_char(Color::Black));
+        if let Some(promotion_piece) = self.promotion_piece {
+            output.push(promotion_piece.to_char(Color::Black));
         }
         write!(f, "{}", output)
     }
 }
 
+/// Prints the error as text.
+impl Display for MoveParseError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            MoveParseError::InvalidLength(ply_str) => write!(f, "invalid move length: {ply_str}"),
+            MoveParseError::InvalidSquare(square_str) => write!(f, "invalid square: {square_str}"),
+            MoveParseError::InvalidFile(file_char) => write!(f, "invalid file: {file_char}"),
+            MoveParseError::InvalidPromotionPiece(piece_char) => write!(f, "invalid promotion piece: {piece_char}"),
+            MoveParseError::InvalidSan(san) => write!(f, "invalid san: {san}"),
+            MoveParseError::IllegalMove(ply_str) => write!(f, "illegal move: {ply_str}"),
+            MoveParseError::AmbiguousMove(san) => write!(f, "ambiguous move: {san}"),
+        }
+    }
+}
+
+impl Error for MoveParseError {}
+
 impl Ply {
     /// Returns the score of the ply based on [MVV-LVA](https://www.chessprogramming.org/MVV-LVA).
     pub fn score(&self) -> i32 {
@@ -161,42 +199,25 @@ impl Ply {
         }
     }
     
-    /// Tries to construct a ply from the given string for the given position.
-    pub fn from_string(ply_str: &str, position: Position) -> Option<Ply> {
+    /// Tries to construct a ply from the given string in UCI notation for the given position.
+    pub fn from_string(ply_str: &str, position: Position) -> Result<Ply, MoveParseError> {
         // get the chars from the ply string
         let char_vec: Vec<char> = ply_str.chars().collect();
         if !(4..=5).contains(&char_vec.len()) {
-            return None;
+            return Err(MoveParseError::InvalidLength(String::from(ply_str)));
         }
 
-        // source square as string
-        let mut source_str = String::from("");
-        source_str.push(char_vec[0]);
-        source_str.push(char_vec[1]);
-
-        // target square as string
-        let mut target_str = String::from("");
-        target_str.push(char_vec[2]);
-        target_str.push(char_vec[3]);
-
-        // try to construct squares from the strings
-        let source_square = Square::from_string(source_str.as_str());
-        let target_square = Square::from_string(target_str.as_str());
-        if source_square.is_err() || target_square.is_err() {
-            return None;
-        }
-        let source_square = source_square.unwrap();
-        let target_square = target_square.unwrap();
+        // try to construct the source and target squares
+        let source_square = Square::from_string(char_vec[0..2].iter().collect::<String>().as_str())?;
+        let target_square = Square::from_string(char_vec[2..4].iter().collect::<String>().as_str())?;
 
         let mut promotion_piece: Option<Piece> = None;
 
         if char_vec.len() == 5 {
             // move is a promotion
-            let mut promotion_piece_string = String::from("");
-            promotion_piece_string.push(char_vec[4]);
-            match Piece::from_string(promotion_piece_string.as_str()) {
-                None => return None, // invalid promotion piece string
-                Some(piece) => promotion_piece = Some(piece),
+            match Piece::from_string(char_vec[4].to_string().as_str()) {
+                Some(piece) if piece != Piece::Pawn && piece != Piece::King => promotion_piece = Some(piece),
+                _other => return Err(MoveParseError::InvalidPromotionPiece(char_vec[4])),
             };
         }
 
@@ -209,12 +230,30 @@ impl Ply {
         }
 
         // search for ply in the move list
-        let ply = match move_list_vec.iter().find(|r| r.source == source_square && r.target == target_square && r.promotion_piece == promotion_piece) {
-            None => return None, // if the move list does not contain a ply with the specified source and target squares, the move is not legal
-            Some(ply) => *ply,
-        };
+        // castling moves are encoded as the king capturing its own rook, but may also be given as the king moving to its target square
+        match move_list_vec.iter()
+            .find(|r| r.source == source_square && r.target == target_square && r.promotion_piece == promotion_piece)
+            .or_else(|| move_list_vec.iter().find(|r| position.is_castling(**r) && r.source == source_square && r.get_castling_king_target(position) == target_square)) {
+            // if the move list does not contain a ply with the specified source and target squares, the move is not legal
+            None => Err(MoveParseError::IllegalMove(String::from(ply_str))),
+            Some(ply) => Ok(*ply),
+        }
+    }
 
-        Some(ply)
+    /// Returns the ply in [UCI](https://www.chessprogramming.org/UCI) notation for the given position.
+    ///
+    /// In Chess960 notation, castling moves are written as the king capturing its own rook, which is also how they are encoded.
+    /// Otherwise, they are written as the king moving to its target square, e.g. e1g1.
+    pub fn to_uci(&self, position: Position, chess960: bool) -> String {
+        if !chess960 && position.is_castling(*self) {
+            return format!("{}{}", self.source, self.get_castling_king_target(position));
+        }
+        format!("{self}")
+    }
+
+    /// Returns the square the king ends up on if the ply is a castling move in the given position.
+    fn get_castling_king_target(&self, position: Position) -> Square {
+        get_castling_targets(position.color_to_move, self.target.get_file().to_index() > self.source.get_file().to_index()).0
     }
 }
 
@@ -222,9 +261,7 @@ impl Ply {
 mod tests {
     use crate::board::piece::Piece;
     use crate::board::{Board, square};
-    use crate::lookup::LOOKUP_TABLE;
-    use crate::lookup::lookup_table::LookupTable;
-    use crate::move_gen::ply::Ply;
+    use crate::move_gen::ply::{MoveParseError, Ply};
     
     #[test]
     fn default_returns_illegal_ply() {
@@ -292,31 +329,74 @@ mod tests {
     }
 
     #[test]
-    fn from_string_with_invalid_move_returns_none() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
+    fn test_to_uci() {
+        let position = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap().position;
+
+        // castling moves
+        let ply = Ply { source: square::E1, target: square::H1, piece: Piece::King, captured_piece: None, promotion_piece: None };
+        assert_eq!("e1g1", ply.to_uci(position, false));
+        assert_eq!("e1h1", ply.to_uci(position, true));
+        let ply = Ply { source: square::E1, target: square::A1, piece: Piece::King, captured_piece: None, promotion_piece: None };
+        assert_eq!("e1c1", ply.to_uci(position, false));
+        assert_eq!("e1a1", ply.to_uci(position, true));
+
+        // other moves are printed the same way in both notations
+        let ply = Ply { source: square::E1, target: square::F1, piece: Piece::King, captured_piece: None, promotion_piece: None };
+        assert_eq!("e1f1", ply.to_uci(position, false));
+        assert_eq!("e1f1", ply.to_uci(position, true));
+    }
 
+    #[test]
+    fn from_string_with_chess960_castling_returns_ply() {
+        // the king on f1 can both move to g1 and castle kingside, which also puts it on g1
+        let position = Board::from_fen("4k3/8/8/8/8/8/8/5K1R w K - 0 1").unwrap().position;
+        assert_eq!(Ok(Ply {
+            source: square::F1,
+            target: square::G1,
+            piece: Piece::King,
+            captured_piece: None,
+            promotion_piece: None,
+        }), Ply::from_string("f1g1", position));
+        assert_eq!(Ok(Ply {
+            source: square::F1,
+            target: square::H1,
+            piece: Piece::King,
+            captured_piece: None,
+            promotion_piece: None,
+        }), Ply::from_string("f1h1", position));
+    }
+
+    #[test]
+    fn from_string_with_invalid_move_returns_error() {
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
-        assert_eq!(None, Ply::from_string("nonsense", position));
-        assert_eq!(None, Ply::from_string("a1a1", position));
-        assert_eq!(None, Ply::from_string("e1d1", position));
+        assert_eq!(Err(MoveParseError::InvalidLength(String::from("nonsense"))), Ply::from_string("nonsense", position));
+        assert_eq!(Err(MoveParseError::InvalidSquare(String::from("i2"))), Ply::from_string("i2e4", position));
+        assert_eq!(Err(MoveParseError::InvalidSquare(String::from("e9"))), Ply::from_string("e2e9", position));
+        assert_eq!(Err(MoveParseError::InvalidPromotionPiece('x')), Ply::from_string("e2e4x", position));
+        assert_eq!(Err(MoveParseError::InvalidPromotionPiece('k')), Ply::from_string("e2e4k", position));
+        assert_eq!(Err(MoveParseError::IllegalMove(String::from("a1a1"))), Ply::from_string("a1a1", position));
+        assert_eq!(Err(MoveParseError::IllegalMove(String::from("e1d1"))), Ply::from_string("e1d1", position));
 
         let position = Board::from_fen("r1bqk1nr/pppp3p/2n2p2/2b1p2Q/2B1P2N/2P5/PP1P1P1p/RNB1K3 b Qkq - 1 9").unwrap().position;
-        assert_eq!(None, Ply::from_string("h2h1q", position));
+        assert_eq!(Err(MoveParseError::IllegalMove(String::from("h2h1q"))), Ply::from_string("h2h1q", position));
 
         let position = Board::from_fen("r1bqk1nr/pppp1p1p/2n5/2b1p3/2B1P3/2P2N2/PP1P1P1p/RNBQK3 w Qkq - 0 8").unwrap().position;
-        assert_eq!(None, Ply::from_string("b1c3", position));
+        assert_eq!(Err(MoveParseError::IllegalMove(String::from("b1c3"))), Ply::from_string("b1c3", position));
     }
 
     #[test]
-    fn from_string_with_valid_move_returns_ply() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
+    fn move_parse_error_formats_correctly() {
+        assert_eq!("invalid move length: e2e", format!("{}", MoveParseError::InvalidLength(String::from("e2e"))));
+        assert_eq!("invalid square: i2", format!("{}", MoveParseError::InvalidSquare(String::from("i2"))));
+        assert_eq!("invalid promotion piece: x", format!("{}", MoveParseError::InvalidPromotionPiece('x')));
+        assert_eq!("illegal move: e1d1", format!("{}", MoveParseError::IllegalMove(String::from("e1d1"))));
+        assert_eq!("ambiguous move: Nd2", format!("{}", MoveParseError::AmbiguousMove(String::from("Nd2"))));
+    }
 
+    #[test]
+    fn from_string_with_valid_move_returns_ply() {
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
-        assert_eq!(Some(Ply {
+        assert_eq!(Ok(Ply {
             source: square::E2,
             target: square::E4,
             piece: Piece::Pawn,
@@ -325,16 +405,17 @@ mod tests {
         }), Ply::from_string("e2e4", position));
 
         let position = Board::from_fen("r1bqk1nr/pppp1ppp/2n5/2b1p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4").unwrap().position;
-        assert_eq!(Some(Ply {
+        assert_eq!(Ok(Ply {
             source: square::E1,
-            target: square::G1,
+            target: square::H1,
             piece: Piece::King,
             captured_piece: None,
             promotion_piece: None,
         }), Ply::from_string("e1g1", position));
+        assert_eq!(Ply::from_string("e1g1", position), Ply::from_string("e1h1", position));
 
         let position = Board::from_fen("r1bqk1nr/pppp1p1p/2n5/2b1p1N1/2B1P3/2P5/PP1P1P1p/RNBQK3 b Qkq - 1 8").unwrap().position;
-        assert_eq!(Some(Ply {
+        assert_eq!(Ok(Ply {
             source: square::H2,
             target: square::H1,
             piece: Piece::Pawn,
@@ -343,7 +424,7 @@ mod tests {
         }), Ply::from_string("h2h1n", position));
 
         let position = Board::from_fen("r1bq2nr/1pppk2p/2n2p2/p1b1p3/2B1P2N/2P5/PP1P1P1p/RNB1K1Q1 b Q - 1 11").unwrap().position;
-        assert_eq!(Some(Ply {
+        assert_eq!(Ok(Ply {
             source: square::H2,
             target: square::G1,
             piece: Piece::Pawn,
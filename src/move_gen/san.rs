@@ -0,0 +1,321 @@
+use crate::board::color::Color;
+use crate::board::piece::Piece;
+use crate::board::position::Position;
+use crate::board::square::Square;
+use crate::move_gen;
+use crate::move_gen::ply::{MoveParseError, Ply};
+
+impl Ply {
+    /// Returns the ply in [Standard Algebraic Notation](https://www.chessprogramming.org/Algebraic_Chess_Notation#SAN) for the given position.
+    ///
+    /// The ply is expected to be legal in the given position.
+    pub fn to_san(&self, position: &Position) -> String {
+        let mut san = String::from("");
+
+        if position.is_castling(*self) {
+            // the side is determined by the rook being to the right or to the left of the king
+            if self.target.get_file().to_index() > self.source.get_file().to_index() {
+                san += "O-O";
+            } else {
+                san += "O-O-O";
+            }
+        } else if self.piece == Piece::Pawn {
+            // pawn captures are prefixed with the source file
+            if self.captured_piece.is_some() {
+                san += format!("{}x", self.source.get_file()).as_str();
+            }
+            san += format!("{}", self.target).as_str();
+            if let Some(promotion_piece) = self.promotion_piece {
+                san.push('=');
+                san.push(promotion_piece.to_char(Color::White));
+            }
+        } else {
+            san.push(self.piece.to_char(Color::White));
+            san += self.get_disambiguation(position).as_str();
+            if self.captured_piece.is_some() {
+                san.push('x');
+            }
+            san += format!("{}", self.target).as_str();
+        }
+
+        // add check or checkmate suffix
+        let next_position = position.make_move(*self);
+        if next_position.is_in_check(next_position.color_to_move) {
+            if move_gen::generate_moves(next_position).is_empty() {
+                san.push('#');
+            } else {
+                san.push('+');
+            }
+        }
+
+        san
+    }
+
+    /// Tries to construct a ply from the given SAN string for the given position.
+    ///
+    /// Common sloppy forms are accepted as well, e.g. a missing or superfluous disambiguation (`N1f3`, `Ng1f3`),
+    /// a missing capture marker, a promotion without `=` (`e8Q`), castling with zeros (`0-0`)
+    /// and any check, checkmate or annotation suffix.
+    /// Returns an error if the string is malformed or does not describe exactly one legal move.
+    pub fn from_san(san_str: &str, position: &Position) -> Result<Ply, MoveParseError> {
+        let invalid_san = || MoveParseError::InvalidSan(String::from(san_str));
+
+        // strip check, checkmate and annotation suffixes
+        let san = san_str.trim().trim_end_matches(['+', '#', '!', '?']);
+
+        // generate all legal moves for the given position
+        let move_list = move_gen::generate_moves(*position);
+        let mut move_list_vec: Vec<Ply> = Vec::new();
+        for i in 0..move_list.len() {
+            move_list_vec.push(move_list.get(i));
+        }
+
+        // castling moves
+        let king_side = match san {
+            "O-O" | "0-0" => Some(true),
+            "O-O-O" | "0-0-0" => Some(false),
+            _ => None,
+        };
+        if let Some(king_side) = king_side {
+            let candidates: Vec<Ply> = move_list_vec.into_iter()
+                .filter(|r| position.is_castling(*r) && (r.target.get_file().to_index() > r.source.get_file().to_index()) == king_side)
+                .collect();
+            return Self::get_single_candidate(candidates, san_str);
+        }
+
+        // remove capture markers and separators
+        let mut char_vec: Vec<char> = san.chars().filter(|c| !matches!(c, 'x' | ':' | '-')).collect();
+
+        // the piece to move, pawn moves start without a piece letter
+        let piece = match char_vec.first() {
+            Some('N') => Piece::Knight,
+            Some('B') => Piece::Bishop,
+            Some('R') => Piece::Rook,
+            Some('Q') => Piece::Queen,
+            Some('K') => Piece::King,
+            _ => Piece::Pawn,
+        };
+        if piece != Piece::Pawn {
+            char_vec.remove(0);
+        }
+
+        // promotion piece with or without '='
+        let mut promotion_piece: Option<Piece> = None;
+        if let Some(last) = char_vec.last() {
+            if last.is_ascii_alphabetic() {
+                promotion_piece = match last.to_ascii_lowercase() {
+                    'n' => Some(Piece::Knight),
+                    'b' => Some(Piece::Bishop),
+                    'r' => Some(Piece::Rook),
+                    'q' => Some(Piece::Queen),
+                    _ => return Err(invalid_san()),
+                };
+                char_vec.pop();
+                if char_vec.last() == Some(&'=') {
+                    char_vec.pop();
+                }
+            }
+        }
+
+        // the last two chars form the target square
+        if char_vec.len() < 2 {
+            return Err(invalid_san());
+        }
+        let target_str: String = char_vec.split_off(char_vec.len() - 2).into_iter().collect();
+        let target = Square::from_string(target_str.as_str()).map_err(|_| invalid_san())?;
+
+        // the remaining chars are the file and/or rank of the source square
+        let mut source_file: Option<u8> = None;
+        let mut source_rank: Option<u8> = None;
+        for c in char_vec {
+            match c {
+                'a'..='h' if source_file.is_none() => source_file = Some(c as u8 - b'a'),
+                '1'..='8' if source_rank.is_none() => source_rank = Some(c as u8 - b'1'),
+                _ => return Err(invalid_san()),
+            }
+        }
+
+        // search for exactly one matching ply in the move list
+        let candidates: Vec<Ply> = move_list_vec.into_iter()
+            .filter(|r| r.piece == piece
+                && r.target == target
+                && !position.is_castling(*r)
+                && r.promotion_piece == promotion_piece
+                && source_file.is_none_or(|file| r.source.get_file().to_index() == file)
+                && source_rank.is_none_or(|rank| r.source.get_rank().to_index() == rank))
+            .collect();
+        Self::get_single_candidate(candidates, san_str)
+    }
+
+    /// Returns the only ply of the given candidates matching the SAN string, or an error if there is none or more than one.
+    fn get_single_candidate(candidates: Vec<Ply>, san_str: &str) -> Result<Ply, MoveParseError> {
+        match candidates.as_slice() {
+            [ply] => Ok(*ply),
+            [] => Err(MoveParseError::IllegalMove(String::from(san_str))),
+            _other => Err(MoveParseError::AmbiguousMove(String::from(san_str))),
+        }
+    }
+
+    /// Returns the source square information needed to tell the ply apart from other legal moves
+    /// of the same piece type to the same target square.
+    fn get_disambiguation(&self, position: &Position) -> String {
+        let move_list = move_gen::generate_moves(*position);
+        let mut others: Vec<Ply> = Vec::new();
+        for i in 0..move_list.len() {
+            let ply = move_list.get(i);
+            if ply.piece == self.piece && ply.target == self.target && ply.source != self.source && !position.is_castling(ply) {
+                others.push(ply);
+            }
+        }
+
+        if others.is_empty() {
+            return String::from("");
+        }
+
+        // prefer the file, then the rank, then both
+        if others.iter().all(|r| r.source.get_file() != self.source.get_file()) {
+            format!("{}", self.source.get_file())
+        } else if others.iter().all(|r| r.source.get_rank() != self.source.get_rank()) {
+            format!("{}", self.source.get_rank())
+        } else {
+            format!("{}", self.source)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::board::piece::Piece;
+    use crate::board::position::Position;
+    use crate::board::square;
+    use crate::move_gen;
+    use crate::move_gen::ply::{MoveParseError, Ply};
+
+    fn position(fen: &str) -> Position {
+        Board::from_fen(fen).unwrap().position
+    }
+
+    fn san(fen: &str, uci: &str) -> String {
+        let position = position(fen);
+        Ply::from_string(uci, position).unwrap().to_san(&position)
+    }
+
+    #[test]
+    fn test_to_san() {
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        assert_eq!("e4", san(start, "e2e4"));
+        assert_eq!("Nf3", san(start, "g1f3"));
+
+        // captures and en passant
+        assert_eq!("exd5", san("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2", "e4d5"));
+        assert_eq!("exd6", san("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3", "e5d6"));
+        assert_eq!("Bxf7+", san("rnbqkbnr/pppp1ppp/8/4p3/2B1P3/8/PPPP1PPP/RNBQK1NR w KQkq - 0 3", "c4f7"));
+
+        // promotions
+        assert_eq!("e8=Q+", san("k7/4P3/8/8/8/8/8/K7 w - - 0 1", "e7e8q"));
+        assert_eq!("exf8=N", san("5r2/k3P3/8/8/8/8/8/K7 w - - 0 1", "e7f8n"));
+
+        // castling
+        assert_eq!("O-O", san("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1g1"));
+        assert_eq!("O-O-O", san("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1c1"));
+        assert_eq!("O-O-O", san("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1", "e8a8"));
+
+        // checkmate
+        assert_eq!("Qxf7#", san("r1bqkbnr/pppp1ppp/2n5/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 0 4", "h5f7"));
+    }
+
+    #[test]
+    fn to_san_disambiguates_correctly() {
+        // by file
+        assert_eq!("Nbd2", san("4k3/8/8/8/8/5N2/8/1N2K3 w - - 0 1", "b1d2"));
+        // by rank
+        assert_eq!("R1a3", san("4k3/R7/8/8/8/8/8/R3K3 w - - 0 1", "a1a3"));
+        // by file and rank
+        assert_eq!("Qh4e1", san("8/8/k7/8/4Q2Q/8/1K6/7Q w - - 0 1", "h4e1"));
+        // pinned pieces do not need to be disambiguated
+        assert_eq!("Nd2", san("k7/8/8/8/6K1/5N2/8/1N1b4 w - - 0 1", "b1d2"));
+    }
+
+    #[test]
+    fn test_from_san() {
+        let start = position("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        let ply = Ply::from_san("Nf3", &start).unwrap();
+        assert_eq!((square::G1, square::F3, Piece::Knight), (ply.source, ply.target, ply.piece));
+        assert_eq!(Ok(ply), Ply::from_san("N1f3", &start));
+        assert_eq!(Ok(ply), Ply::from_san("Ngf3", &start));
+        assert_eq!(Ok(ply), Ply::from_san("Ng1f3", &start));
+        assert_eq!(Ok(ply), Ply::from_san("Ng1-f3", &start));
+        assert_eq!(Ok(ply), Ply::from_san("Nf3!?", &start));
+        assert_eq!(square::E4, Ply::from_san("e4", &start).unwrap().target);
+        assert_eq!(Err(MoveParseError::IllegalMove(String::from("e5"))), Ply::from_san("e5", &start));
+        assert_eq!(Err(MoveParseError::IllegalMove(String::from("Nd4"))), Ply::from_san("Nd4", &start));
+        assert_eq!(Err(MoveParseError::InvalidSan(String::new())), Ply::from_san("", &start));
+        assert_eq!(Err(MoveParseError::InvalidSan(String::from("Ni3"))), Ply::from_san("Ni3", &start));
+        assert_eq!(Err(MoveParseError::InvalidSan(String::from("Nzf3"))), Ply::from_san("Nzf3", &start));
+        assert_eq!(Err(MoveParseError::IllegalMove(String::from("O-O"))), Ply::from_san("O-O", &start));
+
+        // captures with and without capture marker
+        let position = position("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2");
+        let ply = Ply::from_san("exd5", &position).unwrap();
+        assert_eq!((square::E4, square::D5, Some(Piece::Pawn)), (ply.source, ply.target, ply.captured_piece));
+        assert_eq!(Ok(ply), Ply::from_san("ed5", &position));
+        assert_eq!(Ok(ply), Ply::from_san("e4xd5", &position));
+    }
+
+    #[test]
+    fn from_san_parses_promotions() {
+        let position = position("5r2/k3P3/8/8/8/8/8/K7 w - - 0 1");
+        let ply = Ply::from_san("e8=Q", &position).unwrap();
+        assert_eq!((square::E8, Some(Piece::Queen)), (ply.target, ply.promotion_piece));
+        assert_eq!(Ok(ply), Ply::from_san("e8Q", &position));
+        assert_eq!(Ok(ply), Ply::from_san("e8=Q+", &position));
+        assert_eq!(Ok(ply), Ply::from_san("e8q", &position));
+        let ply = Ply::from_san("exf8=N", &position).unwrap();
+        assert_eq!((square::F8, Some(Piece::Knight)), (ply.target, ply.promotion_piece));
+        assert_eq!(Err(MoveParseError::IllegalMove(String::from("e8"))), Ply::from_san("e8", &position));
+        assert_eq!(Err(MoveParseError::InvalidSan(String::from("e8=K"))), Ply::from_san("e8=K", &position));
+    }
+
+    #[test]
+    fn from_san_parses_castling() {
+        let position = position("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+        let ply = Ply::from_san("O-O", &position).unwrap();
+        assert_eq!((square::E1, square::H1), (ply.source, ply.target));
+        assert_eq!(Ok(ply), Ply::from_san("0-0", &position));
+        let ply = Ply::from_san("O-O-O", &position).unwrap();
+        assert_eq!((square::E1, square::A1), (ply.source, ply.target));
+        assert_eq!(Ok(ply), Ply::from_san("0-0-0+", &position));
+
+        // the king moving next to the rook is not a castling move
+        assert_eq!(square::F1, Ply::from_san("Kf1", &position).unwrap().target);
+    }
+
+    #[test]
+    fn from_san_rejects_ambiguous_moves() {
+        let position = position("4k3/8/8/8/8/5N2/8/1N2K3 w - - 0 1");
+        assert_eq!(Err(MoveParseError::AmbiguousMove(String::from("Nd2"))), Ply::from_san("Nd2", &position));
+        assert_eq!(square::B1, Ply::from_san("Nbd2", &position).unwrap().source);
+        assert_eq!(square::F3, Ply::from_san("N3d2", &position).unwrap().source);
+    }
+
+    #[test]
+    fn san_round_trips_for_all_legal_moves() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "1r2k1r1/8/8/8/8/8/8/R1K3R1 w GAgb - 0 1",
+        ];
+        for fen in fens {
+            let position = position(fen);
+            let move_list = move_gen::generate_moves(position);
+            for i in 0..move_list.len() {
+                let ply = move_list.get(i);
+                assert_eq!(Ok(ply), Ply::from_san(ply.to_san(&position).as_str(), &position), "{fen} {ply}");
+            }
+        }
+    }
+}
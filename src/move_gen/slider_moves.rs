@@ -14,7 +14,7 @@ pub fn generate_slider_moves(position: Position, move_list: &mut MoveList) {
 /// Generates all legal slider moves for a given piece type in the given position.
 fn generate_slider_moves_by_piece(position: Position, piece: Piece, move_list: &mut MoveList) {
     // get a reference to the lookup table
-    let lookup = LOOKUP_TABLE.get().unwrap();
+    let lookup = &*LOOKUP_TABLE;
 
     // get occupancies
     let occupancies = position.get_occupancies();
@@ -58,17 +58,11 @@ fn generate_slider_moves_by_piece(position: Position, piece: Piece, move_list: &
 mod tests {
     use crate::board::Board;
     use crate::board::piece::Piece;
-    use crate::lookup::LOOKUP_TABLE;
-    use crate::lookup::lookup_table::LookupTable;
     use crate::move_gen::move_list::MoveList;
     use crate::move_gen::slider_moves::{generate_slider_moves, generate_slider_moves_by_piece};
 
     #[test]
     fn test_generate_slider_moves() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // position 1 (starting position)
 
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
@@ -142,10 +136,6 @@ mod tests {
 
     #[test]
     fn test_generate_slider_moves_by_piece_for_bishop() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // position 1 (starting position)
 
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
@@ -219,10 +209,6 @@ mod tests {
 
     #[test]
     fn test_generate_slider_moves_by_piece_for_rook() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // position 1 (starting position)
 
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
@@ -296,10 +282,6 @@ mod tests {
 
     #[test]
     fn test_generate_slider_moves_by_piece_for_queen() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // position 1 (starting position)
 
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
@@ -0,0 +1,811 @@
+//! The pgn module reads and writes games in [Portable Game Notation](https://www.chessprogramming.org/Portable_Game_Notation).
+//!
+//! Games are streamed one at a time with the [PgnReader], so that large databases never have to be loaded into memory at once.
+//! The movetext is parsed into a tree of moves, which keeps comments, [NAGs](https://www.chessprogramming.org/Portable_Game_Notation#NAG)
+//! and (nested) variations. The mainline can be replayed into a sequence of boards with [PgnGame::boards].
+
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use crate::board::Board;
+use crate::board::color::Color;
+use crate::board::fen::START_FEN;
+use crate::move_gen::ply::Ply;
+
+/// The tags of the seven tag roster, which are written first and in this order.
+const SEVEN_TAG_ROSTER: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+/// The maximum number of characters in a line of written movetext.
+const MAX_LINE_LENGTH: usize = 80;
+
+/// A game read from or written to a PGN file.
+#[derive(Clone, PartialEq, Debug)]
+pub struct PgnGame {
+    /// The tag pairs in the order they appeared in the file.
+    pub tags: Vec<(String, String)>,
+    /// The board the game starts from, which is set up from the `FEN` tag if there is one.
+    pub start: Board,
+    /// The moves of the mainline.
+    pub moves: Vec<PgnMove>,
+}
+
+/// A move of a game together with its annotations.
+#[derive(Clone, PartialEq, Debug)]
+pub struct PgnMove {
+    /// The move itself.
+    pub ply: Ply,
+    /// The numeric annotation glyphs of the move, e.g. 1 for `!` or 4 for `??`.
+    pub nags: Vec<u8>,
+    /// The comment preceding the move, if any.
+    pub comment_before: Option<String>,
+    /// The comment following the move, if any.
+    pub comment: Option<String>,
+    /// Alternatives to the move, each starting from the position before the move.
+    pub variations: Vec<Vec<PgnMove>>,
+}
+
+/// Streams games from PGN text, one game at a time.
+///
+/// A game ends at its termination marker, or where the tag section of the following game starts after the movetext
+/// or after a blank line. Games that can not be parsed are returned as errors,
+/// and reading continues with the next game.
+pub struct PgnReader<R: BufRead> {
+    /// The underlying reader.
+    reader: R,
+    /// The first line of the next game, which has already been read while looking for the end of the current game.
+    next_line: Option<String>,
+    /// The number of lines read so far.
+    line_number: usize,
+}
+
+/// A token of PGN text.
+#[derive(Clone, PartialEq, Debug)]
+enum Token {
+    Tag(String, String),
+    Comment(String),
+    StartVariation,
+    EndVariation,
+    Nag(u8),
+    Result(String),
+    San(String),
+}
+
+/// A line of moves that is currently being parsed, i.e. the mainline or a variation.
+struct Line {
+    /// The moves of the line parsed so far.
+    moves: Vec<PgnMove>,
+    /// The board after the last move of the line.
+    board: Board,
+    /// The board before the last move of the line, which is where variations of that move start.
+    previous_board: Board,
+    /// A comment that is waiting for the next move.
+    comment_before: Option<String>,
+}
+
+impl PgnMove {
+    /// Constructs a new move without annotations.
+    pub fn new(ply: Ply) -> Self {
+        Self {
+            ply,
+            nags: Vec::new(),
+            comment_before: None,
+            comment: None,
+            variations: Vec::new(),
+        }
+    }
+}
+
+impl Default for PgnGame {
+    /// Returns an empty game from the standard starting position without any tags.
+    fn default() -> Self {
+        Self {
+            tags: Vec::new(),
+            start: Board::from_fen(START_FEN).unwrap(),
+            moves: Vec::new(),
+        }
+    }
+}
+
+impl PgnGame {
+    /// Constructs an empty game starting from the given board.
+    /// If the board is not the standard starting position, the `SetUp` and `FEN` tags are set accordingly.
+    pub fn new(start: Board) -> Self {
+        let mut game = Self {
+            start,
+            ..Self::default()
+        };
+        let fen = start.to_fen();
+        if fen != START_FEN {
+            game.set_tag("SetUp", "1");
+            game.set_tag("FEN", fen.as_str());
+        }
+        game
+    }
+
+    /// Parses a single game from PGN text.
+    pub fn parse(pgn: &str) -> Result<PgnGame, String> {
+        let tokens = tokenize(pgn)?;
+
+        // the tag section comes first and determines the starting position
+        let mut game = PgnGame::default();
+        for token in tokens.iter() {
+            if let Token::Tag(name, value) = token {
+                game.tags.push((name.clone(), value.clone()));
+            }
+        }
+        if let Some(fen) = game.get_tag("FEN") {
+            game.start = Board::from_fen(fen).map_err(|error| format!("invalid FEN tag: {error}"))?;
+        }
+
+        // parse the movetext, keeping a stack of the lines that are currently open
+        let mut lines: Vec<Line> = vec![Line::new(game.start)];
+        let mut result: Option<String> = None;
+        for token in tokens {
+            let line = lines.last_mut().unwrap();
+            match token {
+                Token::Tag(_, _) => {}
+                Token::Comment(comment) => match line.moves.last_mut() {
+                    Some(last_move) if line.comment_before.is_none() => append_comment(&mut last_move.comment, comment),
+                    _other => append_comment(&mut line.comment_before, comment),
+                },
+                Token::Nag(nag) => match line.moves.last_mut() {
+                    Some(last_move) => last_move.nags.push(nag),
+                    None => return Err(String::from("NAG before the first move")),
+                },
+                Token::StartVariation => {
+                    if line.moves.is_empty() {
+                        return Err(String::from("variation before the first move"));
+                    }
+                    let board = line.previous_board;
+                    lines.push(Line::new(board));
+                }
+                Token::EndVariation => {
+                    if lines.len() < 2 {
+                        return Err(String::from("unexpected end of variation"));
+                    }
+                    let variation = lines.pop().unwrap();
+                    let parent = lines.last_mut().unwrap();
+                    if !variation.moves.is_empty() {
+                        parent.moves.last_mut().unwrap().variations.push(variation.moves);
+                    }
+                }
+                Token::Result(game_result) => result = Some(game_result),
+                Token::San(san) => {
+                    let ply = match Ply::from_san(san.as_str(), &line.board.position) {
+                        Err(error) => return Err(format!("{error} after {}", line.board.to_fen())),
+                        Ok(ply) => ply,
+                    };
+                    let mut pgn_move = PgnMove::new(ply);
+                    pgn_move.comment_before = line.comment_before.take();
+                    line.moves.push(pgn_move);
+                    line.previous_board = line.board;
+                    line.board = line.board.make_move(ply);
+                }
+            }
+        }
+        if lines.len() > 1 {
+            return Err(String::from("unterminated variation"));
+        }
+
+        // a comment after the last move is attached to it
+        let mainline = lines.pop().unwrap();
+        game.moves = mainline.moves;
+        if let Some(comment) = mainline.comment_before {
+            match game.moves.last_mut() {
+                Some(last_move) => append_comment(&mut last_move.comment, comment),
+                None => return Err(String::from("comment without moves")),
+            }
+        }
+
+        // the game termination marker fills in a missing result tag
+        if game.get_tag("Result").is_none() {
+            if let Some(result) = result {
+                game.set_tag("Result", result.as_str());
+            }
+        }
+
+        Ok(game)
+    }
+
+    /// Returns the value of the tag with the given name.
+    pub fn get_tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter()
+            .find(|(tag_name, _)| tag_name == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Sets the tag with the given name to the given value, adding it if it does not exist yet.
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag_name, _)| tag_name == name) {
+            Some(tag) => tag.1 = String::from(value),
+            None => self.tags.push((String::from(name), String::from(value))),
+        }
+    }
+
+    /// Returns the result of the game as written in the `Result` tag, or `*` if it is unknown.
+    pub fn result(&self) -> &str {
+        self.get_tag("Result").unwrap_or("*")
+    }
+
+    /// Replays the mainline and returns the starting board followed by the board after each move.
+    pub fn boards(&self) -> Vec<Board> {
+        let mut boards: Vec<Board> = vec![self.start];
+        for pgn_move in self.moves.iter() {
+            boards.push(boards.last().unwrap().make_move(pgn_move.ply));
+        }
+        boards
+    }
+
+    /// Returns the game in PGN notation.
+    ///
+    /// The seven tag roster is written first, with `?` for missing values, followed by all other tags.
+    /// The movetext is wrapped so that no line is longer than 80 characters.
+    pub fn to_pgn(&self) -> String {
+        let mut pgn = String::from("");
+
+        // tag section
+        for name in SEVEN_TAG_ROSTER {
+            let value = match (self.get_tag(name), name) {
+                (Some(value), _) => value,
+                (None, "Date") => "????.??.??",
+                (None, "Result") => "*",
+                (None, _) => "?",
+            };
+            pgn += format!("[{name} \"{}\"]\n", escape_tag_value(value)).as_str();
+        }
+        for (name, value) in self.tags.iter().filter(|(name, _)| !SEVEN_TAG_ROSTER.contains(&name.as_str())) {
+            pgn += format!("[{name} \"{}\"]\n", escape_tag_value(value)).as_str();
+        }
+        pgn.push('\n');
+
+        // movetext
+        let mut tokens: Vec<String> = Vec::new();
+        write_line(&self.moves, self.start, &mut tokens);
+        tokens.push(String::from(self.result()));
+
+        let mut line = String::from("");
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() > MAX_LINE_LENGTH {
+                pgn += line.as_str();
+                pgn.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() && !token.starts_with(')') && !line.ends_with('(') {
+                line.push(' ');
+            }
+            line += token.as_str();
+        }
+        pgn += line.as_str();
+        pgn.push('\n');
+
+        pgn
+    }
+
+    /// Writes the game in PGN notation to the given writer, followed by an empty line to separate it from the next game.
+    pub fn write_pgn(&self, writer: &mut impl Write) -> io::Result<()> {
+        writeln!(writer, "{}", self.to_pgn())
+    }
+}
+
+impl Line {
+    /// Constructs an empty line starting from the given board.
+    fn new(board: Board) -> Self {
+        Self {
+            moves: Vec::new(),
+            board,
+            previous_board: board,
+            comment_before: None,
+        }
+    }
+}
+
+impl<R: BufRead> PgnReader<R> {
+    /// Constructs a new reader that reads games from the given source.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            next_line: None,
+            line_number: 0,
+        }
+    }
+
+    /// Reads the next line without its line break. Returns `None` at the end of the input.
+    fn read_line(&mut self) -> Option<io::Result<String>> {
+        let mut line = String::new();
+        match self.reader.read_line(&mut line) {
+            Ok(0) => None,
+            Ok(_) => {
+                self.line_number += 1;
+                Some(Ok(String::from(line.trim_end_matches(['\n', '\r']))))
+            }
+            Err(error) => Some(Err(error)),
+        }
+    }
+}
+
+/// Opens the PGN file at the given path for streaming.
+pub fn open(path: &Path) -> io::Result<PgnReader<BufReader<File>>> {
+    Ok(PgnReader::new(BufReader::new(File::open(path)?)))
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = Result<PgnGame, String>;
+
+    /// Reads and parses the next game.
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut text = String::from("");
+        let mut first_line_number = 0;
+        let mut has_movetext = false;
+        let mut has_tags = false;
+        let mut after_blank_line = false;
+        let mut in_comment = false;
+
+        loop {
+            let line = match self.next_line.take() {
+                Some(line) => line,
+                None => match self.read_line() {
+                    None => break,
+                    Some(Err(error)) => return Some(Err(error.to_string())),
+                    Some(Ok(line)) => line,
+                },
+            };
+
+            // lines starting with '%' are escaped and ignored
+            if line.starts_with('%') {
+                continue;
+            }
+
+            let trimmed_line = line.trim();
+            let mut is_terminated = false;
+            if !in_comment && trimmed_line.starts_with('[') {
+                // a tag after the movetext or after a blank line ending the tag section starts the next game
+                if has_movetext || (has_tags && after_blank_line) {
+                    self.next_line = Some(line);
+                    break;
+                }
+                has_tags = true;
+            } else {
+                // keep track of brace comments spanning several lines, so that a comment line starting with '[' is not taken for a tag
+                let mut movetext = String::new();
+                for c in line.chars() {
+                    match c {
+                        '{' if !in_comment => in_comment = true,
+                        '}' if in_comment => in_comment = false,
+                        ';' if !in_comment => break,
+                        c if !in_comment => movetext.push(c),
+                        _ => {}
+                    }
+                }
+                has_movetext |= !movetext.trim().is_empty();
+
+                // the game termination marker ends the game
+                is_terminated = !in_comment && matches!(movetext.split_whitespace().last(), Some("1-0" | "0-1" | "1/2-1/2" | "*"));
+            }
+            after_blank_line = trimmed_line.is_empty();
+
+            if text.is_empty() {
+                if trimmed_line.is_empty() {
+                    continue;
+                }
+                first_line_number = self.line_number;
+            }
+            text += line.as_str();
+            text.push('\n');
+
+            if is_terminated {
+                break;
+            }
+        }
+
+        if text.trim().is_empty() {
+            return None;
+        }
+        Some(PgnGame::parse(text.as_str()).map_err(|error| format!("game starting at line {first_line_number}: {error}")))
+    }
+}
+
+/// Appends a comment to an optional existing comment.
+fn append_comment(existing: &mut Option<String>, comment: String) {
+    match existing {
+        Some(existing) => {
+            existing.push(' ');
+            *existing += comment.as_str();
+        }
+        None => *existing = Some(comment),
+    }
+}
+
+/// Escapes quotes and backslashes in a tag value.
+fn escape_tag_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Returns the NAG for a move suffix annotation such as `!` or `?!`.
+fn get_suffix_nag(suffix: &str) -> Option<u8> {
+    match suffix {
+        "!" => Some(1),
+        "?" => Some(2),
+        "!!" => Some(3),
+        "??" => Some(4),
+        "!?" => Some(5),
+        "?!" => Some(6),
+        _ => None,
+    }
+}
+
+/// Appends the tokens of the given line of moves, starting from the given board, to the token list.
+fn write_line(moves: &[PgnMove], start: Board, tokens: &mut Vec<String>) {
+    let mut board = start;
+    let mut needs_move_number = true;
+    for pgn_move in moves {
+        if let Some(comment) = &pgn_move.comment_before {
+            write_comment(comment, tokens);
+            needs_move_number = true;
+        }
+
+        // White's moves are always numbered, Black's only after an interruption of the movetext
+        let white_to_move = board.position.color_to_move == Color::White;
+        let san = pgn_move.ply.to_san(&board.position);
+        if white_to_move {
+            tokens.push(format!("{}.", board.fullmove_counter));
+            tokens.push(san);
+        } else if needs_move_number {
+            tokens.push(format!("{}...", board.fullmove_counter));
+            tokens.push(san);
+        } else {
+            tokens.push(san);
+        }
+        needs_move_number = false;
+
+        for nag in pgn_move.nags.iter() {
+            tokens.push(format!("${nag}"));
+        }
+        if let Some(comment) = &pgn_move.comment {
+            write_comment(comment, tokens);
+            needs_move_number = true;
+        }
+        for variation in pgn_move.variations.iter() {
+            tokens.push(String::from("("));
+            write_line(variation, board, tokens);
+            tokens.push(String::from(")"));
+            needs_move_number = true;
+        }
+
+        board = board.make_move(pgn_move.ply);
+    }
+}
+
+/// Appends a brace comment to the token list, split into words so that it can be wrapped.
+fn write_comment(comment: &str, tokens: &mut Vec<String>) {
+    let words: Vec<&str> = comment.split_whitespace().collect();
+    if words.is_empty() {
+        tokens.push(String::from("{}"));
+        return;
+    }
+    for (index, word) in words.iter().enumerate() {
+        let mut token = String::from(*word);
+        if index == 0 {
+            token.insert(0, '{');
+        }
+        if index == words.len() - 1 {
+            token.push('}');
+        }
+        tokens.push(token);
+    }
+}
+
+/// Splits PGN text into tokens.
+fn tokenize(pgn: &str) -> Result<Vec<Token>, String> {
+    let mut tokens: Vec<Token> = Vec::new();
+    let chars: Vec<char> = pgn.chars().collect();
+    let mut index = 0;
+
+    while index < chars.len() {
+        let c = chars[index];
+        match c {
+            c if c.is_whitespace() => index += 1,
+            '%' if index == 0 || chars[index - 1] == '\n' => {
+                // escaped line
+                while index < chars.len() && chars[index] != '\n' {
+                    index += 1;
+                }
+            }
+            '[' => {
+                let end = find(&chars, index, ']').ok_or("unterminated tag")?;
+                tokens.push(parse_tag(chars[index + 1..end].iter().collect::<String>().as_str())?);
+                index = end + 1;
+            }
+            '{' => {
+                let end = find(&chars, index, '}').ok_or("unterminated comment")?;
+                let comment: String = chars[index + 1..end].iter().collect();
+                tokens.push(Token::Comment(comment.split_whitespace().collect::<Vec<&str>>().join(" ")));
+                index = end + 1;
+            }
+            ';' => {
+                let end = find(&chars, index, '\n').unwrap_or(chars.len());
+                let comment: String = chars[index + 1..end].iter().collect();
+                tokens.push(Token::Comment(String::from(comment.trim())));
+                index = end;
+            }
+            '(' => {
+                tokens.push(Token::StartVariation);
+                index += 1;
+            }
+            ')' => {
+                tokens.push(Token::EndVariation);
+                index += 1;
+            }
+            '$' => {
+                let start = index + 1;
+                index = start;
+                while index < chars.len() && chars[index].is_ascii_digit() {
+                    index += 1;
+                }
+                let nag: String = chars[start..index].iter().collect();
+                tokens.push(Token::Nag(nag.parse::<u8>().map_err(|_| format!("invalid NAG ${nag}"))?));
+            }
+            _ => {
+                let start = index;
+                while index < chars.len() && !chars[index].is_whitespace() && !"[]{}();$".contains(chars[index]) {
+                    index += 1;
+                }
+                let symbol: String = chars[start..index].iter().collect();
+                tokens.append(&mut parse_symbol(symbol.as_str()));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Returns the index of the first occurrence of the given char after the given index.
+fn find(chars: &[char], index: usize, c: char) -> Option<usize> {
+    chars[index + 1..].iter().position(|other| *other == c).map(|position| index + 1 + position)
+}
+
+/// Parses the content of a tag pair, e.g. `Event "Casual Game"`.
+fn parse_tag(tag: &str) -> Result<Token, String> {
+    let (name, value) = tag.trim().split_once(char::is_whitespace).ok_or(format!("invalid tag [{tag}]"))?;
+    let value = value.trim();
+    if !value.starts_with('"') || !value.ends_with('"') || value.len() < 2 {
+        return Err(format!("invalid tag [{tag}]"));
+    }
+    let value = value[1..value.len() - 1].replace("\\\"", "\"").replace("\\\\", "\\");
+    Ok(Token::Tag(String::from(name), value))
+}
+
+/// Parses a symbol of the movetext, which may be a move number, a move with suffix annotations, or a game result.
+fn parse_symbol(symbol: &str) -> Vec<Token> {
+    if matches!(symbol, "1-0" | "0-1" | "1/2-1/2" | "*") {
+        return vec![Token::Result(String::from(symbol))];
+    }
+
+    // strip the move number, which may be directly followed by the move, e.g. 1.e4
+    let mut symbol = symbol;
+    let number_length = symbol.find(|c: char| !c.is_ascii_digit()).unwrap_or(symbol.len());
+    if number_length > 0 && symbol[number_length..].starts_with('.') {
+        symbol = symbol[number_length..].trim_start_matches('.');
+    }
+    if symbol.is_empty() {
+        return Vec::new();
+    }
+
+    // suffix annotations are turned into NAGs
+    let san = symbol.trim_end_matches(['!', '?']);
+    let mut tokens = vec![Token::San(String::from(san))];
+    if let Some(nag) = get_suffix_nag(&symbol[san.len()..]) {
+        tokens.push(Token::Nag(nag));
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use crate::board::Board;
+    use crate::board::fen::START_FEN;
+    use crate::board::piece::Piece;
+    use crate::board::square;
+    use crate::move_gen::ply::Ply;
+    use crate::pgn::{PgnGame, PgnMove, PgnReader};
+
+    /// Asserts that writing and reading the game again keeps the moves and produces the same PGN.
+    fn assert_round_trip(game: &PgnGame) {
+        let pgn = game.to_pgn();
+        let parsed_game = PgnGame::parse(pgn.as_str()).unwrap();
+        assert_eq!(game.start, parsed_game.start);
+        assert_eq!(game.moves, parsed_game.moves);
+        assert_eq!(pgn, parsed_game.to_pgn());
+    }
+
+    const SCHOLARS_MATE: &str = r#"[Event "Casual Game"]
+[Site "Berlin"]
+[Date "2024.01.01"]
+[Round "1"]
+[White "Alice"]
+[Black "Bob"]
+[Result "1-0"]
+
+1. e4 e5 2. Bc4 Nc6 3. Qh5 Nf6?? 4. Qxf7# 1-0
+"#;
+
+    #[test]
+    fn parse_reads_tags_and_moves() {
+        let game = PgnGame::parse(SCHOLARS_MATE).unwrap();
+        assert_eq!(7, game.tags.len());
+        assert_eq!(Some("Casual Game"), game.get_tag("Event"));
+        assert_eq!(Some("Bob"), game.get_tag("Black"));
+        assert_eq!(None, game.get_tag("FEN"));
+        assert_eq!("1-0", game.result());
+        assert_eq!(7, game.moves.len());
+        assert_eq!(square::F7, game.moves[6].ply.target);
+        assert_eq!(Some(Piece::Pawn), game.moves[6].ply.captured_piece);
+        assert_eq!(vec![4], game.moves[5].nags);
+    }
+
+    #[test]
+    fn boards_replays_the_mainline() {
+        let game = PgnGame::parse(SCHOLARS_MATE).unwrap();
+        let boards = game.boards();
+        assert_eq!(8, boards.len());
+        assert_eq!(Board::from_fen(START_FEN).unwrap(), boards[0]);
+        assert_eq!("r1bqkb1r/pppp1Qpp/2n2n2/4p3/2B1P3/8/PPPP1PPP/RNB1K1NR b KQkq - 0 4", boards[7].to_fen());
+    }
+
+    #[test]
+    fn parse_reads_comments_nags_and_variations() {
+        let pgn = "{Opening} 1. e4 {King's pawn} $1 (1. d4 d5 (1... Nf6 2. c4 {Indian}) 2. c4) 1... c5 ; Sicilian\n2. Nf3 *";
+        let game = PgnGame::parse(pgn).unwrap();
+        assert_eq!(3, game.moves.len());
+        assert_eq!("*", game.result());
+
+        let first_move = &game.moves[0];
+        assert_eq!(Some(String::from("Opening")), first_move.comment_before);
+        assert_eq!(Some(String::from("King's pawn")), first_move.comment);
+        assert_eq!(vec![1], first_move.nags);
+        assert_eq!(1, first_move.variations.len());
+
+        // the variation replaces 1. e4 and contains a nested variation replacing 1... d5
+        let variation = &first_move.variations[0];
+        assert_eq!(3, variation.len());
+        assert_eq!(square::D4, variation[0].ply.target);
+        assert_eq!(1, variation[1].variations.len());
+        let nested_variation = &variation[1].variations[0];
+        assert_eq!(square::F6, nested_variation[0].ply.target);
+        assert_eq!(Some(String::from("Indian")), nested_variation[1].comment);
+
+        // line comments are kept as well
+        assert_eq!(Some(String::from("Sicilian")), game.moves[1].comment);
+    }
+
+    #[test]
+    fn parse_handles_sloppy_movetext() {
+        let game = PgnGame::parse("1.e4 e5 2.Nf3 Nc6 3.Bb5 a6 4.Ba4 Nf6 5.0-0 Be7 6.Re1! b5 7.Bb3 d6 8.c3 0-0 9.h3 1/2-1/2").unwrap();
+        assert_eq!(17, game.moves.len());
+        assert_eq!(vec![1], game.moves[10].nags);
+        assert_eq!(square::H1, game.moves[8].ply.target);
+        assert_eq!(Some("1/2-1/2"), game.get_tag("Result"));
+    }
+
+    #[test]
+    fn parse_sets_up_position_from_fen_tag() {
+        let pgn = "[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/8/R3K3 b Q - 0 40\"]\n\n40... Kd7 41. O-O-O+ *";
+        let game = PgnGame::parse(pgn).unwrap();
+        assert_eq!(Board::from_fen("4k3/8/8/8/8/8/8/R3K3 b Q - 0 40").unwrap(), game.start);
+        assert_eq!(square::A1, game.moves[1].ply.target);
+        assert_eq!("8/3k4/8/8/8/8/8/2KR4 b - - 2 41", game.boards()[2].to_fen());
+    }
+
+    #[test]
+    fn parse_reports_errors() {
+        assert!(PgnGame::parse("1. e4 e4 *").is_err());
+        assert!(PgnGame::parse("1. e4 (1. d4 *").is_err());
+        assert!(PgnGame::parse("1. e4 ) *").is_err());
+        assert!(PgnGame::parse("[Event \"unterminated\"\n1. e4 *").is_err());
+        assert!(PgnGame::parse("[FEN \"invalid\"]\n1. e4 *").is_err());
+    }
+
+    #[test]
+    fn reader_streams_games() {
+        let pgn = format!("{SCHOLARS_MATE}\n[Event \"Second\"]\n\n1. d4 {{a comment\n[with a bracket]}} d5 *\n\n% escaped line\n[Event \"Third\"]\n1. e4 e4 *\n\n[Event \"Fourth\"]\n1. c4 *\n");
+        let mut reader = PgnReader::new(Cursor::new(pgn));
+
+        let game = reader.next().unwrap().unwrap();
+        assert_eq!(Some("Casual Game"), game.get_tag("Event"));
+
+        let game = reader.next().unwrap().unwrap();
+        assert_eq!(Some("Second"), game.get_tag("Event"));
+        assert_eq!(Some(String::from("a comment [with a bracket]")), game.moves[0].comment);
+        assert_eq!(2, game.moves.len());
+
+        let error = reader.next().unwrap().unwrap_err();
+        assert!(error.starts_with("game starting at line 17"), "{error}");
+
+        let game = reader.next().unwrap().unwrap();
+        assert_eq!(Some("Fourth"), game.get_tag("Event"));
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn reader_separates_games_without_movetext() {
+        let pgn = "[Event \"First\"]\n[Site \"Here\"]\n\n[Event \"Second\"]\n\n[Event \"Third\"]\n1. e4 *\n1. d4 1-0\n";
+        let mut reader = PgnReader::new(Cursor::new(pgn));
+
+        let game = reader.next().unwrap().unwrap();
+        assert_eq!(Some("First"), game.get_tag("Event"));
+        assert_eq!(Some("Here"), game.get_tag("Site"));
+        assert!(game.moves.is_empty());
+
+        let game = reader.next().unwrap().unwrap();
+        assert_eq!(Some("Second"), game.get_tag("Event"));
+        assert!(game.moves.is_empty());
+
+        let game = reader.next().unwrap().unwrap();
+        assert_eq!(Some("Third"), game.get_tag("Event"));
+        assert_eq!(1, game.moves.len());
+
+        let game = reader.next().unwrap().unwrap();
+        assert_eq!(None, game.get_tag("Event"));
+        assert_eq!("1-0", game.result());
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn to_pgn_writes_seven_tag_roster_first() {
+        let mut game = PgnGame::parse("[Black \"Bob\"]\n[Annotator \"Carol\"]\n\n1. e4 1-0").unwrap();
+        game.set_tag("White", "Alice \"the Great\"");
+        let expected = r#"[Event "?"]
+[Site "?"]
+[Date "????.??.??"]
+[Round "?"]
+[White "Alice \"the Great\""]
+[Black "Bob"]
+[Result "1-0"]
+[Annotator "Carol"]
+
+1. e4 1-0
+"#;
+        assert_eq!(expected, game.to_pgn());
+        assert_round_trip(&game);
+    }
+
+    #[test]
+    fn to_pgn_writes_annotations_and_variations() {
+        let pgn = "{Opening} 1. e4 $1 {King's pawn} (1. d4 d5 (1... Nf6 2. c4 {Indian}) 2. c4) 1... c5 2. Nf3 *";
+        let game = PgnGame::parse(pgn).unwrap();
+        let pgn = game.to_pgn();
+        let movetext = pgn.split_once("\n\n").unwrap().1.trim_end().replace('\n', " ");
+        assert_eq!("{Opening} 1. e4 $1 {King's pawn} (1. d4 d5 (1... Nf6 2. c4 {Indian}) 2. c4) 1... c5 2. Nf3 *", movetext);
+        assert_round_trip(&game);
+    }
+
+    #[test]
+    fn to_pgn_wraps_long_lines() {
+        let mut game = PgnGame::default();
+        let mut board = game.start;
+        for san in ["Nf3", "Nf6", "Ng1", "Ng8"].iter().cycle().take(60) {
+            let ply = Ply::from_san(san, &board.position).unwrap();
+            game.moves.push(PgnMove::new(ply));
+            board = board.make_move(ply);
+        }
+        game.set_tag("Result", "1/2-1/2");
+
+        let pgn = game.to_pgn();
+        assert!(pgn.lines().all(|line| line.len() <= 80));
+        assert!(pgn.lines().filter(|line| !line.starts_with('[') && !line.is_empty()).count() > 1);
+        assert_round_trip(&game);
+    }
+
+    #[test]
+    fn new_sets_fen_tag_for_custom_positions() {
+        let game = PgnGame::new(Board::from_fen(START_FEN).unwrap());
+        assert!(game.tags.is_empty());
+
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let game = PgnGame::new(board);
+        assert_eq!(Some("1"), game.get_tag("SetUp"));
+        assert_eq!(Some("4k3/8/8/8/8/8/8/4K3 w - - 0 1"), game.get_tag("FEN"));
+        assert_round_trip(&game);
+    }
+}
@@ -1,10 +1,17 @@
-use std::sync::mpsc::{Receiver, Sender};
-use std::time::{Duration, Instant};
-use arrayvec::ArrayVec;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::thread::ScopedJoinHandle;
+use std::time::Duration;
+use crate::bench;
+use crate::bench::BenchResult;
 use crate::board::Board;
 use crate::board::piece::NUM_PIECES;
 use crate::board::position::Position;
 use crate::board::square::NUM_SQUARES;
+use crate::engine::{Engine, EngineConfig, SearchLimits};
 use crate::ladybug::Message;
 use crate::move_gen;
 use crate::move_gen::ply::Ply;
@@ -17,30 +24,110 @@ mod quiescence_search;
 /// This number shouldn't ever be reached.
 pub const MAX_PLY: usize = 100;
 
+/// How long a running search, perft or bench waits for a command before checking whether it is finished.
+const COMMAND_TIMEOUT: Duration = Duration::from_millis(50);
+
 /// Encodes the commands the search can receive from Ladybug.
 pub enum SearchCommand {
     /// Search the given position for the given amount of milliseconds.
-    SearchTime(Board, ArrayVec<u64, 1000>, u64),
+    SearchTime(Board, Vec<u64>, u64),
     /// Search the given position until the given depth is reached.
-    SearchDepth(Board, ArrayVec<u64, 1000>, u64),
+    SearchDepth(Board, Vec<u64>, u64),
+    /// Search the given position until the given number of nodes is reached.
+    SearchNodes(Board, Vec<u64>, u64),
+    /// Search the given position until one of the given limits is reached.
+    SearchLimits(Board, Vec<u64>, SearchLimits),
     /// Perform a perft for the given position up to the specified depth.
     Perft(Position, u64),
-    /// Stop the search immediately.
+    /// Search the bench positions to the given depth.
+    Bench(u64),
+    /// Stop the running search, perft or bench immediately. A stopped search still sends the best move found so far.
     Stop,
 }
 
+/// Encodes the events the search sends to Ladybug.
+///
+/// The events only contain the results, formatting them (e.g. as UCI output) is left to the front end.
+/// Moves are given together with the position they are played in, so that they can be converted to any notation.
+#[derive(Clone, PartialEq, Debug)]
+pub enum SearchEvent {
+    /// The position to search has no legal moves, so no search is started.
+    NoLegalMoves,
+    /// The position to search is a draw by insufficient material. It is searched anyway, since a move is expected.
+    InsufficientMaterial,
+    /// An iteration of the iterative deepening has been completed.
+    /// The number of nodes, the nodes per second and the time only cover this iteration.
+    IterationCompleted {
+        /// The root position of the search.
+        position: Position,
+        /// The depth of the iteration.
+        depth: u64,
+        /// The maximum depth reached in the quiescence search.
+        seldepth: u64,
+        /// The score of the best move in centipawns.
+        score: i32,
+        /// The number of nodes searched.
+        nodes: u128,
+        /// The number of nodes searched per second.
+        nps: u128,
+        /// The time spent on the iteration.
+        time: Duration,
+        /// The principal variation.
+        pv: Vec<Ply>,
+    },
+    /// The search is finished.
+    BestMove {
+        /// The root position of the search.
+        position: Position,
+        /// The best move.
+        best_move: Ply,
+        /// The expected reply to the best move, if any.
+        ponder_move: Option<Ply>,
+    },
+    /// The number of leaf nodes below a root move of a perft.
+    PerftDivide {
+        /// The root position of the perft.
+        position: Position,
+        /// The root move.
+        ply: Ply,
+        /// The number of leaf nodes below the move.
+        nodes: u64,
+    },
+    /// A perft is finished.
+    PerftDone {
+        /// The total number of leaf nodes.
+        nodes: u64,
+        /// The time the perft took.
+        time: Duration,
+    },
+    /// A perft was aborted with `SearchCommand::Stop`.
+    PerftStopped,
+    /// A bench position has been searched.
+    BenchPosition {
+        /// The index of the position in `BENCH_POSITIONS`.
+        index: usize,
+        /// The number of nodes searched in the position.
+        nodes: u128,
+    },
+    /// A bench is finished.
+    BenchDone(BenchResult),
+    /// A bench was aborted with `SearchCommand::Stop`.
+    BenchStopped,
+}
+
 /// The search struct is responsible for performing all tasks involving calculation and search.
+/// It receives commands from Ladybug, runs them on an [`Engine`] and sends the results back as [`SearchEvent`]s.
 pub struct Search {
     /// Used to receive search commands from Ladybug.
     command_receiver: Receiver<SearchCommand>,
     /// Used to send search results to Ladybug.
     message_sender: Sender<Message>,
-    /// Used to measure the total expired time across all iterations during search.
-    total_time: Option<Instant>,
-    /// Flag to signal that the search should stop immediately.
-    stop: bool,
-    /// Contains information collected and used during the search.
-    search_info: SearchInfo,
+    /// The engine performing the actual search.
+    engine: Engine,
+    /// The number of threads used for perft.
+    perft_threads: usize,
+    /// Commands that were received during a search, perft or bench, which are handled once it is finished.
+    pending_commands: VecDeque<SearchCommand>,
 }
 
 /// Contains information collected and used during the search.
@@ -58,6 +145,8 @@ pub struct SearchInfo {
     pub history_moves: [[i32; NUM_SQUARES as usize]; NUM_PIECES as usize],
     /// This flag signals whether the search is currently following the pv line from the previous iteration.
     pub follow_pv: bool,
+    /// The maximum ply index reached during the current iteration of the search.
+    pub seldepth: u64,
 }
 
 impl Default for SearchInfo {
@@ -72,6 +161,7 @@ impl Default for SearchInfo {
             killer_moves: [[Ply::default(); MAX_PLY]; 2],
             history_moves: [[0; NUM_SQUARES as usize]; NUM_PIECES as usize],
             follow_pv: true,
+            seldepth: 0,
         }
     }
 }
@@ -82,6 +172,7 @@ impl SearchInfo {
         self.node_count = 0;
         self.pv_length = [0; MAX_PLY];
         self.follow_pv = true;
+        self.seldepth = 0;
     }
 
     /// Clears all search information.
@@ -98,17 +189,20 @@ impl Search {
         Self {
             command_receiver: input_receiver,
             message_sender: output_sender,
-            total_time: None,
-            stop: true,
-            search_info: SearchInfo::default(),
+            engine: Engine::new(EngineConfig::default()),
+            perft_threads: thread::available_parallelism().map_or(1, |num_threads| num_threads.get()),
+            pending_commands: VecDeque::new(),
         }
     }
 
     /// Start accepting search commands from Ladybug.
     pub fn run(&mut self) {
         loop {
-            // blocks until the search receives a command from Ladybug
-            let input = self.command_receiver.recv();
+            // commands received during a search, perft or bench are handled first, otherwise block until the search receives a command from Ladybug
+            let input = match self.pending_commands.pop_front() {
+                Some(command) => Ok(command),
+                None => self.command_receiver.recv(),
+            };
 
             // if the main thread closes the connection, the search thread must not continue running
             if input.is_err() {
@@ -120,57 +214,167 @@ impl Search {
             
             match command { 
                 SearchCommand::Perft(position, depth) => self.handle_perft(position, depth),
-                SearchCommand::SearchTime(board, board_history, time) => self.handle_search(board, None, Some(time), board_history),
-                SearchCommand::SearchDepth(board, board_history, depth) => self.handle_search(board, Some(depth), None, board_history),
+                SearchCommand::SearchTime(board, board_history, time) => self.handle_search(board, SearchLimits { move_time: Some(Duration::from_millis(time)), ..Default::default() }, board_history),
+                SearchCommand::SearchDepth(board, board_history, depth) => self.handle_search(board, SearchLimits { depth: Some(depth), ..Default::default() }, board_history),
+                SearchCommand::SearchNodes(board, board_history, nodes) => self.handle_search(board, SearchLimits { nodes: Some(nodes), ..Default::default() }, board_history),
+                SearchCommand::SearchLimits(board, board_history, limits) => self.handle_search(board, limits, board_history),
+                SearchCommand::Bench(depth) => self.handle_bench(depth),
                 _other => {},
             }
         }
     }
 
-    /// Sends the given String to the main thread.
-    fn send_output(&self, output: String) {
-        let send_result = self.message_sender.send(Message::SearchMessage(output));
-
-        // if the main thread closes the connection, the search thread must not continue running
-        if send_result.is_err() {
-            panic!("The main thread has unexpectedly closed the channel connection.")
-        }
+    /// Sends the given event to the main thread.
+    fn send_event(&self, event: SearchEvent) {
+        send_event(&self.message_sender, event);
     }
 
     /// Handles the various "Search" commands.
-    fn handle_search(&mut self, board: Board, depth_limit: Option<u64>, time_limit: Option<u64>, board_history: ArrayVec<u64, 1000>) {
+    /// The engine searches on its own thread, so that the search can be aborted with `SearchCommand::Stop`.
+    fn handle_search(&mut self, board: Board, limits: SearchLimits, board_history: Vec<u64>) {
         let move_list = move_gen::generate_moves(board.position);
         if move_list.is_empty() {
-            self.send_output(String::from("info string no legal moves"));
+            self.send_event(SearchEvent::NoLegalMoves);
             return;
         }
 
-        // check if a depth value was provided, if not, use max depth
-        let depth_limit = depth_limit.unwrap_or(MAX_PLY as u64);
+        // report dead draws, but search anyway, since the GUI expects a move
+        if board.position.is_insufficient_material() {
+            self.send_event(SearchEvent::InsufficientMaterial);
+        }
 
-        // check if a time limit was provided
-        let time_limit = match time_limit {
-            // if no time limit ws provided, use a default limit of 72 hours
-            None => Duration::from_secs(72 * 60 * 60),
-            Some(time) => Duration::from_millis(time),
-        };
+        // the fields are borrowed separately, since the engine is used by the search thread while commands are received
+        let Search { command_receiver, message_sender, engine, pending_commands, .. } = self;
+        engine.set_position(board, board_history);
+        let cancel_handle = engine.cancel_handle();
 
-        self.iterative_search(board, depth_limit, time_limit, board_history);
+        // send the information for each iteration, with the nodes and time of this iteration only
+        let mut previous_nodes: u128 = 0;
+        let mut previous_time = Duration::ZERO;
+        let result = thread::scope(|scope| {
+            let search_thread = scope.spawn(|| engine.search_with_progress(limits, |progress| {
+                let nodes = progress.nodes - previous_nodes;
+                let time = progress.time - previous_time;
+                previous_nodes = progress.nodes;
+                previous_time = progress.time;
+
+                // calculate nodes per second
+                let nps = match nodes.checked_div(time.as_millis()) {
+                    Some(nodes_per_millisecond) => nodes_per_millisecond * 1000,
+                    None => nodes,
+                };
+
+                send_event(message_sender, SearchEvent::IterationCompleted {
+                    position: board.position,
+                    depth: progress.depth,
+                    seldepth: progress.seldepth,
+                    score: progress.score,
+                    nodes,
+                    nps,
+                    time,
+                    pv: progress.pv.clone(),
+                });
+            }));
+            receive_commands_until_finished(command_receiver, pending_commands, &search_thread, || cancel_handle.cancel());
+            search_thread.join().unwrap()
+        });
+
+        // send the best move to the main thread
+        if let Some(best_move) = result.best_move {
+            self.send_event(SearchEvent::BestMove { position: board.position, best_move, ponder_move: result.ponder_move });
+        }
     }
-    
+
     /// Handles the "Perft" command.
-    fn handle_perft(&self, position: Position, depth: u64) {
+    fn handle_perft(&mut self, position: Position, depth: u64) {
         self.perft(position, depth);
     }
+
+    /// Handles the "Bench" command.
+    /// The bench runs on its own thread, so that it can be aborted with `SearchCommand::Stop` like a perft.
+    fn handle_bench(&mut self, depth: u64) {
+        let stop = AtomicBool::new(false);
+        let running_engine = Mutex::new(None);
+        // the sender is cloned, since the bench thread sends the results of each position while commands are received
+        let message_sender = self.message_sender.clone();
+        let result = thread::scope(|scope| {
+            let bench_thread = scope.spawn(|| bench::run_until_stopped(depth, &stop, &running_engine, |index, nodes| {
+                send_event(&message_sender, SearchEvent::BenchPosition { index, nodes });
+            }));
+            self.receive_commands_until_finished(&bench_thread, || {
+                stop.store(true, Ordering::Relaxed);
+                if let Some(cancel_handle) = running_engine.lock().unwrap().as_ref() {
+                    cancel_handle.cancel();
+                }
+            });
+            bench_thread.join().unwrap()
+        });
+
+        match result {
+            Some(result) => self.send_event(SearchEvent::BenchDone(result)),
+            None => self.send_event(SearchEvent::BenchStopped),
+        }
+    }
+
+    /// Keeps receiving commands until the given thread is finished, see [`receive_commands_until_finished`].
+    fn receive_commands_until_finished<T>(&mut self, thread: &ScopedJoinHandle<T>, on_stop: impl FnOnce()) {
+        receive_commands_until_finished(&self.command_receiver, &mut self.pending_commands, thread, on_stop);
+    }
+}
+
+/// Keeps receiving commands until the given thread is finished. Other commands are kept in `pending_commands` and handled afterwards.
+///
+/// Once a stop command is received, or Ladybug closed the connection, `on_stop` is called once. It must also stop work
+/// which is only about to start, e.g. by setting a flag that is checked before the work starts.
+fn receive_commands_until_finished<T>(command_receiver: &Receiver<SearchCommand>, pending_commands: &mut VecDeque<SearchCommand>, thread: &ScopedJoinHandle<T>, on_stop: impl FnOnce()) {
+    let mut on_stop = Some(on_stop);
+    while !thread.is_finished() {
+        match command_receiver.recv_timeout(COMMAND_TIMEOUT) {
+            Ok(SearchCommand::Stop) => {
+                if let Some(on_stop) = on_stop.take() {
+                    on_stop();
+                }
+            }
+            Ok(command) => pending_commands.push_back(command),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => {
+                // no further commands can arrive, so the thread is stopped and the caller waits for it to finish
+                if let Some(on_stop) = on_stop.take() {
+                    on_stop();
+                }
+                return;
+            }
+        }
+    }
+}
+
+/// Sends the given event to the main thread using the given sender.
+fn send_event(message_sender: &Sender<Message>, event: SearchEvent) {
+    let send_result = message_sender.send(Message::SearchMessage(Box::new(event)));
+
+    // if the main thread closes the connection, the search thread must not continue running
+    if send_result.is_err() {
+        panic!("The main thread has unexpectedly closed the channel connection.")
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    #![allow(clippy::field_reassign_with_default)]
+
+    use std::sync::mpsc;
+    use std::sync::mpsc::{Receiver, Sender};
+    use std::thread;
+    use std::time::Duration;
+    use crate::board::Board;
+    use crate::board::fen::START_FEN;
     use crate::board::piece::{NUM_PIECES, Piece};
     use crate::board::square;
     use crate::board::square::NUM_SQUARES;
     use crate::move_gen::ply::Ply;
-    use crate::search::{MAX_PLY, SearchInfo};
+    use crate::engine::SearchLimits;
+    use crate::ladybug::Message;
+    use crate::search::{MAX_PLY, Search, SearchCommand, SearchEvent, SearchInfo};
 
     #[test]
     fn test_default() {
@@ -181,6 +385,7 @@ mod tests {
         assert_eq!([[Ply::default(); MAX_PLY]; 2], search_info.killer_moves);
         assert_eq!([[0; NUM_SQUARES as usize]; NUM_PIECES as usize], search_info.history_moves);
         assert!(search_info.follow_pv);
+        assert_eq!(0, search_info.seldepth);
     }
 
     #[test]
@@ -206,6 +411,7 @@ mod tests {
         search_info.killer_moves[0][5] = killer_move;
         search_info.history_moves[2][13] = 40;
         search_info.follow_pv = false;
+        search_info.seldepth = 12;
 
         search_info.clear_iteration();
 
@@ -213,6 +419,7 @@ mod tests {
         assert_eq!(0, search_info.node_count);
         assert_eq!([0; MAX_PLY], search_info.pv_length);
         assert!(search_info.follow_pv);
+        assert_eq!(0, search_info.seldepth);
 
         // this should stay the same
         assert_eq!(pv_ply, search_info.pv_table[4][4]);
@@ -236,4 +443,95 @@ mod tests {
         assert_eq!([[Ply::default(); MAX_PLY]; 2], search_info.killer_moves);
         assert_eq!([[0; NUM_SQUARES as usize]; NUM_PIECES as usize], search_info.history_moves);
     }
+
+    #[test]
+    fn test_search_sends_events() {
+        let (search_command_sender, search_command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
+        let (message_sender, message_receiver): (Sender<Message>, Receiver<Message>) = mpsc::channel();
+        let mut search = Search::new(search_command_receiver, message_sender);
+
+        // the search keeps listening for commands while searching, so the connection is closed once the search is finished
+        let board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        search_command_sender.send(SearchCommand::SearchDepth(board, Vec::new(), 2)).unwrap();
+        let search_thread = thread::spawn(move || search.run());
+        let mut events: Vec<SearchEvent> = Vec::new();
+        while !matches!(events.last(), Some(SearchEvent::BestMove { .. })) {
+            events.push(receive_event(&message_receiver));
+        }
+        drop(search_command_sender);
+        search_thread.join().unwrap();
+
+        let events: Vec<SearchEvent> = events.into_iter().chain(message_receiver.try_iter().map(|message| match message {
+            Message::SearchMessage(event) => *event,
+            Message::ConsoleMessage(_) => panic!("unexpected console message"),
+        })).collect();
+        assert_eq!(3, events.len());
+        for (event, expected_depth) in events.iter().take(2).zip(1..) {
+            match event {
+                SearchEvent::IterationCompleted { position, depth, seldepth, pv, .. } => {
+                    assert_eq!(board.position, *position);
+                    assert_eq!(expected_depth, *depth);
+                    assert!(*seldepth >= *depth);
+                    assert!(!pv.is_empty());
+                }
+                other => panic!("unexpected event {other:?}"),
+            }
+        }
+        let best_move = Ply::from_string("a1a8", board.position).unwrap();
+        assert!(matches!(events[2], SearchEvent::BestMove { best_move: ply, .. } if ply == best_move));
+    }
+
+    #[test]
+    fn test_search_sends_no_legal_moves() {
+        let (search_command_sender, search_command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
+        let (message_sender, message_receiver): (Sender<Message>, Receiver<Message>) = mpsc::channel();
+        let mut search = Search::new(search_command_receiver, message_sender);
+
+        let board = Board::from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1").unwrap();
+        search_command_sender.send(SearchCommand::SearchDepth(board, Vec::new(), 2)).unwrap();
+        drop(search_command_sender);
+        search.run();
+
+        match message_receiver.try_recv().unwrap() {
+            Message::SearchMessage(event) => assert_eq!(SearchEvent::NoLegalMoves, *event),
+            Message::ConsoleMessage(_) => panic!("unexpected console message"),
+        }
+        assert!(message_receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_search_can_be_stopped() {
+        let (search_command_sender, search_command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
+        let (message_sender, message_receiver): (Sender<Message>, Receiver<Message>) = mpsc::channel();
+        let mut search = Search::new(search_command_receiver, message_sender);
+
+        // without limits, the search only finishes by being stopped, and commands other than stop are kept until then
+        let board = Board::from_fen(START_FEN).unwrap();
+        search_command_sender.send(SearchCommand::SearchLimits(board, Vec::new(), SearchLimits::default())).unwrap();
+        search_command_sender.send(SearchCommand::SearchDepth(board, Vec::new(), 1)).unwrap();
+        let search_thread = thread::spawn(move || search.run());
+        assert!(matches!(receive_event(&message_receiver), SearchEvent::IterationCompleted { depth: 1, .. }));
+        search_command_sender.send(SearchCommand::Stop).unwrap();
+
+        // the stopped search sends its best move before the pending search starts
+        loop {
+            match receive_event(&message_receiver) {
+                SearchEvent::IterationCompleted { .. } => {}
+                SearchEvent::BestMove { .. } => break,
+                other => panic!("unexpected event {other:?}"),
+            }
+        }
+        assert!(matches!(receive_event(&message_receiver), SearchEvent::IterationCompleted { depth: 1, .. }));
+        assert!(matches!(receive_event(&message_receiver), SearchEvent::BestMove { .. }));
+        drop(search_command_sender);
+        search_thread.join().unwrap();
+    }
+
+    /// Receives the next event from the search, failing if there is none within a few seconds.
+    fn receive_event(message_receiver: &Receiver<Message>) -> SearchEvent {
+        match message_receiver.recv_timeout(Duration::from_secs(10)).unwrap() {
+            Message::SearchMessage(event) => *event,
+            Message::ConsoleMessage(_) => panic!("unexpected console message"),
+        }
+    }
 }
@@ -1,89 +1,99 @@
 #![allow(clippy::too_many_arguments)]
 
-use std::time::Duration;
-use arrayvec::ArrayVec;
+use std::time::{Duration, Instant};
 use crate::{evaluation, move_gen};
 use crate::board::Board;
+use crate::engine::{Engine, SearchResult};
 use crate::evaluation::{NEGATIVE_INFINITY, POSITIVE_INFINITY};
-use crate::search::{MAX_PLY, Search};
+use crate::move_gen::ply::Ply;
+use crate::search::MAX_PLY;
+
+impl Engine {
+    /// Search the current position with iterative deepening.
+    ///
+    /// After every completed iteration, the given function is called with the intermediate result.
+    pub(crate) fn iterative_search(&mut self, max_depth: u64, time_limit: Duration, on_iteration: &mut dyn FnMut(&SearchResult)) -> SearchResult {
+        let mut board = self.board;
+        let mut board_history = self.board_history.clone();
 
-impl Search {
-    /// Search the given position with iterative deepening.
-    pub fn iterative_search(&mut self, board: Board, max_depth: u64, time_limit: Duration, mut board_history: ArrayVec<u64, 1000>) {
         // reset the stop flag to allow searching
         self.stop = false;
 
         // start the total time
-        self.total_time = Some(std::time::Instant::now());
+        let start_time = Instant::now();
+        self.total_time = Some(start_time);
+        self.total_node_count = 0;
 
         // initialize the best move to the first legal one, in case the search stops prematurely
-        let mut best_move = move_gen::generate_moves(board.position).get(0);
+        let move_list = move_gen::generate_moves(board.position);
+        let mut result = SearchResult {
+            best_move: match move_list.is_empty() {
+                true => None,
+                false => Some(move_list.get(0)),
+            },
+            ..Default::default()
+        };
 
         // start at depth 1 and increment the depth until the max depth is reached or the time runs out
         for depth in 1..=max_depth {
-            // set the start time for this iteration
-            let iteration_time = std::time::Instant::now();
-            
+            // without legal moves, there is nothing to search
+            if result.best_move.is_none() {
+                break;
+            }
+
             // search to the current depth and save the score
-            let score = self.negamax(board, depth, 0, NEGATIVE_INFINITY, POSITIVE_INFINITY, time_limit, &mut board_history);
+            let score = self.negamax(&mut board, depth, 0, NEGATIVE_INFINITY, POSITIVE_INFINITY, time_limit, &mut board_history);
 
             if self.stop {
                 // if the stop flag is set, break out of iterative deepening immediately
                 break;
             }
 
-            // calculate nodes per second
-            let mut nps: u128 = 0;
-            let iteration_time_elapsed = iteration_time.elapsed().as_millis();
-            if iteration_time_elapsed > 0 {
-                nps = (self.search_info.node_count / iteration_time_elapsed) * 1000;
-            }
-            else {
-                nps = self.search_info.node_count;
-            }
-
-            // send the information for the current iteration
-            let mut output = format!("info depth {depth} score cp {score} nodes {nodes} time {iteration_time_elapsed} nps {nps} pv", nodes = self.search_info.node_count);
-            for ply_num in 0..self.search_info.pv_length[0] {
-                output += format!(" {}", self.search_info.pv_table[0][ply_num as usize]).as_str();
-            }
-            self.send_output(output);
-
-            // set the best move to the result of this iteration
-            best_move = self.search_info.pv_table[0][0];
+            // set the result to the result of this iteration
+            self.total_node_count += self.search_info.node_count;
+            let pv: Vec<Ply> = self.search_info.pv_table[0][..self.search_info.pv_length[0] as usize].to_vec();
+            result = SearchResult {
+                best_move: Some(self.search_info.pv_table[0][0]),
+                ponder_move: pv.get(1).copied(),
+                score,
+                pv,
+                depth,
+                seldepth: self.search_info.seldepth,
+                nodes: self.total_node_count,
+                time: start_time.elapsed(),
+            };
+            on_iteration(&result);
 
             // clear the search info for this iteration
             self.search_info.clear_iteration();
         }
 
-        // send the best move to the main thread
-        self.send_output(format!("bestmove {}", best_move));
-
         // reset the total time
         self.total_time = None;
 
         // clear all search info
         self.search_info.clear_all();
+
+        result.time = start_time.elapsed();
+        result
     }
 
     /// A basic implementation of the [negamax](https://www.chessprogramming.org/Negamax) algorithm with alpha beta pruning.
     ///
     /// Instead of implementing two routines for the maximizing and minimizing players, this method
     /// negates the scores for each recursive call, making minimax easier to implement.
-    pub fn negamax(&mut self, board: Board, depth: u64, ply_index: u64, mut alpha: i32, beta: i32, time_limit: Duration, board_history: &mut ArrayVec<u64, 1000>) -> i32 {
+    pub fn negamax(&mut self, board: &mut Board, depth: u64, ply_index: u64, mut alpha: i32, beta: i32, time_limit: Duration, board_history: &mut Vec<u64>) -> i32 {
         // check if the max ply number is reached
         if ply_index as usize >= MAX_PLY {
             // the maximum number of plies is reached - return static evaluation to avoid overflows
-            return evaluation::evaluate(board.position);
+            return self.evaluate(board.position);
         }
 
-        // check if the time limit is reached
-        if let Some(instant) = self.total_time {
-            if instant.elapsed() > time_limit {
-                // the time limit is reached - break out of recursion immediately
-                self.stop = true;
-                return 0;
-            }
+        // check if the time or node limit is reached or the search was cancelled
+        if self.is_limit_reached(time_limit) {
+            // break out of recursion immediately
+            self.stop = true;
+            return 0;
         }
 
         // set the pv length
@@ -108,14 +118,15 @@ impl Search {
             };
         }
         
-        // check if the position is a draw by 3-fold repetition or the 50 move rule
-         if board.is_draw(board_history) {
-             return 0;
-         }
+        // check if the position is a draw by 3-fold repetition, the 50 move rule or insufficient material
+        // (not at the root, since a move has to be returned even in drawn positions)
+        if ply_index > 0 && board.is_draw(board_history) {
+            return 0;
+        }
 
         // if depth 0 is reached, start the quiescence search
         if depth == 0 {
-            return self.quiescence_search(board.position, ply_index, alpha, beta, time_limit);
+            return self.quiescence_search(&mut board.position, ply_index, alpha, beta, time_limit);
         }
 
         // iterate over all possible moves and call negamax recursively for the arising positions
@@ -123,16 +134,17 @@ impl Search {
             let ply = move_list.get(i);
             
             // make the move
-            let new_board = board.make_move(ply);
+            let undo = board.make_move_in_place(ply);
             
             // push the new position's hash to the board history
-            board_history.push(new_board.position.hash);
+            board_history.push(board.position.hash);
             
             // the score of the new position
-            let score = -self.negamax(new_board, depth - 1, ply_index + 1, -beta, -alpha, time_limit, board_history);
+            let score = -self.negamax(board, depth - 1, ply_index + 1, -beta, -alpha, time_limit, board_history);
 
-            // pop the new position's hash from the board history
+            // pop the new position's hash from the board history and unmake the move
             board_history.pop();
+            board.unmake_move(undo);
 
             // fail-hard beta cutoff
             if score >= beta {
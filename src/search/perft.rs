@@ -1,56 +1,359 @@
+use std::fmt::{Display, Formatter};
+use std::ops::AddAssign;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::thread;
+use std::time::Instant;
+use crate::board::castling_rights::get_castling_targets;
+use crate::board::piece::Piece;
 use crate::board::position::Position;
 use crate::move_gen;
-use crate::search::Search;
+use crate::move_gen::ply::Ply;
+use crate::search::{Search, SearchEvent};
+
+/// The total size of the perft hash tables used by `Search::perft` in megabytes.
+pub const PERFT_TABLE_SIZE_MB: usize = 16;
+
+/// The number of plies after which `Search::perft` splits the tree into work items for its threads.
+pub const PERFT_SPLIT_DEPTH: u64 = 2;
+
+/// The minimum size of the perft hash table of each thread in megabytes, unless the tables are disabled.
+const MIN_THREAD_TABLE_SIZE_MB: usize = 1;
+
+/// Detailed counters of a perft, matching the tables on the [chessprogramming wiki](https://www.chessprogramming.org/Perft_Results).
+///
+/// All counters except `nodes` refer to the moves leading to the leaf nodes, e.g. `captures` is the number of leaf nodes reached by a capture.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct PerftStats {
+    /// The number of leaf nodes.
+    pub nodes: u64,
+    /// The number of captures, including en passant captures.
+    pub captures: u64,
+    /// The number of en passant captures.
+    pub en_passants: u64,
+    /// The number of castling moves.
+    pub castles: u64,
+    /// The number of promotions.
+    pub promotions: u64,
+    /// The number of moves giving check.
+    pub checks: u64,
+    /// The number of moves giving check only with pieces other than the moved one.
+    pub discovered_checks: u64,
+    /// The number of moves giving check with two pieces.
+    pub double_checks: u64,
+    /// The number of moves giving checkmate.
+    pub checkmates: u64,
+}
+
+/// An entry of the perft hash table.
+#[derive(Copy, Clone, Debug, Default)]
+struct PerftEntry {
+    /// The hash of the position.
+    hash: u64,
+    /// The remaining depth. Entries with a depth of zero are empty, since nodes at depth zero are never stored.
+    depth: u64,
+    /// The number of leaf nodes below the position.
+    nodes: u64,
+}
+
+/// A hash table storing the leaf node counts of positions, keyed by `Position::hash` and the remaining depth.
+///
+/// Transpositions are very common in perft trees, so the table speeds up deep perfts considerably.
+/// A table without entries stores nothing, which disables hashing.
+#[derive(Clone, Debug, Default)]
+pub struct PerftTable {
+    /// The entries of the table. Each position and depth has exactly one slot, which is always replaced.
+    entries: Vec<PerftEntry>,
+}
+
+impl AddAssign for PerftStats {
+    fn add_assign(&mut self, other: Self) {
+        self.nodes += other.nodes;
+        self.captures += other.captures;
+        self.en_passants += other.en_passants;
+        self.castles += other.castles;
+        self.promotions += other.promotions;
+        self.checks += other.checks;
+        self.discovered_checks += other.discovered_checks;
+        self.double_checks += other.double_checks;
+        self.checkmates += other.checkmates;
+    }
+}
+
+/// Prints the counters as a table, one counter per line.
+impl Display for PerftStats {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        writeln!(f, "Nodes:             {}", self.nodes)?;
+        writeln!(f, "Captures:          {}", self.captures)?;
+        writeln!(f, "En passant:        {}", self.en_passants)?;
+        writeln!(f, "Castles:           {}", self.castles)?;
+        writeln!(f, "Promotions:        {}", self.promotions)?;
+        writeln!(f, "Checks:            {}", self.checks)?;
+        writeln!(f, "Discovered checks: {}", self.discovered_checks)?;
+        writeln!(f, "Double checks:     {}", self.double_checks)?;
+        write!(f, "Checkmates:        {}", self.checkmates)
+    }
+}
+
+impl PerftTable {
+    /// Constructs a table with the given size in megabytes.
+    pub fn new(size_mb: usize) -> Self {
+        let num_entries = size_mb * 1024 * 1024 / size_of::<PerftEntry>();
+        Self {
+            entries: vec![PerftEntry::default(); num_entries],
+        }
+    }
+
+    /// Returns the index of the slot for the given hash and depth.
+    fn get_index(&self, hash: u64, depth: u64) -> usize {
+        ((hash ^ depth.wrapping_mul(0x9e37_79b9_7f4a_7c15)) % self.entries.len() as u64) as usize
+    }
+
+    /// Returns the stored number of leaf nodes for the given hash and depth, if any.
+    fn probe(&self, hash: u64, depth: u64) -> Option<u64> {
+        if self.entries.is_empty() {
+            return None;
+        }
+        let entry = self.entries[self.get_index(hash, depth)];
+        (entry.hash == hash && entry.depth == depth).then_some(entry.nodes)
+    }
+
+    /// Stores the number of leaf nodes for the given hash and depth.
+    fn store(&mut self, hash: u64, depth: u64, nodes: u64) {
+        if self.entries.is_empty() {
+            return;
+        }
+        let index = self.get_index(hash, depth);
+        self.entries[index] = PerftEntry { hash, depth, nodes };
+    }
+}
 
 impl Search {
     /// This function performs a [Perft](https://www.chessprogramming.org/Perft) (Performance Test).
     /// A perft counts the number of leaf nodes for a fixed depth, and serves two purposes:
     /// - verify that the move generation is working correctly
     /// - measure the speed of the move generation
-    pub fn perft(&self, position: Position, depth: u64) -> u64 {
+    ///
+    /// The number of leaf nodes is printed for every root move (divide), followed by the total.
+    /// The tree is split across all available threads, leaf nodes are bulk-counted and transpositions are looked up in perft hash tables.
+    ///
+    /// While the perft is running, the search keeps listening for commands, so that it can be aborted with `SearchCommand::Stop`.
+    /// Returns `None` if the perft was aborted.
+    pub fn perft(&mut self, position: Position, depth: u64) -> Option<u64> {
         // used to measure the elapsed time
-        let time = std::time::Instant::now();
+        let time = Instant::now();
 
-        // the number of leaf nodes
-        let mut node_count: u64 = 0;
+        // a perft of depth 0 only counts the position itself, so there are no root moves to divide
+        if depth == 0 {
+            self.send_event(SearchEvent::PerftDone { nodes: 1, time: time.elapsed() });
+            return Some(1);
+        }
 
-        // generate all legal moves for the position
-        let move_list = move_gen::generate_moves(position);
+        let stop = AtomicBool::new(false);
+        let num_threads = self.perft_threads;
+        let (divide, elapsed) = thread::scope(|scope| {
+            // the time is taken in the perft thread, so that it does not include the wait for the next command timeout
+            let perft_thread = scope.spawn(|| (divide_parallel(position, depth, PERFT_SPLIT_DEPTH, num_threads, PERFT_TABLE_SIZE_MB, &stop), time.elapsed()));
 
-        // call the perft_driver function for all legal moves and add the results to node_count
-        for i in 0..move_list.len() {
-            let ply = move_list.get(i);
-            let node_count_inner = self.perft_driver(position.make_move(ply), depth - 1);
-            node_count += node_count_inner;
-            self.send_output(format!("{ply}: {node_count_inner}"));
+            self.receive_commands_until_finished(&perft_thread, || stop.store(true, Ordering::Relaxed));
+            perft_thread.join().unwrap()
+        });
+
+        let Some(divide) = divide else {
+            self.send_event(SearchEvent::PerftStopped);
+            return None;
+        };
+
+        for (ply, node_count) in divide.iter() {
+            self.send_event(SearchEvent::PerftDivide { position, ply: *ply, nodes: *node_count });
         }
 
-        self.send_output(format!("Searched {node_count} nodes in {:?}", time.elapsed()));
+        let node_count = divide.iter().map(|(_, node_count)| node_count).sum();
+        self.send_event(SearchEvent::PerftDone { nodes: node_count, time: elapsed });
 
-        node_count
+        Some(node_count)
     }
+}
 
-    /// This is the recursive perft driver function, which is required by the `perft` function.
-    /// It is used to traverse the tree and count the number of leaf nodes.
-    fn perft_driver(&self, position: Position, depth: u64) -> u64 {
-        // if depth is zero, return a node count of 1 to break out of the recursion
-        if depth == 0 {
-            return 1;
+/// Counts the leaf nodes below each legal move of the given position, which is called a divide.
+/// This helps to find the subtree in which a move generation bug hides, by comparing the numbers with another engine.
+///
+/// The moves are made and unmade in place, so the position is unchanged when the function returns.
+/// At depth 0, no moves are played, so the returned list is empty.
+pub fn divide(position: &mut Position, depth: u64, table: &mut PerftTable) -> Vec<(Ply, u64)> {
+    if depth == 0 {
+        return Vec::new();
+    }
+
+    let move_list = move_gen::generate_moves(*position);
+    let mut divide: Vec<(Ply, u64)> = Vec::new();
+    for i in 0..move_list.len() {
+        let ply = move_list.get(i);
+        let undo = position.make_move_in_place(ply);
+        divide.push((ply, count_nodes(position, depth - 1, table)));
+        position.unmake_move(undo);
+    }
+    divide
+}
+
+/// Counts the leaf nodes below each legal move like `divide`, but distributes the work across the given number of threads.
+///
+/// The tree is split after `split_depth` plies: every position reached in that many moves from the root becomes a work item,
+/// and each thread takes the next unclaimed item until none are left. A split depth of 1 distributes the root moves.
+/// Each thread uses its own hash table with an equal share of `table_size_mb` megabytes, but at least `MIN_THREAD_TABLE_SIZE_MB`
+/// megabytes, so that the tables are not disabled when there are more threads than megabytes. A size of 0 disables the tables.
+///
+/// The given flag is checked at every inner node. If it is set, the perft is aborted and `None` is returned.
+/// Like `divide`, the returned list is empty at depth 0.
+pub fn divide_parallel(position: Position, depth: u64, split_depth: u64, num_threads: usize, table_size_mb: usize, stop: &AtomicBool) -> Option<Vec<(Ply, u64)>> {
+    if depth == 0 {
+        return Some(Vec::new());
+    }
+
+    let move_list = move_gen::generate_moves(position);
+    let root_moves: Vec<Ply> = (0..move_list.len()).map(|i| move_list.get(i)).collect();
+
+    // each work item consists of the index of its root move, its position and its remaining depth
+    let mut work_items: Vec<(usize, Position, u64)> = Vec::new();
+    for (index, ply) in root_moves.iter().enumerate() {
+        collect_work_items(index, position.make_move(*ply), depth - 1, split_depth.max(1) - 1, &mut work_items);
+    }
+
+    let next_item = AtomicUsize::new(0);
+    let node_counts: Vec<AtomicU64> = root_moves.iter().map(|_| AtomicU64::new(0)).collect();
+    let num_threads = num_threads.clamp(1, work_items.len().max(1));
+    thread::scope(|scope| {
+        for _ in 0..num_threads {
+            scope.spawn(|| {
+                let mut table = PerftTable::new(get_thread_table_size_mb(table_size_mb, num_threads));
+                while let Some((index, mut position, depth)) = work_items.get(next_item.fetch_add(1, Ordering::Relaxed)).copied() {
+                    match count_nodes_until_stopped(&mut position, depth, &mut table, stop) {
+                        Some(node_count) => node_counts[index].fetch_add(node_count, Ordering::Relaxed),
+                        None => break,
+                    };
+                }
+            });
         }
+    });
 
-        // the number of leaf nodes
-        let mut node_count: u64 = 0;
+    if stop.load(Ordering::Relaxed) {
+        return None;
+    }
+    Some(root_moves.into_iter().zip(node_counts).map(|(ply, node_count)| (ply, node_count.into_inner())).collect())
+}
 
-        // generate all legal moves for the position
-        let move_list = move_gen::generate_moves(position);
+/// Returns the size of the perft hash table of each thread in megabytes, when the given total size is shared by the given number of threads.
+fn get_thread_table_size_mb(table_size_mb: usize, num_threads: usize) -> usize {
+    if table_size_mb == 0 {
+        return 0;
+    }
+    (table_size_mb / num_threads).max(MIN_THREAD_TABLE_SIZE_MB)
+}
 
-        // call the perft_driver function recursively for all legal moves and add the results to node_count
-        for i in 0..move_list.len() {
-            let ply = move_list.get(i);
-            node_count += self.perft_driver(position.make_move(ply), depth - 1);
+/// Adds the positions reached from the given position in `split_plies` moves to the work items of a parallel perft.
+/// Positions at depth zero are added as they are, since they have no subtree to split.
+fn collect_work_items(index: usize, position: Position, depth: u64, split_plies: u64, work_items: &mut Vec<(usize, Position, u64)>) {
+    if split_plies == 0 || depth == 0 {
+        work_items.push((index, position, depth));
+        return;
+    }
+    let move_list = move_gen::generate_moves(position);
+    for i in 0..move_list.len() {
+        collect_work_items(index, position.make_move(move_list.get(i)), depth - 1, split_plies - 1, work_items);
+    }
+}
+
+/// Counts the leaf nodes for the given depth.
+///
+/// At depth 1, the legal moves are counted without making them (bulk counting).
+/// Node counts of inner positions are stored in the given table and looked up when a position is reached again.
+pub fn count_nodes(position: &mut Position, depth: u64, table: &mut PerftTable) -> u64 {
+    count_nodes_until_stopped(position, depth, table, &AtomicBool::new(false)).unwrap()
+}
+
+/// Counts the leaf nodes like `count_nodes`, but checks the given flag at every inner node.
+/// If the flag is set, the count is aborted and `None` is returned.
+fn count_nodes_until_stopped(position: &mut Position, depth: u64, table: &mut PerftTable, stop: &AtomicBool) -> Option<u64> {
+    if depth == 0 {
+        return Some(1);
+    }
+
+    let move_list = move_gen::generate_moves(*position);
+    if depth == 1 {
+        return Some(move_list.len() as u64);
+    }
+
+    if stop.load(Ordering::Relaxed) {
+        return None;
+    }
+
+    if let Some(node_count) = table.probe(position.hash, depth) {
+        return Some(node_count);
+    }
+
+    let mut node_count: u64 = 0;
+    for i in 0..move_list.len() {
+        let undo = position.make_move_in_place(move_list.get(i));
+        let node_count_inner = count_nodes_until_stopped(position, depth - 1, table, stop);
+        position.unmake_move(undo);
+        node_count += node_count_inner?;
+    }
+
+    table.store(position.hash, depth, node_count);
+    Some(node_count)
+}
+
+/// Counts the leaf nodes for the given depth together with the detailed counters of the moves leading to them.
+/// At depth zero, the position itself is the only leaf node.
+pub fn count_detailed(position: &mut Position, depth: u64) -> PerftStats {
+    if depth == 0 {
+        return PerftStats { nodes: 1, ..PerftStats::default() };
+    }
+
+    let mut stats = PerftStats::default();
+    let move_list = move_gen::generate_moves(*position);
+    for i in 0..move_list.len() {
+        let ply = move_list.get(i);
+        if depth == 1 {
+            stats += get_move_stats(position, ply);
+        } else {
+            let undo = position.make_move_in_place(ply);
+            stats += count_detailed(position, depth - 1);
+            position.unmake_move(undo);
         }
+    }
+    stats
+}
 
-        node_count
+/// Returns the counters of a single leaf node reached by playing the given move in the given position.
+fn get_move_stats(position: &mut Position, ply: Ply) -> PerftStats {
+    let color = position.color_to_move;
+    let is_castling = position.is_castling(ply);
+    let is_en_passant = ply.piece == Piece::Pawn && position.en_passant == Some(ply.target);
+
+    // when castling, the rook is the only piece that can give a direct check
+    let checking_square = if is_castling {
+        get_castling_targets(color, ply.target.get_file().to_index() > ply.source.get_file().to_index()).1
+    } else {
+        ply.target
+    };
+
+    let undo = position.make_move_in_place(ply);
+    let king_square = position.pieces[color.other().to_index() as usize][Piece::King.to_index() as usize].get_active_bits()[0];
+    let checkers = position.get_attackers(king_square, color);
+    let is_check = checkers.value != 0;
+    let is_checkmate = is_check && move_gen::generate_moves(*position).is_empty();
+    position.unmake_move(undo);
+
+    PerftStats {
+        nodes: 1,
+        captures: ply.captured_piece.is_some() as u64,
+        en_passants: is_en_passant as u64,
+        castles: is_castling as u64,
+        promotions: ply.promotion_piece.is_some() as u64,
+        checks: is_check as u64,
+        discovered_checks: (is_check && checkers.value & (1 << checking_square.index) == 0) as u64,
+        double_checks: (checkers.get_num_active_bits() > 1) as u64,
+        checkmates: is_checkmate as u64,
     }
 }
 
@@ -62,24 +365,20 @@ mod tests {
     //! To run all tests, use `cargo test --release -- --include-ignored`.
     //! ----------------------------------------------------------------------------------------------------------------------------------------
 
+    use std::sync::atomic::AtomicBool;
     use std::sync::mpsc;
     use std::sync::mpsc::{Receiver, Sender};
     use std::thread;
     use crate::board::Board;
+    use crate::board::position::Position;
     use crate::ladybug::Message;
-    use crate::lookup::LOOKUP_TABLE;
-    use crate::lookup::lookup_table::LookupTable;
-    use crate::search::{Search, SearchCommand};
+    use crate::move_gen;
+    use crate::search::{Search, SearchCommand, SearchEvent};
+    use crate::search::perft;
+    use crate::search::perft::{PerftStats, PerftTable};
 
-    /// Helper function to initialize the lookup table.
-    fn initialize_lookup_table() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-    }
-    
     /// Creates a search instance and spawns a test thread that will take the search thread's output.
-    /// We don't actually care for the output since we only look at the returned u64 value,
+    /// We don't actually care for the output since we only look at the returned node count,
     /// but since the search tries to send the results to the main thread, the receiver must not be dropped.
     fn setup() -> Search {
         // create search_command_sender and search_command_receiver so that the test thread can send commands to the search thread
@@ -91,12 +390,10 @@ mod tests {
         // initialize the search
         let search = Search::new(search_command_receiver, test_sender);
         
-        // spawn the test thread
+        // spawn the test thread, which also keeps the command sender alive, since the perft stops if the connection is closed
         thread::spawn(move || {
-            loop {
-                let _search_output = test_receiver.recv();
-                search_command_sender.send(SearchCommand::Stop).unwrap();
-            };
+            let _search_command_sender = search_command_sender;
+            while test_receiver.recv().is_ok() {}
         });
         
         search
@@ -108,53 +405,48 @@ mod tests {
     #[test]
     // starting position depth 1
     fn perft_position1_depth1() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
-        assert_eq!(20, search.perft(position, 1));
+        assert_eq!(Some(20), search.perft(position, 1));
     }
 
     #[test]
     // starting position depth 2
     fn perft_position1_depth2() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
-        assert_eq!(400, search.perft(position, 2));
+        assert_eq!(Some(400), search.perft(position, 2));
     }
 
     #[test]
     // starting position depth 3
     fn perft_position1_depth3() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
-        assert_eq!(8_902, search.perft(position, 3));
+        assert_eq!(Some(8_902), search.perft(position, 3));
     }
 
     #[test]
     #[ignore]
     // starting position depth 4
     fn perft_position1_depth4() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
-        assert_eq!(197_281, search.perft(position, 4));
+        assert_eq!(Some(197_281), search.perft(position, 4));
     }
 
     #[test]
     #[ignore]
     // starting position depth 5
     fn perft_position1_depth5() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
-        assert_eq!(4_865_609, search.perft(position, 5));
+        assert_eq!(Some(4_865_609), search.perft(position, 5));
     }
 
     // ----------------------------------------------------------------------------------------------------------------------------------------
@@ -163,53 +455,48 @@ mod tests {
     #[test]
     // position 2 depth 1
     fn perft_position2_depth1() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap().position;
-        assert_eq!(48, search.perft(position, 1));
+        assert_eq!(Some(48), search.perft(position, 1));
     }
 
     #[test]
     // position 2 depth 2
     fn perft_position2_depth2() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap().position;
-        assert_eq!(2039, search.perft(position, 2));
+        assert_eq!(Some(2039), search.perft(position, 2));
     }
 
     #[test]
     // position 2 depth 3
     fn perft_position2_depth3() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap().position;
-        assert_eq!(97_862, search.perft(position, 3));
+        assert_eq!(Some(97_862), search.perft(position, 3));
     }
 
     #[test]
     #[ignore]
     // position 2 depth 4
     fn perft_position2_depth4() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap().position;
-        assert_eq!(4_085_603, search.perft(position, 4));
+        assert_eq!(Some(4_085_603), search.perft(position, 4));
     }
 
     #[test]
     #[ignore]
     // position 2 depth 5
     fn perft_position2_depth5() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap().position;
-        assert_eq!(193_690_690, search.perft(position, 5));
+        assert_eq!(Some(193_690_690), search.perft(position, 5));
     }
 
     // ----------------------------------------------------------------------------------------------------------------------------------------
@@ -218,53 +505,48 @@ mod tests {
     #[test]
     // position 3 depth 1
     fn perft_position3_depth1() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1").unwrap().position;
-        assert_eq!(14, search.perft(position, 1));
+        assert_eq!(Some(14), search.perft(position, 1));
     }
 
     #[test]
     // position 3 depth 2
     fn perft_position3_depth2() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1").unwrap().position;
-        assert_eq!(191, search.perft(position, 2));
+        assert_eq!(Some(191), search.perft(position, 2));
     }
 
     #[test]
     // position 3 depth 3
     fn perft_position3_depth3() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1").unwrap().position;
-        assert_eq!(2_812, search.perft(position, 3));
+        assert_eq!(Some(2_812), search.perft(position, 3));
     }
 
     #[test]
     #[ignore]
     // position 3 depth 4
     fn perft_position3_depth4() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1").unwrap().position;
-        assert_eq!(43_238, search.perft(position, 4));
+        assert_eq!(Some(43_238), search.perft(position, 4));
     }
 
     #[test]
     #[ignore]
     // position 3 depth 5
     fn perft_position3_depth5() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1").unwrap().position;
-        assert_eq!(674_624, search.perft(position, 5));
+        assert_eq!(Some(674_624), search.perft(position, 5));
     }
 
     // ----------------------------------------------------------------------------------------------------------------------------------------
@@ -273,53 +555,48 @@ mod tests {
     #[test]
     // position 4 depth 1
     fn perft_position4_depth1() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1").unwrap().position;
-        assert_eq!(6, search.perft(position, 1));
+        assert_eq!(Some(6), search.perft(position, 1));
     }
 
     #[test]
     // position 4 depth 2
     fn perft_position4_depth2() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1").unwrap().position;
-        assert_eq!(264, search.perft(position, 2));
+        assert_eq!(Some(264), search.perft(position, 2));
     }
 
     #[test]
     // position 4 depth 3
     fn perft_position4_depth3() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1").unwrap().position;
-        assert_eq!(9_467, search.perft(position, 3));
+        assert_eq!(Some(9_467), search.perft(position, 3));
     }
 
     #[test]
     #[ignore]
     // position 4 depth 4
     fn perft_position4_depth4() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1").unwrap().position;
-        assert_eq!(422_333, search.perft(position, 4));
+        assert_eq!(Some(422_333), search.perft(position, 4));
     }
 
     #[test]
     #[ignore]
     // position 4 depth 5
     fn perft_position4_depth5() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1").unwrap().position;
-        assert_eq!(15_833_292, search.perft(position, 5));
+        assert_eq!(Some(15_833_292), search.perft(position, 5));
     }
 
     // ----------------------------------------------------------------------------------------------------------------------------------------
@@ -328,53 +605,48 @@ mod tests {
     #[test]
     // position 5 depth 1
     fn perft_position5_depth1() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8").unwrap().position;
-        assert_eq!(44, search.perft(position, 1));
+        assert_eq!(Some(44), search.perft(position, 1));
     }
 
     #[test]
     // position 5 depth 2
     fn perft_position5_depth2() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8").unwrap().position;
-        assert_eq!(1_486, search.perft(position, 2));
+        assert_eq!(Some(1_486), search.perft(position, 2));
     }
 
     #[test]
     // position 5 depth 3
     fn perft_position5_depth3() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8").unwrap().position;
-        assert_eq!(62_379, search.perft(position, 3));
+        assert_eq!(Some(62_379), search.perft(position, 3));
     }
 
     #[test]
     #[ignore]
     // position 5 depth 4
     fn perft_position5_depth4() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8").unwrap().position;
-        assert_eq!(2_103_487, search.perft(position, 4));
+        assert_eq!(Some(2_103_487), search.perft(position, 4));
     }
 
     #[test]
     #[ignore]
     // position 5 depth 5
     fn perft_position5_depth5() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8").unwrap().position;
-        assert_eq!(89_941_194, search.perft(position, 5));
+        assert_eq!(Some(89_941_194), search.perft(position, 5));
     }
 
     // ----------------------------------------------------------------------------------------------------------------------------------------
@@ -383,53 +655,48 @@ mod tests {
     #[test]
     // position 6 depth 1
     fn perft_position6_depth1() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10").unwrap().position;
-        assert_eq!(46, search.perft(position, 1));
+        assert_eq!(Some(46), search.perft(position, 1));
     }
 
     #[test]
     // position 6 depth 2
     fn perft_position6_depth2() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10").unwrap().position;
-        assert_eq!(2_079, search.perft(position, 2));
+        assert_eq!(Some(2_079), search.perft(position, 2));
     }
 
     #[test]
     // position 6 depth 3
     fn perft_position6_depth3() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10").unwrap().position;
-        assert_eq!(89_890, search.perft(position, 3));
+        assert_eq!(Some(89_890), search.perft(position, 3));
     }
 
     #[test]
     #[ignore]
     // position 6 depth 4
     fn perft_position6_depth4() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10").unwrap().position;
-        assert_eq!(3_894_594, search.perft(position, 4));
+        assert_eq!(Some(3_894_594), search.perft(position, 4));
     }
 
     #[test]
     #[ignore]
     // position 6 depth 5
     fn perft_position6_depth5() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10").unwrap().position;
-        assert_eq!(164_075_551, search.perft(position, 5));
+        assert_eq!(Some(164_075_551), search.perft(position, 5));
     }
 
     // ----------------------------------------------------------------------------------------------------------------------------------------
@@ -438,64 +705,58 @@ mod tests {
     #[test]
     // position 7 depth 1
     fn perft_position7_depth1() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1").unwrap().position;
-        assert_eq!(24, search.perft(position, 1));
+        assert_eq!(Some(24), search.perft(position, 1));
     }
 
     #[test]
     // position 7 depth 2
     fn perft_position7_depth2() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1").unwrap().position;
-        assert_eq!(496, search.perft(position, 2));
+        assert_eq!(Some(496), search.perft(position, 2));
     }
 
     #[test]
     // position 7 depth 3
     fn perft_position7_depth3() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1").unwrap().position;
-        assert_eq!(9_483, search.perft(position, 3));
+        assert_eq!(Some(9_483), search.perft(position, 3));
     }
 
     #[test]
     #[ignore]
     // position 7 depth 4
     fn perft_position7_depth4() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1").unwrap().position;
-        assert_eq!(182_838, search.perft(position, 4));
+        assert_eq!(Some(182_838), search.perft(position, 4));
     }
 
     #[test]
     #[ignore]
     // position 7 depth 5
     fn perft_position7_depth5() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1").unwrap().position;
-        assert_eq!(3_605_103, search.perft(position, 5));
+        assert_eq!(Some(3_605_103), search.perft(position, 5));
     }
 
     #[test]
     #[ignore]
     // position 7 depth 6
     fn perft_position7_depth6() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1").unwrap().position;
-        assert_eq!(71_179_139, search.perft(position, 6));
+        assert_eq!(Some(71_179_139), search.perft(position, 6));
     }
 
 
@@ -505,64 +766,58 @@ mod tests {
     #[test]
     // position 8 depth 1
     fn perft_position8_depth1() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("8/5bk1/8/2Pp4/8/1K6/8/8 w - d6 0 1").unwrap().position;
-        assert_eq!(8, search.perft(position, 1));
+        assert_eq!(Some(8), search.perft(position, 1));
     }
 
     #[test]
     // position 8 depth 2
     fn perft_position8_depth2() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("8/5bk1/8/2Pp4/8/1K6/8/8 w - d6 0 1").unwrap().position;
-        assert_eq!(104, search.perft(position, 2));
+        assert_eq!(Some(104), search.perft(position, 2));
     }
 
     #[test]
     // position 8 depth 3
     fn perft_position8_depth3() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("8/5bk1/8/2Pp4/8/1K6/8/8 w - d6 0 1").unwrap().position;
-        assert_eq!(736, search.perft(position, 3));
+        assert_eq!(Some(736), search.perft(position, 3));
     }
 
     #[test]
     #[ignore]
     // position 8 depth 4
     fn perft_position8_depth4() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("8/5bk1/8/2Pp4/8/1K6/8/8 w - d6 0 1").unwrap().position;
-        assert_eq!(9_287, search.perft(position, 4));
+        assert_eq!(Some(9_287), search.perft(position, 4));
     }
 
     #[test]
     #[ignore]
     // position 8 depth 5
     fn perft_position8_depth5() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("8/5bk1/8/2Pp4/8/1K6/8/8 w - d6 0 1").unwrap().position;
-        assert_eq!(62_297, search.perft(position, 5));
+        assert_eq!(Some(62_297), search.perft(position, 5));
     }
 
     #[test]
     #[ignore]
     // position 8 depth 6
     fn perft_position8_depth6() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("8/5bk1/8/2Pp4/8/1K6/8/8 w - d6 0 1").unwrap().position;
-        assert_eq!(824_064, search.perft(position, 6));
+        assert_eq!(Some(824_064), search.perft(position, 6));
     }
 
     // ----------------------------------------------------------------------------------------------------------------------------------------
@@ -571,63 +826,57 @@ mod tests {
     #[test]
     // position 9 depth 1
     fn perft_position9_depth1() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("8/8/1k6/8/2pP4/8/5BK1/8 b - d3 0 1").unwrap().position;
-        assert_eq!(8, search.perft(position, 1));
+        assert_eq!(Some(8), search.perft(position, 1));
     }
 
     #[test]
     // position 9 depth 2
     fn perft_position9_depth2() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("8/8/1k6/8/2pP4/8/5BK1/8 b - d3 0 1").unwrap().position;
-        assert_eq!(104, search.perft(position, 2));
+        assert_eq!(Some(104), search.perft(position, 2));
     }
 
     #[test]
     // position 9 depth 3
     fn perft_position9_depth3() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("8/8/1k6/8/2pP4/8/5BK1/8 b - d3 0 1").unwrap().position;
-        assert_eq!(736, search.perft(position, 3));
+        assert_eq!(Some(736), search.perft(position, 3));
     }
 
     #[test]
     // position 9 depth 4
     fn perft_position9_depth4() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("8/8/1k6/8/2pP4/8/5BK1/8 b - d3 0 1").unwrap().position;
-        assert_eq!(9_287, search.perft(position, 4));
+        assert_eq!(Some(9_287), search.perft(position, 4));
     }
 
     #[test]
     #[ignore]
     // position 9 depth 5
     fn perft_position9_depth5() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("8/8/1k6/8/2pP4/8/5BK1/8 b - d3 0 1").unwrap().position;
-        assert_eq!(62_297, search.perft(position, 5));
+        assert_eq!(Some(62_297), search.perft(position, 5));
     }
 
     #[test]
     #[ignore]
     // position 9 depth 6
     fn perft_position9_depth6() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("8/8/1k6/8/2pP4/8/5BK1/8 b - d3 0 1").unwrap().position;
-        assert_eq!(824_064, search.perft(position, 6));
+        assert_eq!(Some(824_064), search.perft(position, 6));
     }
 
     // ----------------------------------------------------------------------------------------------------------------------------------------
@@ -636,64 +885,58 @@ mod tests {
     #[test]
     // position 10 depth 1
     fn perft_position10_depth1() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("8/5k2/8/2Pp4/2B5/1K6/8/8 w - d6 0 1").unwrap().position;
-        assert_eq!(15, search.perft(position, 1));
+        assert_eq!(Some(15), search.perft(position, 1));
     }
 
     #[test]
     // position 10 depth 2
     fn perft_position10_depth2() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("8/5k2/8/2Pp4/2B5/1K6/8/8 w - d6 0 1").unwrap().position;
-        assert_eq!(126, search.perft(position, 2));
+        assert_eq!(Some(126), search.perft(position, 2));
     }
 
     #[test]
     // position 10 depth 3
     fn perft_position10_depth3() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("8/5k2/8/2Pp4/2B5/1K6/8/8 w - d6 0 1").unwrap().position;
-        assert_eq!(1_928, search.perft(position, 3));
+        assert_eq!(Some(1_928), search.perft(position, 3));
     }
 
     #[test]
     #[ignore]
     // position 10 depth 4
     fn perft_position10_depth4() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("8/5k2/8/2Pp4/2B5/1K6/8/8 w - d6 0 1").unwrap().position;
-        assert_eq!(13_931, search.perft(position, 4));
+        assert_eq!(Some(13_931), search.perft(position, 4));
     }
 
     #[test]
     #[ignore]
     // position 10 depth 5
     fn perft_position10_depth5() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("8/5k2/8/2Pp4/2B5/1K6/8/8 w - d6 0 1").unwrap().position;
-        assert_eq!(20_6379, search.perft(position, 5));
+        assert_eq!(Some(20_6379), search.perft(position, 5));
     }
 
     #[test]
     #[ignore]
     // position 10 depth 6
     fn perft_position10_depth6() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("8/5k2/8/2Pp4/2B5/1K6/8/8 w - d6 0 1").unwrap().position;
-        assert_eq!(1_440_467, search.perft(position, 6));
+        assert_eq!(Some(1_440_467), search.perft(position, 6));
     }
 
     // ----------------------------------------------------------------------------------------------------------------------------------------
@@ -702,63 +945,433 @@ mod tests {
     #[test]
     // position 11 depth 1
     fn perft_position11_depth1() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1").unwrap().position;
-        assert_eq!(15, search.perft(position, 1));
+        assert_eq!(Some(15), search.perft(position, 1));
     }
 
     #[test]
     // position 11 depth 2
     fn perft_position11_depth2() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1").unwrap().position;
-        assert_eq!(126, search.perft(position, 2));
+        assert_eq!(Some(126), search.perft(position, 2));
     }
 
     #[test]
     // position 11 depth 3
     fn perft_position11_depth3() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1").unwrap().position;
-        assert_eq!(1_928, search.perft(position, 3));
+        assert_eq!(Some(1_928), search.perft(position, 3));
     }
 
     #[test]
     #[ignore]
     // position 11 depth 4
     fn perft_position11_depth4() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1").unwrap().position;
-        assert_eq!(13_931, search.perft(position, 4));
+        assert_eq!(Some(13_931), search.perft(position, 4));
     }
 
     #[test]
     #[ignore]
     // position 11 depth 5
     fn perft_position11_depth5() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1").unwrap().position;
-        assert_eq!(20_6379, search.perft(position, 5));
+        assert_eq!(Some(20_6379), search.perft(position, 5));
     }
 
     #[test]
     #[ignore]
     // position 11 depth 6
     fn perft_position11_depth6() {
-        initialize_lookup_table();
-        let search = setup();
+        let mut search = setup();
         
         let position = Board::from_fen("8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1").unwrap().position;
-        assert_eq!(1_440_467, search.perft(position, 6));
+        assert_eq!(Some(1_440_467), search.perft(position, 6));
+    }
+
+    // ----------------------------------------------------------------------------------------------------------------------------------------
+    // Chess960 Position 1 (https://www.chessprogramming.org/Chess960_Perft_Results)
+    // ----------------------------------------------------------------------------------------------------------------------------------------
+    #[test]
+    // chess960 position 1 depth 1
+    fn perft_chess960_position1_depth1() {
+        let mut search = setup();
+        
+        let position = Board::from_fen("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9").unwrap().position;
+        assert_eq!(Some(21), search.perft(position, 1));
+    }
+
+    #[test]
+    // chess960 position 1 depth 2
+    fn perft_chess960_position1_depth2() {
+        let mut search = setup();
+        
+        let position = Board::from_fen("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9").unwrap().position;
+        assert_eq!(Some(528), search.perft(position, 2));
+    }
+
+    #[test]
+    // chess960 position 1 depth 3
+    fn perft_chess960_position1_depth3() {
+        let mut search = setup();
+        
+        let position = Board::from_fen("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9").unwrap().position;
+        assert_eq!(Some(12_189), search.perft(position, 3));
+    }
+
+    #[test]
+    #[ignore]
+    // chess960 position 1 depth 4
+    fn perft_chess960_position1_depth4() {
+        let mut search = setup();
+        
+        let position = Board::from_fen("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9").unwrap().position;
+        assert_eq!(Some(326_672), search.perft(position, 4));
+    }
+
+    #[test]
+    #[ignore]
+    // chess960 position 1 depth 5
+    fn perft_chess960_position1_depth5() {
+        let mut search = setup();
+        
+        let position = Board::from_fen("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9").unwrap().position;
+        assert_eq!(Some(8_146_062), search.perft(position, 5));
+    }
+
+
+    // ----------------------------------------------------------------------------------------------------------------------------------------
+    // Chess960 Position 2 (https://www.chessprogramming.org/Chess960_Perft_Results)
+    // ----------------------------------------------------------------------------------------------------------------------------------------
+    #[test]
+    // chess960 position 2 depth 1
+    fn perft_chess960_position2_depth1() {
+        let mut search = setup();
+        
+        let position = Board::from_fen("2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9").unwrap().position;
+        assert_eq!(Some(21), search.perft(position, 1));
+    }
+
+    #[test]
+    // chess960 position 2 depth 2
+    fn perft_chess960_position2_depth2() {
+        let mut search = setup();
+        
+        let position = Board::from_fen("2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9").unwrap().position;
+        assert_eq!(Some(807), search.perft(position, 2));
+    }
+
+    #[test]
+    // chess960 position 2 depth 3
+    fn perft_chess960_position2_depth3() {
+        let mut search = setup();
+        
+        let position = Board::from_fen("2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9").unwrap().position;
+        assert_eq!(Some(18_002), search.perft(position, 3));
+    }
+
+    #[test]
+    #[ignore]
+    // chess960 position 2 depth 4
+    fn perft_chess960_position2_depth4() {
+        let mut search = setup();
+        
+        let position = Board::from_fen("2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9").unwrap().position;
+        assert_eq!(Some(667_366), search.perft(position, 4));
+    }
+
+    #[test]
+    #[ignore]
+    // chess960 position 2 depth 5
+    fn perft_chess960_position2_depth5() {
+        let mut search = setup();
+        
+        let position = Board::from_fen("2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9").unwrap().position;
+        assert_eq!(Some(16_253_601), search.perft(position, 5));
+    }
+
+
+    // ----------------------------------------------------------------------------------------------------------------------------------------
+    // Chess960 Position 3 (https://www.chessprogramming.org/Chess960_Perft_Results)
+    // ----------------------------------------------------------------------------------------------------------------------------------------
+    #[test]
+    // chess960 position 3 depth 1
+    fn perft_chess960_position3_depth1() {
+        let mut search = setup();
+        
+        let position = Board::from_fen("b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9").unwrap().position;
+        assert_eq!(Some(20), search.perft(position, 1));
+    }
+
+    #[test]
+    // chess960 position 3 depth 2
+    fn perft_chess960_position3_depth2() {
+        let mut search = setup();
+        
+        let position = Board::from_fen("b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9").unwrap().position;
+        assert_eq!(Some(479), search.perft(position, 2));
+    }
+
+    #[test]
+    // chess960 position 3 depth 3
+    fn perft_chess960_position3_depth3() {
+        let mut search = setup();
+        
+        let position = Board::from_fen("b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9").unwrap().position;
+        assert_eq!(Some(10_471), search.perft(position, 3));
+    }
+
+    #[test]
+    #[ignore]
+    // chess960 position 3 depth 4
+    fn perft_chess960_position3_depth4() {
+        let mut search = setup();
+        
+        let position = Board::from_fen("b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9").unwrap().position;
+        assert_eq!(Some(273_318), search.perft(position, 4));
+    }
+
+    #[test]
+    #[ignore]
+    // chess960 position 3 depth 5
+    fn perft_chess960_position3_depth5() {
+        let mut search = setup();
+        
+        let position = Board::from_fen("b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9").unwrap().position;
+        assert_eq!(Some(6_417_013), search.perft(position, 5));
+    }
+
+
+    // ----------------------------------------------------------------------------------------------------------------------------------------
+    // Chess960 Position 4 (https://www.chessprogramming.org/Chess960_Perft_Results)
+    // ----------------------------------------------------------------------------------------------------------------------------------------
+    #[test]
+    // chess960 position 4 depth 1
+    fn perft_chess960_position4_depth1() {
+        let mut search = setup();
+        
+        let position = Board::from_fen("qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9").unwrap().position;
+        assert_eq!(Some(22), search.perft(position, 1));
+    }
+
+    #[test]
+    // chess960 position 4 depth 2
+    fn perft_chess960_position4_depth2() {
+        let mut search = setup();
+        
+        let position = Board::from_fen("qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9").unwrap().position;
+        assert_eq!(Some(593), search.perft(position, 2));
+    }
+
+    #[test]
+    // chess960 position 4 depth 3
+    fn perft_chess960_position4_depth3() {
+        let mut search = setup();
+        
+        let position = Board::from_fen("qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9").unwrap().position;
+        assert_eq!(Some(13_440), search.perft(position, 3));
+    }
+
+    #[test]
+    #[ignore]
+    // chess960 position 4 depth 4
+    fn perft_chess960_position4_depth4() {
+        let mut search = setup();
+        
+        let position = Board::from_fen("qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9").unwrap().position;
+        assert_eq!(Some(382_958), search.perft(position, 4));
+    }
+
+    #[test]
+    #[ignore]
+    // chess960 position 4 depth 5
+    fn perft_chess960_position4_depth5() {
+        let mut search = setup();
+        
+        let position = Board::from_fen("qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9").unwrap().position;
+        assert_eq!(Some(9_183_776), search.perft(position, 5));
+    }
+
+    // ----------------------------------------------------------------------------------------------------------------------------------------
+    // Make/unmake vs. copy-make
+    // ----------------------------------------------------------------------------------------------------------------------------------------
+
+    /// Counts the leaf nodes like `perft_driver`, but with copy-make instead of make/unmake.
+    /// Also verifies at every node that making a move in place and unmaking it restores the exact position.
+    fn perft_copy_make(position: Position, depth: u64) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let move_list = move_gen::generate_moves(position);
+        let mut node_count = 0;
+        for i in 0..move_list.len() {
+            let ply = move_list.get(i);
+            let next_position = position.make_move(ply);
+
+            let mut in_place_position = position;
+            let undo = in_place_position.make_move_in_place(ply);
+            assert_eq!(next_position, in_place_position);
+            assert_eq!(next_position.hash, in_place_position.hash);
+            in_place_position.unmake_move(undo);
+            assert_eq!(position, in_place_position);
+            assert_eq!((position.hash, position.material, position.pst), (in_place_position.hash, in_place_position.material, in_place_position.pst));
+
+            node_count += perft_copy_make(next_position, depth - 1);
+        }
+        node_count
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn perft_with_make_unmake_matches_copy_make() {
+        let mut search = setup();
+
+        let fens = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+            "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+        ];
+        for fen in fens {
+            let position = Board::from_fen(fen).unwrap().position;
+            assert_eq!(Some(perft_copy_make(position, 3)), search.perft(position, 3), "{fen}");
+        }
+    }
+
+    // ----------------------------------------------------------------------------------------------------------------------------------------
+    // Perft toolkit
+    // ----------------------------------------------------------------------------------------------------------------------------------------
+
+    #[test]
+    fn count_detailed_matches_reference_tables() {
+        // the detailed results from https://www.chessprogramming.org/Perft_Results
+        let references = [
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 4, [197_281, 1_576, 0, 0, 0, 469, 0, 0, 8]),
+            ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", 3, [97_862, 17_102, 45, 3_162, 0, 993, 0, 0, 1]),
+            ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 4, [43_238, 3_348, 123, 0, 0, 1_680, 106, 0, 17]),
+            ("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", 3, [9_467, 1_021, 4, 0, 120, 38, 2, 0, 22]),
+        ];
+        for (fen, depth, [nodes, captures, en_passants, castles, promotions, checks, discovered_checks, double_checks, checkmates]) in references {
+            let mut position = Board::from_fen(fen).unwrap().position;
+            let expected = PerftStats { nodes, captures, en_passants, castles, promotions, checks, discovered_checks, double_checks, checkmates };
+            assert_eq!(expected, perft::count_detailed(&mut position, depth), "{fen}");
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn count_detailed_counts_double_checks() {
+        let mut position = Board::from_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1").unwrap().position;
+        let stats = perft::count_detailed(&mut position, 5);
+        assert_eq!(PerftStats { nodes: 674_624, captures: 52_051, en_passants: 1_165, castles: 0, promotions: 0, checks: 52_950, discovered_checks: 1_292, double_checks: 3, checkmates: 0 }, stats);
+    }
+
+    #[test]
+    fn count_nodes_with_and_without_table_match() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+        ];
+        for fen in fens {
+            let mut position = Board::from_fen(fen).unwrap().position;
+            let original_position = position;
+            let mut table = PerftTable::new(1);
+            let node_count = perft::count_nodes(&mut position, 3, &mut PerftTable::default());
+            assert_eq!(node_count, perft::count_nodes(&mut position, 3, &mut table), "{fen}");
+            // the second run is answered from the table
+            assert_eq!(node_count, perft::count_nodes(&mut position, 3, &mut table), "{fen}");
+            assert_eq!(perft::count_detailed(&mut position, 2).nodes, perft::count_nodes(&mut position, 2, &mut table), "{fen}");
+            assert_eq!(original_position, position);
+        }
+    }
+
+    #[test]
+    fn divide_parallel_matches_divide() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+        ];
+        let stop = AtomicBool::new(false);
+        for fen in fens {
+            let mut position = Board::from_fen(fen).unwrap().position;
+            let expected = perft::divide(&mut position, 3, &mut PerftTable::default());
+            for (num_threads, split_depth) in [(1, 1), (4, 1), (4, 2), (3, 3), (8, 5)] {
+                assert_eq!(Some(expected.clone()), perft::divide_parallel(position, 3, split_depth, num_threads, 4, &stop), "{fen}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_get_thread_table_size_mb() {
+        assert_eq!(4, perft::get_thread_table_size_mb(16, 4));
+        assert_eq!(1, perft::get_thread_table_size_mb(16, 32));
+        assert_eq!(0, perft::get_thread_table_size_mb(0, 4));
+    }
+
+    #[test]
+    fn divide_parallel_with_stop_flag_returns_none() {
+        let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
+        let stop = AtomicBool::new(true);
+        assert_eq!(None, perft::divide_parallel(position, 10, 2, 4, 4, &stop));
+    }
+
+    #[test]
+    fn perft_can_be_stopped() {
+        let (search_command_sender, search_command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
+        let (message_sender, message_receiver): (Sender<Message>, Receiver<Message>) = mpsc::channel();
+        let mut search = Search::new(search_command_receiver, message_sender);
+
+        // commands other than stop are kept until the perft is finished
+        let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
+        search_command_sender.send(SearchCommand::Perft(position, 1)).unwrap();
+        search_command_sender.send(SearchCommand::Stop).unwrap();
+        assert_eq!(None, search.perft(position, 10));
+        assert_eq!(1, search.pending_commands.len());
+
+        match message_receiver.recv().unwrap() {
+            Message::SearchMessage(event) => assert_eq!(SearchEvent::PerftStopped, *event),
+            Message::ConsoleMessage(_) => panic!("unexpected console message"),
+        }
+    }
+
+    #[test]
+    fn divide_counts_nodes_per_move() {
+        let mut position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
+        let divide = perft::divide(&mut position, 3, &mut PerftTable::default());
+        assert_eq!(20, divide.len());
+        assert_eq!(8_902, divide.iter().map(|(_, node_count)| node_count).sum::<u64>());
+        let (_, node_count) = divide.iter().find(|(ply, _)| ply.to_uci(position, false) == "e2e4").unwrap();
+        assert_eq!(600, *node_count);
+
+        assert_eq!(vec![1; 20], perft::divide(&mut position, 1, &mut PerftTable::default()).iter().map(|(_, node_count)| *node_count).collect::<Vec<u64>>());
+        assert!(perft::divide(&mut position, 0, &mut PerftTable::default()).is_empty());
+        assert_eq!(Some(Vec::new()), perft::divide_parallel(position, 0, 2, 4, 4, &AtomicBool::new(false)));
+    }
+
+    #[test]
+    fn perft_with_depth_zero_counts_root() {
+        let (_search_command_sender, search_command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
+        let (message_sender, message_receiver): (Sender<Message>, Receiver<Message>) = mpsc::channel();
+        let mut search = Search::new(search_command_receiver, message_sender);
+
+        let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
+        assert_eq!(Some(1), search.perft(position, 0));
+        match message_receiver.recv().unwrap() {
+            Message::SearchMessage(event) => assert!(matches!(*event, SearchEvent::PerftDone { nodes: 1, .. })),
+            Message::ConsoleMessage(_) => panic!("unexpected console message"),
+        }
+    }
+}
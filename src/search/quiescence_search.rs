@@ -1,28 +1,29 @@
 use std::time::Duration;
 use crate::board::position::Position;
-use crate::{evaluation, move_gen};
-use crate::search::{Search};
+use crate::move_gen;
+use crate::engine::Engine;
 
-impl Search {
+impl Engine {
     /// The [Quiescence Search](https://www.chessprogramming.org/Quiescence_Search) function is very similar
     /// to the negamax function, but instead of looking at all moves, it only looks at captures.
     /// It also uses something called a "standing pat", which is initialized with the static evaluation and is
     /// used to cause beta-cutoffs earlier, thus reducing the number of nodes searched.
-    pub fn quiescence_search(&mut self, position: Position, ply_index: u64, mut alpha: i32, beta: i32, time_limit: Duration) -> i32 {
-        // check if the time limit is reached
-        if let Some(instant) = self.total_time {
-            if instant.elapsed() > time_limit {
-                // the time limit is reached - break out of recursion immediately
-                self.stop = true;
-                return 0;
-            }
+    pub fn quiescence_search(&mut self, position: &mut Position, ply_index: u64, mut alpha: i32, beta: i32, time_limit: Duration) -> i32 {
+        // check if the time or node limit is reached or the search was cancelled
+        if self.is_limit_reached(time_limit) {
+            // break out of recursion immediately
+            self.stop = true;
+            return 0;
         }
 
         // increment the number of nodes searched
         self.search_info.node_count += 1;
 
+        // keep track of the maximum depth reached
+        self.search_info.seldepth = self.search_info.seldepth.max(ply_index);
+
         // Establish the lower bound of the score with the static evaluation
-        let standing_pat = evaluation::evaluate(position); 
+        let standing_pat = self.evaluate(*position); 
         
         // fail-hard beta cutoff
         if standing_pat >= beta {
@@ -37,7 +38,7 @@ impl Search {
         }
         
         // generate all legal capture moves for the current position
-        let mut capture_list = move_gen::generate_moves(position).get_captures();
+        let mut capture_list = move_gen::generate_moves(*position).get_captures();
 
         // sort the capture list
         capture_list.sort(&mut self.search_info, ply_index);
@@ -47,7 +48,9 @@ impl Search {
             let ply = capture_list.get(i);
 
             // the score of the new position
-            let score = -self.quiescence_search(position.make_move(ply), ply_index + 1, -beta, -alpha, time_limit);
+            let undo = position.make_move_in_place(ply);
+            let score = -self.quiescence_search(position, ply_index + 1, -beta, -alpha, time_limit);
+            position.unmake_move(undo);
 
             // fail-hard beta cutoff
             if score >= beta {
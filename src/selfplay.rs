@@ -0,0 +1,498 @@
+//! The selfplay module plays matches between two engine configurations in-process, e.g. to test a change to the evaluation.
+//!
+//! Every opening is played twice with swapped colors, so that neither player profits from a favorable opening.
+//! The games are played with a clock of base time plus increment, and are adjudicated as soon as the scores reported by
+//! the engines make the outcome obvious. The results are evaluated with the [stats] module.
+
+pub mod stats;
+
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::board::Board;
+use crate::board::color::Color;
+use crate::board::epd::Epd;
+use crate::board::fen::START_FEN;
+use crate::engine::{CancelHandle, Engine, EngineConfig, SearchLimits};
+use crate::game;
+use crate::game::{Game, GameResult};
+use crate::move_gen::ply::Ply;
+use crate::pgn;
+use crate::pgn::{PgnGame, PgnMove};
+use crate::selfplay::stats::Outcome;
+
+/// The number of moves the remaining time is divided into, like for the UCI "go wtime" command.
+const MOVES_TO_GO: u32 = 40;
+
+/// A participant of a match.
+#[derive(Clone, Debug)]
+pub struct Player {
+    /// The name written to the PGN.
+    pub name: String,
+    /// The configuration of the engine.
+    pub config: EngineConfig,
+}
+
+/// The time control of a match. Both players start with the base time, and receive the increment after each of their moves.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct TimeControl {
+    pub base: Duration,
+    pub increment: Duration,
+}
+
+/// The rules for ending games early, based on the scores reported by the engines. A move count of zero disables the rule.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Adjudication {
+    /// The first move number at which a game can be adjudicated as a draw.
+    pub draw_move_number: u32,
+    /// The number of consecutive plies both engines have to report a score within the draw score.
+    pub draw_move_count: u32,
+    /// The maximum absolute score in centipawns for a draw.
+    pub draw_score: i32,
+    /// The number of consecutive moves a player has to report a score of at most the negative resign score.
+    pub resign_move_count: u32,
+    /// The score in centipawns at which a player resigns.
+    pub resign_score: i32,
+}
+
+/// The settings of a match.
+#[derive(Clone, Debug)]
+pub struct MatchSettings {
+    /// The two players. The first player has White in the first game of each pair.
+    pub players: [Player; 2],
+    /// The number of games to play.
+    pub num_games: usize,
+    /// The time control of all games.
+    pub time_control: TimeControl,
+    /// The rules for adjudicating games.
+    pub adjudication: Adjudication,
+    /// The number of games played at the same time.
+    pub concurrency: usize,
+}
+
+/// A starting position of a match, given by a board and the moves played from it.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Opening {
+    pub start: Board,
+    pub moves: Vec<Ply>,
+}
+
+/// The reason a match game ended.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Termination {
+    /// The game ended by the rules of chess.
+    Rules(GameResult),
+    /// The given color ran out of time.
+    TimeForfeit(Color),
+    /// The given color was adjudicated lost, because its engine kept reporting a hopeless score.
+    Resignation(Color),
+    /// Both engines kept reporting a drawish score.
+    DrawAdjudication,
+}
+
+/// A finished game of a match.
+#[derive(Clone, Debug)]
+pub struct MatchGame {
+    /// The index of the game in the match, starting at zero.
+    pub index: usize,
+    /// The index of the player who had White.
+    pub white: usize,
+    /// The reason the game ended.
+    pub termination: Termination,
+    /// The game in PGN notation, including the scores and depths reported by the engines.
+    pub pgn: PgnGame,
+}
+
+impl Default for Adjudication {
+    /// Default constructor for `Adjudication`.
+    /// Draws are adjudicated after 8 plies within 10 centipawns from move 40 on, and resignations after 3 moves at -600 centipawns.
+    fn default() -> Self {
+        Self {
+            draw_move_number: 40,
+            draw_move_count: 8,
+            draw_score: 10,
+            resign_move_count: 3,
+            resign_score: 600,
+        }
+    }
+}
+
+impl Default for Opening {
+    /// Default constructor for `Opening`. Returns the standard starting position.
+    fn default() -> Self {
+        Self {
+            start: Board::from_fen(START_FEN).unwrap(),
+            moves: Vec::new(),
+        }
+    }
+}
+
+impl Termination {
+    /// Returns the winner, or `None` if the game is drawn.
+    pub fn get_winner(&self) -> Option<Color> {
+        match self {
+            Termination::Rules(result) => result.get_winner(),
+            Termination::TimeForfeit(loser) | Termination::Resignation(loser) => Some(loser.other()),
+            Termination::DrawAdjudication => None,
+        }
+    }
+
+    /// Returns the result as it is written in PGN, i.e. `1-0`, `0-1` or `1/2-1/2`.
+    pub fn to_pgn(&self) -> &'static str {
+        game::get_pgn_result(self.get_winner())
+    }
+
+    /// Returns the value of the PGN `Termination` tag.
+    fn to_pgn_termination(self) -> &'static str {
+        match self {
+            Termination::Rules(_) => "normal",
+            Termination::TimeForfeit(_) => "time forfeit",
+            Termination::Resignation(_) | Termination::DrawAdjudication => "adjudication",
+        }
+    }
+}
+
+/// Prints the reason the game ended, e.g. "White mates" or "Black loses on time".
+impl Display for Termination {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Termination::Rules(result) => write!(f, "{result}"),
+            Termination::TimeForfeit(loser) => write!(f, "{loser} loses on time"),
+            Termination::Resignation(loser) => write!(f, "{loser} resigns"),
+            Termination::DrawAdjudication => write!(f, "Draw by adjudication"),
+        }
+    }
+}
+
+impl MatchGame {
+    /// Returns the outcome of the game from the point of view of the first player.
+    pub fn get_outcome(&self) -> Outcome {
+        let first_player_color = if self.white == 0 { Color::White } else { Color::Black };
+        match self.termination.get_winner() {
+            None => Outcome::Draw,
+            Some(winner) if winner == first_player_color => Outcome::Win,
+            Some(_) => Outcome::Loss,
+        }
+    }
+}
+
+/// Plays the games of a match and calls the given function with each finished game.
+///
+/// The games are played on `concurrency` threads, so they can finish out of order. If the given function returns false,
+/// no further games are started, and the searches of the games that are still running are cancelled and the games discarded.
+/// Game `2i` and `2i + 1` start from opening `i` with swapped colors. If there are fewer openings than pairs of games,
+/// the openings are repeated, and without any openings the games start from the standard starting position.
+pub fn run_match(settings: &MatchSettings, openings: &[Opening], mut on_game: impl FnMut(MatchGame) -> bool) {
+    let num_threads = settings.concurrency.max(1);
+    let next_index = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    // the cancel handles of the engines playing on each thread
+    let running_engines: Vec<Mutex<Vec<CancelHandle>>> = (0..num_threads).map(|_| Mutex::new(Vec::new())).collect();
+    let (game_sender, game_receiver) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for thread_engines in running_engines.iter() {
+            let game_sender = game_sender.clone();
+            let next_index = &next_index;
+            let stop = &stop;
+            scope.spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    let index = next_index.fetch_add(1, Ordering::Relaxed);
+                    if index >= settings.num_games {
+                        break;
+                    }
+                    let opening = match openings.is_empty() {
+                        true => Opening::default(),
+                        false => openings[(index / 2) % openings.len()].clone(),
+                    };
+                    let Some(game) = play_game_until_stopped(settings, &opening, index, stop, thread_engines) else {
+                        break;
+                    };
+                    if game_sender.send(game).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(game_sender);
+
+        for game in game_receiver.iter() {
+            if !on_game(game) {
+                stop.store(true, Ordering::Relaxed);
+                for thread_engines in running_engines.iter() {
+                    thread_engines.lock().unwrap().iter().for_each(CancelHandle::cancel);
+                }
+                break;
+            }
+        }
+    });
+}
+
+/// Plays a single game of a match from the given opening. The first player has White in games with an even index.
+pub fn play_game(settings: &MatchSettings, opening: &Opening, index: usize) -> MatchGame {
+    play_game_until_stopped(settings, opening, index, &AtomicBool::new(false), &Mutex::new(Vec::new())).unwrap()
+}
+
+/// Plays a single game like `play_game`, but aborts it as soon as the given flag is set, in which case `None` is returned.
+///
+/// The cancel handles of both engines are stored in `running_engines` when the game starts, so that a running search
+/// can be cancelled from another thread, which has to set the stop flag before cancelling.
+fn play_game_until_stopped(settings: &MatchSettings, opening: &Opening, index: usize, stop: &AtomicBool, running_engines: &Mutex<Vec<CancelHandle>>) -> Option<MatchGame> {
+    let white = index % 2;
+    let adjudication = settings.adjudication;
+    let time_control = settings.time_control;
+
+    // the engines and clocks are indexed by color
+    let mut engines = [white, 1 - white].map(|player| Engine::new(settings.players[player].config.clone()));
+    *running_engines.lock().unwrap() = engines.iter().map(Engine::cancel_handle).collect();
+    let mut clocks = [time_control.base; 2];
+
+    let mut game = Game::new(opening.start);
+    let mut pgn = PgnGame::new(opening.start);
+    for ply in opening.moves.iter() {
+        game.make_move(*ply);
+        pgn.moves.push(PgnMove::new(*ply));
+    }
+
+    let mut num_draw_plies = 0;
+    let mut num_resign_moves = [0; 2];
+    let termination = loop {
+        if let Some(result) = game.get_result() {
+            break Termination::Rules(result);
+        }
+
+        let board = game.get_board();
+        let color = board.position.color_to_move;
+        let color_index = color.to_index() as usize;
+
+        let remaining_time = clocks[color_index];
+        let move_time = (remaining_time / MOVES_TO_GO + time_control.increment).min(remaining_time / 2);
+        let engine = &mut engines[color_index];
+        engine.set_position(board, game.get_board_history());
+        if stop.load(Ordering::Relaxed) {
+            return None;
+        }
+        let start_time = Instant::now();
+        let result = engine.search(SearchLimits { move_time: Some(move_time), ..Default::default() });
+        let elapsed = start_time.elapsed();
+
+        // the search may have been cancelled, in which case its result must not be played
+        if stop.load(Ordering::Relaxed) {
+            return None;
+        }
+
+        if elapsed > remaining_time {
+            break Termination::TimeForfeit(color);
+        }
+        clocks[color_index] = remaining_time - elapsed + time_control.increment;
+
+        // there is always a best move, since positions without legal moves end the game
+        let best_move = result.best_move.unwrap();
+        let mut pgn_move = PgnMove::new(best_move);
+        pgn_move.comment = Some(format!("{:+.2}/{} {:.3}s", result.score as f64 / 100.0, result.depth, elapsed.as_secs_f64()));
+        pgn.moves.push(pgn_move);
+        game.make_move(best_move);
+
+        num_resign_moves[color_index] = match result.score <= -adjudication.resign_score {
+            true => num_resign_moves[color_index] + 1,
+            false => 0,
+        };
+        if adjudication.resign_move_count > 0 && num_resign_moves[color_index] >= adjudication.resign_move_count {
+            break Termination::Resignation(color);
+        }
+
+        num_draw_plies = match result.score.abs() <= adjudication.draw_score {
+            true => num_draw_plies + 1,
+            false => 0,
+        };
+        if adjudication.draw_move_count > 0 && board.fullmove_counter >= adjudication.draw_move_number && num_draw_plies >= adjudication.draw_move_count {
+            break Termination::DrawAdjudication;
+        }
+    };
+
+    pgn.set_tag("Event", "Ladybug match");
+    pgn.set_tag("Round", (index + 1).to_string().as_str());
+    pgn.set_tag("White", settings.players[white].name.as_str());
+    pgn.set_tag("Black", settings.players[1 - white].name.as_str());
+    pgn.set_tag("Result", termination.to_pgn());
+    pgn.set_tag("TimeControl", format!("{}+{}", time_control.base.as_secs_f64(), time_control.increment.as_secs_f64()).as_str());
+    pgn.set_tag("Termination", termination.to_pgn_termination());
+
+    Some(MatchGame {
+        index,
+        white,
+        termination,
+        pgn,
+    })
+}
+
+/// Reads the openings of a match from the given file.
+///
+/// Files with the extension `.pgn` are read as PGN, and the mainline of each game is used as an opening.
+/// All other files are read as EPD, where empty lines and lines starting with `#` are skipped.
+pub fn load_openings(path: &Path) -> Result<Vec<Opening>, String> {
+    let is_pgn = path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("pgn"));
+    let openings = match is_pgn {
+        true => pgn::open(path)
+            .map_err(|error| error.to_string())?
+            .map(|game| game.map(|game| Opening {
+                start: game.start,
+                moves: game.moves.iter().map(|pgn_move| pgn_move.ply).collect(),
+            }))
+            .collect::<Result<Vec<Opening>, String>>()?,
+        false => std::fs::read_to_string(path)
+            .map_err(|error| error.to_string())?
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && !line.trim().starts_with('#'))
+            .map(|(index, line)| Epd::parse(line.trim())
+                .map(|epd| Opening { start: epd.board, moves: Vec::new() })
+                .map_err(|error| format!("line {}: {error}", index + 1)))
+            .collect::<Result<Vec<Opening>, String>>()?,
+    };
+
+    match openings.is_empty() {
+        true => Err(String::from("no openings found")),
+        false => Ok(openings),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::time::{Duration, Instant};
+    use crate::board::Board;
+    use crate::board::color::Color;
+    use crate::board::fen::START_FEN;
+    use crate::engine::EngineConfig;
+    use crate::game::GameResult;
+    use crate::move_gen::ply::Ply;
+    use crate::selfplay;
+    use crate::selfplay::{Adjudication, MatchGame, MatchSettings, Opening, Player, Termination, TimeControl};
+    use crate::selfplay::stats::Outcome;
+
+    /// Returns the settings for a short match between two default engines.
+    fn settings(num_games: usize) -> MatchSettings {
+        MatchSettings {
+            players: [0, 1].map(|index| Player { name: format!("Engine {index}"), config: EngineConfig::default() }),
+            num_games,
+            time_control: TimeControl { base: Duration::from_millis(500), increment: Duration::from_millis(10) },
+            adjudication: Adjudication::default(),
+            concurrency: 2,
+        }
+    }
+
+    #[test]
+    fn play_game_finishes_won_position() {
+        let opening = Opening { start: Board::from_fen("6k1/8/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap(), moves: Vec::new() };
+        let game = selfplay::play_game(&settings(2), &opening, 1);
+        assert_eq!(1, game.white);
+        assert!(matches!(game.termination, Termination::Rules(GameResult::Checkmate(Color::White)) | Termination::Resignation(Color::Black)));
+        assert_eq!(Outcome::Loss, game.get_outcome());
+
+        assert_eq!(Some("Engine 1"), game.pgn.get_tag("White"));
+        assert_eq!(Some("Engine 0"), game.pgn.get_tag("Black"));
+        assert_eq!("1-0", game.pgn.result());
+        assert_eq!(Some("0.5+0.01"), game.pgn.get_tag("TimeControl"));
+        assert!(game.pgn.moves.iter().all(|pgn_move| pgn_move.comment.is_some()));
+    }
+
+    #[test]
+    fn play_game_starts_after_opening_moves() {
+        let start = Board::from_fen(START_FEN).unwrap();
+        let e2e4 = Ply::from_string("e2e4", start.position).unwrap();
+        let opening = Opening { start, moves: vec![e2e4] };
+
+        let mut settings = settings(2);
+        settings.adjudication.draw_move_number = 1;
+        settings.adjudication.draw_move_count = 1;
+        settings.adjudication.draw_score = 1000;
+        let game = selfplay::play_game(&settings, &opening, 0);
+
+        assert_eq!(Termination::DrawAdjudication, game.termination);
+        assert_eq!(2, game.pgn.moves.len());
+        assert_eq!(e2e4, game.pgn.moves[0].ply);
+        assert_eq!(None, game.pgn.moves[0].comment);
+        assert_eq!(Some("adjudication"), game.pgn.get_tag("Termination"));
+    }
+
+    #[test]
+    fn run_match_swaps_colors_and_stops() {
+        let mut settings = settings(4);
+        settings.adjudication.draw_move_number = 1;
+        settings.adjudication.draw_move_count = 1;
+        settings.adjudication.draw_score = 1000;
+
+        let mut games: Vec<MatchGame> = Vec::new();
+        selfplay::run_match(&settings, &[], |game| {
+            games.push(game);
+            true
+        });
+        games.sort_by_key(|game| game.index);
+        assert_eq!(vec![0, 1, 0, 1], games.iter().map(|game| game.white).collect::<Vec<usize>>());
+        assert!(games.iter().all(|game| game.get_outcome() == Outcome::Draw));
+
+        let mut num_games = 0;
+        selfplay::run_match(&settings, &[], |_| {
+            num_games += 1;
+            false
+        });
+        assert_eq!(1, num_games);
+    }
+
+    #[test]
+    fn run_match_cancels_running_games() {
+        // the first two games start in a stalemate and finish immediately, the others would take minutes
+        let stalemate = Opening { start: Board::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap(), moves: Vec::new() };
+        let mut settings = settings(4);
+        settings.time_control = TimeControl { base: Duration::from_secs(60), increment: Duration::ZERO };
+
+        let start_time = Instant::now();
+        let mut num_games = 0;
+        selfplay::run_match(&settings, &[stalemate, Opening::default()], |_| {
+            num_games += 1;
+            num_games < 2
+        });
+        assert_eq!(2, num_games);
+        assert!(start_time.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn termination_formats_correctly() {
+        assert_eq!("0-1", Termination::TimeForfeit(Color::White).to_pgn());
+        assert_eq!("White loses on time", Termination::TimeForfeit(Color::White).to_string());
+        assert_eq!("1-0", Termination::Resignation(Color::Black).to_pgn());
+        assert_eq!("Black resigns", Termination::Resignation(Color::Black).to_string());
+        assert_eq!("1/2-1/2", Termination::DrawAdjudication.to_pgn());
+        assert_eq!("Stalemate", Termination::Rules(GameResult::Stalemate).to_string());
+    }
+
+    #[test]
+    fn test_load_openings() {
+        let path = std::env::temp_dir().join("ladybug_test_load_openings.epd");
+        fs::write(&path, "# comment\nrnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq -\n\n4k3/8/8/8/8/8/4P3/4K3 w - -\n").unwrap();
+        let openings = selfplay::load_openings(&path);
+        fs::write(&path, "invalid\n").unwrap();
+        let invalid_openings = selfplay::load_openings(&path);
+        fs::remove_file(&path).unwrap();
+
+        let openings = openings.unwrap();
+        assert_eq!(2, openings.len());
+        assert_eq!("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", openings[1].start.to_fen());
+        assert!(invalid_openings.unwrap_err().starts_with("line 1:"));
+
+        let path = std::env::temp_dir().join("ladybug_test_load_openings.pgn");
+        fs::write(&path, "[Event \"?\"]\n\n1. e4 c5 2. Nf3 *\n\n[Event \"?\"]\n\n1. d4 d5 *\n").unwrap();
+        let openings = selfplay::load_openings(&path);
+        fs::remove_file(&path).unwrap();
+
+        let openings = openings.unwrap();
+        assert_eq!(2, openings.len());
+        assert_eq!(3, openings[0].moves.len());
+        assert_eq!(Board::from_fen(START_FEN).unwrap(), openings[1].start);
+        assert_eq!(2, openings[1].moves.len());
+    }
+}
@@ -0,0 +1,226 @@
+//! This module evaluates the results of a match.
+//!
+//! The Elo difference is estimated from the score of the first player, with a 95% confidence interval.
+//! The [SPRT](https://www.chessprogramming.org/Sequential_Probability_Ratio_Test) decides whether the Elo difference
+//! is more likely `elo0` or `elo1`, using the normal approximation of the log-likelihood ratio.
+
+use std::fmt::{Display, Formatter};
+
+/// The quantile of the standard normal distribution for a 95% confidence interval.
+const CONFIDENCE_QUANTILE: f64 = 1.959964;
+
+/// The outcome of a game from the point of view of the first player.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Outcome {
+    Win,
+    Draw,
+    Loss,
+}
+
+/// The number of wins, draws and losses of the first player.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct MatchStats {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+/// The parameters of a sequential probability ratio test.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Sprt {
+    /// The Elo difference of the null hypothesis.
+    pub elo0: f64,
+    /// The Elo difference of the alternative hypothesis.
+    pub elo1: f64,
+    /// The probability of accepting the alternative hypothesis although the null hypothesis is true.
+    pub alpha: f64,
+    /// The probability of accepting the null hypothesis although the alternative hypothesis is true.
+    pub beta: f64,
+}
+
+/// The state of a sequential probability ratio test.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum SprtVerdict {
+    /// The Elo difference is more likely `elo0`.
+    AcceptH0,
+    /// The Elo difference is more likely `elo1`.
+    AcceptH1,
+    /// More games are needed to decide.
+    Continue,
+}
+
+impl MatchStats {
+    /// Counts the given outcome.
+    pub fn add(&mut self, outcome: Outcome) {
+        match outcome {
+            Outcome::Win => self.wins += 1,
+            Outcome::Draw => self.draws += 1,
+            Outcome::Loss => self.losses += 1,
+        }
+    }
+
+    /// Returns the number of games played.
+    pub fn num_games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Returns the average score per game, counting wins as 1 and draws as 0.5.
+    pub fn score(&self) -> f64 {
+        match self.num_games() {
+            0 => 0.5,
+            num_games => (self.wins as f64 + 0.5 * self.draws as f64) / num_games as f64,
+        }
+    }
+
+    /// Returns the estimated Elo difference.
+    pub fn elo(&self) -> f64 {
+        score_to_elo(self.score())
+    }
+
+    /// Returns the half width of the 95% confidence interval of the Elo difference.
+    pub fn elo_error(&self) -> f64 {
+        if self.num_games() == 0 {
+            return 0.0;
+        }
+        let score = self.score();
+        let deviation = CONFIDENCE_QUANTILE * (self.variance() / self.num_games() as f64).sqrt();
+        (score_to_elo((score + deviation).min(1.0)) - score_to_elo((score - deviation).max(0.0))) / 2.0
+    }
+
+    /// Returns the variance of the score of a single game.
+    fn variance(&self) -> f64 {
+        let score = self.score();
+        let squared_deviations = self.wins as f64 * (1.0 - score).powi(2)
+            + self.draws as f64 * (0.5 - score).powi(2)
+            + self.losses as f64 * score.powi(2);
+        squared_deviations / self.num_games() as f64
+    }
+}
+
+/// Prints the results and the Elo difference, e.g. "+12 =30 -8, Elo: 13.9 +/- 52.1".
+/// The error is printed as undefined while the confidence interval is unbounded, e.g. after a few games without a loss.
+impl Display for MatchStats {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "+{} ={} -{}, Elo: {:.1} +/- ", self.wins, self.draws, self.losses, self.elo())?;
+        match self.elo_error() {
+            error if error.is_finite() => write!(f, "{error:.1}"),
+            _ => write!(f, "undefined"),
+        }
+    }
+}
+
+impl Default for Sprt {
+    /// Default constructor for `Sprt`. Tests whether a change gains at least 5 Elo, with error rates of 5%.
+    fn default() -> Self {
+        Self {
+            elo0: 0.0,
+            elo1: 5.0,
+            alpha: 0.05,
+            beta: 0.05,
+        }
+    }
+}
+
+impl Sprt {
+    /// Returns the log-likelihood ratio of the two hypotheses for the given results.
+    pub fn llr(&self, stats: &MatchStats) -> f64 {
+        if stats.num_games() == 0 || stats.variance() <= 0.0 {
+            return 0.0;
+        }
+        let score0 = elo_to_score(self.elo0);
+        let score1 = elo_to_score(self.elo1);
+        stats.num_games() as f64 * (score1 - score0) * (2.0 * stats.score() - score0 - score1) / (2.0 * stats.variance())
+    }
+
+    /// Returns the lower and upper bound of the log-likelihood ratio, at which the null or alternative hypothesis is accepted.
+    pub fn bounds(&self) -> (f64, f64) {
+        ((self.beta / (1.0 - self.alpha)).ln(), ((1.0 - self.beta) / self.alpha).ln())
+    }
+
+    /// Decides the test for the given results.
+    pub fn verdict(&self, stats: &MatchStats) -> SprtVerdict {
+        let llr = self.llr(stats);
+        let (lower_bound, upper_bound) = self.bounds();
+        if llr >= upper_bound {
+            SprtVerdict::AcceptH1
+        } else if llr <= lower_bound {
+            SprtVerdict::AcceptH0
+        } else {
+            SprtVerdict::Continue
+        }
+    }
+}
+
+/// Prints the verdict, e.g. "H1 accepted".
+impl Display for SprtVerdict {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            SprtVerdict::AcceptH0 => write!(f, "H0 accepted"),
+            SprtVerdict::AcceptH1 => write!(f, "H1 accepted"),
+            SprtVerdict::Continue => write!(f, "continue"),
+        }
+    }
+}
+
+/// Converts an average score into an Elo difference.
+fn score_to_elo(score: f64) -> f64 {
+    400.0 * (score / (1.0 - score)).log10()
+}
+
+/// Converts an Elo difference into the expected average score.
+fn elo_to_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::selfplay::stats::{elo_to_score, MatchStats, Outcome, score_to_elo, Sprt, SprtVerdict};
+
+    #[test]
+    fn test_add() {
+        let mut stats = MatchStats::default();
+        stats.add(Outcome::Win);
+        stats.add(Outcome::Draw);
+        stats.add(Outcome::Draw);
+        stats.add(Outcome::Loss);
+        assert_eq!(MatchStats { wins: 1, draws: 2, losses: 1 }, stats);
+        assert_eq!(4, stats.num_games());
+        assert_eq!(0.5, stats.score());
+    }
+
+    #[test]
+    fn test_elo() {
+        assert!((score_to_elo(0.75) - 190.85).abs() < 0.01);
+        assert!((elo_to_score(190.85) - 0.75).abs() < 0.0001);
+
+        let stats = MatchStats { wins: 60, draws: 20, losses: 20 };
+        assert!((stats.elo() - 147.19).abs() < 0.01);
+        assert!((stats.elo_error() - 66.0).abs() < 0.1);
+
+        let stats = MatchStats { wins: 20, draws: 60, losses: 20 };
+        assert_eq!(0.0, stats.elo());
+        assert_eq!("+20 =60 -20, Elo: 0.0 +/- 43.3", stats.to_string());
+
+        let stats = MatchStats { wins: 3, draws: 0, losses: 1 };
+        assert_eq!(f64::INFINITY, stats.elo_error());
+        assert_eq!("+3 =0 -1, Elo: 190.8 +/- undefined", stats.to_string());
+        assert_eq!("+2 =0 -0, Elo: inf +/- undefined", MatchStats { wins: 2, draws: 0, losses: 0 }.to_string());
+
+        assert_eq!(0.0, MatchStats::default().elo());
+        assert_eq!(0.0, MatchStats::default().elo_error());
+    }
+
+    #[test]
+    fn test_sprt() {
+        let sprt = Sprt::default();
+        let (lower_bound, upper_bound) = sprt.bounds();
+        assert!((lower_bound + 2.944).abs() < 0.001);
+        assert!((upper_bound - 2.944).abs() < 0.001);
+
+        assert_eq!(0.0, sprt.llr(&MatchStats::default()));
+        assert_eq!(SprtVerdict::Continue, sprt.verdict(&MatchStats { wins: 10, draws: 10, losses: 8 }));
+        assert_eq!(SprtVerdict::AcceptH1, sprt.verdict(&MatchStats { wins: 1200, draws: 1000, losses: 1000 }));
+        assert_eq!(SprtVerdict::AcceptH0, sprt.verdict(&MatchStats { wins: 1000, draws: 1000, losses: 1200 }));
+        assert_eq!("H1 accepted", SprtVerdict::AcceptH1.to_string());
+    }
+}
@@ -0,0 +1,189 @@
+//! The suite module runs EPD test suites like WAC or STS against Ladybug.
+//!
+//! Every position of a suite is searched with the given limit, and the move Ladybug plays is compared to the
+//! `bm` (best move) and `am` (avoid move) operations of the position.
+//! The positions are sent to Ladybug as UCI commands, exactly like a GUI would do it.
+
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::sync::mpsc;
+use std::sync::mpsc::{Receiver, Sender};
+use std::thread;
+use crate::board::epd::Epd;
+use crate::ladybug::{Ladybug, Message};
+use crate::move_gen::ply::Ply;
+use crate::search::{Search, SearchCommand};
+
+/// The limit of the search for each position of a suite.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum SuiteLimit {
+    /// Search to the given depth.
+    Depth(u64),
+    /// Search for the given number of milliseconds.
+    MoveTime(u64),
+    /// Search the given number of nodes.
+    Nodes(u64),
+}
+
+/// The outcome of a single position of a suite.
+#[derive(Clone, PartialEq, Debug)]
+pub struct SuiteResult {
+    /// The id of the position, or its position in the suite if it has no id.
+    pub id: String,
+    /// The move Ladybug played, in SAN.
+    pub played_move: String,
+    /// The expected best moves in SAN.
+    pub best_moves: Vec<String>,
+    /// The moves to avoid in SAN.
+    pub avoid_moves: Vec<String>,
+    /// Whether the played move is one of the best moves and none of the moves to avoid.
+    pub passed: bool,
+}
+
+/// Runs suite positions on a dedicated Ladybug instance.
+pub struct SuiteRunner {
+    /// Used to send UCI commands to Ladybug.
+    message_sender: Sender<Message>,
+    /// Used to receive Ladybug's output.
+    output_receiver: Receiver<String>,
+}
+
+impl SuiteLimit {
+    /// Returns the UCI go command for the limit.
+    fn to_go_command(self) -> String {
+        match self {
+            SuiteLimit::Depth(depth) => format!("go depth {depth}"),
+            SuiteLimit::MoveTime(time) => format!("go movetime {time}"),
+            SuiteLimit::Nodes(nodes) => format!("go nodes {nodes}"),
+        }
+    }
+}
+
+impl Default for SuiteRunner {
+    /// Default constructor for `SuiteRunner`.
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SuiteRunner {
+    /// Spawns the Ladybug and search threads and constructs a runner connected to them.
+    pub fn new() -> Self {
+        let (search_command_sender, search_command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
+        let (message_sender, message_receiver): (Sender<Message>, Receiver<Message>) = mpsc::channel();
+        let (output_sender, output_receiver): (Sender<String>, Receiver<String>) = mpsc::channel();
+
+        let mut search = Search::new(search_command_receiver, message_sender.clone());
+        let _ = thread::Builder::new().name("search".to_string()).spawn(move || search.run());
+
+        let mut ladybug = Ladybug::new(search_command_sender, output_sender, message_receiver);
+        thread::spawn(move || ladybug.run());
+
+        Self {
+            message_sender,
+            output_receiver,
+        }
+    }
+
+    /// Searches the given position with the given limit and checks the played move against the `bm` and `am` operations.
+    /// The given index is used as id if the position has no `id` operation.
+    pub fn run_position(&self, epd: &Epd, index: usize, limit: SuiteLimit) -> Result<SuiteResult, String> {
+        let best_moves = epd.get_best_moves()?;
+        let avoid_moves = epd.get_avoid_moves()?;
+        if best_moves.is_empty() && avoid_moves.is_empty() {
+            return Err(String::from("position has neither a bm nor an am operation"));
+        }
+
+        // search the position and wait for the best move
+        self.send(String::from("ucinewgame"));
+        self.send(format!("position fen {}", epd.board.to_fen()));
+        self.send(limit.to_go_command());
+        let played_move = loop {
+            let output = self.output_receiver.recv().map_err(|error| error.to_string())?;
+            if let Some(uci_move) = output.strip_prefix("bestmove ") {
+                break Ply::from_string(uci_move, epd.board.position).map_err(|error| format!("invalid best move: {error}"))?;
+            }
+            if output.starts_with("info string no legal moves") {
+                return Err(String::from("position has no legal moves"));
+            }
+        };
+
+        let position = &epd.board.position;
+        let to_san = |plies: &Vec<Ply>| plies.iter().map(|ply| ply.to_san(position)).collect::<Vec<String>>();
+        Ok(SuiteResult {
+            id: epd.get_id().map_or(format!("#{index}"), String::from),
+            played_move: played_move.to_san(position),
+            best_moves: to_san(&best_moves),
+            avoid_moves: to_san(&avoid_moves),
+            passed: (best_moves.is_empty() || best_moves.contains(&played_move)) && !avoid_moves.contains(&played_move),
+        })
+    }
+
+    /// Sends the given UCI command to Ladybug.
+    fn send(&self, command: String) {
+        let _ = self.message_sender.send(Message::ConsoleMessage(command));
+    }
+}
+
+/// Reads the positions of an EPD suite from the given file.
+///
+/// Empty lines and lines starting with `#` are skipped. Lines that can not be parsed are returned as errors, together with their line number.
+pub fn load_suite(path: &Path) -> io::Result<Vec<Result<Epd, String>>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut positions: Vec<Result<Epd, String>> = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let trimmed_line = line.trim();
+        if trimmed_line.is_empty() || trimmed_line.starts_with('#') {
+            continue;
+        }
+        positions.push(Epd::parse(trimmed_line).map_err(|error| format!("line {}: {error}", index + 1)));
+    }
+    Ok(positions)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use crate::board::epd::Epd;
+    use crate::suite;
+    use crate::suite::{SuiteLimit, SuiteRunner};
+
+    #[test]
+    fn run_position_checks_best_moves() {
+        let runner = SuiteRunner::new();
+
+        let epd = Epd::parse("8/8/1Q6/8/7B/2R4N/5K1P/k7 w - - bm Ra3#; id \"mate\";").unwrap();
+        let result = runner.run_position(&epd, 1, SuiteLimit::Depth(1)).unwrap();
+        assert_eq!("mate", result.id);
+        assert_eq!("Ra3#", result.played_move);
+        assert_eq!(vec![String::from("Ra3#")], result.best_moves);
+        assert!(result.passed);
+
+        let epd = Epd::parse("8/8/1Q6/8/7B/2R4N/5K1P/k7 w - - am Ra3#;").unwrap();
+        let result = runner.run_position(&epd, 2, SuiteLimit::Depth(1)).unwrap();
+        assert_eq!("#2", result.id);
+        assert!(!result.passed);
+    }
+
+    #[test]
+    fn run_position_rejects_positions_without_expectations() {
+        let runner = SuiteRunner::new();
+        let epd = Epd::parse("8/8/1Q6/8/7B/2R4N/5K1P/k7 w - - id \"no bm\";").unwrap();
+        assert!(runner.run_position(&epd, 1, SuiteLimit::MoveTime(10)).is_err());
+    }
+
+    #[test]
+    fn test_load_suite() {
+        let path = std::env::temp_dir().join("ladybug_test_load_suite.epd");
+        fs::write(&path, "# comment\n8/8/1Q6/8/7B/2R4N/5K1P/k7 w - - bm Ra3#;\n\ninvalid\n").unwrap();
+        let positions = suite::load_suite(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(2, positions.len());
+        assert!(positions[0].is_ok());
+        assert!(positions[1].as_ref().unwrap_err().starts_with("line 4:"));
+    }
+}
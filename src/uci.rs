@@ -1,3 +1,8 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use crate::board::fen::FenError;
+use crate::move_gen::ply::MoveParseError;
+
 /// Represents a UCI command.
 #[derive(PartialEq, Debug)]
 pub enum UciCommand {
@@ -8,20 +13,89 @@ pub enum UciCommand {
     GoClockTime(Vec<String>),
     GoMoveTime(String),
     GoDepth(String),
+    GoNodes(String),
+    GoInfinite,
     GoPerft(String),
+    Stop,
     Quit,
     Help,
     Display,
+    Eval,
+    EvalJson,
+    SetOption(String, String),
+    Bench(Option<String>),
+}
+
+/// An error that occurred while parsing or handling a UCI command.
+#[derive(Clone, PartialEq, Debug)]
+pub enum UciError {
+    /// The input contains no command.
+    EmptyCommand,
+    /// The command is unknown. Contains the command.
+    UnknownCommand(String),
+    /// The command is known, but the argument is not. Contains the argument.
+    UnknownArgument(String),
+    /// The command requires an argument which is missing. Contains the command.
+    MissingArgument(String),
+    /// The command takes no further arguments. Contains the first surplus argument.
+    UnexpectedArgument(String),
+    /// An argument has an invalid value. Contains the name of the argument and the value.
+    InvalidValue(String, String),
+    /// The option of a "setoption" command is unknown. Contains the name of the option.
+    UnknownOption(String),
+    /// The FEN of a "position" command is invalid.
+    InvalidFen(FenError),
+    /// A move of a "position" command is invalid or illegal.
+    InvalidMove(MoveParseError),
+}
+
+/// Prints the error as text.
+impl Display for UciError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            UciError::EmptyCommand => write!(f, "empty command"),
+            UciError::UnknownCommand(command) => write!(f, "unknown command: {command}"),
+            UciError::UnknownArgument(argument) => write!(f, "unknown argument: {argument}"),
+            UciError::MissingArgument(command) => write!(f, "missing argument for {command}"),
+            UciError::UnexpectedArgument(argument) => write!(f, "unexpected argument: {argument}"),
+            UciError::InvalidValue(name, value) => write!(f, "invalid value for {name}: {value}"),
+            UciError::UnknownOption(name) => write!(f, "unknown option: {name}"),
+            UciError::InvalidFen(error) => write!(f, "invalid fen: {error}"),
+            UciError::InvalidMove(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl Error for UciError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            UciError::InvalidFen(error) => Some(error),
+            UciError::InvalidMove(error) => Some(error),
+            _other => None,
+        }
+    }
+}
+
+impl From<FenError> for UciError {
+    fn from(error: FenError) -> Self {
+        UciError::InvalidFen(error)
+    }
+}
+
+impl From<MoveParseError> for UciError {
+    fn from(error: MoveParseError) -> Self {
+        UciError::InvalidMove(error)
+    }
 }
 
 /// Tries to parse a string as a UCI command.
-pub fn parse_uci(input: String) -> Result<UciCommand, String> {
+pub fn parse_uci(input: String) -> Result<UciCommand, UciError> {
     // split input string into parts
     let mut uci_parts: Vec<String> = input.split_whitespace().map(|s| s.to_string()).collect();
     
     // if uci_parts is empty, return error
     if uci_parts.is_empty() {
-        return Err(String::from("info string unknown command"))
+        return Err(UciError::EmptyCommand)
     }
     
     // return matching uci command, otherwise error
@@ -31,61 +105,86 @@ pub fn parse_uci(input: String) -> Result<UciCommand, String> {
         "ucinewgame" => Ok(UciCommand::UciNewGame),
         "position" => {
             match uci_parts.len() > 1 {
-                false => Err(String::from("info string unknown command")),
+                false => Err(UciError::MissingArgument(String::from("position"))),
                 true => Ok(UciCommand::Position(uci_parts.split_off(1)))
             }
         }
         "go" => {
             if uci_parts.len() < 2 {
-                Err(String::from("info string unknown command"))
+                Err(UciError::MissingArgument(String::from("go")))
             } else {
                 match uci_parts[1].as_str() {
-                    "depth" => {
-                        if uci_parts.len() != 3 {
-                            Err(String::from("info string unknown command"))
-                        }
-                        else {
-                            Ok(UciCommand::GoDepth(uci_parts[2].clone()))
-                        }
-                    }
-                    "perft" => {
-                        if uci_parts.len() != 3 {
-                            Err(String::from("info string unknown command"))
-                        }
-                        else {
-                            Ok(UciCommand::GoPerft(uci_parts[2].clone()))
-                        }
-                    }
+                    "depth" => parse_single_argument(&uci_parts).map(UciCommand::GoDepth),
+                    "nodes" => parse_single_argument(&uci_parts).map(UciCommand::GoNodes),
+                    "perft" => parse_single_argument(&uci_parts).map(UciCommand::GoPerft),
+                    "infinite" => match uci_parts.len() {
+                        2 => Ok(UciCommand::GoInfinite),
+                        _ => Err(UciError::UnexpectedArgument(uci_parts[2].clone())),
+                    },
                     "wtime" => Ok(UciCommand::GoClockTime(uci_parts.split_off(1))),
-                    "movetime" => {
-                        if uci_parts.len() != 3 {
-                            Err(String::from("info string unknown command"))
-                        }
-                        else {
-                            Ok(UciCommand::GoMoveTime(uci_parts[2].clone()))
-                        }
-                    }
-                    _other => Err(String::from("info string unknown command"))
+                    "movetime" => parse_single_argument(&uci_parts).map(UciCommand::GoMoveTime),
+                    other => Err(UciError::UnknownArgument(String::from(other)))
                 }
             }
         }
+        "stop" => Ok(UciCommand::Stop),
         "quit" => Ok(UciCommand::Quit),
         "help" => Ok(UciCommand::Help),
         "display" => Ok(UciCommand::Display),
-        _other => Err(String::from("info string unknown command")),
+        "setoption" => {
+            // the option name and value may both contain spaces
+            let value_index = uci_parts.iter().position(|part| part == "value").unwrap_or(uci_parts.len());
+            if uci_parts.len() > 1 && uci_parts[1] != "name" {
+                return Err(UciError::UnknownArgument(uci_parts[1].clone()));
+            }
+            if uci_parts.len() < 3 || value_index < 3 {
+                return Err(UciError::MissingArgument(String::from("setoption name")));
+            }
+            let name = uci_parts[2..value_index].join(" ");
+            let value = uci_parts.get(value_index + 1..).map(|parts| parts.join(" ")).unwrap_or_default();
+            Ok(UciCommand::SetOption(name, value))
+        }
+        "eval" => {
+            match uci_parts.len() {
+                1 => Ok(UciCommand::Eval),
+                2 if uci_parts[1] == "--json" => Ok(UciCommand::EvalJson),
+                _ => Err(UciError::UnexpectedArgument(uci_parts[1].clone())),
+            }
+        }
+        "bench" => {
+            match uci_parts.len() {
+                1 => Ok(UciCommand::Bench(None)),
+                2 => Ok(UciCommand::Bench(Some(uci_parts[1].clone()))),
+                _ => Err(UciError::UnexpectedArgument(uci_parts[2].clone())),
+            }
+        }
+        other => Err(UciError::UnknownCommand(String::from(other))),
+    }
+}
+
+/// Returns the single argument of a "go" command like "go depth 5".
+fn parse_single_argument(uci_parts: &[String]) -> Result<String, UciError> {
+    match uci_parts.len() {
+        0..=2 => Err(UciError::MissingArgument(uci_parts.join(" "))),
+        3 => Ok(uci_parts[2].clone()),
+        _ => Err(UciError::UnexpectedArgument(uci_parts[3].clone())),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::uci;
-    use crate::uci::UciCommand;
+    use crate::board::fen::FenError;
+    use crate::move_gen::ply::MoveParseError;
+    use crate::uci::{UciCommand, UciError};
 
     #[test]
     fn parse_uci_with_invalid_input_returns_error() {
-        assert_eq!(Err(String::from("info string unknown command")), uci::parse_uci(String::from("Not Uci")));
-        assert_eq!(Err(String::from("info string unknown command")), uci::parse_uci(String::from("       ")));
-        assert_eq!(Err(String::from("info string unknown command")), uci::parse_uci(String::from("123456789")));
+        assert_eq!(Err(UciError::UnknownCommand(String::from("Not"))), uci::parse_uci(String::from("Not Uci")));
+        assert_eq!(Err(UciError::EmptyCommand), uci::parse_uci(String::from("       ")));
+        assert_eq!(Err(UciError::UnknownCommand(String::from("123456789"))), uci::parse_uci(String::from("123456789")));
+        assert_eq!(Err(UciError::UnknownArgument(String::from("ponder"))), uci::parse_uci(String::from("go ponder")));
+        assert_eq!(Err(UciError::MissingArgument(String::from("go"))), uci::parse_uci(String::from("go")));
     }
 
     #[test]
@@ -105,7 +204,7 @@ mod tests {
 
     #[test]
     fn test_parse_uci_for_position() {
-        assert_eq!(Err(String::from("info string unknown command")), uci::parse_uci(String::from("position")));
+        assert_eq!(Err(UciError::MissingArgument(String::from("position"))), uci::parse_uci(String::from("position")));
         
         assert_eq!(Ok(UciCommand::Position(vec!(String::from("startpos"), String::from("moves"), String::from("h3h4"), String::from("c6g2")))),
                    uci::parse_uci(String::from("position startpos moves h3h4 c6g2")));
@@ -129,21 +228,38 @@ mod tests {
 
     #[test]
     fn test_parse_uci_for_go_depth() {
-        assert_eq!(Err("info string unknown command".to_string()), uci::parse_uci(String::from("go depth")));
+        assert_eq!(Err(UciError::MissingArgument("go depth".to_string())), uci::parse_uci(String::from("go depth")));
         assert_eq!(UciCommand::GoDepth("5".to_string()), uci::parse_uci(String::from("go depth 5")).unwrap());
         assert_eq!(UciCommand::GoDepth("10".to_string()), uci::parse_uci(String::from("go depth 10")).unwrap());
     }
     
+    #[test]
+    fn test_parse_uci_for_go_nodes() {
+        assert_eq!(Err(UciError::MissingArgument("go nodes".to_string())), uci::parse_uci(String::from("go nodes")));
+        assert_eq!(UciCommand::GoNodes("10000".to_string()), uci::parse_uci(String::from("go nodes 10000")).unwrap());
+    }
+
+    #[test]
+    fn test_parse_uci_for_go_infinite() {
+        assert_eq!(Err(UciError::UnexpectedArgument(String::from("depth"))), uci::parse_uci(String::from("go infinite depth 5")));
+        assert_eq!(UciCommand::GoInfinite, uci::parse_uci(String::from("go infinite")).unwrap());
+    }
+
     #[test]
     fn test_parse_uci_for_go_perft() {
-        assert_eq!(Err(String::from("info string unknown command")), uci::parse_uci(String::from("go perft")));
-        assert_eq!(Err(String::from("info string unknown command")), uci::parse_uci(String::from("go perft one two")));
+        assert_eq!(Err(UciError::MissingArgument(String::from("go perft"))), uci::parse_uci(String::from("go perft")));
+        assert_eq!(Err(UciError::UnexpectedArgument(String::from("two"))), uci::parse_uci(String::from("go perft one two")));
         
         assert_eq!(UciCommand::GoPerft(String::from("5")), uci::parse_uci(String::from("go perft 5")).unwrap());
         assert_eq!(UciCommand::GoPerft(String::from("0")), uci::parse_uci(String::from("go perft 0")).unwrap());
         assert_eq!(UciCommand::GoPerft(String::from("100")), uci::parse_uci(String::from("go perft 100")).unwrap());
     }
 
+    #[test]
+    fn test_parse_uci_for_stop() {
+        assert_eq!(UciCommand::Stop, uci::parse_uci(String::from("stop")).unwrap());
+    }
+
     #[test]
     fn test_parse_uci_for_quit() {
         assert_eq!(UciCommand::Quit, uci::parse_uci(String::from("quit")).unwrap());
@@ -158,4 +274,40 @@ mod tests {
     fn test_parse_uci_for_display() {
         assert_eq!(UciCommand::Display, uci::parse_uci(String::from("display")).unwrap());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_parse_uci_for_setoption() {
+        assert_eq!(Ok(UciCommand::SetOption(String::from("UCI_Chess960"), String::from("true"))),
+                   uci::parse_uci(String::from("setoption name UCI_Chess960 value true")));
+        assert_eq!(Ok(UciCommand::SetOption(String::from("Clear Hash"), String::new())),
+                   uci::parse_uci(String::from("setoption name Clear Hash")));
+        assert_eq!(Ok(UciCommand::SetOption(String::from("Debug Log File"), String::from("C:\\My Logs\\ladybug.log"))),
+                   uci::parse_uci(String::from("setoption name Debug Log File value C:\\My Logs\\ladybug.log")));
+        assert_eq!(Err(UciError::MissingArgument(String::from("setoption name"))), uci::parse_uci(String::from("setoption")));
+        assert_eq!(Err(UciError::UnknownArgument(String::from("UCI_Chess960"))), uci::parse_uci(String::from("setoption UCI_Chess960")));
+        assert_eq!(Err(UciError::MissingArgument(String::from("setoption name"))), uci::parse_uci(String::from("setoption name value x")));
+    }
+
+    #[test]
+    fn test_parse_uci_for_bench() {
+        assert_eq!(UciCommand::Bench(None), uci::parse_uci(String::from("bench")).unwrap());
+        assert_eq!(UciCommand::Bench(Some(String::from("3"))), uci::parse_uci(String::from("bench 3")).unwrap());
+        assert_eq!(Err(UciError::UnexpectedArgument(String::from("x"))), uci::parse_uci(String::from("bench 3 x")));
+    }
+
+    #[test]
+    fn test_parse_uci_for_eval() {
+        assert_eq!(UciCommand::Eval, uci::parse_uci(String::from("eval")).unwrap());
+        assert_eq!(UciCommand::EvalJson, uci::parse_uci(String::from("eval --json")).unwrap());
+        assert_eq!(Err(UciError::UnexpectedArgument(String::from("json"))), uci::parse_uci(String::from("eval json")));
+    }
+
+    #[test]
+    fn uci_error_formats_correctly() {
+        assert_eq!("unknown command: foo", UciError::UnknownCommand(String::from("foo")).to_string());
+        assert_eq!("missing argument for go depth", UciError::MissingArgument(String::from("go depth")).to_string());
+        assert_eq!("invalid value for depth: ten", UciError::InvalidValue(String::from("depth"), String::from("ten")).to_string());
+        assert_eq!("invalid fen: invalid color to move: x", UciError::from(FenError::InvalidColorToMove(String::from("x"))).to_string());
+        assert_eq!("illegal move: e2e5", UciError::from(MoveParseError::IllegalMove(String::from("e2e5"))).to_string());
+    }
+}
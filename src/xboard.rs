@@ -0,0 +1,243 @@
+//! The xboard module parses commands of the [Chess Engine Communication Protocol](https://www.gnu.org/software/xboard/engine-intf.html) (CECP),
+//! which is spoken by xboard, WinBoard and many chess servers.
+//!
+//! Ladybug detects the protocol from the first command she receives: if it is `xboard` or `protover`, all further input is
+//! parsed as CECP, otherwise as UCI.
+
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+/// Represents an xboard command.
+#[derive(PartialEq, Debug)]
+pub enum XboardCommand {
+    Xboard,
+    /// The protocol version of the GUI, which expects the supported features in response.
+    Protover(u32),
+    /// The GUI accepted or rejected a feature. Contains the name of the feature.
+    Accepted(String),
+    Rejected(String),
+    New,
+    /// A move of the opponent in coordinate notation, given with or without the `usermove` prefix.
+    UserMove(String),
+    Go,
+    Force,
+    /// Play the best move found so far, sent as `?`.
+    MoveNow,
+    /// A conventional or incremental time control: the number of moves per session (zero for all moves), the base time and the increment.
+    Level(u32, Duration, Duration),
+    /// A fixed time per move, set with `st`.
+    MoveTime(Duration),
+    /// A depth limit, set with `sd`.
+    Depth(u64),
+    /// The remaining time on Ladybug's clock, set with `time`.
+    Time(Duration),
+    /// The remaining time on the opponent's clock, set with `otim`.
+    OpponentTime(Duration),
+    Undo,
+    Remove,
+    /// Sets up the position given as FEN.
+    SetBoard(String),
+    /// The game ended with the given result, which may be followed by a comment.
+    Result(String),
+    Post,
+    NoPost,
+    Ping(String),
+    Quit,
+    /// A command that is valid but has no effect on Ladybug, e.g. `hard` or `computer`. Contains the command.
+    Ignore(String),
+}
+
+/// An error that occurred while parsing an xboard command.
+#[derive(Clone, PartialEq, Debug)]
+pub enum XboardError {
+    /// The input contains no command.
+    EmptyCommand,
+    /// The command is unknown. Contains the command.
+    UnknownCommand(String),
+    /// The command requires an argument which is missing. Contains the command.
+    MissingArgument(String),
+    /// An argument has an invalid value. Contains the name of the argument and the value.
+    InvalidValue(String, String),
+}
+
+/// Prints the error as text.
+impl Display for XboardError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            XboardError::EmptyCommand => write!(f, "empty command"),
+            XboardError::UnknownCommand(_) => write!(f, "unknown command"),
+            XboardError::MissingArgument(command) => write!(f, "missing argument for {command}"),
+            XboardError::InvalidValue(name, value) => write!(f, "invalid value for {name}: {value}"),
+        }
+    }
+}
+
+impl Error for XboardError {}
+
+/// Checks whether the given input is the first command of an xboard session.
+pub fn is_xboard_handshake(input: &str) -> bool {
+    matches!(input.split_whitespace().next(), Some("xboard" | "protover"))
+}
+
+/// Tries to parse a string as an xboard command.
+pub fn parse_xboard(input: String) -> Result<XboardCommand, XboardError> {
+    // split input string into parts
+    let xboard_parts: Vec<&str> = input.split_whitespace().collect();
+
+    // if xboard_parts is empty, return error
+    let Some(command) = xboard_parts.first() else {
+        return Err(XboardError::EmptyCommand);
+    };
+    let argument = |index: usize| xboard_parts.get(index).copied().ok_or(XboardError::MissingArgument(command.to_string()));
+
+    // return matching xboard command, otherwise error
+    match *command {
+        "xboard" => Ok(XboardCommand::Xboard),
+        "protover" => parse_value(argument(1)?, "protover").map(XboardCommand::Protover),
+        "accepted" => Ok(XboardCommand::Accepted(argument(1)?.to_string())),
+        "rejected" => Ok(XboardCommand::Rejected(argument(1)?.to_string())),
+        "new" => Ok(XboardCommand::New),
+        "usermove" => Ok(XboardCommand::UserMove(argument(1)?.to_string())),
+        "go" => Ok(XboardCommand::Go),
+        "force" => Ok(XboardCommand::Force),
+        "?" => Ok(XboardCommand::MoveNow),
+        "level" => {
+            let moves = parse_value(argument(1)?, "moves")?;
+            let base = parse_base_time(argument(2)?)?;
+            let increment = parse_seconds(argument(3)?, "increment")?;
+            Ok(XboardCommand::Level(moves, base, increment))
+        }
+        "st" => parse_seconds(argument(1)?, "st").map(XboardCommand::MoveTime),
+        "sd" => parse_value(argument(1)?, "sd").map(XboardCommand::Depth),
+        "time" => parse_centiseconds(argument(1)?, "time").map(XboardCommand::Time),
+        "otim" => parse_centiseconds(argument(1)?, "otim").map(XboardCommand::OpponentTime),
+        "undo" => Ok(XboardCommand::Undo),
+        "remove" => Ok(XboardCommand::Remove),
+        "setboard" => match xboard_parts.len() {
+            1 => Err(XboardError::MissingArgument(String::from("setboard"))),
+            _ => Ok(XboardCommand::SetBoard(xboard_parts[1..].join(" "))),
+        },
+        "result" => match xboard_parts.len() {
+            1 => Err(XboardError::MissingArgument(String::from("result"))),
+            _ => Ok(XboardCommand::Result(xboard_parts[1..].join(" "))),
+        },
+        "post" => Ok(XboardCommand::Post),
+        "nopost" => Ok(XboardCommand::NoPost),
+        "ping" => Ok(XboardCommand::Ping(argument(1)?.to_string())),
+        "quit" => Ok(XboardCommand::Quit),
+        "random" | "hard" | "easy" | "computer" | "name" | "rating" | "ics" | "variant" | "white" | "black" => Ok(XboardCommand::Ignore(command.to_string())),
+        // without the usermove feature, moves are sent without a prefix
+        other if is_coordinate_move(other) => Ok(XboardCommand::UserMove(String::from(other))),
+        other => Err(XboardError::UnknownCommand(String::from(other))),
+    }
+}
+
+/// Checks whether the given string looks like a move in coordinate notation, e.g. `e2e4` or `e7e8q`.
+fn is_coordinate_move(input: &str) -> bool {
+    let chars: Vec<char> = input.chars().collect();
+    (4..=5).contains(&chars.len())
+        && ('a'..='h').contains(&chars[0]) && ('1'..='8').contains(&chars[1])
+        && ('a'..='h').contains(&chars[2]) && ('1'..='8').contains(&chars[3])
+        && chars.get(4).is_none_or(|promotion| ['q', 'r', 'b', 'n'].contains(promotion))
+}
+
+/// Parses the given argument as a number.
+fn parse_value<T: std::str::FromStr>(value: &str, name: &str) -> Result<T, XboardError> {
+    value.parse().map_err(|_| XboardError::InvalidValue(String::from(name), String::from(value)))
+}
+
+/// Parses the given number of seconds, which may have a fractional part.
+fn parse_seconds(value: &str, name: &str) -> Result<Duration, XboardError> {
+    parse_value::<f64>(value, name)
+        .ok()
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+        .ok_or(XboardError::InvalidValue(String::from(name), String::from(value)))
+}
+
+/// Parses the given number of centiseconds. Negative values, which are sent when a clock has run out, count as zero.
+fn parse_centiseconds(value: &str, name: &str) -> Result<Duration, XboardError> {
+    parse_value::<i64>(value, name).map(|centiseconds| Duration::from_millis(centiseconds.max(0) as u64 * 10))
+}
+
+/// Parses the base time of a `level` command, which is given in minutes, or in minutes and seconds like `0:30`.
+fn parse_base_time(value: &str) -> Result<Duration, XboardError> {
+    let invalid_value = || XboardError::InvalidValue(String::from("base"), String::from(value));
+    let (minutes, seconds) = value.split_once(':').unwrap_or((value, "0"));
+    let minutes: u64 = minutes.parse().map_err(|_| invalid_value())?;
+    let seconds: u64 = seconds.parse().map_err(|_| invalid_value())?;
+    Ok(Duration::from_secs(minutes * 60 + seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use crate::xboard;
+    use crate::xboard::{XboardCommand, XboardError};
+
+    #[test]
+    fn parse_xboard_with_invalid_input_returns_error() {
+        assert_eq!(Err(XboardError::EmptyCommand), xboard::parse_xboard(String::from("   ")));
+        assert_eq!(Err(XboardError::UnknownCommand(String::from("foo"))), xboard::parse_xboard(String::from("foo bar")));
+        assert_eq!(Err(XboardError::MissingArgument(String::from("protover"))), xboard::parse_xboard(String::from("protover")));
+        assert_eq!(Err(XboardError::InvalidValue(String::from("sd"), String::from("x"))), xboard::parse_xboard(String::from("sd x")));
+        assert_eq!(Err(XboardError::MissingArgument(String::from("level"))), xboard::parse_xboard(String::from("level 40 5")));
+        assert_eq!(Err(XboardError::InvalidValue(String::from("base"), String::from("5:x"))), xboard::parse_xboard(String::from("level 40 5:x 0")));
+        assert_eq!(Err(XboardError::InvalidValue(String::from("st"), String::from("-1"))), xboard::parse_xboard(String::from("st -1")));
+    }
+
+    #[test]
+    fn test_is_xboard_handshake() {
+        assert!(xboard::is_xboard_handshake("xboard"));
+        assert!(xboard::is_xboard_handshake("protover 2\n"));
+        assert!(!xboard::is_xboard_handshake("uci"));
+        assert!(!xboard::is_xboard_handshake(""));
+    }
+
+    #[test]
+    fn test_parse_xboard_for_protocol_commands() {
+        assert_eq!(Ok(XboardCommand::Xboard), xboard::parse_xboard(String::from("xboard")));
+        assert_eq!(Ok(XboardCommand::Protover(2)), xboard::parse_xboard(String::from("protover 2")));
+        assert_eq!(Ok(XboardCommand::Accepted(String::from("usermove"))), xboard::parse_xboard(String::from("accepted usermove")));
+        assert_eq!(Ok(XboardCommand::Rejected(String::from("ping"))), xboard::parse_xboard(String::from("rejected ping")));
+        assert_eq!(Ok(XboardCommand::Ping(String::from("7"))), xboard::parse_xboard(String::from("ping 7")));
+        assert_eq!(Ok(XboardCommand::Ignore(String::from("hard"))), xboard::parse_xboard(String::from("hard")));
+        assert_eq!(Ok(XboardCommand::Quit), xboard::parse_xboard(String::from("quit")));
+    }
+
+    #[test]
+    fn test_parse_xboard_for_game_commands() {
+        assert_eq!(Ok(XboardCommand::New), xboard::parse_xboard(String::from("new")));
+        assert_eq!(Ok(XboardCommand::Go), xboard::parse_xboard(String::from("go")));
+        assert_eq!(Ok(XboardCommand::Force), xboard::parse_xboard(String::from("force")));
+        assert_eq!(Ok(XboardCommand::MoveNow), xboard::parse_xboard(String::from("?")));
+        assert_eq!(Ok(XboardCommand::UserMove(String::from("e2e4"))), xboard::parse_xboard(String::from("usermove e2e4")));
+        assert_eq!(Ok(XboardCommand::UserMove(String::from("e7e8q"))), xboard::parse_xboard(String::from("e7e8q")));
+        assert_eq!(Ok(XboardCommand::UserMove(String::from("a2a1n"))), xboard::parse_xboard(String::from("a2a1n")));
+        assert_eq!(Err(XboardError::UnknownCommand(String::from("e7e8k"))), xboard::parse_xboard(String::from("e7e8k")));
+        assert_eq!(Err(XboardError::UnknownCommand(String::from("e7e81"))), xboard::parse_xboard(String::from("e7e81")));
+        assert_eq!(Ok(XboardCommand::Undo), xboard::parse_xboard(String::from("undo")));
+        assert_eq!(Ok(XboardCommand::Remove), xboard::parse_xboard(String::from("remove")));
+        assert_eq!(Ok(XboardCommand::SetBoard(String::from("4k3/8/8/8/8/8/8/4K3 w - - 0 1"))), xboard::parse_xboard(String::from("setboard 4k3/8/8/8/8/8/8/4K3 w - - 0 1")));
+        assert_eq!(Ok(XboardCommand::Result(String::from("1-0 {White mates}"))), xboard::parse_xboard(String::from("result 1-0 {White mates}")));
+        assert_eq!(Ok(XboardCommand::Post), xboard::parse_xboard(String::from("post")));
+        assert_eq!(Ok(XboardCommand::NoPost), xboard::parse_xboard(String::from("nopost")));
+    }
+
+    #[test]
+    fn test_parse_xboard_for_time_commands() {
+        assert_eq!(Ok(XboardCommand::Level(40, Duration::from_secs(300), Duration::ZERO)), xboard::parse_xboard(String::from("level 40 5 0")));
+        assert_eq!(Ok(XboardCommand::Level(0, Duration::from_secs(90), Duration::from_millis(500))), xboard::parse_xboard(String::from("level 0 1:30 0.5")));
+        assert_eq!(Ok(XboardCommand::MoveTime(Duration::from_secs(10))), xboard::parse_xboard(String::from("st 10")));
+        assert_eq!(Ok(XboardCommand::Depth(6)), xboard::parse_xboard(String::from("sd 6")));
+        assert_eq!(Ok(XboardCommand::Time(Duration::from_millis(12340))), xboard::parse_xboard(String::from("time 1234")));
+        assert_eq!(Ok(XboardCommand::OpponentTime(Duration::ZERO)), xboard::parse_xboard(String::from("otim -20")));
+    }
+
+    #[test]
+    fn xboard_error_formats_correctly() {
+        assert_eq!("unknown command", XboardError::UnknownCommand(String::from("foo")).to_string());
+        assert_eq!("missing argument for level", XboardError::MissingArgument(String::from("level")).to_string());
+        assert_eq!("invalid value for sd: x", XboardError::InvalidValue(String::from("sd"), String::from("x")).to_string());
+    }
+}
@@ -32,8 +32,6 @@ pub fn get_hash(position: &Position) -> u64 {
 mod tests {
     use crate::board::Board;
     use crate::board::position::Position;
-    use crate::lookup::LOOKUP_TABLE;
-    use crate::lookup::lookup_table::LookupTable;
     use crate::move_gen;
     use crate::zobrist::get_hash;
 
@@ -71,10 +69,6 @@ mod tests {
     #[test]
     #[ignore]
     fn test_incremental_hash_updates() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // Position 1 - Starting Position
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
         zobrist_perft(position, 5);
@@ -98,21 +92,17 @@ mod tests {
 
     #[test]
     fn test_get_hash() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // starting position
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
         assert_eq!(0x463b96181691fc9c, get_hash(&position));
 
         // position after e2e4
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap().position;
-        //assert_eq!(0x823c9b50fd114196, get_hash(position));
+        assert_eq!(0x823c9b50fd114196, get_hash(&position));
 
         // position after e2e4 d7d5
         let position = Board::from_fen("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2").unwrap().position;
-        //assert_eq!(0x0756b94461c50fb0, get_hash(position));
+        assert_eq!(0x0756b94461c50fb0, get_hash(&position));
 
         // position after e2e4 d7d5 e4e5
         let position = Board::from_fen("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2").unwrap().position;
@@ -2,8 +2,6 @@ use std::sync::mpsc;
 use std::sync::mpsc::{Receiver, Sender};
 use std::thread;
 use ladybug::ladybug::{Ladybug, Message};
-use ladybug::lookup::LOOKUP_TABLE;
-use ladybug::lookup::lookup_table::LookupTable;
 use ladybug::search::{Search, SearchCommand};
 
 /// Helper function to assert that the engine returns the expected output after reaching the given depth.
@@ -31,11 +29,6 @@ pub fn go_position(sender: &Sender<Message>, fen: &str, depth: u8) {
 /// to the test function instead of creating dedicated input and output threads. The test thread will act as both input and output thread,
 /// and is thus able to properly test Ladybug's output for various input.
 pub fn setup() -> (Sender<Message>, Receiver<String>) {
-    // initialize the lookup table
-    let mut lookup = LookupTable::default();
-    lookup.initialize_tables();
-    let _ = LOOKUP_TABLE.set(lookup);
-
     // create search_command_sender and search_command_receiver so that the ladybug thread can send commands to the search thread
     let (search_command_sender, search_command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
 
@@ -0,0 +1,31 @@
+use std::path::Path;
+use ladybug::suite;
+use ladybug::suite::{SuiteLimit, SuiteRunner};
+
+/// Helper function to run all positions of the given suite in `tests/suites` and assert that every position is solved.
+fn assert_suite_passes(file_name: &str, limit: SuiteLimit) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("suites").join(file_name);
+    let positions = suite::load_suite(&path).unwrap();
+    assert!(!positions.is_empty());
+
+    let runner = SuiteRunner::new();
+    for (index, epd) in positions.iter().enumerate() {
+        let result = runner.run_position(epd.as_ref().unwrap(), index + 1, limit).unwrap();
+        assert!(result.passed, "{}: played {}, expected {:?}", result.id, result.played_move, result.best_moves);
+    }
+}
+
+#[test]
+fn mates_in_1() {
+    assert_suite_passes("mates_in_1.epd", SuiteLimit::Depth(1));
+}
+
+#[test]
+fn mates_in_2() {
+    assert_suite_passes("mates_in_2.epd", SuiteLimit::Depth(3));
+}
+
+#[test]
+fn mates_in_3() {
+    assert_suite_passes("mates_in_3.epd", SuiteLimit::Depth(5));
+}